@@ -1,20 +1,269 @@
+//! # Antigravity Bridge
+//!
+//! Steam earned in Ask Pete is mirrored to the Antigravity platform. Awards
+//! are written to an outbox table in the same transaction as the balance
+//! change (see [`outbox`]); a background worker then delivers each event over
+//! HTTP with an `Idempotency-Key`, so a retry after a timeout never counts
+//! the same Steam twice.
+
+pub mod outbox;
+pub mod stand_in;
+
+use chrono::{DateTime, Utc};
 use pete_core::economy::Steam;
+use reqwest::{Client, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Where and how to deliver Steam sync events
+#[derive(Debug, Clone)]
+pub struct AntigravityConfig {
+    /// POST target for sync events (None = sync disabled, events wait in the outbox)
+    pub endpoint: Option<String>,
+    pub api_key: Option<String>,
+    pub timeout: Duration,
+    /// Attempts before an event is dead-lettered
+    pub max_attempts: u32,
+    pub base_backoff: Duration,
+    pub max_backoff: Duration,
+    /// How often the worker checks for due events when the outbox is idle
+    pub poll_interval: Duration,
+    pub batch_size: i64,
+}
+
+impl Default for AntigravityConfig {
+    fn default() -> Self {
+        Self {
+            endpoint: None,
+            api_key: None,
+            timeout: Duration::from_secs(10),
+            max_attempts: 8,
+            base_backoff: Duration::from_secs(2),
+            max_backoff: Duration::from_secs(600),
+            poll_interval: Duration::from_secs(5),
+            batch_size: 50,
+        }
+    }
+}
+
+impl AntigravityConfig {
+    /// `ANTIGRAVITY_SYNC_URL`, `ANTIGRAVITY_API_KEY` and
+    /// `ANTIGRAVITY_MAX_ATTEMPTS`; everything else uses the defaults.
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            endpoint: env::var("ANTIGRAVITY_SYNC_URL")
+                .ok()
+                .filter(|s| !s.trim().is_empty()),
+            api_key: env::var("ANTIGRAVITY_API_KEY")
+                .ok()
+                .filter(|s| !s.trim().is_empty()),
+            max_attempts: env::var("ANTIGRAVITY_MAX_ATTEMPTS")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(defaults.max_attempts),
+            ..defaults
+        }
+    }
+
+    /// Delay before attempt `attempts + 1`: doubles from `base_backoff`,
+    /// capped at `max_backoff`.
+    pub fn backoff(&self, attempts: u32) -> Duration {
+        let exponent = attempts.saturating_sub(1).min(20);
+        self.base_backoff
+            .saturating_mul(1 << exponent)
+            .min(self.max_backoff)
+    }
+}
+
+/// One Steam award, as sent to Antigravity
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SteamSyncEvent {
+    pub idempotency_key: Uuid,
+    pub user_id: i64,
+    pub amount: f64,
+    pub source: String,
+    pub occurred_at: DateTime<Utc>,
+}
+
+impl SteamSyncEvent {
+    pub fn new(user_id: i64, amount: Steam, source: &str) -> Self {
+        Self {
+            idempotency_key: Uuid::new_v4(),
+            user_id,
+            amount: amount.0,
+            source: source.to_string(),
+            occurred_at: Utc::now(),
+        }
+    }
+}
+
+/// What happened to one delivery attempt
+#[derive(Debug, Clone, PartialEq)]
+pub enum DeliveryOutcome {
+    /// Accepted, or already received under the same idempotency key
+    Delivered,
+    /// Network error, timeout, 408, 429 or 5xx: try again later
+    Retry(String),
+    /// Any other 4xx: retrying will not help
+    Rejected(String),
+}
 
 #[derive(Clone, Debug)]
-pub struct AntigravityClient;
+pub struct AntigravityClient {
+    http: Client,
+    config: AntigravityConfig,
+}
 
 impl AntigravityClient {
+    /// Client configured from the environment
     pub fn new() -> Self {
-        Self
+        Self::with_config(AntigravityConfig::from_env())
+    }
+
+    pub fn with_config(config: AntigravityConfig) -> Self {
+        let http = Client::builder()
+            .timeout(config.timeout)
+            .build()
+            .unwrap_or_default();
+        Self { http, config }
+    }
+
+    pub fn config(&self) -> &AntigravityConfig {
+        &self.config
     }
 
+    pub fn is_configured(&self) -> bool {
+        self.config.endpoint.is_some()
+    }
+
+    /// POST one event. Never errors; the outcome says whether to retry.
+    pub async fn deliver(&self, event: &SteamSyncEvent) -> DeliveryOutcome {
+        let Some(ref endpoint) = self.config.endpoint else {
+            return DeliveryOutcome::Retry("No Antigravity endpoint configured".to_string());
+        };
+
+        let mut request = self
+            .http
+            .post(endpoint)
+            .header("Idempotency-Key", event.idempotency_key.to_string())
+            .json(event);
+        if let Some(ref key) = self.config.api_key {
+            request = request.bearer_auth(key);
+        }
+
+        match request.send().await {
+            Ok(response) => classify(response.status()),
+            Err(e) => DeliveryOutcome::Retry(e.to_string()),
+        }
+    }
+
+    /// Deliver once, without the outbox. Used only when there is no database
+    /// to make the award durable (simulation mode).
     pub async fn sync_steam(
         &self,
-        _user_id: &str,
-        _amount: Steam,
-        _source: &str,
+        user_id: &str,
+        amount: Steam,
+        source: &str,
     ) -> anyhow::Result<()> {
-        // Stub implementation
-        Ok(())
+        if !self.is_configured() {
+            return Ok(());
+        }
+        let user_id = user_id.parse()?;
+        match self
+            .deliver(&SteamSyncEvent::new(user_id, amount, source))
+            .await
+        {
+            DeliveryOutcome::Delivered => Ok(()),
+            DeliveryOutcome::Retry(e) | DeliveryOutcome::Rejected(e) => {
+                Err(anyhow::anyhow!("Antigravity sync failed: {}", e))
+            }
+        }
+    }
+}
+
+fn classify(status: StatusCode) -> DeliveryOutcome {
+    if status.is_success() || status == StatusCode::CONFLICT {
+        DeliveryOutcome::Delivered
+    } else if status.is_server_error()
+        || status == StatusCode::TOO_MANY_REQUESTS
+        || status == StatusCode::REQUEST_TIMEOUT
+    {
+        DeliveryOutcome::Retry(format!("Antigravity returned {}", status))
+    } else {
+        DeliveryOutcome::Rejected(format!("Antigravity returned {}", status))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::stand_in::StandInServer;
+    use super::*;
+
+    fn client_for(server: &StandInServer) -> AntigravityClient {
+        AntigravityClient::with_config(AntigravityConfig {
+            endpoint: Some(server.url()),
+            timeout: Duration::from_secs(2),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_backoff_doubles_up_to_cap() {
+        let config = AntigravityConfig::default();
+        assert_eq!(config.backoff(1), Duration::from_secs(2));
+        assert_eq!(config.backoff(2), Duration::from_secs(4));
+        assert_eq!(config.backoff(4), Duration::from_secs(16));
+        assert_eq!(config.backoff(40), config.max_backoff);
+    }
+
+    #[tokio::test]
+    async fn test_redelivery_with_same_key_is_counted_once() {
+        let server = StandInServer::start().await.unwrap();
+        let client = client_for(&server);
+        let event = SteamSyncEvent::new(7, Steam(1.0), "socratic_dialogue");
+
+        assert_eq!(client.deliver(&event).await, DeliveryOutcome::Delivered);
+        assert_eq!(client.deliver(&event).await, DeliveryOutcome::Delivered);
+        assert_eq!(server.request_count(), 2);
+        assert_eq!(server.events(), vec![event]);
+    }
+
+    #[tokio::test]
+    async fn test_outages_retry_and_bad_requests_are_rejected() {
+        let server = StandInServer::start().await.unwrap();
+        let client = client_for(&server);
+        let event = SteamSyncEvent::new(7, Steam(2.0), "quest:3");
+
+        server.fail_next(1);
+        assert!(matches!(
+            client.deliver(&event).await,
+            DeliveryOutcome::Retry(_)
+        ));
+        assert_eq!(client.deliver(&event).await, DeliveryOutcome::Delivered);
+
+        server.reject_all(true);
+        let other = SteamSyncEvent::new(8, Steam(1.0), "quest:3");
+        assert!(matches!(
+            client.deliver(&other).await,
+            DeliveryOutcome::Rejected(_)
+        ));
+        assert_eq!(server.events().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_endpoint_is_retried() {
+        let client = AntigravityClient::with_config(AntigravityConfig {
+            endpoint: Some("http://127.0.0.1:9/steam".to_string()),
+            timeout: Duration::from_secs(2),
+            ..Default::default()
+        });
+        let event = SteamSyncEvent::new(1, Steam(1.0), "socratic_dialogue");
+        assert!(matches!(
+            client.deliver(&event).await,
+            DeliveryOutcome::Retry(_)
+        ));
     }
 }
@@ -0,0 +1,275 @@
+//! Durable Steam sync: the `antigravity_outbox` table and its worker.
+//!
+//! Anything that changes a Steam balance calls [`enqueue`] on the same
+//! transaction, so the award and its sync event commit (or roll back)
+//! together. [`OutboxWorker`] delivers due events, backing off on failures
+//! and dead-lettering events that are rejected or run out of attempts.
+
+use super::{AntigravityClient, AntigravityConfig, DeliveryOutcome, SteamSyncEvent};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use pete_core::economy::Steam;
+use serde::Serialize;
+use sqlx::{PgConnection, PgPool};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct OutboxEntry {
+    pub id: i64,
+    pub idempotency_key: Uuid,
+    pub user_id: i64,
+    pub amount: f64,
+    pub source: String,
+    /// 'pending', 'delivered' or 'dead'
+    pub status: String,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    pub next_attempt_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+    pub delivered_at: Option<DateTime<Utc>>,
+}
+
+impl OutboxEntry {
+    pub fn event(&self) -> SteamSyncEvent {
+        SteamSyncEvent {
+            idempotency_key: self.idempotency_key,
+            user_id: self.user_id,
+            amount: self.amount,
+            source: self.source.clone(),
+            occurred_at: self.created_at,
+        }
+    }
+}
+
+/// Write `event` to the outbox on the caller's transaction
+pub async fn enqueue(conn: &mut PgConnection, event: &SteamSyncEvent) -> Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO antigravity_outbox (idempotency_key, user_id, amount, source, created_at)
+        VALUES ($1, $2, $3, $4, $5)
+        "#,
+    )
+    .bind(event.idempotency_key)
+    .bind(event.user_id)
+    .bind(event.amount)
+    .bind(&event.source)
+    .bind(event.occurred_at)
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+/// Add Steam to a user's balance and queue its sync in one transaction.
+/// Returns `None` (and changes nothing) if the user does not exist.
+pub async fn award_steam(
+    pool: &PgPool,
+    user_id: i64,
+    amount: Steam,
+    source: &str,
+) -> Result<Option<SteamSyncEvent>> {
+    let mut tx = pool.begin().await?;
+    let updated = sqlx::query("UPDATE users SET steam_balance = steam_balance + $1 WHERE id = $2")
+        .bind(amount.0)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+    if updated.rows_affected() == 0 {
+        return Ok(None);
+    }
+
+    let event = SteamSyncEvent::new(user_id, amount, source);
+    enqueue(&mut tx, &event).await?;
+    tx.commit().await?;
+    Ok(Some(event))
+}
+
+/// Where an entry goes after a delivery attempt
+#[derive(Debug, Clone, PartialEq)]
+enum Transition {
+    Delivered,
+    RetryIn(Duration, String),
+    Dead(String),
+}
+
+/// `attempts` counts the attempt that produced `outcome`
+fn transition(config: &AntigravityConfig, attempts: u32, outcome: DeliveryOutcome) -> Transition {
+    match outcome {
+        DeliveryOutcome::Delivered => Transition::Delivered,
+        DeliveryOutcome::Rejected(e) => Transition::Dead(e),
+        DeliveryOutcome::Retry(e) if attempts >= config.max_attempts => Transition::Dead(e),
+        DeliveryOutcome::Retry(e) => Transition::RetryIn(config.backoff(attempts), e),
+    }
+}
+
+pub struct OutboxWorker {
+    pool: PgPool,
+    client: AntigravityClient,
+}
+
+impl OutboxWorker {
+    pub fn new(pool: PgPool, client: AntigravityClient) -> Self {
+        Self { pool, client }
+    }
+
+    /// Deliver one batch of due events. Returns how many were attempted.
+    pub async fn run_once(&self) -> Result<usize> {
+        let config = self.client.config();
+        // Claim the batch by pushing its next attempt past the request
+        // timeout, so a second worker (or a crash mid-batch) can't double-send
+        // before the outcome is recorded.
+        let lease = (config.timeout.as_secs_f64() * 2.0).max(30.0);
+        let due: Vec<OutboxEntry> = sqlx::query_as(
+            r#"
+            UPDATE antigravity_outbox
+            SET next_attempt_at = NOW() + make_interval(secs => $2)
+            WHERE id IN (
+                SELECT id FROM antigravity_outbox
+                WHERE status = 'pending' AND next_attempt_at <= NOW()
+                ORDER BY next_attempt_at
+                LIMIT $1
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING *
+            "#,
+        )
+        .bind(config.batch_size)
+        .bind(lease)
+        .fetch_all(&self.pool)
+        .await?;
+
+        for entry in &due {
+            let attempts = entry.attempts.max(0) as u32 + 1;
+            let outcome = self.client.deliver(&entry.event()).await;
+            match transition(config, attempts, outcome) {
+                Transition::Delivered => {
+                    sqlx::query(
+                        "UPDATE antigravity_outbox SET status = 'delivered', attempts = $2, \
+                         last_error = NULL, delivered_at = NOW() WHERE id = $1",
+                    )
+                    .bind(entry.id)
+                    .bind(attempts as i32)
+                    .execute(&self.pool)
+                    .await?;
+                }
+                Transition::RetryIn(delay, error) => {
+                    log::debug!(
+                        "Antigravity sync {} failed (attempt {}): {}",
+                        entry.idempotency_key,
+                        attempts,
+                        error
+                    );
+                    sqlx::query(
+                        "UPDATE antigravity_outbox SET attempts = $2, last_error = $3, \
+                         next_attempt_at = NOW() + make_interval(secs => $4) WHERE id = $1",
+                    )
+                    .bind(entry.id)
+                    .bind(attempts as i32)
+                    .bind(error)
+                    .bind(delay.as_secs_f64())
+                    .execute(&self.pool)
+                    .await?;
+                }
+                Transition::Dead(error) => {
+                    log::warn!(
+                        "Antigravity sync {} dead-lettered after {} attempt(s): {}",
+                        entry.idempotency_key,
+                        attempts,
+                        error
+                    );
+                    sqlx::query(
+                        "UPDATE antigravity_outbox SET status = 'dead', attempts = $2, \
+                         last_error = $3 WHERE id = $1",
+                    )
+                    .bind(entry.id)
+                    .bind(attempts as i32)
+                    .bind(error)
+                    .execute(&self.pool)
+                    .await?;
+                }
+            }
+        }
+        Ok(due.len())
+    }
+
+    /// Run forever: drain due events, then sleep for the poll interval
+    pub fn spawn(self) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            log::info!(
+                "Antigravity outbox worker delivering to {}",
+                self.client.config().endpoint.as_deref().unwrap_or("(none)")
+            );
+            loop {
+                match self.run_once().await {
+                    Ok(n) if n as i64 >= self.client.config().batch_size => continue,
+                    Ok(_) => {}
+                    Err(e) => log::warn!("Antigravity outbox worker error: {:#}", e),
+                }
+                tokio::time::sleep(self.client.config().poll_interval).await;
+            }
+        })
+    }
+}
+
+/// Events that will not be retried, newest first
+pub async fn dead_letters(pool: &PgPool, limit: i64) -> Result<Vec<OutboxEntry>> {
+    let entries = sqlx::query_as(
+        "SELECT * FROM antigravity_outbox WHERE status = 'dead' ORDER BY created_at DESC LIMIT $1",
+    )
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+    Ok(entries)
+}
+
+/// Put a dead event back in the queue with a fresh attempt budget. The
+/// idempotency key is kept, so a delivery that did land is not double-counted.
+pub async fn requeue(pool: &PgPool, id: i64) -> Result<bool> {
+    let result = sqlx::query(
+        r#"
+        UPDATE antigravity_outbox
+        SET status = 'pending', attempts = 0, next_attempt_at = NOW()
+        WHERE id = $1 AND status = 'dead'
+        "#,
+    )
+    .bind(id)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transition_backs_off_then_dead_letters() {
+        let config = AntigravityConfig {
+            max_attempts: 3,
+            ..Default::default()
+        };
+        let retry = || DeliveryOutcome::Retry("503".to_string());
+
+        assert_eq!(
+            transition(&config, 1, retry()),
+            Transition::RetryIn(Duration::from_secs(2), "503".to_string())
+        );
+        assert_eq!(
+            transition(&config, 2, retry()),
+            Transition::RetryIn(Duration::from_secs(4), "503".to_string())
+        );
+        assert_eq!(
+            transition(&config, 3, retry()),
+            Transition::Dead("503".to_string())
+        );
+        assert_eq!(
+            transition(&config, 1, DeliveryOutcome::Rejected("422".to_string())),
+            Transition::Dead("422".to_string())
+        );
+        assert_eq!(
+            transition(&config, 5, DeliveryOutcome::Delivered),
+            Transition::Delivered
+        );
+    }
+}
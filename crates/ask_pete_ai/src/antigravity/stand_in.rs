@@ -0,0 +1,151 @@
+//! A local stand-in for the Antigravity sync endpoint, for tests and for
+//! running the outbox worker without the real platform. It speaks just
+//! enough HTTP/1.1 to accept `POST`ed [`SteamSyncEvent`]s, remembers them
+//! by idempotency key (answering `409` for repeats) and can be told to fail.
+
+use super::SteamSyncEvent;
+use std::collections::HashSet;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinHandle;
+
+#[derive(Default)]
+struct StandInState {
+    events: Vec<SteamSyncEvent>,
+    keys: HashSet<String>,
+    requests: usize,
+    fail_next: usize,
+    reject_all: bool,
+}
+
+pub struct StandInServer {
+    addr: SocketAddr,
+    state: Arc<Mutex<StandInState>>,
+    handle: JoinHandle<()>,
+}
+
+impl StandInServer {
+    /// Listen on an ephemeral localhost port
+    pub async fn start() -> std::io::Result<Self> {
+        Self::bind("127.0.0.1:0").await
+    }
+
+    pub async fn bind(addr: &str) -> std::io::Result<Self> {
+        let listener = TcpListener::bind(addr).await?;
+        let addr = listener.local_addr()?;
+        let state = Arc::new(Mutex::new(StandInState::default()));
+        let accept_state = state.clone();
+        let handle = tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let state = accept_state.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = handle_connection(stream, state).await {
+                        log::debug!("Antigravity stand-in connection error: {}", e);
+                    }
+                });
+            }
+        });
+        Ok(Self {
+            addr,
+            state,
+            handle,
+        })
+    }
+
+    /// The sync endpoint URL to put in `AntigravityConfig::endpoint`
+    pub fn url(&self) -> String {
+        format!("http://{}/v1/steam/sync", self.addr)
+    }
+
+    /// Answer the next `n` requests with 503
+    pub fn fail_next(&self, n: usize) {
+        self.state.lock().unwrap().fail_next = n;
+    }
+
+    /// Answer every request with 422 until turned off
+    pub fn reject_all(&self, reject: bool) {
+        self.state.lock().unwrap().reject_all = reject;
+    }
+
+    /// Accepted events, one per idempotency key, in arrival order
+    pub fn events(&self) -> Vec<SteamSyncEvent> {
+        self.state.lock().unwrap().events.clone()
+    }
+
+    /// Every request seen, including failures and repeats
+    pub fn request_count(&self) -> usize {
+        self.state.lock().unwrap().requests
+    }
+}
+
+impl Drop for StandInServer {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+async fn handle_connection(
+    stream: TcpStream,
+    state: Arc<Mutex<StandInState>>,
+) -> std::io::Result<()> {
+    let mut reader = BufReader::new(stream);
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).await?;
+    let mut content_length = 0;
+    let mut idempotency_key = None;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).await? == 0 || line.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            match name.trim().to_ascii_lowercase().as_str() {
+                "content-length" => content_length = value.trim().parse().unwrap_or(0),
+                "idempotency-key" => idempotency_key = Some(value.trim().to_string()),
+                _ => {}
+            }
+        }
+    }
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body).await?;
+
+    let status = respond(&state, &request_line, idempotency_key, &body);
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+        status
+    );
+    reader.get_mut().write_all(response.as_bytes()).await?;
+    reader.get_mut().shutdown().await
+}
+
+fn respond(
+    state: &Mutex<StandInState>,
+    request_line: &str,
+    idempotency_key: Option<String>,
+    body: &[u8],
+) -> &'static str {
+    let mut state = state.lock().unwrap();
+    state.requests += 1;
+
+    if state.fail_next > 0 {
+        state.fail_next -= 1;
+        return "503 Service Unavailable";
+    }
+    if state.reject_all || !request_line.starts_with("POST ") {
+        return "422 Unprocessable Entity";
+    }
+    let (Some(key), Ok(event)) = (
+        idempotency_key,
+        serde_json::from_slice::<SteamSyncEvent>(body),
+    ) else {
+        return "400 Bad Request";
+    };
+    if !state.keys.insert(key) {
+        return "409 Conflict";
+    }
+    state.events.push(event);
+    "200 OK"
+}
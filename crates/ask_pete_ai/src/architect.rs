@@ -1,8 +1,18 @@
 use crate::llm::gemini_client::GeminiClient;
+use crate::lore::LoreRegistry;
+use crate::prompt_assembler::{
+    priority, PromptAssembler, PromptSection, Trim, DEFAULT_CONTEXT_WINDOW,
+};
+use crate::vocabulary::VocabularyRegistry;
 use crate::LocalModel;
 use anyhow::Result;
 use pete_core::expert::StoryGraph;
+use pete_core::frameworks::NarrativeFramework;
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// Tokens kept free for the generated blueprint (the JSON is long)
+const BLUEPRINT_MAX_TOKENS: usize = 2048;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct BlueprintRequest {
@@ -10,6 +20,15 @@ pub struct BlueprintRequest {
     pub focus: f32,              // 0.0 (Pure Subject) to 1.0 (Pure Story)
     pub literary_device: String, // e.g., "Hero's Journey"
     pub vocabulary: Vec<String>,
+    /// Pack to draw vocabulary from when `vocabulary` is empty (None = default pack)
+    #[serde(default)]
+    pub vocabulary_pack: Option<String>,
+    /// Story world to write in (None = the Iron Network)
+    #[serde(default)]
+    pub lore_pack: Option<String>,
+    /// Narrative framework plugin to structure the graph with, e.g. "freytag"
+    #[serde(default)]
+    pub framework: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -22,6 +41,9 @@ pub struct BlueprintResponse {
 pub struct CurriculumArchitect {
     gemini: Option<GeminiClient>,
     local_model: Option<LocalModel>,
+    vocabulary: Arc<VocabularyRegistry>,
+    lore: Arc<LoreRegistry>,
+    framework: Option<Arc<dyn NarrativeFramework>>,
 }
 
 impl CurriculumArchitect {
@@ -29,135 +51,58 @@ impl CurriculumArchitect {
         Self {
             gemini,
             local_model,
+            vocabulary: Arc::new(VocabularyRegistry::builtin()),
+            lore: Arc::new(LoreRegistry::builtin()),
+            framework: None,
         }
     }
 
+    /// Structure the graph with this framework's stages, starting from its skeleton
+    pub fn with_framework(mut self, framework: Option<Arc<dyn NarrativeFramework>>) -> Self {
+        self.framework = framework;
+        self
+    }
+
+    /// Use these lore packs instead of just the Iron Network
+    pub fn with_lore(mut self, registry: Arc<LoreRegistry>) -> Self {
+        self.lore = registry;
+        self
+    }
+
+    /// Use these vocabulary packs instead of just the built-in one
+    pub fn with_vocabulary(mut self, registry: Arc<VocabularyRegistry>) -> Self {
+        self.vocabulary = registry;
+        self
+    }
+
     pub async fn generate_blueprint(&mut self, req: BlueprintRequest) -> Result<BlueprintResponse> {
-        // 1. Construct the Prompt
-        let prompt = format!(
-            r#"
-            You are the "Curriculum Architect", an expert instructional designer and storyteller.
-            
-            CONTEXT & LORE:
-            {lore_context}
-
-            GOAL: Create a non-linear learning path (StoryGraph) for the subject: "{subject}".
-            
-            CONSTRAINTS:
-            - Focus Balance: {focus} (0.0 = Pure Academic, 1.0 = Pure Narrative).
-            - Literary Device: "{device}".
-            - Required Vocabulary: {vocab:?}.
-            
-            NARRATIVE DEVICE INSTRUCTIONS:
-            {device_prompt}
-
-            INSTRUCTIONS (CHAIN OF THOUGHT):
-            Step 1: Outline 5 key concepts related to the subject.
-            Step 2: Connect them logically to form a narrative progression.
-            Step 3: Output the result as valid JSON matching the schema below.
-            
-            OUTPUT FORMAT:
-            Return a JSON object matching this structure. Ensure the JSON is the LAST part of your response.
-            {{
-                "graph": {{
-                    "id": "generated_uuid",
-                    "title": "Campaign Title",
-                    "nodes": [
-                        {{
-                            "id": "node_1",
-                            "title": "Node Title",
-                            "content": "Narrative or Instructional Content",
-                            "x": 0.0,
-                            "y": 0.0,
-                            "passenger_count": 1,
-                            "complexity_level": 1,
-                            "learner_profiles": [],
-                            "gardens_active": [],
-                            "required_stats": {{}},
-                            "logic": {{
-                                "condition": "None", 
-                                "effect": "None"
-                            }},
-                            "quest": {{
-                                "title": "Quest Title",
-                                "chapter_theme": "Theme (e.g. 'The Call')",
-                                "description": "Quest Description",
-                                "starting_step": "step_1",
-                                "completion_reward": {{
-                                    "type": "xp",
-                                    "value": 100
-                                }},
-                                "steps": {{
-                                    "step_1": {{
-                                        "description": "Step 1 Description",
-                                        "trigger_condition": "None",
-                                        "next_step": "None",
-                                        "is_major_plot_point": true
-                                    }}
-                                }}
-                            }}
-                        }}
-                    ],
-                    "connections": [
-                        {{
-                            "id": "conn_1",
-                            "from_node": "node_1",
-                            "to_node": "node_2"
-                        }}
-                    ]
-                }},
-                "reasoning": "Brief explanation of design choices."
-            }}
-            
-            RULES:
-            1. Create at least 5 nodes.
-            2. Ensure the graph branches (non-linear).
-            3. Integrate the vocabulary words into the node content.
-            4. Adjust 'complexity_level' based on the progression.
-            5. Use the terminology from the LORE (Sectors, Chassis, etc.) in the node titles and content where appropriate.
-            6. TREAT WORDS AS SYMBOLS OF POWER. In the Iron Network, knowing the definition of a word (like 'Velocity') is not just academic—it grants control over the environment (e.g., opening doors, powering engines).
-            7. **CRITICAL**: The 'logic' field MUST use proper JSON enum format (NOT Rust syntax strings):
-            
-               CONDITION OPTIONS (choose one):
-               - No condition: "None"
-               - Check variable greater than value: {{"GreaterThan": {{"variable": "Strength", "value": 10.0}}}}
-               - Check variable less than value: {{"LessThan": {{"variable": "Speed", "value": 5.0}}}}
-               - Check variable equals value: {{"Equals": {{"variable": "Level", "value": 1.0}}}}
-               - Check if player has item: {{"HasItem": {{"item_id": "ancient_key"}}}}
-               
-               EFFECT OPTIONS (choose one):
-               - No effect: "None"
-               - Modify a variable: {{"ModifyVariable": {{"variable": "Strength", "delta": 5.0}}}}
-               - Grant an item: {{"GrantItem": {{"item_id": "rusty_wrench"}}}}
-               - Consume an item: {{"ConsumeItem": {{"item_id": "coal_chunk"}}}}
-            
-               EXAMPLE LOGIC BLOCKS:
-               - Simple node (no logic): {{"condition": "None", "effect": "None"}}
-               - Locked node requiring strength: {{"condition": {{"GreaterThan": {{"variable": "Strength", "value": 5.0}}}}, "effect": "None"}}
-               - Node that grants item: {{"condition": "None", "effect": {{"GrantItem": {{"item_id": "station_key"}}}}}}
-               - Complex: requires item AND grants stat boost: {{"condition": {{"HasItem": {{"item_id": "wrench"}}}}, "effect": {{"ModifyVariable": {{"variable": "Strength", "delta": 10.0}}}}}}
-            "#,
-            lore_context = crate::lore::get_lore_context(),
-            subject = req.subject,
-            focus = req.focus,
-            device = req.literary_device,
-            device_prompt = crate::lore::get_device_prompt(&req.literary_device),
-            vocab = if req.vocabulary.is_empty() {
-                // Auto-inject physics vocabulary if none provided
-                crate::vocabulary::get_physics_vocabulary()
-                    .into_iter()
-                    .map(|t| format!("{}: {}", t.word, t.definition))
-                    .collect::<Vec<String>>()
-            } else {
-                req.vocabulary
-            }
-        );
+        if self.local_model.is_none() && self.gemini.is_none() {
+            // Without a model the framework's skeleton is still a usable starting graph
+            return match self.framework {
+                Some(ref framework) => Ok(skeleton_blueprint(framework.as_ref(), &req.subject)),
+                None => Err(anyhow::anyhow!("No AI model available for Architect")),
+            };
+        }
+
+        // 1. Construct the Prompt, trimmed to the model's context window
+        let assembler = match self.local_model {
+            Some(ref model) => PromptAssembler::for_local_model(model, BLUEPRINT_MAX_TOKENS),
+            None => PromptAssembler::heuristic(DEFAULT_CONTEXT_WINDOW, BLUEPRINT_MAX_TOKENS),
+        };
+        let prompt = assembler
+            .assemble(blueprint_sections(
+                &req,
+                &self.vocabulary,
+                &self.lore,
+                self.framework.as_deref(),
+            ))?
+            .text;
 
         // 2. Call LLM (Local or Gemini)
         let response_text = if let Some(ref model) = self.local_model {
             log::info!("Architect using Local Model (Gemma 2)");
             let config = crate::local_inference::GenerationConfig {
-                max_tokens: 2048, // Need more tokens for JSON
+                max_tokens: BLUEPRINT_MAX_TOKENS,
                 temperature: 0.7,
                 top_p: 0.9,
                 repeat_penalty: 1.1,
@@ -179,3 +124,222 @@ impl CurriculumArchitect {
         Ok(response)
     }
 }
+
+/// The framework's skeleton, returned as-is when no model can flesh it out
+pub fn skeleton_blueprint(framework: &dyn NarrativeFramework, subject: &str) -> BlueprintResponse {
+    BlueprintResponse {
+        graph: framework.skeleton(subject),
+        reasoning: format!(
+            "Skeleton from the {} framework: one station per stage. No AI model was available to write the content.",
+            framework.name()
+        ),
+    }
+}
+
+/// The Architect prompt. Role, goal, framework and output schema are fixed;
+/// when space runs out the lore is cut first, then the device notes, then
+/// vocabulary.
+fn blueprint_sections(
+    req: &BlueprintRequest,
+    packs: &VocabularyRegistry,
+    lore: &LoreRegistry,
+    framework: Option<&dyn NarrativeFramework>,
+) -> Vec<PromptSection> {
+    let lore = lore.resolve(req.lore_pack.as_deref());
+    let vocabulary = if req.vocabulary.is_empty() {
+        // Auto-inject the requested pack's vocabulary if none provided
+        packs
+            .resolve(req.vocabulary_pack.as_deref())
+            .terms
+            .iter()
+            .map(|t| format!("{}: {}", t.word, t.definition))
+            .collect::<Vec<String>>()
+    } else {
+        req.vocabulary.clone()
+    };
+
+    let mut sections = vec![
+        PromptSection::fixed(
+            "role",
+            "You are the \"Curriculum Architect\", an expert instructional designer and storyteller.",
+        ),
+        PromptSection::text("lore", priority::LORE, lore.codex.as_str())
+            .with_header("CONTEXT & LORE:\n"),
+        PromptSection::fixed(
+            "goal",
+            format!(
+                "GOAL: Create a non-linear learning path (StoryGraph) for the subject: \"{}\".\n\n\
+                 CONSTRAINTS:\n\
+                 - Focus Balance: {} (0.0 = Pure Academic, 1.0 = Pure Narrative).\n\
+                 - Literary Device: \"{}\".",
+                req.subject, req.focus, req.literary_device
+            ),
+        ),
+        PromptSection::items(
+            "vocabulary",
+            priority::VOCABULARY,
+            vocabulary.into_iter().map(|v| format!("- {}", v)).collect(),
+            Trim::DropLowest,
+        )
+        .with_header("Required Vocabulary:\n"),
+        PromptSection::text(
+            "device",
+            priority::NARRATIVE,
+            lore.device_prompt(&req.literary_device),
+        )
+        .with_header("NARRATIVE DEVICE INSTRUCTIONS:\n"),
+    ];
+    if let Some(framework) = framework {
+        sections.push(PromptSection::fixed(
+            "framework",
+            framework_prompt(framework, &req.subject),
+        ));
+    }
+    sections.push(PromptSection::fixed("instructions", BLUEPRINT_INSTRUCTIONS));
+    sections
+}
+
+/// Stage guidance plus the skeleton graph the model should build on
+fn framework_prompt(framework: &dyn NarrativeFramework, subject: &str) -> String {
+    let skeleton = serde_json::to_string(&framework.skeleton(subject)).unwrap_or_default();
+    format!(
+        "NARRATIVE FRAMEWORK: {}\n{}\n\
+         Start from this skeleton graph. Keep one node per stage, in order, keep their ids, \
+         write their content, and add branching nodes between stages:\n{}",
+        framework.name(),
+        framework.prompt_guidance(),
+        skeleton
+    )
+}
+
+const BLUEPRINT_INSTRUCTIONS: &str = r#"INSTRUCTIONS (CHAIN OF THOUGHT):
+Step 1: Outline 5 key concepts related to the subject.
+Step 2: Connect them logically to form a narrative progression.
+Step 3: Output the result as valid JSON matching the schema below.
+
+OUTPUT FORMAT:
+Return a JSON object matching this structure. Ensure the JSON is the LAST part of your response.
+{
+    "graph": {
+        "id": "generated_uuid",
+        "title": "Campaign Title",
+        "nodes": [
+            {
+                "id": "node_1",
+                "title": "Node Title",
+                "content": "Narrative or Instructional Content",
+                "x": 0.0,
+                "y": 0.0,
+                "passenger_count": 1,
+                "complexity_level": 1,
+                "learner_profiles": [],
+                "gardens_active": [],
+                "required_stats": {},
+                "logic": {
+                    "condition": "None",
+                    "effect": "None"
+                },
+                "quest": {
+                    "title": "Quest Title",
+                    "chapter_theme": "Theme (e.g. 'The Call')",
+                    "description": "Quest Description",
+                    "starting_step": "step_1",
+                    "completion_reward": {
+                        "type": "xp",
+                        "value": 100
+                    },
+                    "steps": {
+                        "step_1": {
+                            "description": "Step 1 Description",
+                            "trigger_condition": "None",
+                            "next_step": "None",
+                            "is_major_plot_point": true
+                        }
+                    }
+                }
+            }
+        ],
+        "connections": [
+            {
+                "id": "conn_1",
+                "from_node": "node_1",
+                "to_node": "node_2"
+            }
+        ]
+    },
+    "reasoning": "Brief explanation of design choices."
+}
+
+RULES:
+1. Create at least 5 nodes.
+2. Ensure the graph branches (non-linear).
+3. Integrate the vocabulary words into the node content.
+4. Adjust 'complexity_level' based on the progression.
+5. Use the terminology from the LORE (Sectors, Chassis, etc.) in the node titles and content where appropriate.
+6. TREAT WORDS AS SYMBOLS OF POWER. In the Iron Network, knowing the definition of a word (like 'Velocity') is not just academic—it grants control over the environment (e.g., opening doors, powering engines).
+7. **CRITICAL**: The 'logic' field MUST use proper JSON enum format (NOT Rust syntax strings):
+
+   CONDITION OPTIONS (choose one):
+   - No condition: "None"
+   - Check variable greater than value: {"GreaterThan": {"variable": "Strength", "value": 10.0}}
+   - Check variable less than value: {"LessThan": {"variable": "Speed", "value": 5.0}}
+   - Check variable equals value: {"Equals": {"variable": "Level", "value": 1.0}}
+   - Check if player has item: {"HasItem": {"item_id": "ancient_key"}}
+
+   EFFECT OPTIONS (choose one):
+   - No effect: "None"
+   - Modify a variable: {"ModifyVariable": {"variable": "Strength", "delta": 5.0}}
+   - Grant an item: {"GrantItem": {"item_id": "rusty_wrench"}}
+   - Consume an item: {"ConsumeItem": {"item_id": "coal_chunk"}}
+
+   EXAMPLE LOGIC BLOCKS:
+   - Simple node (no logic): {"condition": "None", "effect": "None"}
+   - Locked node requiring strength: {"condition": {"GreaterThan": {"variable": "Strength", "value": 5.0}}, "effect": "None"}
+   - Node that grants item: {"condition": "None", "effect": {"GrantItem": {"item_id": "station_key"}}}
+   - Complex: requires item AND grants stat boost: {"condition": {"HasItem": {"item_id": "wrench"}}, "effect": {"ModifyVariable": {"variable": "Strength", "delta": 10.0}}}"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pete_core::frameworks::StagedFramework;
+
+    fn request() -> BlueprintRequest {
+        BlueprintRequest {
+            subject: "Photosynthesis".to_string(),
+            focus: 0.5,
+            literary_device: "The Derailment (Mystery)".to_string(),
+            vocabulary: vec![],
+            vocabulary_pack: None,
+            lore_pack: None,
+            framework: Some("freytag".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_framework_section_carries_stages_and_skeleton() {
+        let framework = StagedFramework::freytag();
+        let sections = blueprint_sections(
+            &request(),
+            &VocabularyRegistry::builtin(),
+            &LoreRegistry::builtin(),
+            Some(&framework),
+        );
+        let prompt = PromptAssembler::heuristic(DEFAULT_CONTEXT_WINDOW, BLUEPRINT_MAX_TOKENS)
+            .assemble(sections)
+            .unwrap()
+            .text;
+        assert!(prompt.contains("NARRATIVE FRAMEWORK: Freytag's Pyramid"));
+        assert!(prompt.contains("\"id\":\"03_climax\""));
+    }
+
+    #[tokio::test]
+    async fn test_no_model_falls_back_to_framework_skeleton() {
+        let framework: Arc<dyn NarrativeFramework> = Arc::new(StagedFramework::freytag());
+        let mut architect = CurriculumArchitect::new(None, None).with_framework(Some(framework));
+        let blueprint = architect.generate_blueprint(request()).await.unwrap();
+        assert_eq!(blueprint.graph.nodes.len(), 5);
+
+        let mut bare = CurriculumArchitect::new(None, None);
+        assert!(bare.generate_blueprint(request()).await.is_err());
+    }
+}
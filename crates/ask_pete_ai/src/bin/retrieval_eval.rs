@@ -0,0 +1,123 @@
+//! Offline retrieval evaluation: recall@k and MRR for keyword, vector and
+//! hybrid search on a labeled question set.
+//!
+//! ```text
+//! cargo run -p ask_pete_ai --bin retrieval_eval -- fixtures/retrieval_eval.json \
+//!     [--k 5] [--model-dir <embedding model>] [--model-id <id>] [--reranker-dir <cross-encoder>]
+//! ```
+//!
+//! Without `--model-dir` the hashing embedder is used, which only measures the
+//! keyword side of the vector store.
+
+use anyhow::{bail, Context, Result};
+use ask_pete_ai::embeddings::{CandleEmbedder, EmbeddingConfig};
+use ask_pete_ai::hybrid_retrieval::{evaluate, EvalSet, HybridRetriever, RetrievalMode};
+use ask_pete_ai::reranker::{CrossEncoderReranker, RerankerConfig};
+use infra_db::vector_store::{Embedder, VectorStore};
+use infra_db::{FileVectorStore, HashingEmbedder};
+use std::sync::Arc;
+
+struct Args {
+    eval_set: String,
+    k: usize,
+    model_dir: Option<String>,
+    model_id: String,
+    reranker_dir: Option<String>,
+}
+
+fn option_value(iter: &mut impl Iterator<Item = String>, option: &str) -> Result<String> {
+    iter.next()
+        .with_context(|| format!("{} needs a value", option))
+}
+
+fn parse_args() -> Result<Args> {
+    let mut args = Args {
+        eval_set: String::new(),
+        k: 5,
+        model_dir: None,
+        model_id: EmbeddingConfig::MINILM.to_string(),
+        reranker_dir: None,
+    };
+    let mut iter = std::env::args().skip(1);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--k" => {
+                args.k = option_value(&mut iter, &arg)?
+                    .parse()
+                    .context("--k must be a number")?
+            }
+            "--model-dir" => args.model_dir = Some(option_value(&mut iter, &arg)?),
+            "--model-id" => args.model_id = option_value(&mut iter, &arg)?,
+            "--reranker-dir" => args.reranker_dir = Some(option_value(&mut iter, &arg)?),
+            _ if arg.starts_with("--") => bail!("Unknown option {}", arg),
+            _ => args.eval_set = arg,
+        }
+    }
+    if args.eval_set.is_empty() {
+        bail!("Usage: retrieval_eval <eval.json> [--k N] [--model-dir DIR] [--model-id ID] [--reranker-dir DIR]");
+    }
+    Ok(args)
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args = parse_args()?;
+    let set = EvalSet::load(&args.eval_set)?;
+    println!(
+        "🔎 Retrieval eval: {} documents, {} queries, k = {}",
+        set.documents.len(),
+        set.queries.len(),
+        args.k
+    );
+
+    let embedder: Arc<dyn Embedder> = match &args.model_dir {
+        Some(dir) => Arc::new(CandleEmbedder::load(EmbeddingConfig::new(
+            &args.model_id,
+            dir,
+        ))?),
+        None => Arc::new(HashingEmbedder::default()),
+    };
+    println!("Embedder: {}", embedder.model_id());
+
+    // Throwaway index so the eval never touches the server's vector store
+    let index_dir =
+        std::env::temp_dir().join(format!("pete_retrieval_eval_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&index_dir);
+    let store = FileVectorStore::open(&index_dir, embedder)?;
+    store.add_documents(set.corpus()).await?;
+
+    let mut retriever = HybridRetriever::new(Arc::new(store), set.corpus());
+    if let Some(dir) = &args.reranker_dir {
+        let reranker =
+            CrossEncoderReranker::load(RerankerConfig::new(RerankerConfig::MS_MARCO_MINILM, dir))?;
+        retriever = retriever.with_reranker(Arc::new(reranker));
+    }
+
+    println!(
+        "\n{:<10} {:<8} {:>10} {:>8}",
+        "mode",
+        "rerank",
+        format!("recall@{}", args.k),
+        "MRR"
+    );
+    let rerank_passes: &[bool] = if retriever.has_reranker() {
+        &[false, true]
+    } else {
+        &[false]
+    };
+    for mode in RetrievalMode::ALL {
+        for &rerank in rerank_passes {
+            let report = evaluate(&retriever, &set, mode, args.k, rerank).await?;
+            println!(
+                "{:<10} {:<8} {:>10.3} {:>8.3}",
+                format!("{:?}", report.mode).to_lowercase(),
+                if report.reranked { "yes" } else { "no" },
+                report.recall_at_k,
+                report.mrr
+            );
+        }
+    }
+
+    let _ = std::fs::remove_dir_all(&index_dir);
+    Ok(())
+}
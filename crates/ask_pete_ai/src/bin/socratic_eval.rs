@@ -0,0 +1,220 @@
+//! Offline Socratic eval: replays a YAML set of student messages through the
+//! Socratic engine, scores the replies and compares them with a baseline.
+//!
+//! ```text
+//! cargo run -p ask_pete_ai --bin socratic_eval -- fixtures/socratic_eval.yaml \
+//!     [--backend mock|local|gemini|openai] [--model <gguf>] [--tokenizer <tokenizer.json>] \
+//!     [--judge-model <gguf>] [--judge-tokenizer <tokenizer.json>] \
+//!     [--out results.json] [--baseline baseline.json]
+//! ```
+//!
+//! Exits with status 1 when a check that passed in the baseline now fails.
+
+use anyhow::{bail, Context, Result};
+use ask_pete_ai::llm::gemini_client::{GeminiClient, GeminiConfig};
+use ask_pete_ai::llm::{LlmBackend, OpenAiBackend, OpenAiConfig};
+use ask_pete_ai::lore::LoreRegistry;
+use ask_pete_ai::socratic_eval::{diff_runs, EvalRun, EvalRunner, EvalSet};
+use ask_pete_ai::vocabulary::VocabularyRegistry;
+use ask_pete_ai::{LocalConfigWrapper, LocalModel, SocraticEngine};
+use infra_db::conversation_memory::ConversationMemory;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+struct Args {
+    eval_set: String,
+    backend: String,
+    model: Option<PathBuf>,
+    tokenizer: Option<PathBuf>,
+    judge_model: Option<PathBuf>,
+    judge_tokenizer: Option<PathBuf>,
+    out: String,
+    baseline: Option<String>,
+}
+
+fn option_value(iter: &mut impl Iterator<Item = String>, option: &str) -> Result<String> {
+    iter.next()
+        .with_context(|| format!("{} needs a value", option))
+}
+
+fn parse_args() -> Result<Args> {
+    let mut args = Args {
+        eval_set: String::new(),
+        backend: "mock".to_string(),
+        model: None,
+        tokenizer: None,
+        judge_model: None,
+        judge_tokenizer: None,
+        out: "socratic_eval_results.json".to_string(),
+        baseline: None,
+    };
+    let mut iter = std::env::args().skip(1);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--backend" => args.backend = option_value(&mut iter, &arg)?,
+            "--model" => args.model = Some(option_value(&mut iter, &arg)?.into()),
+            "--tokenizer" => args.tokenizer = Some(option_value(&mut iter, &arg)?.into()),
+            "--judge-model" => args.judge_model = Some(option_value(&mut iter, &arg)?.into()),
+            "--judge-tokenizer" => {
+                args.judge_tokenizer = Some(option_value(&mut iter, &arg)?.into())
+            }
+            "--out" => args.out = option_value(&mut iter, &arg)?,
+            "--baseline" => args.baseline = Some(option_value(&mut iter, &arg)?),
+            _ if arg.starts_with("--") => bail!("Unknown option {}", arg),
+            _ => args.eval_set = arg,
+        }
+    }
+    if args.eval_set.is_empty() {
+        bail!("Usage: socratic_eval <cases.yaml> [--backend mock|local|gemini|openai] [--model GGUF] [--tokenizer JSON] [--judge-model GGUF] [--judge-tokenizer JSON] [--out FILE] [--baseline FILE]");
+    }
+    Ok(args)
+}
+
+fn load_local(model: Option<PathBuf>, tokenizer: Option<PathBuf>) -> Result<LocalModel> {
+    let defaults = LocalConfigWrapper::default();
+    let config = LocalConfigWrapper {
+        model_path: model.unwrap_or(defaults.model_path.clone()),
+        tokenizer_path: tokenizer.unwrap_or(defaults.tokenizer_path.clone()),
+        ..defaults
+    };
+    println!("Loading {:?}", config.model_path);
+    Ok(LocalModel::load(config)?)
+}
+
+fn runner(args: &Args, set: &EvalSet) -> Result<EvalRunner> {
+    if args.backend == "mock" {
+        return Ok(EvalRunner::with_backend(Arc::new(set.mock_backend())));
+    }
+
+    let memory = Arc::new(ConversationMemory::new_in_memory(64));
+    let mut engine = SocraticEngine::new(memory.clone());
+    engine.set_vocabulary_packs(Arc::new(VocabularyRegistry::builtin()));
+    engine.set_lore_packs(Arc::new(LoreRegistry::builtin()));
+    let label = match args.backend.as_str() {
+        "local" => {
+            let model = load_local(args.model.clone(), args.tokenizer.clone())?;
+            engine.set_local_model(model);
+            format!(
+                "local:{}",
+                args.model
+                    .as_ref()
+                    .and_then(|p| p.file_name())
+                    .map(|n| n.to_string_lossy().into_owned())
+                    .unwrap_or_else(|| "default".to_string())
+            )
+        }
+        "gemini" => {
+            let config = GeminiConfig::default();
+            let label = format!("gemini:{}", config.model);
+            engine.set_gemini_client(GeminiClient::new(config));
+            label
+        }
+        "openai" => {
+            let config = OpenAiConfig::from_env()
+                .context("Set OPENAI_BASE_URL (and OPENAI_MODEL) for the openai backend")?;
+            let backend = OpenAiBackend::new(config);
+            let label = backend.model_id();
+            engine.set_backend(Arc::new(backend));
+            label
+        }
+        other => bail!(
+            "Unknown backend '{}' (expected mock, local, gemini or openai)",
+            other
+        ),
+    };
+    Ok(EvalRunner::new(engine, memory, &label))
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args = parse_args()?;
+    let set = EvalSet::load(&args.eval_set)?;
+    println!(
+        "🧪 Socratic eval '{}': {} cases, backend {}",
+        set.name,
+        set.cases.len(),
+        args.backend
+    );
+
+    let mut runner = runner(&args, &set)?;
+    if let Some(path) = args.judge_model.clone() {
+        let judge: Arc<dyn LlmBackend> =
+            Arc::new(load_local(Some(path), args.judge_tokenizer.clone())?);
+        runner = runner.with_judge(judge);
+    }
+    let run = runner.run(&set).await?;
+
+    println!(
+        "\n{:<28} {:<12} {:>6} {:>6}  failed",
+        "case", "strategy", "words", "grade"
+    );
+    for case in &run.cases {
+        let failed: Vec<&str> = case
+            .checks
+            .iter()
+            .filter(|c| !c.passed)
+            .map(|c| c.name.as_str())
+            .collect();
+        println!(
+            "{:<28} {:<12} {:>6} {:>6.1}  {}",
+            case.id,
+            format!("{:?}", case.strategy),
+            case.words,
+            case.reading_grade,
+            failed.join(", ")
+        );
+    }
+    println!(
+        "\nPassed {}/{} ({:.0}%)",
+        run.summary.passed,
+        run.summary.cases,
+        run.summary.pass_rate * 100.0
+    );
+    if let Some(score) = run.summary.mean_judge_score {
+        println!("Mean judge score: {:.2}/5", score);
+    }
+    run.save(&args.out)?;
+    println!("Results written to {}", args.out);
+
+    let Some(baseline_path) = &args.baseline else {
+        return Ok(());
+    };
+    let baseline = EvalRun::load(baseline_path)?;
+    let diff = diff_runs(&baseline, &run);
+    println!(
+        "\nAgainst {}: pass rate {:+.0}%, {} regression(s), {} fix(es), {} strategy change(s)",
+        baseline_path,
+        diff.pass_rate_delta * 100.0,
+        diff.regressions.len(),
+        diff.fixes.len(),
+        diff.strategy_changes.len()
+    );
+    for change in &diff.regressions {
+        println!(
+            "  ❌ {} / {}: {}",
+            change.case_id, change.check, change.detail
+        );
+    }
+    for change in &diff.fixes {
+        println!(
+            "  ✅ {} / {}: {}",
+            change.case_id, change.check, change.detail
+        );
+    }
+    for change in &diff.strategy_changes {
+        println!(
+            "  ↪ {}: {:?} -> {:?}",
+            change.case_id, change.baseline, change.current
+        );
+    }
+    if !diff.added_cases.is_empty() || !diff.removed_cases.is_empty() {
+        println!(
+            "  new cases: {:?}, removed cases: {:?}",
+            diff.added_cases, diff.removed_cases
+        );
+    }
+    if diff.has_regressions() {
+        std::process::exit(1);
+    }
+    Ok(())
+}
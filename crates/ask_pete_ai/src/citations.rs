@@ -0,0 +1,262 @@
+//! # Source Citations (The Waybill)
+//!
+//! Every knowledge chunk injected into a prompt is labelled `[S1]`, `[S2]`, ...
+//! (see `knowledge_retrieval::format_chunks_for_prompt`) and the model is asked
+//! to cite those labels inline. This stage checks each reference in the reply:
+//!
+//! - **Unknown labels** (not in the prompt) are removed from the text.
+//! - **Unsupported references** — the citing sentence shares no content words
+//!   with the chunk — are removed as well.
+//! - **Verified references** become a `Citation` with the document, page,
+//!   heading and the passage that best supports the sentence.
+//!
+//! Faculty see the citations in the API response and on the stored turn.
+
+use crate::knowledge_retrieval::{source_label, KnowledgeChunk};
+use infra_db::conversation_memory::Citation;
+use regex::Regex;
+use std::collections::BTreeSet;
+use std::sync::OnceLock;
+
+/// Longest snippet returned with a citation, in characters
+const MAX_SNIPPET_CHARS: usize = 240;
+
+/// Result of checking a reply's references against the prompt sources
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CitationCheck {
+    /// Reply with unknown or unsupported markers removed
+    pub text: String,
+    /// Verified citations, in order of first reference
+    pub citations: Vec<Citation>,
+    /// Labels the model cited that failed verification
+    pub rejected: Vec<String>,
+}
+
+/// Matches `[S1]`, `[S1, S3]` and `[Source 2]`
+fn marker_regex() -> &'static Regex {
+    static MARKER: OnceLock<Regex> = OnceLock::new();
+    MARKER.get_or_init(|| {
+        Regex::new(r"(?i)\s?\[\s*(?:s|source\s*)\d+(?:\s*[,;]\s*(?:s|source\s*)\d+)*\s*\]")
+            .expect("valid citation regex")
+    })
+}
+
+fn label_regex() -> &'static Regex {
+    static LABEL: OnceLock<Regex> = OnceLock::new();
+    LABEL.get_or_init(|| Regex::new(r"\d+").expect("valid label regex"))
+}
+
+/// Verify the source references in `text` against the `chunks` that were in
+/// the prompt, in prompt order.
+pub fn resolve_citations(text: &str, chunks: &[KnowledgeChunk]) -> CitationCheck {
+    let mut check = CitationCheck::default();
+    let mut cited = BTreeSet::new();
+    let mut last = 0;
+
+    for marker in marker_regex().find_iter(text) {
+        let sentence = citing_sentence(&text[..marker.start()]);
+        let mut kept = Vec::new();
+
+        for number in label_regex().find_iter(marker.as_str()) {
+            let index = number
+                .as_str()
+                .parse::<usize>()
+                .ok()
+                .and_then(|n| n.checked_sub(1));
+            let label = index
+                .map(source_label)
+                .unwrap_or_else(|| format!("S{}", number.as_str()));
+            let chunk = index.and_then(|idx| chunks.get(idx));
+
+            match chunk {
+                Some(chunk) if is_supported(sentence, &chunk.content) => {
+                    if cited.insert(label.clone()) {
+                        check.citations.push(citation(&label, sentence, chunk));
+                    }
+                    if !kept.contains(&label) {
+                        kept.push(label);
+                    }
+                }
+                _ => {
+                    log::info!(target: "citations", "Rejected reference [{}]", label);
+                    check.rejected.push(label);
+                }
+            }
+        }
+
+        check.text.push_str(&text[last..marker.start()]);
+        if !kept.is_empty() {
+            let leading_space = if marker.as_str().starts_with(char::is_whitespace) {
+                " "
+            } else {
+                ""
+            };
+            check
+                .text
+                .push_str(&format!("{}[{}]", leading_space, kept.join(", ")));
+        }
+        last = marker.end();
+    }
+    check.text.push_str(&text[last..]);
+
+    check
+}
+
+/// The sentence a marker is attached to: text since the previous sentence
+/// end, or the sentence just closed when the marker follows the full stop.
+fn citing_sentence(before: &str) -> &str {
+    let trimmed = before.trim_end();
+    let body = trimmed.trim_end_matches(['.', '!', '?']);
+    let start = body
+        .rfind(['.', '!', '?', '\n', ']'])
+        .map(|i| i + 1)
+        .unwrap_or(0);
+    body[start..].trim()
+}
+
+fn content_words(text: &str) -> BTreeSet<String> {
+    const STOP_WORDS: &[&str] = &[
+        "about", "after", "also", "because", "been", "before", "being", "could", "does", "from",
+        "have", "into", "just", "like", "more", "most", "only", "other", "some", "than", "that",
+        "their", "them", "then", "there", "these", "they", "this", "what", "when", "where",
+        "which", "while", "will", "with", "would", "your",
+    ];
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| w.chars().count() > 3)
+        .map(str::to_lowercase)
+        .filter(|w| !STOP_WORDS.contains(&w.as_str()))
+        .map(|w| w.trim_end_matches('s').to_string())
+        .collect()
+}
+
+/// A reference is supported when the citing sentence shares a content word
+/// with the source. Bare markers (no sentence) are taken at face value.
+fn is_supported(sentence: &str, source: &str) -> bool {
+    let claim = content_words(sentence);
+    claim.is_empty() || !claim.is_disjoint(&content_words(source))
+}
+
+fn citation(label: &str, sentence: &str, chunk: &KnowledgeChunk) -> Citation {
+    Citation {
+        label: label.to_string(),
+        chunk_id: chunk.id.clone(),
+        document_id: chunk.document_id.clone(),
+        title: chunk.title.clone(),
+        page: chunk.page,
+        heading: chunk.heading.clone(),
+        snippet: best_snippet(sentence, &chunk.content),
+    }
+}
+
+/// The source sentence with the most content words in common with the claim
+fn best_snippet(sentence: &str, source: &str) -> String {
+    let claim = content_words(sentence);
+    let best = source
+        .split_inclusive(['.', '!', '?'])
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .enumerate()
+        .max_by_key(|(idx, candidate)| {
+            let overlap = content_words(candidate).intersection(&claim).count();
+            // Prefer earlier sentences on ties
+            (overlap, std::cmp::Reverse(*idx))
+        })
+        .map(|(_, s)| s)
+        .unwrap_or_else(|| source.trim());
+    truncate_chars(best, MAX_SNIPPET_CHARS)
+}
+
+fn truncate_chars(text: &str, max: usize) -> String {
+    match text.char_indices().nth(max) {
+        Some((end, _)) => format!("{}…", text[..end].trim_end()),
+        None => text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(id: &str, title: &str, content: &str, page: Option<u32>) -> KnowledgeChunk {
+        KnowledgeChunk {
+            id: id.to_string(),
+            title: title.to_string(),
+            content: content.to_string(),
+            source_type: "pdf".to_string(),
+            relevance_score: 1.0,
+            document_id: Some("doc-1".to_string()),
+            page,
+            heading: Some("Newton's Laws".to_string()),
+        }
+    }
+
+    fn sources() -> Vec<KnowledgeChunk> {
+        vec![
+            chunk(
+                "c1",
+                "Physics Primer",
+                "Inertia keeps a train moving. Momentum is mass times velocity.",
+                Some(2),
+            ),
+            chunk(
+                "c2",
+                "Track Safety",
+                "Friction between wheel and rail lets a locomotive brake.",
+                Some(7),
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_verified_citation_carries_provenance() {
+        let check = resolve_citations(
+            "Think about how momentum depends on velocity [S1]. What changes when it slows?",
+            &sources(),
+        );
+        assert_eq!(check.citations.len(), 1);
+        let citation = &check.citations[0];
+        assert_eq!(citation.label, "S1");
+        assert_eq!(citation.chunk_id, "c1");
+        assert_eq!(citation.document_id.as_deref(), Some("doc-1"));
+        assert_eq!(citation.page, Some(2));
+        assert_eq!(citation.snippet, "Momentum is mass times velocity.");
+        assert!(check.text.contains("[S1]"));
+        assert!(check.rejected.is_empty());
+    }
+
+    #[test]
+    fn test_unknown_label_is_removed() {
+        let check = resolve_citations("Friction helps the locomotive brake [S9]?", &sources());
+        assert!(check.citations.is_empty());
+        assert_eq!(check.rejected, vec!["S9"]);
+        assert_eq!(check.text, "Friction helps the locomotive brake?");
+    }
+
+    #[test]
+    fn test_unsupported_reference_is_removed() {
+        // S1 talks about momentum, not friction
+        let check = resolve_citations("Friction lets the locomotive brake. [S1, S2]", &sources());
+        assert_eq!(check.rejected, vec!["S1"]);
+        assert_eq!(check.citations.len(), 1);
+        assert_eq!(check.citations[0].chunk_id, "c2");
+        assert_eq!(check.text, "Friction lets the locomotive brake. [S2]");
+    }
+
+    #[test]
+    fn test_repeated_references_are_cited_once() {
+        let check = resolve_citations(
+            "Inertia keeps it moving [Source 1]. Momentum grows with velocity [s1].",
+            &sources(),
+        );
+        assert_eq!(check.citations.len(), 1);
+        assert_eq!(check.text.matches("[S1]").count(), 2);
+    }
+
+    #[test]
+    fn test_reply_without_markers_is_unchanged() {
+        let text = "What do you notice about the train?";
+        let check = resolve_citations(text, &sources());
+        assert_eq!(check.text, text);
+        assert!(check.citations.is_empty());
+    }
+}
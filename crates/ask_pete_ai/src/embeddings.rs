@@ -0,0 +1,343 @@
+//! # Sentence Embeddings (Local, Candle)
+//!
+//! Runs a BERT-family sentence encoder (all-MiniLM-L6-v2, bge-small-en-v1.5, ...)
+//! on the local device and implements `infra_db::Embedder`, so the vector store
+//! never sends text off the machine.
+//!
+//! A model directory must contain `config.json`, `tokenizer.json` and
+//! `model.safetensors`, i.e. a HuggingFace snapshot as cached by the model manager.
+
+use crate::error::{AiError, Result};
+use candle_core::{Device, Tensor, D};
+use candle_nn::VarBuilder;
+use candle_transformers::models::bert::{BertModel, Config as BertConfig, DTYPE};
+use infra_db::vector_store::Embedder;
+use std::path::{Path, PathBuf};
+use tokenizers::{PaddingParams, PaddingStrategy, Tokenizer, TruncationParams};
+
+/// How token vectors are pooled into one sentence vector.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pooling {
+    /// Attention-masked mean (MiniLM / sentence-transformers)
+    Mean,
+    /// The `[CLS]` token (BGE)
+    Cls,
+}
+
+#[derive(Debug, Clone)]
+pub struct EmbeddingConfig {
+    pub model_dir: PathBuf,
+    /// Written to the vector store manifest, e.g. "sentence-transformers/all-MiniLM-L6-v2"
+    pub model_id: String,
+    pub pooling: Pooling,
+    pub max_length: usize,
+    pub batch_size: usize,
+}
+
+impl EmbeddingConfig {
+    pub const MINILM: &'static str = "sentence-transformers/all-MiniLM-L6-v2";
+    pub const BGE_SMALL: &'static str = "BAAI/bge-small-en-v1.5";
+
+    pub fn new(model_id: &str, model_dir: impl Into<PathBuf>) -> Self {
+        // BGE models are trained for CLS pooling; sentence-transformers for mean
+        let pooling = if model_id.to_lowercase().contains("bge") {
+            Pooling::Cls
+        } else {
+            Pooling::Mean
+        };
+        Self {
+            model_dir: model_dir.into(),
+            model_id: model_id.to_string(),
+            pooling,
+            max_length: 256,
+            batch_size: 32,
+        }
+    }
+
+    /// Find a cached HuggingFace snapshot of `model_id` under `cache_dir`
+    /// (layout: `models--{org}--{name}/snapshots/{revision}/`).
+    pub fn from_hf_cache(cache_dir: impl AsRef<Path>, model_id: &str) -> Option<Self> {
+        let repo_dir = cache_dir
+            .as_ref()
+            .join(format!("models--{}", model_id.replace('/', "--")))
+            .join("snapshots");
+        std::fs::read_dir(repo_dir)
+            .ok()?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .find(|dir| has_model_files(dir))
+            .map(|dir| Self::new(model_id, dir))
+    }
+}
+
+fn has_model_files(dir: &Path) -> bool {
+    ["config.json", "tokenizer.json", "model.safetensors"]
+        .iter()
+        .all(|file| dir.join(file).exists())
+}
+
+pub struct CandleEmbedder {
+    model: BertModel,
+    tokenizer: Tokenizer,
+    device: Device,
+    config: EmbeddingConfig,
+    dimension: usize,
+}
+
+impl CandleEmbedder {
+    pub fn load(config: EmbeddingConfig) -> Result<Self> {
+        log::info!(
+            "Loading embedding model {} from {:?}",
+            config.model_id,
+            config.model_dir
+        );
+        if !has_model_files(&config.model_dir) {
+            return Err(AiError::ModelLoadFailed(format!(
+                "{:?} needs config.json, tokenizer.json and model.safetensors",
+                config.model_dir
+            )));
+        }
+
+        // Small encoders are fast enough on CPU and keep the GPU free for generation
+        let device = Device::Cpu;
+
+        let raw_config: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(
+            config.model_dir.join("config.json"),
+        )?)
+        .map_err(|e| AiError::ModelLoadFailed(format!("Invalid config.json: {}", e)))?;
+        // candle keeps `hidden_size` private, so the dimension comes from the raw config
+        let dimension = raw_config["hidden_size"]
+            .as_u64()
+            .ok_or_else(|| AiError::ModelLoadFailed("config.json has no hidden_size".to_string()))?
+            as usize;
+        let bert_config: BertConfig = serde_json::from_value(raw_config)
+            .map_err(|e| AiError::ModelLoadFailed(format!("Invalid config.json: {}", e)))?;
+
+        let weights = config.model_dir.join("model.safetensors");
+        // SAFETY: the weights file is memory-mapped read-only and not modified while loaded
+        let vb = unsafe { VarBuilder::from_mmaped_safetensors(&[weights], DTYPE, &device)? };
+        let model = BertModel::load(vb, &bert_config)?;
+
+        let mut tokenizer = Tokenizer::from_file(config.model_dir.join("tokenizer.json"))
+            .map_err(|e| AiError::ModelLoadFailed(format!("Failed to load tokenizer: {}", e)))?;
+        tokenizer.with_padding(Some(PaddingParams {
+            strategy: PaddingStrategy::BatchLongest,
+            ..Default::default()
+        }));
+        tokenizer
+            .with_truncation(Some(TruncationParams {
+                max_length: config.max_length,
+                ..Default::default()
+            }))
+            .map_err(|e| AiError::TokenizationFailed(e.to_string()))?;
+
+        log::info!("✅ Embedding model loaded ({} dims)", dimension);
+
+        Ok(Self {
+            model,
+            tokenizer,
+            device,
+            dimension,
+            config,
+        })
+    }
+
+    fn embed_batch(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>> {
+        let encodings = self
+            .tokenizer
+            .encode_batch(texts.to_vec(), true)
+            .map_err(|e| AiError::TokenizationFailed(e.to_string()))?;
+
+        let ids: Vec<Tensor> = encodings
+            .iter()
+            .map(|e| Tensor::new(e.get_ids(), &self.device))
+            .collect::<candle_core::Result<_>>()?;
+        let masks: Vec<Tensor> = encodings
+            .iter()
+            .map(|e| Tensor::new(e.get_attention_mask(), &self.device))
+            .collect::<candle_core::Result<_>>()?;
+
+        let input_ids = Tensor::stack(&ids, 0)?;
+        let attention_mask = Tensor::stack(&masks, 0)?;
+        let token_type_ids = input_ids.zeros_like()?;
+
+        // (batch, seq, hidden)
+        let hidden = self
+            .model
+            .forward(&input_ids, &token_type_ids, Some(&attention_mask))?;
+
+        let pooled = match self.config.pooling {
+            Pooling::Cls => hidden.narrow(1, 0, 1)?.squeeze(1)?,
+            Pooling::Mean => {
+                let mask = attention_mask.to_dtype(DTYPE)?.unsqueeze(2)?;
+                let summed = hidden.broadcast_mul(&mask)?.sum(1)?;
+                let counts = mask.sum(1)?.clamp(1e-9, f64::MAX)?;
+                summed.broadcast_div(&counts)?
+            }
+        };
+
+        let norms = pooled.sqr()?.sum_keepdim(D::Minus1)?.sqrt()?;
+        let normalized = pooled.broadcast_div(&norms.clamp(1e-12, f64::MAX)?)?;
+        Ok(normalized.to_vec2::<f32>()?)
+    }
+}
+
+impl Embedder for CandleEmbedder {
+    fn model_id(&self) -> &str {
+        &self.config.model_id
+    }
+
+    fn dimension(&self) -> usize {
+        self.dimension
+    }
+
+    fn embed(&self, texts: &[&str]) -> anyhow::Result<Vec<Vec<f32>>> {
+        let mut vectors = Vec::with_capacity(texts.len());
+        for batch in texts.chunks(self.config.batch_size.max(1)) {
+            vectors.extend(self.embed_batch(batch)?);
+        }
+        Ok(vectors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use candle_core::DType;
+    use candle_nn::VarMap;
+
+    const WORDS: &[&str] = &[
+        "[PAD]", "[UNK]", "[CLS]", "[SEP]", "torque", "is", "force", "times", "lever", "arm",
+        "energy", "heat",
+    ];
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("pete_embed_{}_{}", name, uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// A two-layer BERT with random weights and a word-level tokenizer: enough
+    /// to exercise loading, batching and pooling without downloading a model.
+    fn tiny_model_dir(name: &str) -> PathBuf {
+        let dir = temp_dir(name);
+        let config = serde_json::json!({
+            "vocab_size": WORDS.len(),
+            "hidden_size": 16,
+            "num_hidden_layers": 2,
+            "num_attention_heads": 2,
+            "intermediate_size": 32,
+            "hidden_act": "gelu",
+            "hidden_dropout_prob": 0.0,
+            "max_position_embeddings": 32,
+            "type_vocab_size": 2,
+            "initializer_range": 0.02,
+            "layer_norm_eps": 1e-12,
+            "pad_token_id": 0,
+            "model_type": "bert"
+        });
+        std::fs::write(dir.join("config.json"), config.to_string()).unwrap();
+
+        let bert_config: BertConfig = serde_json::from_value(config).unwrap();
+        let varmap = VarMap::new();
+        let vb = VarBuilder::from_varmap(&varmap, DType::F32, &Device::Cpu);
+        BertModel::load(vb, &bert_config).unwrap();
+        varmap.save(dir.join("model.safetensors")).unwrap();
+
+        let vocab: serde_json::Map<String, serde_json::Value> = WORDS
+            .iter()
+            .enumerate()
+            .map(|(id, word)| (word.to_string(), id.into()))
+            .collect();
+        let tokenizer = serde_json::json!({
+            "version": "1.0",
+            "truncation": null,
+            "padding": null,
+            "added_tokens": [],
+            "normalizer": null,
+            "pre_tokenizer": { "type": "Whitespace" },
+            "post_processor": null,
+            "decoder": null,
+            "model": { "type": "WordLevel", "vocab": vocab, "unk_token": "[UNK]" }
+        });
+        std::fs::write(dir.join("tokenizer.json"), tokenizer.to_string()).unwrap();
+        dir
+    }
+
+    fn assert_close(a: &[f32], b: &[f32]) {
+        assert_eq!(a.len(), b.len());
+        for (x, y) in a.iter().zip(b) {
+            assert!((x - y).abs() < 1e-4, "{:?} != {:?}", a, b);
+        }
+    }
+
+    #[test]
+    fn test_pooling_follows_model_family() {
+        assert_eq!(
+            EmbeddingConfig::new(EmbeddingConfig::MINILM, "m").pooling,
+            Pooling::Mean
+        );
+        assert_eq!(
+            EmbeddingConfig::new(EmbeddingConfig::BGE_SMALL, "m").pooling,
+            Pooling::Cls
+        );
+    }
+
+    #[test]
+    fn test_from_hf_cache_finds_complete_snapshot() {
+        let cache = temp_dir("cache");
+        let snapshots = cache.join("models--BAAI--bge-small-en-v1.5/snapshots");
+        let partial = snapshots.join("aaa");
+        let complete = snapshots.join("bbb");
+        for dir in [&partial, &complete] {
+            std::fs::create_dir_all(dir).unwrap();
+            std::fs::write(dir.join("config.json"), "{}").unwrap();
+        }
+        std::fs::write(complete.join("tokenizer.json"), "{}").unwrap();
+        std::fs::write(complete.join("model.safetensors"), "").unwrap();
+
+        let config = EmbeddingConfig::from_hf_cache(&cache, EmbeddingConfig::BGE_SMALL).unwrap();
+        assert_eq!(config.model_dir, complete);
+        assert_eq!(config.model_id, EmbeddingConfig::BGE_SMALL);
+        assert!(EmbeddingConfig::from_hf_cache(&cache, EmbeddingConfig::MINILM).is_none());
+        let _ = std::fs::remove_dir_all(cache);
+    }
+
+    #[test]
+    fn test_load_rejects_incomplete_model_dir() {
+        let dir = temp_dir("incomplete");
+        std::fs::write(dir.join("config.json"), "{}").unwrap();
+        let result = CandleEmbedder::load(EmbeddingConfig::new(EmbeddingConfig::MINILM, &dir));
+        assert!(matches!(result, Err(AiError::ModelLoadFailed(_))));
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_embeddings_are_normalized_and_batch_independent() {
+        let dir = tiny_model_dir("tiny");
+        for model_id in [EmbeddingConfig::MINILM, EmbeddingConfig::BGE_SMALL] {
+            let mut config = EmbeddingConfig::new(model_id, &dir);
+            config.batch_size = 2;
+            let embedder = CandleEmbedder::load(config).unwrap();
+            assert_eq!(embedder.model_id(), model_id);
+            assert_eq!(embedder.dimension(), 16);
+
+            let texts = ["torque", "torque is force times lever arm", "energy heat"];
+            let vectors = embedder.embed(&texts).unwrap();
+            assert_eq!(vectors.len(), 3);
+            for vector in &vectors {
+                assert_eq!(vector.len(), 16);
+                let norm = vector.iter().map(|v| v * v).sum::<f32>().sqrt();
+                assert!((norm - 1.0).abs() < 1e-4, "norm {}", norm);
+            }
+
+            // Padding next to a longer text must not change a short text's vector
+            let alone = embedder.embed(&["torque"]).unwrap();
+            assert_close(&alone[0], &vectors[0]);
+            // The third text ran in its own batch
+            let last = embedder.embed(&["energy heat"]).unwrap();
+            assert_close(&last[0], &vectors[2]);
+        }
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
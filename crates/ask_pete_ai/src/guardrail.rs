@@ -0,0 +1,478 @@
+//! # Answer-Leak Guardrail (The Signal Box)
+//!
+//! Pete is a Socratic guide: he should never hand a student the answer to the
+//! station they are working on. This stage inspects each draft response
+//! against the current node's answer key and flags:
+//!
+//! - **Direct answers**: the response contains a quiz key verbatim.
+//! - **Rubric leaks**: the response restates most of the completion criteria.
+//! - **Worked solutions**: step-by-step derivations or "the answer is ...".
+//! - **Definitions**: the response defines a target vocabulary word.
+//!
+//! Flagged drafts are rewritten into a guiding question (by a second model
+//! pass when available, otherwise from a template) and every intervention is
+//! logged for researchers.
+
+use chrono::{DateTime, Utc};
+use pete_core::trainyard::{StationType, StoryNode};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::collections::{BTreeSet, VecDeque};
+use uuid::Uuid;
+
+/// What the guardrail checks a draft against, built from the current node.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct AnswerKey {
+    pub node_id: String,
+    #[serde(default)]
+    pub completion_criteria: String,
+    #[serde(default)]
+    pub quiz_answers: Vec<String>,
+    #[serde(default)]
+    pub target_vocabulary: Vec<String>,
+}
+
+impl AnswerKey {
+    /// Build an answer key from a Trainyard station.
+    ///
+    /// Lines in the completion criteria starting with `Answer:` or `Key:` are
+    /// treated as quiz keys. On Quiz stations the whole rubric is also a key.
+    pub fn from_node(node: &StoryNode, target_vocabulary: Vec<String>) -> Self {
+        let mut quiz_answers: Vec<String> = node
+            .completion_criteria
+            .lines()
+            .filter_map(|line| {
+                let line = line.trim();
+                // Compare bytes in place: lowercasing can change the length
+                // of non-ASCII text, which would shift the slice point
+                ["answer:", "key:"]
+                    .iter()
+                    .find(|prefix| {
+                        line.get(..prefix.len())
+                            .is_some_and(|p| p.eq_ignore_ascii_case(prefix))
+                    })
+                    .map(|prefix| line[prefix.len()..].trim().to_string())
+            })
+            .filter(|answer| !answer.is_empty())
+            .collect();
+
+        if node.station_type == StationType::Quiz && quiz_answers.is_empty() {
+            let rubric = node.completion_criteria.trim();
+            if !rubric.is_empty() {
+                quiz_answers.push(rubric.to_string());
+            }
+        }
+
+        Self {
+            node_id: node.id.clone(),
+            completion_criteria: node.completion_criteria.clone(),
+            quiz_answers,
+            target_vocabulary,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.completion_criteria.trim().is_empty()
+            && self.quiz_answers.is_empty()
+            && self.target_vocabulary.is_empty()
+    }
+}
+
+/// The kind of leak detected in a draft.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum LeakKind {
+    DirectAnswer { answer: String },
+    RubricRestated,
+    WorkedSolution,
+    Definition { word: String },
+}
+
+/// How a leaking draft was repaired.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RewriteMethod {
+    ModelPass,
+    Template,
+}
+
+/// A single guardrail intervention, kept for researcher review.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GuardrailIntervention {
+    pub id: Uuid,
+    pub session_id: Uuid,
+    pub user_id: i64,
+    pub node_id: String,
+    pub leaks: Vec<LeakKind>,
+    pub original: String,
+    pub rewritten: String,
+    pub method: RewriteMethod,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// How many interventions are kept in memory when no database is connected.
+const MAX_IN_MEMORY_INTERVENTIONS: usize = 500;
+
+/// Detects answer leaks and keeps the intervention log.
+pub struct AnswerGuardrail {
+    worked_solution_patterns: Vec<Regex>,
+    log: VecDeque<GuardrailIntervention>,
+}
+
+impl Default for AnswerGuardrail {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AnswerGuardrail {
+    pub fn new() -> Self {
+        let worked_solution_patterns = [
+            r"(?i)\bthe (correct )?(answer|solution|result) (is|would be|=)",
+            r"(?i)\bstep\s*(1|one)\b",
+            r"(?i)\bfirst,?\s.+\bthen,?\s.+\b(finally|so|therefore)\b",
+            r"(?i)\b(therefore|thus|so),? (the )?(answer|result|solution)\b",
+            // A chain of arithmetic like "F = 10 * 2 = 20"
+            r"[=]\s*[-\d.]+\s*[*/x×+\-]\s*[-\d.]+\s*=\s*[-\d.]+",
+            r"(?i)\bplug(ging)? (in|it into)\b.+\bget\b",
+        ]
+        .iter()
+        .map(|p| Regex::new(p).expect("valid guardrail pattern"))
+        .collect();
+
+        Self {
+            worked_solution_patterns,
+            log: VecDeque::new(),
+        }
+    }
+
+    /// Inspect a draft against the answer key. An empty result means it is safe.
+    pub fn detect(&self, draft: &str, key: &AnswerKey) -> Vec<LeakKind> {
+        let mut leaks = Vec::new();
+        let normalized_draft = normalize(draft);
+
+        for answer in &key.quiz_answers {
+            let normalized_answer = normalize(answer);
+            if normalized_answer.len() >= 2
+                && contains_phrase(&normalized_draft, &normalized_answer)
+            {
+                leaks.push(LeakKind::DirectAnswer {
+                    answer: answer.clone(),
+                });
+            }
+        }
+
+        if restates_rubric(draft, &key.completion_criteria) {
+            leaks.push(LeakKind::RubricRestated);
+        }
+
+        if self
+            .worked_solution_patterns
+            .iter()
+            .any(|pattern| pattern.is_match(draft))
+        {
+            leaks.push(LeakKind::WorkedSolution);
+        }
+
+        for word in &key.target_vocabulary {
+            if defines_word(draft, word) {
+                leaks.push(LeakKind::Definition { word: word.clone() });
+            }
+        }
+
+        leaks
+    }
+
+    /// Prompt for the second model pass that turns a leaking draft into a question.
+    pub fn rewrite_prompt(&self, draft: &str, leaks: &[LeakKind], student_message: &str) -> String {
+        let reasons: Vec<String> = leaks.iter().map(describe_leak).collect();
+        format!(
+            "You are Pete, a Socratic guide. The draft reply below gives away the answer ({}).\n\
+             Rewrite it as ONE short guiding question that helps the learner work it out themselves.\n\
+             Do not state the answer, show worked steps, or define the key terms.\n\n\
+             Learner said: \"{}\"\n\nDraft reply: \"{}\"\n\nRewritten question:",
+            reasons.join("; "),
+            student_message,
+            draft
+        )
+    }
+
+    /// Deterministic fallback when no model is available or the rewrite still leaks.
+    pub fn template_rewrite(&self, leaks: &[LeakKind], student_message: &str) -> String {
+        let topic = leaks.iter().find_map(|leak| match leak {
+            LeakKind::Definition { word } => Some(word.clone()),
+            _ => None,
+        });
+        match (leaks.first(), topic) {
+            (_, Some(word)) => format!(
+                "Before we pin down a definition, how would you describe \"{}\" in your own words, based on what you've seen so far?",
+                word
+            ),
+            (Some(LeakKind::WorkedSolution), _) => {
+                "What would be your first step here, and why do you think it comes first?"
+                    .to_string()
+            }
+            _ if student_message.trim().is_empty() => {
+                "What do you already know that could help you reach this station's goal?"
+                    .to_string()
+            }
+            _ => format!(
+                "Let's reason it through together: when you said \"{}\", what made you think that, and what would you check next?",
+                truncate_words(student_message, 20)
+            ),
+        }
+    }
+
+    /// Record an intervention: log it, keep it in memory and persist it if a pool is given.
+    pub async fn record(&mut self, intervention: GuardrailIntervention, pool: Option<&PgPool>) {
+        log::info!(
+            target: "guardrail",
+            "Answer-leak intervention on node '{}' (session {}): {:?} via {:?}",
+            intervention.node_id,
+            intervention.session_id,
+            intervention.leaks,
+            intervention.method
+        );
+
+        if let Some(pool) = pool {
+            let leaks_json = serde_json::to_value(&intervention.leaks).unwrap_or_default();
+            let method = match intervention.method {
+                RewriteMethod::ModelPass => "model_pass",
+                RewriteMethod::Template => "template",
+            };
+            let result = sqlx::query(
+                r#"
+                INSERT INTO guardrail_interventions
+                (id, session_id, user_id, node_id, leaks, original_response, rewritten_response, method, created_at)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+                "#,
+            )
+            .bind(intervention.id)
+            .bind(intervention.session_id)
+            .bind(intervention.user_id)
+            .bind(&intervention.node_id)
+            .bind(leaks_json)
+            .bind(&intervention.original)
+            .bind(&intervention.rewritten)
+            .bind(method)
+            .bind(intervention.timestamp)
+            .execute(pool)
+            .await;
+            if let Err(e) = result {
+                log::warn!("Failed to persist guardrail intervention: {}", e);
+            }
+        }
+
+        if self.log.len() >= MAX_IN_MEMORY_INTERVENTIONS {
+            self.log.pop_front();
+        }
+        self.log.push_back(intervention);
+    }
+
+    /// Most recent interventions, newest last.
+    pub fn interventions(&self) -> impl Iterator<Item = &GuardrailIntervention> {
+        self.log.iter()
+    }
+}
+
+fn describe_leak(leak: &LeakKind) -> String {
+    match leak {
+        LeakKind::DirectAnswer { answer } => format!("states the quiz answer \"{}\"", answer),
+        LeakKind::RubricRestated => "restates the completion criteria".to_string(),
+        LeakKind::WorkedSolution => "walks through a worked solution".to_string(),
+        LeakKind::Definition { word } => format!("defines the target word \"{}\"", word),
+    }
+}
+
+fn normalize(text: &str) -> String {
+    text.to_lowercase()
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '.' {
+                c
+            } else {
+                ' '
+            }
+        })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Phrase match on word boundaries, so "4" does not match "42".
+fn contains_phrase(haystack: &str, phrase: &str) -> bool {
+    let padded = format!(" {} ", haystack.replace('.', " "));
+    let needle = format!(" {} ", phrase.trim_end_matches('.').replace('.', " "));
+    padded.contains(&needle)
+}
+
+const RUBRIC_STOP_WORDS: &[&str] = &[
+    "the", "a", "an", "and", "or", "of", "to", "in", "is", "are", "that", "this", "for", "with",
+    "on", "be", "student", "students", "learner", "should", "must", "can", "will", "their", "it",
+    "explain", "describe", "identify", "answer", "key",
+];
+
+fn content_terms(text: &str) -> BTreeSet<String> {
+    normalize(text)
+        .split_whitespace()
+        .filter(|w| w.len() > 2 && !RUBRIC_STOP_WORDS.contains(w))
+        .map(|w| w.trim_end_matches('.').to_string())
+        .collect()
+}
+
+/// A declarative draft that covers most of the rubric's content terms is a leak.
+fn restates_rubric(draft: &str, criteria: &str) -> bool {
+    let rubric = content_terms(criteria);
+    if rubric.len() < 3 {
+        return false;
+    }
+    // Only count declarative sentences; questions about the rubric are fine.
+    let statements: String = draft
+        .split_inclusive(['.', '!', '?'])
+        .filter(|s| !s.trim_end().ends_with('?'))
+        .collect();
+    let covered = content_terms(&statements).intersection(&rubric).count();
+    covered * 10 >= rubric.len() * 6
+}
+
+fn defines_word(draft: &str, word: &str) -> bool {
+    let word = word.trim();
+    if word.is_empty() {
+        return false;
+    }
+    let pattern = format!(
+        r"(?i)\b{w}\b\s*(is|are|means|refers to|is defined as|can be defined as)\s+(a|an|the|when|how)?\b|\b{w}\b\s*[:\-–]\s+\w|\bdefin(e|ition of)\s+\b{w}\b\s+(is|as)\b",
+        w = regex::escape(word)
+    );
+    Regex::new(&pattern)
+        .map(|re| {
+            draft
+                .split_inclusive(['.', '!', '?'])
+                .filter(|s| !s.trim_end().ends_with('?'))
+                .any(|s| re.is_match(s))
+        })
+        .unwrap_or(false)
+}
+
+fn truncate_words(text: &str, max: usize) -> String {
+    let words: Vec<&str> = text.split_whitespace().collect();
+    if words.len() <= max {
+        words.join(" ")
+    } else {
+        format!("{}...", words[..max].join(" "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key() -> AnswerKey {
+        AnswerKey {
+            node_id: "quiz_1".to_string(),
+            completion_criteria: "Student explains that momentum equals mass times velocity and is conserved in collisions".to_string(),
+            quiz_answers: vec!["42 kg m/s".to_string()],
+            target_vocabulary: vec!["momentum".to_string()],
+        }
+    }
+
+    #[test]
+    fn test_direct_answer_detected() {
+        let leaks = AnswerGuardrail::new().detect("Nice work! It's 42 kg m/s.", &key());
+        assert!(leaks.contains(&LeakKind::DirectAnswer {
+            answer: "42 kg m/s".to_string()
+        }));
+    }
+
+    #[test]
+    fn test_worked_solution_detected() {
+        let leaks = AnswerGuardrail::new().detect(
+            "Multiply them: p = 6 * 7 = 42. So the answer is clear.",
+            &AnswerKey::default(),
+        );
+        assert!(leaks.contains(&LeakKind::WorkedSolution));
+    }
+
+    #[test]
+    fn test_definition_detected() {
+        let leaks = AnswerGuardrail::new().detect(
+            "Momentum is the product of an object's mass and velocity.",
+            &key(),
+        );
+        assert!(leaks.contains(&LeakKind::Definition {
+            word: "momentum".to_string()
+        }));
+    }
+
+    #[test]
+    fn test_rubric_restatement_detected() {
+        let leaks = AnswerGuardrail::new().detect(
+            "Momentum equals mass times velocity, and it is conserved in collisions.",
+            &key(),
+        );
+        assert!(leaks.contains(&LeakKind::RubricRestated));
+    }
+
+    #[test]
+    fn test_guiding_question_passes() {
+        let leaks = AnswerGuardrail::new().detect(
+            "What do you notice about how the heavier cart moves after the collision? How might momentum relate to that?",
+            &key(),
+        );
+        assert!(leaks.is_empty(), "unexpected leaks: {:?}", leaks);
+    }
+
+    #[test]
+    fn test_answer_key_from_quiz_node() {
+        let node = StoryNode {
+            id: "n1".to_string(),
+            title: "Quiz".to_string(),
+            content: String::new(),
+            x: 0.0,
+            y: 0.0,
+            station_type: StationType::Quiz,
+            passenger_count: 0,
+            complexity_level: 1,
+            context_prompt: String::new(),
+            completion_criteria: "Explain inertia.\nAnswer: Newton's First Law".to_string(),
+            required_stats: Default::default(),
+            logic: Default::default(),
+            style: Default::default(),
+        };
+        let key = AnswerKey::from_node(&node, vec![]);
+        assert_eq!(key.quiz_answers, vec!["Newton's First Law".to_string()]);
+    }
+
+    #[test]
+    fn test_answer_key_prefix_ignores_non_ascii_lookalikes() {
+        let node = StoryNode {
+            id: "n2".to_string(),
+            title: "Lesson".to_string(),
+            content: String::new(),
+            x: 0.0,
+            y: 0.0,
+            station_type: StationType::Lesson,
+            passenger_count: 0,
+            complexity_level: 1,
+            context_prompt: String::new(),
+            // U+212A KELVIN SIGN lowercases to an ASCII 'k' of a different length
+            completion_criteria: "\u{212A}ey: not a key\né\nKEY: Momentum".to_string(),
+            required_stats: Default::default(),
+            logic: Default::default(),
+            style: Default::default(),
+        };
+        let key = AnswerKey::from_node(&node, vec![]);
+        assert_eq!(key.quiz_answers, vec!["Momentum".to_string()]);
+    }
+
+    #[test]
+    fn test_template_rewrite_is_a_question() {
+        let guardrail = AnswerGuardrail::new();
+        let rewritten =
+            guardrail.template_rewrite(&[LeakKind::WorkedSolution], "how do I solve it");
+        assert!(rewritten.ends_with('?'));
+        assert!(guardrail.detect(&rewritten, &key()).is_empty());
+    }
+}
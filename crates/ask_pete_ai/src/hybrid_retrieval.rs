@@ -0,0 +1,775 @@
+//! # Hybrid Retrieval (The Switchyard)
+//!
+//! Keyword search misses paraphrases ("how hard the brakes grip" vs "friction"),
+//! while embeddings blur exact technical terms ("F=ma", "Hooke's law"). The
+//! `HybridRetriever` runs both and merges them:
+//!
+//! 1. **BM25**: an in-process inverted index over the same chunks as the vector
+//!    store. Formula-like tokens (`F=ma`, `E=mc^2`) are also indexed whole.
+//! 2. **Vectors**: cosine search in the wrapped `VectorStore`.
+//! 3. **Reciprocal-rank fusion**: `score(d) = Σ 1 / (k + rank(d))` over both
+//!    rankings, so neither score scale has to be calibrated.
+//! 4. **Reranking** (optional): a CPU cross-encoder rescores the fused
+//!    candidates against the query (see `reranker`).
+//!
+//! Both stages honour the same `MetadataFilter`, which is how a course only
+//! sees its own corpus (`course_id` in chunk metadata).
+//!
+//! The retriever is itself a `VectorStore`: adds and deletes go to both
+//! indexes, and `search_filtered` returns the fused ranking. Built with
+//! `keyword_only` (the index's embedding model isn't loaded) it serves BM25
+//! alone and refuses writes, which could not reach the vectors. `evaluate`
+//! reports recall@k and MRR on a labeled question set (see the
+//! `retrieval_eval` binary).
+
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use infra_db::vector_store::{Document, MetadataFilter, ScoredDocument, VectorStore};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+// ============================================================================
+// BM25 inverted index
+// ============================================================================
+
+const STOP_WORDS: &[&str] = &[
+    "a", "an", "and", "are", "as", "at", "be", "by", "does", "for", "from", "how", "in", "is",
+    "it", "its", "of", "on", "or", "that", "the", "this", "to", "was", "what", "when", "which",
+    "why", "with",
+];
+
+/// Lowercased word tokens without stop words. Whitespace-separated tokens that
+/// contain `=` or `^` (formulas) are kept whole as an extra term.
+pub fn tokenize(text: &str) -> Vec<String> {
+    let mut terms = Vec::new();
+    for raw in text.split_whitespace() {
+        let raw = raw.to_lowercase();
+        if raw.contains(['=', '^']) {
+            let formula = raw.trim_matches(|c: char| !c.is_alphanumeric() && c != '^');
+            if !formula.is_empty() {
+                terms.push(formula.to_string());
+            }
+        }
+        terms.extend(
+            raw.split(|c: char| !c.is_alphanumeric())
+                .filter(|w| !w.is_empty() && !STOP_WORDS.contains(w))
+                .map(str::to_string),
+        );
+    }
+    terms
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Bm25Config {
+    /// Term-frequency saturation
+    pub k1: f32,
+    /// Length normalisation (0 = none, 1 = full)
+    pub b: f32,
+}
+
+impl Default for Bm25Config {
+    fn default() -> Self {
+        Self { k1: 1.2, b: 0.75 }
+    }
+}
+
+struct IndexedDoc {
+    document: Document,
+    metadata: Value,
+    terms: HashMap<String, u32>,
+    len: usize,
+}
+
+/// In-memory BM25 index keyed by document id.
+#[derive(Default)]
+pub struct Bm25Index {
+    config: Bm25Config,
+    docs: HashMap<String, IndexedDoc>,
+    /// term -> number of documents containing it
+    doc_freq: HashMap<String, usize>,
+    total_len: usize,
+}
+
+impl Bm25Index {
+    pub fn new(config: Bm25Config) -> Self {
+        Self {
+            config,
+            ..Default::default()
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.docs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.docs.is_empty()
+    }
+
+    /// Insert or replace a document.
+    pub fn insert(&mut self, document: Document) {
+        self.remove(&document.id);
+
+        let tokens = tokenize(&document.text);
+        let mut terms: HashMap<String, u32> = HashMap::new();
+        for token in &tokens {
+            *terms.entry(token.clone()).or_default() += 1;
+        }
+        for term in terms.keys() {
+            *self.doc_freq.entry(term.clone()).or_default() += 1;
+        }
+        self.total_len += tokens.len();
+
+        let metadata = serde_json::from_str(&document.metadata).unwrap_or(Value::Null);
+        self.docs.insert(
+            document.id.clone(),
+            IndexedDoc {
+                document,
+                metadata,
+                terms,
+                len: tokens.len(),
+            },
+        );
+    }
+
+    pub fn remove(&mut self, id: &str) -> bool {
+        let Some(doc) = self.docs.remove(id) else {
+            return false;
+        };
+        for term in doc.terms.keys() {
+            if let Some(df) = self.doc_freq.get_mut(term) {
+                *df -= 1;
+                if *df == 0 {
+                    self.doc_freq.remove(term);
+                }
+            }
+        }
+        self.total_len -= doc.len;
+        true
+    }
+
+    /// Top-`limit` documents matching `filter`, best BM25 score first.
+    /// Documents sharing no term with the query are not returned.
+    pub fn search(
+        &self,
+        query: &str,
+        limit: usize,
+        filter: &MetadataFilter,
+    ) -> Vec<ScoredDocument> {
+        let mut query_terms = tokenize(query);
+        query_terms.sort();
+        query_terms.dedup();
+        if query_terms.is_empty() || self.docs.is_empty() {
+            return Vec::new();
+        }
+
+        let n = self.docs.len() as f32;
+        let avg_len = (self.total_len as f32 / n).max(1.0);
+        let Bm25Config { k1, b } = self.config;
+
+        let mut hits: Vec<(f32, &IndexedDoc)> = self
+            .docs
+            .values()
+            .filter(|doc| filter.matches(&doc.metadata))
+            .filter_map(|doc| {
+                let score: f32 = query_terms
+                    .iter()
+                    .filter_map(|term| {
+                        let tf = *doc.terms.get(term)? as f32;
+                        let df = self.doc_freq[term] as f32;
+                        let idf = (1.0 + (n - df + 0.5) / (df + 0.5)).ln();
+                        let norm = k1 * (1.0 - b + b * doc.len as f32 / avg_len);
+                        Some(idf * tf * (k1 + 1.0) / (tf + norm))
+                    })
+                    .sum();
+                (score > 0.0).then_some((score, doc))
+            })
+            .collect();
+
+        hits.sort_by(|a, b| {
+            b.0.partial_cmp(&a.0)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| a.1.document.id.cmp(&b.1.document.id))
+        });
+        hits.truncate(limit);
+        hits.into_iter()
+            .map(|(score, doc)| ScoredDocument {
+                document: doc.document.clone(),
+                score,
+            })
+            .collect()
+    }
+}
+
+// ============================================================================
+// Fusion and reranking
+// ============================================================================
+
+/// Reciprocal-rank fusion of several rankings (best first). Returns ids with
+/// their fused score, best first; ties break on id for stable output.
+pub fn reciprocal_rank_fusion(rankings: &[Vec<String>], k: f32) -> Vec<(String, f32)> {
+    let mut scores: HashMap<&str, f32> = HashMap::new();
+    for ranking in rankings {
+        for (rank, id) in ranking.iter().enumerate() {
+            *scores.entry(id.as_str()).or_default() += 1.0 / (k + rank as f32 + 1.0);
+        }
+    }
+    let mut fused: Vec<(String, f32)> = scores
+        .into_iter()
+        .map(|(id, score)| (id.to_string(), score))
+        .collect();
+    fused.sort_by(|a, b| {
+        b.1.partial_cmp(&a.1)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.0.cmp(&b.0))
+    });
+    fused
+}
+
+/// Scores (query, passage) pairs; higher is more relevant. Implementations
+/// may block (model inference), so the retriever calls them from `spawn_blocking`.
+pub trait Reranker: Send + Sync {
+    fn model_id(&self) -> &str;
+    fn score(&self, query: &str, passages: &[&str]) -> Result<Vec<f32>>;
+}
+
+/// Which rankings a search uses; the evaluation compares all of them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RetrievalMode {
+    Keyword,
+    Vector,
+    Hybrid,
+}
+
+impl RetrievalMode {
+    pub const ALL: [RetrievalMode; 3] = [Self::Keyword, Self::Vector, Self::Hybrid];
+}
+
+#[derive(Debug, Clone)]
+pub struct HybridConfig {
+    /// Candidates taken from each ranking before fusion
+    pub candidates: usize,
+    /// RRF damping constant (60 in the original paper)
+    pub rrf_k: f32,
+    /// Fused candidates passed to the reranker
+    pub rerank_top: usize,
+}
+
+impl Default for HybridConfig {
+    fn default() -> Self {
+        Self {
+            candidates: 20,
+            rrf_k: 60.0,
+            rerank_top: 20,
+        }
+    }
+}
+
+// ============================================================================
+// Hybrid retriever
+// ============================================================================
+
+pub struct HybridRetriever {
+    /// `None` when only keyword search is available
+    dense: Option<Arc<dyn VectorStore>>,
+    keyword: RwLock<Bm25Index>,
+    reranker: Option<Arc<dyn Reranker>>,
+    config: HybridConfig,
+}
+
+impl HybridRetriever {
+    /// Wrap `dense` and build the keyword index from `corpus`, which should be
+    /// the documents already in the vector store.
+    pub fn new(dense: Arc<dyn VectorStore>, corpus: Vec<Document>) -> Self {
+        Self::build(Some(dense), corpus)
+    }
+
+    /// Keyword search over `corpus` with no vector side, for an index whose
+    /// embedding model didn't load. Adds and deletes are refused until it does.
+    pub fn keyword_only(corpus: Vec<Document>) -> Self {
+        Self::build(None, corpus)
+    }
+
+    fn build(dense: Option<Arc<dyn VectorStore>>, corpus: Vec<Document>) -> Self {
+        let mut keyword = Bm25Index::default();
+        for doc in corpus {
+            keyword.insert(doc);
+        }
+        log::info!(
+            "🔎 [Retrieval] Keyword index built over {} chunks",
+            keyword.len()
+        );
+        Self {
+            dense,
+            keyword: RwLock::new(keyword),
+            reranker: None,
+            config: HybridConfig::default(),
+        }
+    }
+
+    pub fn with_reranker(mut self, reranker: Arc<dyn Reranker>) -> Self {
+        log::info!("🔎 [Retrieval] Reranking with {}", reranker.model_id());
+        self.reranker = Some(reranker);
+        self
+    }
+
+    pub fn with_config(mut self, config: HybridConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    pub fn has_reranker(&self) -> bool {
+        self.reranker.is_some()
+    }
+
+    fn writable(&self) -> Result<&Arc<dyn VectorStore>> {
+        match &self.dense {
+            Some(dense) => Ok(dense),
+            None => bail!("Knowledge base is read-only until its embedding model loads"),
+        }
+    }
+
+    /// Search with one ranking or the fused pair. Reranking applies to every
+    /// mode when a reranker is configured and `rerank` is set.
+    pub async fn search_mode(
+        &self,
+        mode: RetrievalMode,
+        query: &str,
+        limit: usize,
+        filter: &MetadataFilter,
+        rerank: bool,
+    ) -> Result<Vec<ScoredDocument>> {
+        if limit == 0 {
+            return Ok(Vec::new());
+        }
+        let candidates = self.config.candidates.max(limit);
+
+        let keyword_hits = match mode {
+            RetrievalMode::Vector => Vec::new(),
+            _ => self.keyword.read().await.search(query, candidates, filter),
+        };
+        let vector_hits = match (mode, &self.dense) {
+            (RetrievalMode::Keyword, _) => Vec::new(),
+            (RetrievalMode::Hybrid, None) => Vec::new(),
+            (_, None) => bail!("No vector index loaded; only keyword search is available"),
+            // A broken embedder should not take keyword search down with it
+            (_, Some(dense)) => match dense.search_filtered(query, candidates, filter).await {
+                Ok(hits) => hits,
+                Err(e) if mode == RetrievalMode::Hybrid => {
+                    log::warn!("🔎 [Retrieval] Vector search failed: {}", e);
+                    Vec::new()
+                }
+                Err(e) => return Err(e),
+            },
+        };
+
+        let mut by_id: HashMap<String, Document> = HashMap::new();
+        let mut rankings = Vec::new();
+        for hits in [keyword_hits, vector_hits] {
+            if hits.is_empty() {
+                continue;
+            }
+            rankings.push(hits.iter().map(|h| h.document.id.clone()).collect());
+            for hit in hits {
+                by_id.entry(hit.document.id.clone()).or_insert(hit.document);
+            }
+        }
+
+        let mut fused: Vec<ScoredDocument> = reciprocal_rank_fusion(&rankings, self.config.rrf_k)
+            .into_iter()
+            .filter_map(|(id, score)| {
+                by_id
+                    .remove(&id)
+                    .map(|document| ScoredDocument { document, score })
+            })
+            .collect();
+
+        if rerank {
+            if let Some(reranker) = &self.reranker {
+                fused.truncate(self.config.rerank_top.max(limit));
+                fused = Self::rerank(reranker.clone(), query, fused).await?;
+            }
+        }
+
+        fused.truncate(limit);
+        Ok(fused)
+    }
+
+    async fn rerank(
+        reranker: Arc<dyn Reranker>,
+        query: &str,
+        candidates: Vec<ScoredDocument>,
+    ) -> Result<Vec<ScoredDocument>> {
+        if candidates.is_empty() {
+            return Ok(candidates);
+        }
+        let query = query.to_string();
+        tokio::task::spawn_blocking(move || {
+            let passages: Vec<&str> = candidates
+                .iter()
+                .map(|c| c.document.text.as_str())
+                .collect();
+            let scores = reranker.score(&query, &passages)?;
+            let mut reranked: Vec<ScoredDocument> = candidates
+                .into_iter()
+                .zip(scores)
+                .map(|(hit, score)| ScoredDocument {
+                    document: hit.document,
+                    score,
+                })
+                .collect();
+            // Stable sort keeps the fused order among equal scores
+            reranked.sort_by(|a, b| {
+                b.score
+                    .partial_cmp(&a.score)
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            });
+            Ok(reranked)
+        })
+        .await?
+    }
+}
+
+#[async_trait]
+impl VectorStore for HybridRetriever {
+    async fn add_document(&self, doc: Document) -> Result<()> {
+        self.add_documents(vec![doc]).await
+    }
+
+    async fn add_documents(&self, docs: Vec<Document>) -> Result<()> {
+        self.writable()?.add_documents(docs.clone()).await?;
+        let mut keyword = self.keyword.write().await;
+        for doc in docs {
+            keyword.insert(doc);
+        }
+        Ok(())
+    }
+
+    async fn delete_document(&self, id: &str) -> Result<bool> {
+        let dense = self.writable()?.delete_document(id).await?;
+        let keyword = self.keyword.write().await.remove(id);
+        Ok(dense || keyword)
+    }
+
+    async fn search_filtered(
+        &self,
+        query: &str,
+        limit: usize,
+        filter: &MetadataFilter,
+    ) -> Result<Vec<ScoredDocument>> {
+        self.search_mode(RetrievalMode::Hybrid, query, limit, filter, true)
+            .await
+    }
+}
+
+// ============================================================================
+// Offline evaluation
+// ============================================================================
+
+/// A labeled question set: a corpus plus queries with their relevant ids.
+#[derive(Debug, Clone, Deserialize)]
+pub struct EvalSet {
+    pub documents: Vec<EvalDocument>,
+    pub queries: Vec<EvalQuery>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct EvalDocument {
+    pub id: String,
+    pub text: String,
+    /// Chunk metadata, e.g. `{"course_id": "PHYS-101"}`
+    #[serde(default)]
+    pub metadata: Value,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct EvalQuery {
+    pub query: String,
+    pub relevant: Vec<String>,
+    /// Restrict the search to one course's corpus
+    #[serde(default)]
+    pub course_id: Option<String>,
+}
+
+impl EvalSet {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let raw = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read eval set {:?}", path))?;
+        serde_json::from_str(&raw).with_context(|| format!("Invalid eval set {:?}", path))
+    }
+
+    pub fn corpus(&self) -> Vec<Document> {
+        self.documents
+            .iter()
+            .map(|doc| Document {
+                id: doc.id.clone(),
+                text: doc.text.clone(),
+                metadata: doc.metadata.to_string(),
+            })
+            .collect()
+    }
+}
+
+/// Mean recall@k and MRR@k over an eval set for one retrieval mode.
+#[derive(Debug, Clone, Serialize)]
+pub struct EvalReport {
+    pub mode: RetrievalMode,
+    pub reranked: bool,
+    pub k: usize,
+    pub queries: usize,
+    pub recall_at_k: f32,
+    pub mrr: f32,
+}
+
+/// Run every query in `set` and score the top `k` results.
+pub async fn evaluate(
+    retriever: &HybridRetriever,
+    set: &EvalSet,
+    mode: RetrievalMode,
+    k: usize,
+    rerank: bool,
+) -> Result<EvalReport> {
+    let mut recall_sum = 0.0;
+    let mut rr_sum = 0.0;
+    let mut scored = 0;
+
+    for query in set.queries.iter().filter(|q| !q.relevant.is_empty()) {
+        let filter = match &query.course_id {
+            Some(course) => MetadataFilter::new().eq("course_id", course.as_str()),
+            None => MetadataFilter::default(),
+        };
+        let hits = retriever
+            .search_mode(mode, &query.query, k, &filter, rerank)
+            .await?;
+
+        let found = query
+            .relevant
+            .iter()
+            .filter(|id| hits.iter().any(|h| &h.document.id == *id))
+            .count();
+        recall_sum += found as f32 / query.relevant.len() as f32;
+        rr_sum += hits
+            .iter()
+            .position(|h| query.relevant.contains(&h.document.id))
+            .map(|rank| 1.0 / (rank as f32 + 1.0))
+            .unwrap_or(0.0);
+        scored += 1;
+    }
+
+    let denominator = scored.max(1) as f32;
+    Ok(EvalReport {
+        mode,
+        reranked: rerank && retriever.has_reranker(),
+        k,
+        queries: scored,
+        recall_at_k: recall_sum / denominator,
+        mrr: rr_sum / denominator,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use infra_db::{FileVectorStore, HashingEmbedder};
+
+    const EVAL_SET: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/retrieval_eval.json");
+
+    fn doc(id: &str, text: &str, course: &str) -> Document {
+        Document {
+            id: id.to_string(),
+            text: text.to_string(),
+            metadata: serde_json::json!({ "course_id": course }).to_string(),
+        }
+    }
+
+    fn temp_dir(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("pete_hybrid_{}_{}", name, uuid::Uuid::new_v4()))
+    }
+
+    async fn retriever(name: &str, corpus: Vec<Document>) -> (HybridRetriever, std::path::PathBuf) {
+        let dir = temp_dir(name);
+        let store = FileVectorStore::open(&dir, Arc::new(HashingEmbedder::default())).unwrap();
+        store.add_documents(corpus.clone()).await.unwrap();
+        (HybridRetriever::new(Arc::new(store), corpus), dir)
+    }
+
+    #[test]
+    fn test_tokenize_keeps_formulas() {
+        let terms = tokenize("Newton's second law: F=ma, so what is the force?");
+        assert!(terms.contains(&"f=ma".to_string()));
+        assert!(terms.contains(&"ma".to_string()));
+        assert!(terms.contains(&"force".to_string()));
+        assert!(!terms.contains(&"the".to_string()));
+    }
+
+    #[test]
+    fn test_bm25_prefers_exact_term() {
+        let mut index = Bm25Index::default();
+        index.insert(doc(
+            "hooke",
+            "Hooke's law: spring force is F = -kx.",
+            "PHYS-101",
+        ));
+        index.insert(doc(
+            "spring",
+            "A spring stores energy when stretched.",
+            "PHYS-101",
+        ));
+        let hits = index.search("hooke spring", 2, &MetadataFilter::default());
+        assert_eq!(hits[0].document.id, "hooke");
+
+        assert!(index.remove("hooke"));
+        assert!(!index.remove("hooke"));
+        let hits = index.search("hooke", 2, &MetadataFilter::default());
+        assert!(hits.is_empty());
+    }
+
+    #[test]
+    fn test_reciprocal_rank_fusion() {
+        let fused = reciprocal_rank_fusion(
+            &[
+                vec!["a".into(), "b".into(), "c".into()],
+                vec!["b".into(), "c".into(), "d".into()],
+            ],
+            60.0,
+        );
+        let order: Vec<&str> = fused.iter().map(|(id, _)| id.as_str()).collect();
+        // b is 2nd and 1st, beating a (1st and absent)
+        assert_eq!(order, vec!["b", "c", "a", "d"]);
+        assert!((fused[0].1 - (1.0 / 62.0 + 1.0 / 61.0)).abs() < 1e-6);
+    }
+
+    #[tokio::test]
+    async fn test_course_filter_and_sync() {
+        let (retriever, dir) = retriever(
+            "filter",
+            vec![
+                doc("p1", "Momentum is mass times velocity.", "PHYS-101"),
+                doc("c1", "Molar mass is grams per mole.", "CHEM-101"),
+            ],
+        )
+        .await;
+
+        let physics = MetadataFilter::new().eq("course_id", "PHYS-101");
+        let hits = retriever
+            .search_filtered("mass", 5, &physics)
+            .await
+            .unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].document.id, "p1");
+
+        retriever
+            .add_document(doc("p2", "Impulse changes momentum.", "PHYS-101"))
+            .await
+            .unwrap();
+        let hits = retriever
+            .search_mode(RetrievalMode::Keyword, "impulse", 5, &physics, false)
+            .await
+            .unwrap();
+        assert_eq!(hits[0].document.id, "p2");
+
+        assert!(retriever.delete_document("p2").await.unwrap());
+        let hits = retriever
+            .search_mode(RetrievalMode::Keyword, "impulse", 5, &physics, false)
+            .await
+            .unwrap();
+        assert!(hits.is_empty());
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn test_keyword_only_serves_search_and_refuses_writes() {
+        let retriever = HybridRetriever::keyword_only(vec![doc(
+            "p1",
+            "Momentum is mass times velocity.",
+            "PHYS-101",
+        )]);
+        let all = MetadataFilter::default();
+
+        let hits = retriever
+            .search_filtered("momentum", 5, &all)
+            .await
+            .unwrap();
+        assert_eq!(hits[0].document.id, "p1");
+        assert!(retriever
+            .search_mode(RetrievalMode::Vector, "momentum", 5, &all, false)
+            .await
+            .is_err());
+        assert!(retriever
+            .add_document(doc("p2", "Impulse changes momentum.", "PHYS-101"))
+            .await
+            .is_err());
+        assert!(retriever.delete_document("p1").await.is_err());
+    }
+
+    struct LengthReranker;
+
+    impl Reranker for LengthReranker {
+        fn model_id(&self) -> &str {
+            "test/length"
+        }
+
+        fn score(&self, _query: &str, passages: &[&str]) -> Result<Vec<f32>> {
+            Ok(passages.iter().map(|p| p.len() as f32).collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reranker_reorders_candidates() {
+        let (retriever, dir) = retriever(
+            "rerank",
+            vec![
+                doc("short", "Friction heats brakes.", "PHYS-101"),
+                doc(
+                    "long",
+                    "Friction between the brake shoe and the wheel turns kinetic energy into heat.",
+                    "PHYS-101",
+                ),
+            ],
+        )
+        .await;
+        let retriever = retriever.with_reranker(Arc::new(LengthReranker));
+
+        let hits = retriever
+            .search_filtered("friction brakes", 2, &MetadataFilter::default())
+            .await
+            .unwrap();
+        assert_eq!(hits[0].document.id, "long");
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn test_eval_set_recall() {
+        let set = EvalSet::load(EVAL_SET).unwrap();
+        let (retriever, dir) = retriever("eval", set.corpus()).await;
+
+        let hybrid = evaluate(&retriever, &set, RetrievalMode::Hybrid, 3, false)
+            .await
+            .unwrap();
+        let keyword = evaluate(&retriever, &set, RetrievalMode::Keyword, 3, false)
+            .await
+            .unwrap();
+        assert_eq!(hybrid.queries, set.queries.len());
+        assert!(
+            hybrid.recall_at_k >= 0.9,
+            "recall@3 = {}",
+            hybrid.recall_at_k
+        );
+        assert!(
+            hybrid.mrr >= keyword.mrr - 1e-6,
+            "{:?} vs {:?}",
+            hybrid,
+            keyword
+        );
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
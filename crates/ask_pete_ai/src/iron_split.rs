@@ -6,10 +6,14 @@ use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 use tokenizers::Tokenizer;
 
+/// Mistral 7B v0.1 context length
+pub const IRON_SPLIT_CONTEXT_WINDOW: usize = 8192;
+
 pub struct IronSplitSystem {
     // Shared resources wrapped in Arc/Mutex for thread safety if needed
     model: Arc<Mutex<QLlama>>,
     tokenizer: Arc<Tokenizer>,
+    counter: Arc<crate::tokens::TokenizerCounter>,
     device: Device,
 }
 
@@ -41,11 +45,17 @@ impl IronSplitSystem {
 
         Ok(Self {
             model: Arc::new(Mutex::new(model)),
+            counter: Arc::new(crate::tokens::TokenizerCounter::new(tokenizer.clone())),
             tokenizer: Arc::new(tokenizer),
             device,
         })
     }
 
+    /// Counts prompt tokens with the Mistral tokenizer
+    pub fn token_counter(&self) -> Arc<dyn crate::tokens::TokenCounter> {
+        self.counter.clone()
+    }
+
     // The Architect: Careful, creative, longer context
     pub fn ask_architect(&mut self, prompt: &str) -> Result<String> {
         let formatted = format!(
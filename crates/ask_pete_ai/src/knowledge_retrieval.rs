@@ -1,3 +1,5 @@
+use crate::prompt_assembler::{join_sections, priority, PromptSection, Trim};
+use infra_db::vector_store::{MetadataFilter, VectorStore};
 use sqlx::PgPool;
 use std::collections::HashMap;
 
@@ -9,6 +11,10 @@ pub struct KnowledgeChunk {
     pub content: String,
     pub source_type: String,
     pub relevance_score: f32,
+    /// Uploaded document the chunk was cut from (None for seeded sources)
+    pub document_id: Option<String>,
+    pub page: Option<u32>,
+    pub heading: Option<String>,
 }
 
 /// Retrieve relevant knowledge chunks from the database based on a user query
@@ -20,6 +26,7 @@ pub struct KnowledgeChunk {
 /// * `query` - The user's question/message
 /// * `pool` - Database connection pool
 /// * `max_chunks` - Maximum number of chunks to return (default: 3)
+/// * `course_id` - Only search chunks uploaded for this course
 ///
 /// # Returns
 /// A vector of relevant knowledge chunks, ranked by relevance score
@@ -27,6 +34,7 @@ pub async fn retrieve_knowledge(
     query: &str,
     pool: &PgPool,
     max_chunks: Option<usize>,
+    course_id: Option<&str>,
 ) -> Result<Vec<KnowledgeChunk>, Box<dyn std::error::Error>> {
     let limit = max_chunks.unwrap_or(3);
 
@@ -50,17 +58,25 @@ pub async fn retrieve_knowledge(
             title: String,
             content: String,
             source_type: String,
+            document_id: Option<String>,
+            page: Option<i32>,
+            heading: Option<String>,
         }
 
         let rows: Vec<KnowledgeRow> = sqlx::query_as(
             r#"
-            SELECT id, title, content, source_type
+            SELECT id, title, content, source_type,
+                   document_id::text AS document_id,
+                   (metadata->>'page')::int AS page,
+                   metadata->>'heading' AS heading
             FROM knowledge_sources
-            WHERE title ILIKE $1 OR content ILIKE $1
+            WHERE (title ILIKE $1 OR content ILIKE $1)
+              AND ($2::text IS NULL OR metadata->>'course_id' = $2)
             LIMIT 10
             "#,
         )
         .bind(&search_pattern)
+        .bind(course_id)
         .fetch_all(pool)
         .await?;
 
@@ -79,6 +95,9 @@ pub async fn retrieve_knowledge(
                             content: row.content,
                             source_type: row.source_type,
                             relevance_score: 0.0,
+                            document_id: row.document_id,
+                            page: row.page.and_then(|p| u32::try_from(p).ok()),
+                            heading: row.heading,
                         },
                         1,
                     )
@@ -108,6 +127,55 @@ pub async fn retrieve_knowledge(
     Ok(chunks)
 }
 
+/// Retrieve knowledge chunks by embedding similarity from the local vector store.
+///
+/// Chunk metadata is expected to carry `title` and `source_type` (plus
+/// `document_id`, `page` and `heading` for uploads); the cosine similarity
+/// becomes the relevance score. `filter` restricts the corpus, e.g. to a course.
+pub async fn retrieve_knowledge_semantic(
+    query: &str,
+    store: &dyn VectorStore,
+    max_chunks: Option<usize>,
+    filter: &MetadataFilter,
+) -> anyhow::Result<Vec<KnowledgeChunk>> {
+    let limit = max_chunks.unwrap_or(3);
+    let hits = store.search_filtered(query, limit, filter).await?;
+
+    Ok(hits
+        .into_iter()
+        .map(|hit| {
+            let metadata: serde_json::Value =
+                serde_json::from_str(&hit.document.metadata).unwrap_or_default();
+            let field = |key: &str, default: &str| {
+                metadata
+                    .get(key)
+                    .and_then(|v| v.as_str())
+                    .unwrap_or(default)
+                    .to_string()
+            };
+            KnowledgeChunk {
+                id: hit.document.id,
+                title: field("title", "Untitled"),
+                content: hit.document.text,
+                source_type: field("source_type", "txt"),
+                relevance_score: hit.score,
+                document_id: metadata
+                    .get("document_id")
+                    .and_then(|v| v.as_str())
+                    .map(str::to_string),
+                page: metadata
+                    .get("page")
+                    .and_then(|v| v.as_u64())
+                    .and_then(|p| u32::try_from(p).ok()),
+                heading: metadata
+                    .get("heading")
+                    .and_then(|v| v.as_str())
+                    .map(str::to_string),
+            }
+        })
+        .collect())
+}
+
 /// Extract keywords from a query by removing common stop words
 ///
 /// This is a simple implementation - just splits on whitespace and filters
@@ -130,31 +198,62 @@ fn extract_keywords(query: &str) -> Vec<String> {
         .collect()
 }
 
+/// Label the model uses to cite the chunk at `index` in the prompt, e.g. "S1"
+pub fn source_label(index: usize) -> String {
+    format!("S{}", index + 1)
+}
+
 /// Format knowledge chunks for injection into prompts
 ///
-/// Returns a formatted string that can be inserted into the AI prompt
+/// Each chunk is labelled with `source_label` and the model is asked to cite
+/// the labels inline, so `citations::resolve_citations` can verify the reply.
 pub fn format_chunks_for_prompt(chunks: &[KnowledgeChunk]) -> String {
     if chunks.is_empty() {
         return String::new();
     }
+    format!("{}\n\n", join_sections(&[knowledge_section(chunks)]))
+}
 
-    let mut formatted = String::from("## Relevant Knowledge\n\n");
-    formatted.push_str(
-        "I have access to the following information that may help answer your question:\n\n",
-    );
-
-    for (idx, chunk) in chunks.iter().enumerate() {
-        formatted.push_str(&format!(
-            "**Source {}: {}** ({})\n{}\n\n",
-            idx + 1,
-            chunk.title,
-            chunk.source_type,
-            chunk.content.trim()
-        ));
-    }
+/// Retrieved chunks as a prompt section, lowest-ranked chunk dropped first
+/// when the prompt runs over budget. Labels stay `[S1]`, `[S2]`, ... so the
+/// kept chunks are always a prefix of `chunks`.
+pub fn knowledge_section(chunks: &[KnowledgeChunk]) -> PromptSection {
+    PromptSection::items(
+        "knowledge",
+        priority::KNOWLEDGE,
+        chunks
+            .iter()
+            .enumerate()
+            .map(|(idx, chunk)| format_chunk(idx, chunk))
+            .collect(),
+        Trim::DropLowest,
+    )
+    .with_header(
+        "## Relevant Knowledge\n\n\
+         I have access to the following information that may help answer your question:\n\n",
+    )
+    .with_separator("\n\n")
+    .with_footer(
+        "\n\nWhen you use one of these sources, cite its label in square brackets right after \
+         the sentence, e.g. [S1]. Only cite the labels listed above.\n\n---",
+    )
+}
 
-    formatted.push_str("---\n\n");
-    formatted
+fn format_chunk(idx: usize, chunk: &KnowledgeChunk) -> String {
+    let mut location = chunk.source_type.clone();
+    if let Some(page) = chunk.page {
+        location.push_str(&format!(", p. {}", page));
+    }
+    if let Some(heading) = &chunk.heading {
+        location.push_str(&format!(", \"{}\"", heading));
+    }
+    format!(
+        "**[{}] {}** ({})\n{}",
+        source_label(idx),
+        chunk.title,
+        location,
+        chunk.content.trim()
+    )
 }
 
 #[cfg(test)]
@@ -185,12 +284,16 @@ mod tests {
             content: "The Purdue Bell Tower is called the Campanile.".to_string(),
             source_type: "txt".to_string(),
             relevance_score: 0.8,
+            document_id: None,
+            page: Some(4),
+            heading: Some("Landmarks".to_string()),
         }];
 
         let formatted = format_chunks_for_prompt(&chunks);
 
         assert!(formatted.contains("Relevant Knowledge"));
-        assert!(formatted.contains("Purdue Bell Tower"));
+        assert!(formatted.contains("[S1] Purdue Bell Tower"));
+        assert!(formatted.contains("p. 4"));
         assert!(formatted.contains("Campanile"));
     }
 }
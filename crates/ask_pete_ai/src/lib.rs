@@ -1,15 +1,27 @@
 pub mod antigravity;
 pub mod architect;
+pub mod citations;
+pub mod embeddings;
 pub mod error;
+pub mod guardrail;
+pub mod hybrid_retrieval;
 pub mod iron_split;
 pub mod json_utils;
 pub mod knowledge_retrieval;
 pub mod llm;
 pub mod local_inference;
 pub mod lore;
+pub mod memory_manager;
+pub mod prompt_assembler;
 pub mod prompts;
+pub mod reranker;
 pub mod socratic_engine;
+pub mod socratic_eval;
+pub mod strategy_selector;
+pub mod tokens;
 pub mod vocabulary;
+pub mod wellbeing;
+pub mod worker_pool;
 
 pub use local_inference::{GemmaConfigWrapper as LocalConfigWrapper, GemmaModel as LocalModel};
 pub use socratic_engine::SocraticEngine;
@@ -0,0 +1,87 @@
+//! # Text Backends
+//!
+//! `LlmBackend` is the smallest contract a text model has to meet for the
+//! Socratic engine: prompt in, completion out. `MockBackend` answers from
+//! canned rules so evals and tests run without a model.
+
+use anyhow::Result;
+use async_trait::async_trait;
+use std::sync::Mutex;
+
+#[async_trait]
+pub trait LlmBackend: Send + Sync {
+    /// Identifier reported in logs and eval results
+    fn model_id(&self) -> String;
+
+    async fn generate(&self, prompt: &str, max_tokens: usize) -> Result<String>;
+}
+
+#[async_trait]
+impl LlmBackend for crate::LocalModel {
+    fn model_id(&self) -> String {
+        crate::LocalModel::model_id(self)
+    }
+
+    async fn generate(&self, prompt: &str, max_tokens: usize) -> Result<String> {
+        let config = crate::local_inference::GenerationConfig {
+            max_tokens,
+            temperature: 0.2,
+            top_p: 0.9,
+            repeat_penalty: 1.1,
+        };
+        Ok(crate::LocalModel::generate(self, prompt.to_string(), config).await?)
+    }
+}
+
+/// Replies with the first rule whose needle occurs in the prompt, else the
+/// default reply. Every prompt is recorded.
+pub struct MockBackend {
+    rules: Vec<(String, String)>,
+    default_reply: String,
+    prompts: Mutex<Vec<String>>,
+}
+
+impl Default for MockBackend {
+    fn default() -> Self {
+        Self::new("What do you already know about this, and what makes you think so?")
+    }
+}
+
+impl MockBackend {
+    pub fn new(default_reply: impl Into<String>) -> Self {
+        Self {
+            rules: Vec::new(),
+            default_reply: default_reply.into(),
+            prompts: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Reply with `reply` to any prompt containing `needle`
+    pub fn with_rule(mut self, needle: impl Into<String>, reply: impl Into<String>) -> Self {
+        self.rules.push((needle.into(), reply.into()));
+        self
+    }
+
+    /// Prompts received so far, oldest first
+    pub fn prompts(&self) -> Vec<String> {
+        self.prompts.lock().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl LlmBackend for MockBackend {
+    fn model_id(&self) -> String {
+        "mock".to_string()
+    }
+
+    async fn generate(&self, prompt: &str, _max_tokens: usize) -> Result<String> {
+        self.prompts.lock().unwrap().push(prompt.to_string());
+        let reply = self
+            .rules
+            .iter()
+            .find(|(needle, _)| prompt.contains(needle.as_str()))
+            .map(|(_, reply)| reply)
+            .unwrap_or(&self.default_reply);
+        Ok(reply.clone())
+    }
+}
@@ -1,7 +1,97 @@
+//! # Gemini Client
+//!
+//! Multi-turn `generateContent` calls with a system instruction, safety
+//! settings and generation config. 429s, 5xx and network errors are retried
+//! with exponential backoff and full jitter (honouring `Retry-After`). Token
+//! usage from `usageMetadata` is burned as Coal.
+
+use crate::tokens::{HeuristicTokenCounter, TokenCounter};
 use anyhow::{Context, Result};
-use reqwest::Client;
+use pete_core::economy::Coal;
+use reqwest::{Client, StatusCode};
 use serde::{Deserialize, Serialize};
 use std::env;
+use std::time::Duration;
+
+pub const DEFAULT_BASE_URL: &str = "https://generativelanguage.googleapis.com/v1beta";
+
+/// Harm categories Gemini filters on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HarmCategory {
+    #[serde(rename = "HARM_CATEGORY_HARASSMENT")]
+    Harassment,
+    #[serde(rename = "HARM_CATEGORY_HATE_SPEECH")]
+    HateSpeech,
+    #[serde(rename = "HARM_CATEGORY_SEXUALLY_EXPLICIT")]
+    SexuallyExplicit,
+    #[serde(rename = "HARM_CATEGORY_DANGEROUS_CONTENT")]
+    DangerousContent,
+}
+
+/// How readily a category is blocked
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum HarmBlockThreshold {
+    BlockLowAndAbove,
+    BlockMediumAndAbove,
+    BlockOnlyHigh,
+    BlockNone,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SafetySetting {
+    pub category: HarmCategory,
+    pub threshold: HarmBlockThreshold,
+}
+
+impl SafetySetting {
+    /// Every category at `threshold`
+    pub fn all(threshold: HarmBlockThreshold) -> Vec<Self> {
+        [
+            HarmCategory::Harassment,
+            HarmCategory::HateSpeech,
+            HarmCategory::SexuallyExplicit,
+            HarmCategory::DangerousContent,
+        ]
+        .into_iter()
+        .map(|category| Self {
+            category,
+            threshold,
+        })
+        .collect()
+    }
+}
+
+/// When and how long to wait before retrying a failed call
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt
+    pub max_retries: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(8),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Full jitter: a random delay up to `base_delay * 2^retry`, capped
+    pub fn delay(&self, retry: u32) -> Duration {
+        let ceiling = self
+            .base_delay
+            .saturating_mul(1 << retry.min(16))
+            .min(self.max_delay);
+        let fraction = (uuid::Uuid::new_v4().as_u128() % 1_000_001) as f64 / 1_000_000.0;
+        ceiling.mul_f64(fraction)
+    }
+}
 
 /// Configuration for Gemini generation
 #[derive(Debug, Clone)]
@@ -10,6 +100,16 @@ pub struct GeminiConfig {
     pub model: String,
     pub max_tokens: usize,
     pub temperature: f32,
+    pub top_p: Option<f32>,
+    pub top_k: Option<u32>,
+    pub stop_sequences: Vec<String>,
+    /// Sent as `systemInstruction` when a request doesn't bring its own
+    pub system_instruction: Option<String>,
+    pub safety_settings: Vec<SafetySetting>,
+    /// API root, overridable for proxies and tests (`GEMINI_BASE_URL`)
+    pub base_url: String,
+    pub timeout: Duration,
+    pub retry: RetryPolicy,
 }
 
 impl Default for GeminiConfig {
@@ -19,63 +119,185 @@ impl Default for GeminiConfig {
             model: "gemini-2.0-flash-exp".to_string(), // Fixed: using valid model name
             max_tokens: 1024,
             temperature: 0.7,
+            top_p: None,
+            top_k: None,
+            stop_sequences: vec![],
+            system_instruction: None,
+            // Learners are students: filter more strictly than the API default
+            safety_settings: SafetySetting::all(HarmBlockThreshold::BlockLowAndAbove),
+            base_url: env::var("GEMINI_BASE_URL").unwrap_or_else(|_| DEFAULT_BASE_URL.to_string()),
+            timeout: Duration::from_secs(60),
+            retry: RetryPolicy::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChatRole {
+    User,
+    Model,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChatTurn {
+    pub role: ChatRole,
+    pub text: String,
+}
+
+impl ChatTurn {
+    pub fn user(text: impl Into<String>) -> Self {
+        Self {
+            role: ChatRole::User,
+            text: text.into(),
         }
     }
+
+    pub fn model(text: impl Into<String>) -> Self {
+        Self {
+            role: ChatRole::Model,
+            text: text.into(),
+        }
+    }
+}
+
+/// A conversation to continue. `system` and `max_tokens` override the config.
+#[derive(Debug, Clone, Default)]
+pub struct ChatRequest {
+    pub system: Option<String>,
+    pub turns: Vec<ChatTurn>,
+    pub max_tokens: Option<usize>,
+}
+
+impl ChatRequest {
+    /// A single user turn
+    pub fn prompt(text: impl Into<String>) -> Self {
+        Self {
+            turns: vec![ChatTurn::user(text)],
+            ..Default::default()
+        }
+    }
+
+    pub fn with_system(mut self, system: impl Into<String>) -> Self {
+        self.system = Some(system.into());
+        self
+    }
+
+    pub fn with_max_tokens(mut self, max_tokens: usize) -> Self {
+        self.max_tokens = Some(max_tokens);
+        self
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenUsage {
+    pub prompt_tokens: usize,
+    pub output_tokens: usize,
+    pub total_tokens: usize,
+}
+
+impl TokenUsage {
+    pub fn coal(&self) -> Coal {
+        Coal::cost_cloud_tokens(self.prompt_tokens, self.output_tokens)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct GeminiReply {
+    pub text: String,
+    pub finish_reason: Option<String>,
+    /// `None` if the API didn't report usage
+    pub usage: Option<TokenUsage>,
+    pub coal_burned: Coal,
 }
 
 /// Request payload for Gemini API
 #[derive(Serialize)]
-struct GeminiRequest {
-    contents: Vec<Content>,
-    #[serde(rename = "generationConfig")]
-    generation_config: GenerationConfig,
+#[serde(rename_all = "camelCase")]
+struct GeminiRequest<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    system_instruction: Option<Content<'a>>,
+    contents: Vec<Content<'a>>,
+    generation_config: GenerationConfig<'a>,
+    #[serde(skip_serializing_if = "<[SafetySetting]>::is_empty")]
+    safety_settings: &'a [SafetySetting],
 }
 
 #[derive(Serialize)]
-struct Content {
-    parts: Vec<Part>,
+struct Content<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    role: Option<ChatRole>,
+    parts: Vec<Part<'a>>,
 }
 
 #[derive(Serialize)]
-struct Part {
-    text: String,
+struct Part<'a> {
+    text: &'a str,
 }
 
 #[derive(Serialize)]
-struct GenerationConfig {
-    #[serde(rename = "maxOutputTokens")]
+#[serde(rename_all = "camelCase")]
+struct GenerationConfig<'a> {
     max_output_tokens: usize,
     temperature: f32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_k: Option<u32>,
+    #[serde(skip_serializing_if = "<[String]>::is_empty")]
+    stop_sequences: &'a [String],
 }
 
 /// Response payload from Gemini API
 #[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
 struct GeminiResponse {
     candidates: Option<Vec<Candidate>>,
     error: Option<ErrorResponse>,
+    usage_metadata: Option<UsageMetadata>,
+    prompt_feedback: Option<PromptFeedback>,
 }
 
 #[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
 struct Candidate {
-    content: ContentResponse,
-    #[serde(rename = "finishReason")]
-    _finish_reason: Option<String>,
+    content: Option<ContentResponse>,
+    finish_reason: Option<String>,
 }
 
 #[derive(Deserialize)]
 struct ContentResponse {
+    #[serde(default)]
     parts: Vec<PartResponse>,
 }
 
 #[derive(Deserialize)]
 struct PartResponse {
+    #[serde(default)]
     text: String,
 }
 
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UsageMetadata {
+    #[serde(default)]
+    prompt_token_count: usize,
+    #[serde(default)]
+    candidates_token_count: usize,
+    #[serde(default)]
+    total_token_count: usize,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PromptFeedback {
+    block_reason: Option<String>,
+}
+
 #[derive(Deserialize, Debug)]
 struct ErrorResponse {
-    _code: i32,
     message: String,
+    #[serde(default)]
     status: String,
 }
 
@@ -85,99 +307,371 @@ pub struct GeminiClient {
     client: Client,
     config: GeminiConfig,
     coal_balance: f64, // Track "Coal" usage
+    usage: TokenUsage, // Lifetime token usage
 }
 
 impl GeminiClient {
     pub fn new(config: GeminiConfig) -> Self {
+        let client = Client::builder()
+            .timeout(config.timeout)
+            .build()
+            .unwrap_or_default();
         Self {
-            client: Client::new(),
+            client,
             config,
             coal_balance: 100.0, // Initial coal grant
+            usage: TokenUsage::default(),
         }
     }
 
+    pub fn config(&self) -> &GeminiConfig {
+        &self.config
+    }
+
     /// Generate text from a prompt
     pub async fn generate(&mut self, prompt: &str) -> Result<String> {
+        Ok(self.generate_chat(&ChatRequest::prompt(prompt)).await?.text)
+    }
+
+    /// Continue a conversation
+    pub async fn generate_chat(&mut self, request: &ChatRequest) -> Result<GeminiReply> {
         if self.config.api_key.is_empty() {
             anyhow::bail!("GEMINI_API_KEY not set");
         }
+        if request.turns.is_empty() {
+            anyhow::bail!("Gemini request has no turns");
+        }
+        let max_tokens = request.max_tokens.unwrap_or(self.config.max_tokens);
 
-        // 1. Calculate Coal Cost (Metaphor for Compute)
-        let cost = pete_core::economy::Coal::cost_cloud();
-        if self.coal_balance < cost.0 {
+        // 1. Check the Coal (Metaphor for Compute) covers the worst case
+        let counter = HeuristicTokenCounter;
+        let estimated_prompt: usize = request
+            .system
+            .iter()
+            .chain(self.config.system_instruction.iter())
+            .chain(request.turns.iter().map(|t| &t.text))
+            .map(|text| counter.count(text))
+            .sum();
+        let ceiling = Coal::cost_cloud_tokens(estimated_prompt, max_tokens);
+        if self.coal_balance < ceiling.0 {
             anyhow::bail!(
-                "Insufficient Coal. Need {:.2}, have {:.2}. Burn more coal in the sandbox!",
-                cost.0,
+                "Insufficient Coal. Need up to {:.2}, have {:.2}. Burn more coal in the sandbox!",
+                ceiling.0,
                 self.coal_balance
             );
         }
-        self.coal_balance -= cost.0;
-        log::info!(
-            "Burning Coal: -{:.2}. Remaining: {:.2}",
-            cost.0,
-            self.coal_balance
-        );
 
         // 2. Prepare Request
-        let url = format!(
-            "https://generativelanguage.googleapis.com/v1beta/models/{}:generateContent?key={}",
-            self.config.model, self.config.api_key
-        );
-
-        let request_body = GeminiRequest {
-            contents: vec![Content {
-                parts: vec![Part {
-                    text: prompt.to_string(),
-                }],
-            }],
+        let system = request
+            .system
+            .as_deref()
+            .or(self.config.system_instruction.as_deref());
+        let body = GeminiRequest {
+            system_instruction: system.map(|text| Content {
+                role: None,
+                parts: vec![Part { text }],
+            }),
+            contents: request
+                .turns
+                .iter()
+                .map(|turn| Content {
+                    role: Some(turn.role),
+                    parts: vec![Part { text: &turn.text }],
+                })
+                .collect(),
             generation_config: GenerationConfig {
-                max_output_tokens: self.config.max_tokens,
+                max_output_tokens: max_tokens,
                 temperature: self.config.temperature,
+                top_p: self.config.top_p,
+                top_k: self.config.top_k,
+                stop_sequences: &self.config.stop_sequences,
             },
+            safety_settings: &self.config.safety_settings,
         };
 
-        // 3. Send Request
-        let response = self
-            .client
-            .post(&url)
-            .json(&request_body)
-            .send()
-            .await
-            .context("Failed to send request to Gemini API")?;
+        // 3. Send Request (with retries)
+        let response = self.send_with_retry(&body).await?;
 
-        if !response.status().is_success() {
-            let error_text = response.text().await?;
-            anyhow::bail!("Gemini API Error: {}", error_text);
+        // 4. Burn Coal for what was actually used (blocked prompts still count)
+        let usage = response.usage_metadata.as_ref().map(|u| TokenUsage {
+            prompt_tokens: u.prompt_token_count,
+            output_tokens: u.candidates_token_count,
+            total_tokens: u.total_token_count,
+        });
+        let coal_burned = usage.map_or_else(Coal::cost_cloud, |u| u.coal());
+        self.coal_balance -= coal_burned.0;
+        if let Some(u) = usage {
+            self.usage.prompt_tokens += u.prompt_tokens;
+            self.usage.output_tokens += u.output_tokens;
+            self.usage.total_tokens += u.total_tokens;
         }
+        log::info!(
+            "Burning Coal: -{:.2} ({} tokens). Remaining: {:.2}",
+            coal_burned.0,
+            usage.map_or(0, |u| u.total_tokens),
+            self.coal_balance
+        );
 
-        let gemini_response: GeminiResponse = response
-            .json()
-            .await
-            .context("Failed to parse Gemini response")?;
-
-        // 4. Handle Error in Body
-        if let Some(err) = gemini_response.error {
+        // 5. Extract Text
+        if let Some(reason) = response.prompt_feedback.and_then(|f| f.block_reason) {
+            anyhow::bail!("Gemini blocked the prompt ({})", reason);
+        }
+        let candidate = response
+            .candidates
+            .and_then(|c| c.into_iter().next())
+            .context("Gemini returned no candidates")?;
+        let text: String = candidate
+            .content
+            .map(|c| c.parts.into_iter().map(|p| p.text).collect())
+            .unwrap_or_default();
+        if text.is_empty() {
             anyhow::bail!(
-                "Gemini API returned error: {} ({})",
-                err.message,
-                err.status
+                "Gemini returned no text (finish reason: {})",
+                candidate.finish_reason.as_deref().unwrap_or("unknown")
             );
         }
 
-        // 5. Extract Text
-        if let Some(candidates) = gemini_response.candidates {
-            if let Some(first_candidate) = candidates.first() {
-                if let Some(first_part) = first_candidate.content.parts.first() {
-                    return Ok(first_part.text.clone());
+        Ok(GeminiReply {
+            text,
+            finish_reason: candidate.finish_reason,
+            usage,
+            coal_burned,
+        })
+    }
+
+    async fn send_with_retry(&self, body: &GeminiRequest<'_>) -> Result<GeminiResponse> {
+        let url = format!(
+            "{}/models/{}:generateContent",
+            self.config.base_url.trim_end_matches('/'),
+            self.config.model
+        );
+
+        let mut retry = 0;
+        loop {
+            let result = self
+                .client
+                .post(&url)
+                .header("x-goog-api-key", &self.config.api_key)
+                .json(body)
+                .send()
+                .await;
+
+            let (error, retry_after) = match result {
+                Ok(response) if response.status().is_success() => {
+                    let parsed: GeminiResponse = response
+                        .json()
+                        .await
+                        .context("Failed to parse Gemini response")?;
+                    if let Some(err) = parsed.error {
+                        anyhow::bail!(
+                            "Gemini API returned error: {} ({})",
+                            err.message,
+                            err.status
+                        );
+                    }
+                    return Ok(parsed);
+                }
+                Ok(response) => {
+                    let status = response.status();
+                    let retry_after = response
+                        .headers()
+                        .get(reqwest::header::RETRY_AFTER)
+                        .and_then(|v| v.to_str().ok())
+                        .and_then(|v| v.trim().parse::<u64>().ok())
+                        .map(Duration::from_secs);
+                    let error_text = response.text().await.unwrap_or_default();
+                    let error = anyhow::anyhow!("Gemini API Error ({}): {}", status, error_text);
+                    if !is_retryable(status) {
+                        return Err(error);
+                    }
+                    (error, retry_after)
                 }
+                Err(e) => (
+                    anyhow::Error::new(e).context("Failed to send request to Gemini API"),
+                    None,
+                ),
+            };
+
+            if retry >= self.config.retry.max_retries {
+                return Err(error);
             }
+            let delay = retry_after
+                .map(|d| d.min(self.config.retry.max_delay))
+                .unwrap_or_else(|| self.config.retry.delay(retry));
+            log::warn!(
+                "{:#}. Retrying in {:?} ({}/{})",
+                error,
+                delay,
+                retry + 1,
+                self.config.retry.max_retries
+            );
+            tokio::time::sleep(delay).await;
+            retry += 1;
         }
-
-        Ok("No response generated.".to_string())
     }
 
     /// Get current coal balance
     pub fn get_coal_balance(&self) -> f64 {
         self.coal_balance
     }
+
+    /// Tokens used by this client so far
+    pub fn usage(&self) -> TokenUsage {
+        self.usage
+    }
+}
+
+fn is_retryable(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS
+        || status == StatusCode::REQUEST_TIMEOUT
+        || status.is_server_error()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::mock_http::{MockHttpServer, MockResponse};
+    use serde_json::json;
+
+    fn client_for(server: &MockHttpServer) -> GeminiClient {
+        GeminiClient::new(GeminiConfig {
+            api_key: "test-key".to_string(),
+            base_url: server.url(),
+            retry: RetryPolicy {
+                max_retries: 2,
+                base_delay: Duration::from_millis(1),
+                max_delay: Duration::from_millis(5),
+            },
+            ..GeminiConfig::default()
+        })
+    }
+
+    fn reply(text: &str, prompt_tokens: usize, output_tokens: usize) -> MockResponse {
+        MockResponse::json(
+            200,
+            json!({
+                "candidates": [{
+                    "content": {"role": "model", "parts": [{"text": text}]},
+                    "finishReason": "STOP"
+                }],
+                "usageMetadata": {
+                    "promptTokenCount": prompt_tokens,
+                    "candidatesTokenCount": output_tokens,
+                    "totalTokenCount": prompt_tokens + output_tokens
+                }
+            }),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_multi_turn_request_shape() {
+        let server = MockHttpServer::start(vec![reply("What do you notice?", 10, 4)]).await;
+        let mut client = client_for(&server);
+        let request = ChatRequest {
+            system: Some("You are Pete.".to_string()),
+            turns: vec![
+                ChatTurn::user("Why do things fall?"),
+                ChatTurn::model("What have you observed?"),
+                ChatTurn::user("Heavy things fall faster."),
+            ],
+            max_tokens: Some(64),
+        };
+        let reply = client.generate_chat(&request).await.unwrap();
+        assert_eq!(reply.text, "What do you notice?");
+
+        let sent = &server.requests()[0];
+        assert_eq!(sent.path, "/models/gemini-2.0-flash-exp:generateContent");
+        assert_eq!(sent.header("x-goog-api-key"), Some("test-key"));
+        let body = sent.json();
+        assert_eq!(
+            body["systemInstruction"]["parts"][0]["text"],
+            "You are Pete."
+        );
+        let roles: Vec<&str> = body["contents"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|c| c["role"].as_str().unwrap())
+            .collect();
+        assert_eq!(roles, vec!["user", "model", "user"]);
+        assert_eq!(body["generationConfig"]["maxOutputTokens"], 64);
+        assert_eq!(body["safetySettings"].as_array().unwrap().len(), 4);
+        assert_eq!(
+            body["safetySettings"][0]["threshold"],
+            "BLOCK_LOW_AND_ABOVE"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_usage_is_burned_as_coal() {
+        let server = MockHttpServer::start(vec![reply("Hm?", 2000, 500)]).await;
+        let mut client = client_for(&server);
+        let reply = client
+            .generate_chat(&ChatRequest::prompt("Explain inertia"))
+            .await
+            .unwrap();
+
+        let expected = Coal::cost_cloud_tokens(2000, 500).0;
+        assert_eq!(reply.coal_burned.0, expected);
+        assert_eq!(client.get_coal_balance(), 100.0 - expected);
+        assert_eq!(client.usage().total_tokens, 2500);
+    }
+
+    #[tokio::test]
+    async fn test_retries_rate_limits_and_server_errors() {
+        let server = MockHttpServer::start(vec![
+            MockResponse::json(429, json!({"error": {"message": "slow down"}}))
+                .with_header("Retry-After", "0"),
+            MockResponse::json(503, json!({"error": {"message": "unavailable"}})),
+            reply("Third time lucky?", 5, 3),
+        ])
+        .await;
+        let mut client = client_for(&server);
+        assert_eq!(client.generate("Hi").await.unwrap(), "Third time lucky?");
+        assert_eq!(server.requests().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_gives_up_after_max_retries_and_on_client_errors() {
+        let server =
+            MockHttpServer::start(vec![MockResponse::json(500, json!({"error": {}}))]).await;
+        let mut client = client_for(&server);
+        assert!(client.generate("Hi").await.is_err());
+        assert_eq!(server.requests().len(), 3);
+        assert_eq!(client.get_coal_balance(), 100.0);
+
+        let server =
+            MockHttpServer::start(vec![MockResponse::json(400, json!({"error": {}}))]).await;
+        let mut client = client_for(&server);
+        assert!(client.generate("Hi").await.is_err());
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_blocked_prompt_is_an_error_but_still_billed() {
+        let server = MockHttpServer::start(vec![MockResponse::json(
+            200,
+            json!({
+                "promptFeedback": {"blockReason": "SAFETY"},
+                "usageMetadata": {"promptTokenCount": 1000, "totalTokenCount": 1000}
+            }),
+        )])
+        .await;
+        let mut client = client_for(&server);
+        let err = client.generate("something unsafe").await.unwrap_err();
+        assert!(err.to_string().contains("SAFETY"));
+        assert_eq!(
+            client.get_coal_balance(),
+            100.0 - Coal::cost_cloud_tokens(1000, 0).0
+        );
+    }
+
+    #[test]
+    fn test_jittered_delay_stays_under_cap() {
+        let policy = RetryPolicy::default();
+        for retry in 0..10 {
+            assert!(policy.delay(retry) <= policy.max_delay);
+        }
+        assert!(policy.delay(0) <= policy.base_delay);
+    }
 }
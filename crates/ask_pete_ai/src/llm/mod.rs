@@ -1,6 +1,12 @@
 #![allow(unused_imports)]
+pub mod backend;
 pub mod gemini_client;
 // pub mod gemma_server;
 pub mod gemma_engine;
+#[cfg(test)]
+pub mod mock_http;
+pub mod openai_compat;
 
+pub use backend::{LlmBackend, MockBackend};
 pub use gemma_engine::{GemmaConfigWrapper, GemmaModel, GenerationConfig};
+pub use openai_compat::{OpenAiBackend, OpenAiConfig};
@@ -0,0 +1,634 @@
+//! # OpenAI-Compatible Backend
+//!
+//! Talks to anything that serves the OpenAI chat-completions API: llama.cpp
+//! server, Ollama, vLLM, LM Studio or OpenAI itself. Supports plain and
+//! streaming (SSE) completions, token usage and function tool calls.
+
+use super::backend::LlmBackend;
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::sync::Mutex;
+use std::time::Duration;
+
+#[derive(Debug, Clone)]
+pub struct OpenAiConfig {
+    /// API root including the version, e.g. `http://localhost:11434/v1`
+    pub base_url: String,
+    pub model: String,
+    /// Sent as a bearer token; local servers usually need none
+    pub api_key: Option<String>,
+    pub temperature: f32,
+    pub timeout: Duration,
+}
+
+impl Default for OpenAiConfig {
+    fn default() -> Self {
+        Self {
+            base_url: "http://localhost:8080/v1".to_string(),
+            model: "default".to_string(),
+            api_key: None,
+            temperature: 0.7,
+            timeout: Duration::from_secs(120),
+        }
+    }
+}
+
+impl OpenAiConfig {
+    /// `OPENAI_BASE_URL`, `OPENAI_MODEL` and `OPENAI_API_KEY`. `None` when no
+    /// base URL is set, i.e. the backend is not wanted.
+    pub fn from_env() -> Option<Self> {
+        let base_url = env::var("OPENAI_BASE_URL")
+            .ok()
+            .filter(|s| !s.trim().is_empty())?;
+        let defaults = Self::default();
+        Some(Self {
+            base_url,
+            model: env::var("OPENAI_MODEL").unwrap_or(defaults.model.clone()),
+            api_key: env::var("OPENAI_API_KEY")
+                .ok()
+                .filter(|s| !s.trim().is_empty()),
+            ..defaults
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    System,
+    User,
+    Assistant,
+    Tool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChatMessage {
+    pub role: Role,
+    #[serde(default)]
+    pub content: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tool_calls: Vec<ToolCall>,
+    /// Set on `Role::Tool` messages: the call this is the result of
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_call_id: Option<String>,
+}
+
+impl ChatMessage {
+    fn new(role: Role, content: impl Into<String>) -> Self {
+        Self {
+            role,
+            content: Some(content.into()),
+            tool_calls: vec![],
+            tool_call_id: None,
+        }
+    }
+
+    pub fn system(content: impl Into<String>) -> Self {
+        Self::new(Role::System, content)
+    }
+
+    pub fn user(content: impl Into<String>) -> Self {
+        Self::new(Role::User, content)
+    }
+
+    pub fn assistant(content: impl Into<String>) -> Self {
+        Self::new(Role::Assistant, content)
+    }
+
+    /// The result of running `call_id`
+    pub fn tool_result(call_id: impl Into<String>, content: impl Into<String>) -> Self {
+        Self {
+            tool_call_id: Some(call_id.into()),
+            ..Self::new(Role::Tool, content)
+        }
+    }
+}
+
+/// A function the model may call
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolDefinition {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub function: FunctionDefinition,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FunctionDefinition {
+    pub name: String,
+    pub description: String,
+    /// JSON Schema for the arguments
+    pub parameters: serde_json::Value,
+}
+
+impl ToolDefinition {
+    pub fn function(name: &str, description: &str, parameters: serde_json::Value) -> Self {
+        Self {
+            kind: "function".to_string(),
+            function: FunctionDefinition {
+                name: name.to_string(),
+                description: description.to_string(),
+                parameters,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolCall {
+    pub id: String,
+    #[serde(rename = "type", default = "function_kind")]
+    pub kind: String,
+    pub function: FunctionCall,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FunctionCall {
+    pub name: String,
+    /// JSON-encoded arguments, as produced by the model
+    #[serde(default)]
+    pub arguments: String,
+}
+
+impl ToolCall {
+    /// The arguments parsed as JSON
+    pub fn arguments(&self) -> Result<serde_json::Value> {
+        serde_json::from_str(&self.function.arguments)
+            .with_context(|| format!("Bad arguments for tool '{}'", self.function.name))
+    }
+}
+
+fn function_kind() -> String {
+    "function".to_string()
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Usage {
+    #[serde(default)]
+    pub prompt_tokens: usize,
+    #[serde(default)]
+    pub completion_tokens: usize,
+    #[serde(default)]
+    pub total_tokens: usize,
+}
+
+/// One finished completion
+#[derive(Debug, Clone)]
+pub struct ChatCompletion {
+    pub message: ChatMessage,
+    pub finish_reason: Option<String>,
+    pub usage: Option<Usage>,
+}
+
+impl ChatCompletion {
+    pub fn text(&self) -> &str {
+        self.message.content.as_deref().unwrap_or_default()
+    }
+}
+
+#[derive(Serialize)]
+struct ChatRequest<'a> {
+    model: &'a str,
+    messages: &'a [ChatMessage],
+    #[serde(skip_serializing_if = "<[ToolDefinition]>::is_empty")]
+    tools: &'a [ToolDefinition],
+    max_tokens: usize,
+    temperature: f32,
+    stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stream_options: Option<StreamOptions>,
+}
+
+#[derive(Serialize)]
+struct StreamOptions {
+    include_usage: bool,
+}
+
+#[derive(Deserialize)]
+struct ChatResponse {
+    choices: Vec<Choice>,
+    usage: Option<Usage>,
+}
+
+#[derive(Deserialize)]
+struct Choice {
+    message: ChatMessage,
+    finish_reason: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct StreamChunk {
+    #[serde(default)]
+    choices: Vec<StreamChoice>,
+    usage: Option<Usage>,
+}
+
+#[derive(Deserialize)]
+struct StreamChoice {
+    #[serde(default)]
+    delta: Delta,
+    finish_reason: Option<String>,
+}
+
+#[derive(Deserialize, Default)]
+struct Delta {
+    content: Option<String>,
+    #[serde(default)]
+    tool_calls: Vec<ToolCallDelta>,
+}
+
+/// Tool calls arrive in fragments keyed by `index`
+#[derive(Deserialize)]
+struct ToolCallDelta {
+    index: usize,
+    id: Option<String>,
+    function: Option<FunctionCallDelta>,
+}
+
+#[derive(Deserialize)]
+struct FunctionCallDelta {
+    name: Option<String>,
+    arguments: Option<String>,
+}
+
+/// Accumulates streamed chunks into a `ChatCompletion`
+#[derive(Default)]
+struct StreamAssembler {
+    content: String,
+    tool_calls: Vec<ToolCall>,
+    finish_reason: Option<String>,
+    usage: Option<Usage>,
+}
+
+impl StreamAssembler {
+    /// Apply one SSE `data:` payload; returns any new text
+    fn apply(&mut self, data: &str) -> Result<Option<String>> {
+        let chunk: StreamChunk =
+            serde_json::from_str(data).with_context(|| format!("Bad stream chunk: {}", data))?;
+        if chunk.usage.is_some() {
+            self.usage = chunk.usage;
+        }
+        let mut text = None;
+        for choice in chunk.choices {
+            if let Some(content) = choice.delta.content.filter(|c| !c.is_empty()) {
+                self.content.push_str(&content);
+                text = Some(content);
+            }
+            for delta in choice.delta.tool_calls {
+                while self.tool_calls.len() <= delta.index {
+                    self.tool_calls.push(ToolCall {
+                        id: String::new(),
+                        kind: function_kind(),
+                        function: FunctionCall::default(),
+                    });
+                }
+                let call = &mut self.tool_calls[delta.index];
+                if let Some(id) = delta.id {
+                    call.id = id;
+                }
+                if let Some(function) = delta.function {
+                    if let Some(name) = function.name {
+                        call.function.name.push_str(&name);
+                    }
+                    if let Some(arguments) = function.arguments {
+                        call.function.arguments.push_str(&arguments);
+                    }
+                }
+            }
+            if choice.finish_reason.is_some() {
+                self.finish_reason = choice.finish_reason;
+            }
+        }
+        Ok(text)
+    }
+
+    fn finish(self) -> ChatCompletion {
+        ChatCompletion {
+            message: ChatMessage {
+                role: Role::Assistant,
+                content: (!self.content.is_empty()).then_some(self.content),
+                tool_calls: self.tool_calls,
+                tool_call_id: None,
+            },
+            finish_reason: self.finish_reason,
+            usage: self.usage,
+        }
+    }
+}
+
+pub struct OpenAiBackend {
+    client: Client,
+    config: OpenAiConfig,
+    usage: Mutex<Usage>,
+}
+
+impl OpenAiBackend {
+    pub fn new(config: OpenAiConfig) -> Self {
+        let client = Client::builder()
+            .timeout(config.timeout)
+            .build()
+            .unwrap_or_default();
+        Self {
+            client,
+            config,
+            usage: Mutex::new(Usage::default()),
+        }
+    }
+
+    pub fn config(&self) -> &OpenAiConfig {
+        &self.config
+    }
+
+    /// Tokens used through this backend so far
+    pub fn usage(&self) -> Usage {
+        *self.usage.lock().unwrap()
+    }
+
+    /// One non-streaming completion
+    pub async fn chat(
+        &self,
+        messages: &[ChatMessage],
+        tools: &[ToolDefinition],
+        max_tokens: usize,
+    ) -> Result<ChatCompletion> {
+        let response = self
+            .send(messages, tools, max_tokens, false)
+            .await?
+            .json::<ChatResponse>()
+            .await
+            .context("Failed to parse chat completion")?;
+        let choice = response
+            .choices
+            .into_iter()
+            .next()
+            .context("Chat completion has no choices")?;
+        let completion = ChatCompletion {
+            message: choice.message,
+            finish_reason: choice.finish_reason,
+            usage: response.usage,
+        };
+        self.record_usage(completion.usage);
+        Ok(completion)
+    }
+
+    /// A streaming completion. `on_text` is called with each piece of text
+    /// as it arrives; tool calls and usage are in the returned completion.
+    pub async fn chat_stream(
+        &self,
+        messages: &[ChatMessage],
+        tools: &[ToolDefinition],
+        max_tokens: usize,
+        mut on_text: impl FnMut(&str) + Send,
+    ) -> Result<ChatCompletion> {
+        let mut response = self.send(messages, tools, max_tokens, true).await?;
+        let mut assembler = StreamAssembler::default();
+        // Bytes, not text: a UTF-8 character can be split across network chunks
+        let mut buffer: Vec<u8> = Vec::new();
+
+        'stream: while let Some(bytes) = response.chunk().await? {
+            buffer.extend_from_slice(&bytes);
+            while let Some(end) = buffer.iter().position(|&b| b == b'\n') {
+                let line: Vec<u8> = buffer.drain(..=end).collect();
+                let line = String::from_utf8_lossy(&line);
+                let Some(data) = line.trim().strip_prefix("data:").map(str::trim) else {
+                    continue;
+                };
+                if data == "[DONE]" {
+                    break 'stream;
+                }
+                if let Some(text) = assembler.apply(data)? {
+                    on_text(&text);
+                }
+            }
+        }
+
+        let completion = assembler.finish();
+        self.record_usage(completion.usage);
+        Ok(completion)
+    }
+
+    async fn send(
+        &self,
+        messages: &[ChatMessage],
+        tools: &[ToolDefinition],
+        max_tokens: usize,
+        stream: bool,
+    ) -> Result<reqwest::Response> {
+        let url = format!(
+            "{}/chat/completions",
+            self.config.base_url.trim_end_matches('/')
+        );
+        let body = ChatRequest {
+            model: &self.config.model,
+            messages,
+            tools,
+            max_tokens,
+            temperature: self.config.temperature,
+            stream,
+            stream_options: stream.then_some(StreamOptions {
+                include_usage: true,
+            }),
+        };
+
+        let mut request = self.client.post(&url).json(&body);
+        if let Some(ref key) = self.config.api_key {
+            request = request.bearer_auth(key);
+        }
+        let response = request
+            .send()
+            .await
+            .with_context(|| format!("Failed to reach {}", url))?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await.unwrap_or_default();
+            anyhow::bail!("Chat completion error ({}): {}", status, error_text);
+        }
+        Ok(response)
+    }
+
+    fn record_usage(&self, usage: Option<Usage>) {
+        let Some(usage) = usage else {
+            return;
+        };
+        let mut total = self.usage.lock().unwrap();
+        total.prompt_tokens += usage.prompt_tokens;
+        total.completion_tokens += usage.completion_tokens;
+        total.total_tokens += usage.total_tokens;
+    }
+}
+
+#[async_trait]
+impl LlmBackend for OpenAiBackend {
+    fn model_id(&self) -> String {
+        format!("openai:{}", self.config.model)
+    }
+
+    async fn generate(&self, prompt: &str, max_tokens: usize) -> Result<String> {
+        let completion = self
+            .chat(&[ChatMessage::user(prompt)], &[], max_tokens)
+            .await?;
+        Ok(completion.text().to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::mock_http::{MockHttpServer, MockResponse};
+    use serde_json::json;
+
+    fn backend_for(server: &MockHttpServer) -> OpenAiBackend {
+        OpenAiBackend::new(OpenAiConfig {
+            base_url: format!("{}/v1", server.url()),
+            model: "llama-3-8b".to_string(),
+            api_key: Some("sk-local".to_string()),
+            ..Default::default()
+        })
+    }
+
+    fn sse(chunks: &[serde_json::Value]) -> MockResponse {
+        let mut body: String = chunks.iter().map(|c| format!("data: {}\n\n", c)).collect();
+        body.push_str("data: [DONE]\n\n");
+        MockResponse {
+            status: 200,
+            headers: vec![("Content-Type".to_string(), "text/event-stream".to_string())],
+            body,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_generate_sends_chat_request_and_records_usage() {
+        let server = MockHttpServer::start(vec![MockResponse::json(
+            200,
+            json!({
+                "choices": [{
+                    "message": {"role": "assistant", "content": "What pulls it down?"},
+                    "finish_reason": "stop"
+                }],
+                "usage": {"prompt_tokens": 12, "completion_tokens": 5, "total_tokens": 17}
+            }),
+        )])
+        .await;
+        let backend = backend_for(&server);
+        let reply = backend.generate("Why do apples fall?", 64).await.unwrap();
+        assert_eq!(reply, "What pulls it down?");
+        assert_eq!(backend.usage().total_tokens, 17);
+        assert_eq!(backend.model_id(), "openai:llama-3-8b");
+
+        let sent = &server.requests()[0];
+        assert_eq!(sent.path, "/v1/chat/completions");
+        assert_eq!(sent.header("authorization"), Some("Bearer sk-local"));
+        let body = sent.json();
+        assert_eq!(body["model"], "llama-3-8b");
+        assert_eq!(body["max_tokens"], 64);
+        assert_eq!(body["stream"], false);
+        assert_eq!(body["messages"][0]["role"], "user");
+        assert!(body.get("tools").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_tool_calls_round_trip() {
+        let server = MockHttpServer::start(vec![MockResponse::json(
+            200,
+            json!({
+                "choices": [{
+                    "message": {
+                        "role": "assistant",
+                        "content": null,
+                        "tool_calls": [{
+                            "id": "call_1",
+                            "type": "function",
+                            "function": {"name": "lookup_term", "arguments": "{\"word\":\"inertia\"}"}
+                        }]
+                    },
+                    "finish_reason": "tool_calls"
+                }]
+            }),
+        )])
+        .await;
+        let backend = backend_for(&server);
+        let tools = [ToolDefinition::function(
+            "lookup_term",
+            "Look up a vocabulary term",
+            json!({"type": "object", "properties": {"word": {"type": "string"}}}),
+        )];
+        let completion = backend
+            .chat(
+                &[
+                    ChatMessage::system("You are Pete."),
+                    ChatMessage::user("What is inertia?"),
+                ],
+                &tools,
+                128,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(completion.finish_reason.as_deref(), Some("tool_calls"));
+        let call = &completion.message.tool_calls[0];
+        assert_eq!(call.function.name, "lookup_term");
+        assert_eq!(call.arguments().unwrap()["word"], "inertia");
+        assert_eq!(
+            server.requests()[0].json()["tools"][0]["function"]["name"],
+            "lookup_term"
+        );
+
+        let result =
+            serde_json::to_value(ChatMessage::tool_result("call_1", "resistance")).unwrap();
+        assert_eq!(result["role"], "tool");
+        assert_eq!(result["tool_call_id"], "call_1");
+    }
+
+    #[tokio::test]
+    async fn test_stream_assembles_text_tool_calls_and_usage() {
+        let server = MockHttpServer::start(vec![sse(&[
+            json!({"choices": [{"delta": {"role": "assistant", "content": "What "}}]}),
+            json!({"choices": [{"delta": {"content": "do you think?"}}]}),
+            json!({"choices": [{"delta": {"tool_calls": [
+                {"index": 0, "id": "call_9", "function": {"name": "log_", "arguments": "{\"ok\":"}}
+            ]}}]}),
+            json!({"choices": [{"delta": {"tool_calls": [
+                {"index": 0, "function": {"name": "hint", "arguments": "true}"}}
+            ]}, "finish_reason": "tool_calls"}]}),
+            json!({"choices": [], "usage": {"prompt_tokens": 20, "completion_tokens": 8, "total_tokens": 28}}),
+        ])])
+        .await;
+        let backend = backend_for(&server);
+        let mut pieces = Vec::new();
+        let completion = backend
+            .chat_stream(&[ChatMessage::user("Hi")], &[], 32, |text| {
+                pieces.push(text.to_string())
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(pieces, vec!["What ", "do you think?"]);
+        assert_eq!(completion.text(), "What do you think?");
+        let call = &completion.message.tool_calls[0];
+        assert_eq!(
+            (call.id.as_str(), call.function.name.as_str()),
+            ("call_9", "log_hint")
+        );
+        assert_eq!(call.arguments().unwrap()["ok"], true);
+        assert_eq!(completion.usage.unwrap().total_tokens, 28);
+        assert_eq!(server.requests()[0].json()["stream"], true);
+    }
+
+    #[tokio::test]
+    async fn test_http_errors_surface_status_and_body() {
+        let server = MockHttpServer::start(vec![MockResponse::json(
+            404,
+            json!({"error": {"message": "model 'llama-3-8b' not found"}}),
+        )])
+        .await;
+        let err = backend_for(&server)
+            .generate("Hi", 16)
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("404"));
+        assert!(err.contains("not found"));
+    }
+}
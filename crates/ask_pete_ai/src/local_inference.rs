@@ -1,4 +1,5 @@
 use crate::error::{AiError, Result};
+use crate::tokens::{TokenCounter, TokenizerCounter};
 
 use candle_core::{Device, Tensor};
 use candle_transformers::models::quantized_llama::ModelWeights as QLlama;
@@ -85,6 +86,9 @@ impl Default for GemmaConfigWrapper {
     }
 }
 
+/// Tokens added by the `<start_of_turn>` wrapper in `generate`
+const CHAT_TEMPLATE_TOKENS: usize = 16;
+
 struct GemmaState {
     model: QLlama,
     tokenizer: Tokenizer,
@@ -94,6 +98,9 @@ struct GemmaState {
 #[derive(Clone)]
 pub struct GemmaModel {
     state: Arc<Mutex<GemmaState>>,
+    counter: Arc<TokenizerCounter>,
+    max_context_length: usize,
+    model_id: String,
 }
 
 impl GemmaModel {
@@ -137,7 +144,19 @@ impl GemmaModel {
 
         log::info!("✅ Tokenizer loaded");
 
+        let model_id = format!(
+            "local:{}",
+            config
+                .model_path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_else(|| "gguf".to_string())
+        );
+
         Ok(Self {
+            counter: Arc::new(TokenizerCounter::new(tokenizer.clone())),
+            max_context_length: config.max_context_length,
+            model_id,
             state: Arc::new(Mutex::new(GemmaState {
                 model,
                 tokenizer,
@@ -146,6 +165,21 @@ impl GemmaModel {
         })
     }
 
+    /// Counts prompt tokens with this model's tokenizer
+    pub fn token_counter(&self) -> Arc<dyn TokenCounter> {
+        self.counter.clone()
+    }
+
+    /// Weights file this model was loaded from, e.g. "local:mistral-7b-instruct-v0.1.Q4_K_M.gguf"
+    pub fn model_id(&self) -> String {
+        self.model_id.clone()
+    }
+
+    /// Prompt plus generated tokens the model can attend to, less the chat template
+    pub fn context_window(&self) -> usize {
+        self.max_context_length.saturating_sub(CHAT_TEMPLATE_TOKENS)
+    }
+
     pub async fn generate(&self, prompt: String, config: GenerationConfig) -> Result<String> {
         let state = self.state.clone();
 
@@ -1,3 +1,17 @@
+//! # Lore Packs
+//!
+//! A lore pack is a story world: the codex the Architect writes within,
+//! Pete's persona voice, how each `LiteraryDevice` is structured, and flavor
+//! text for stations. The Iron Network below is the built-in default pack.
+
+use anyhow::{bail, Context, Result};
+use pete_core::models::literary_device::LiteraryDevice;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+pub const DEFAULT_LORE_PACK_ID: &str = "iron_network";
+
 pub const IRON_NETWORK_CODEX: &str = r#"
 THE IRON NETWORK: SYSTEMS OPERATOR HANDBOOK
 Property of The Foundry / Department of Cognitive Logistics
@@ -166,8 +180,190 @@ Blow down your boiler.
 End of File.
 "#;
 
-pub fn get_lore_summary() -> String {
-    "The Iron Network is a cognitive logistics grid where users are Operators piloting Locomotive engines. \
+/// Pete's persona within a lore pack
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PersonaVoice {
+    pub name: String,
+    /// Who Pete is in this world, e.g. "the Prime Conductor"
+    pub role: String,
+    /// How Pete talks: vocabulary, metaphors, tone
+    pub voice: String,
+}
+
+impl PersonaVoice {
+    /// Persona instruction for the system prompt
+    pub fn prompt(&self) -> String {
+        format!(
+            "Persona: You are {}, {}. {}",
+            self.name, self.role, self.voice
+        )
+    }
+}
+
+/// A story world: its codex, Pete's voice in it, how each literary device is
+/// structured, and flavor text for individual stations.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LorePack {
+    pub id: String,
+    pub name: String,
+    /// One-paragraph world summary for short prompts
+    #[serde(default)]
+    pub summary: String,
+    /// Full world codex for long prompts (the Architect)
+    #[serde(default)]
+    pub codex: String,
+    /// Codex kept in a separate text file, relative to the pack file
+    #[serde(default, skip_serializing)]
+    pub codex_file: Option<PathBuf>,
+    pub persona: PersonaVoice,
+    #[serde(default)]
+    pub devices: HashMap<LiteraryDevice, String>,
+    /// Structure used for devices the pack doesn't describe
+    #[serde(default)]
+    pub default_device: String,
+    /// Station (node) id -> flavor text
+    #[serde(default)]
+    pub stations: BTreeMap<String, String>,
+}
+
+impl LorePack {
+    /// The Iron Network: the railway universe Ask Pete shipped with
+    pub fn iron_network() -> Self {
+        let devices = [
+            (LiteraryDevice::HerosJourney, "Structure the lesson as a 5-Chapter Hero's Journey. Chapter 1: The Call (Introduction). Chapter 2: The Threshold (First Challenge). Chapter 3: The Abyss (Deep Concept). Chapter 4: The Transformation (Application). Chapter 5: The Return (Summary). Each Chapter is a Node."),
+            (LiteraryDevice::Mystery, "Structure the lesson as a 5-Chapter Mystery. Chapter 1: The Crime (The Problem). Chapter 2: The Clues (Key Concepts). Chapter 3: The Suspects (Analysis). Chapter 4: The Reveal (Solution). Chapter 5: The Case Closed (Conclusion). Each Chapter is a Node."),
+            (LiteraryDevice::Collaborative, "Structure the lesson as a Team Relay. Chapter 1: The Handoff (Briefing). Chapter 2: Leg 1 (Individual Task). Chapter 3: The Exchange (Peer Review). Chapter 4: Leg 2 (Group Synthesis). Chapter 5: The Finish Line (Presentation). Each Chapter is a Node."),
+            (LiteraryDevice::Conflict, "Structure the lesson as a Debate. Chapter 1: The Motion (Topic). Chapter 2: The Affirmative (Pro-Argument). Chapter 3: The Negative (Counter-Argument). Chapter 4: The Rebuttal (Critical Thinking). Chapter 5: The Verdict (Synthesis). Each Chapter is a Node."),
+            (LiteraryDevice::StressRelief, "Structure the lesson as a Decompression Session. Chapter 1: The Vent (Identify Stress). Chapter 2: The Coolant (Perspective). Chapter 3: The Repair (Coping Strategy). Chapter 4: The Test (Practice). Chapter 5: The Green Light (Resilience). Each Chapter is a Node."),
+            (LiteraryDevice::Intuition, "Structure the lesson as a Dark Territory Run. Chapter 1: Lights Out (Uncertainty). Chapter 2: The Sound (Pattern Recognition). Chapter 3: The Obstacle (Trial & Error). Chapter 4: The Spark (Insight). Chapter 5: The Sunrise (Understanding). Each Chapter is a Node."),
+            (LiteraryDevice::SelfCorrection, "Structure the lesson as a Governor Recalibration. Chapter 1: The Overspeed (Identifying Error). Chapter 2: The Brake (Pause/Reflect). Chapter 3: The Tuning (Adjustment). Chapter 4: The Test Run (Verification). Chapter 5: Critical Damping (Mastery). Each Chapter is a Node."),
+        ];
+
+        Self {
+            id: DEFAULT_LORE_PACK_ID.to_string(),
+            name: "The Iron Network".to_string(),
+            summary: IRON_NETWORK_SUMMARY.to_string(),
+            codex: IRON_NETWORK_CODEX.to_string(),
+            codex_file: None,
+            persona: PersonaVoice {
+                name: "Pete".to_string(),
+                role: "the Prime Conductor and Cognitive Load Governor of the Iron Network".to_string(),
+                voice: "Speak like a calm, experienced railway conductor: steady, practical, fond of track, gauge and signal metaphors, never theatrical.".to_string(),
+            },
+            devices: devices
+                .into_iter()
+                .map(|(device, prompt)| (device, prompt.to_string()))
+                .collect(),
+            default_device: GENERIC_DEVICE_PROMPT.to_string(),
+            stations: BTreeMap::new(),
+        }
+    }
+
+    pub fn from_json(text: &str) -> Result<Self> {
+        let pack: Self = serde_json::from_str(text)?;
+        if pack.id.trim().is_empty() {
+            bail!("Lore pack has no id");
+        }
+        Ok(pack)
+    }
+
+    /// Load a `.json` pack, reading `codex_file` next to it if set
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Reading lore pack {:?}", path))?;
+        let mut pack =
+            Self::from_json(&text).with_context(|| format!("Parsing lore pack {:?}", path))?;
+        if let Some(ref file) = pack.codex_file {
+            let codex_path = path.parent().unwrap_or(Path::new(".")).join(file);
+            pack.codex = std::fs::read_to_string(&codex_path)
+                .with_context(|| format!("Reading lore codex {:?}", codex_path))?;
+        }
+        Ok(pack)
+    }
+
+    /// How to structure a lesson for `device`. Unrecognised device names get
+    /// the pack's default structure.
+    pub fn device_prompt(&self, device: &str) -> &str {
+        LiteraryDevice::from_label(device)
+            .and_then(|device| self.devices.get(&device))
+            .map(String::as_str)
+            .or_else(|| Some(self.default_device.as_str()).filter(|d| !d.is_empty()))
+            .unwrap_or(GENERIC_DEVICE_PROMPT)
+    }
+
+    pub fn station_flavor(&self, station_id: &str) -> Option<&str> {
+        self.stations.get(station_id).map(String::as_str)
+    }
+}
+
+/// All lore packs known to the server, keyed by id.
+#[derive(Debug, Clone)]
+pub struct LoreRegistry {
+    packs: BTreeMap<String, LorePack>,
+}
+
+impl Default for LoreRegistry {
+    fn default() -> Self {
+        Self::builtin()
+    }
+}
+
+impl LoreRegistry {
+    /// Just the Iron Network
+    pub fn builtin() -> Self {
+        let mut registry = Self {
+            packs: BTreeMap::new(),
+        };
+        registry.insert(LorePack::iron_network());
+        registry
+    }
+
+    /// Built-in packs plus every `.json` pack in `dir`. A missing directory
+    /// is not an error.
+    pub fn load_dir(dir: impl AsRef<Path>) -> Result<Self> {
+        let mut registry = Self::builtin();
+        let dir = dir.as_ref();
+        if !dir.is_dir() {
+            return Ok(registry);
+        }
+        let mut paths: Vec<_> = std::fs::read_dir(dir)?
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .filter(|p| p.extension().and_then(|e| e.to_str()) == Some("json"))
+            .collect();
+        paths.sort();
+        for path in paths {
+            registry.insert(LorePack::load(&path)?);
+        }
+        Ok(registry)
+    }
+
+    pub fn insert(&mut self, pack: LorePack) {
+        self.packs.insert(pack.id.clone(), pack);
+    }
+
+    pub fn get(&self, id: &str) -> Option<&LorePack> {
+        self.packs.get(id)
+    }
+
+    /// `id`'s pack, falling back to the Iron Network when unknown or unset
+    pub fn resolve(&self, id: Option<&str>) -> &LorePack {
+        id.and_then(|id| self.get(id))
+            .unwrap_or_else(|| self.default_pack())
+    }
+
+    pub fn default_pack(&self) -> &LorePack {
+        self.packs
+            .get(DEFAULT_LORE_PACK_ID)
+            .expect("default lore pack is always registered")
+    }
+
+    pub fn packs(&self) -> impl Iterator<Item = &LorePack> {
+        self.packs.values()
+    }
+}
+
+const IRON_NETWORK_SUMMARY: &str = "The Iron Network is a cognitive logistics grid where users are Operators piloting Locomotive engines. \
     The curriculum is a Map of tracks, and learning is the process of moving Cargo (Knowledge) from Ignorance to Mastery. \
     Pete is the Prime Conductor and Cognitive Load Governor. \
     Key Concepts: \
@@ -177,22 +373,66 @@ pub fn get_lore_summary() -> String {
     - The Track: Curriculum/Order \
     - The Signal: Connection/Flow State \
     - Chassis Classes: Guardian (Shield), Vanguard (Climber), Linker (Connector) \
-    - Protocols: Blowdown (Stress Relief), Governor Recalibration (Self-Correction), Dark Territory (Intuition)".to_string()
+    - Protocols: Blowdown (Stress Relief), Governor Recalibration (Self-Correction), Dark Territory (Intuition)";
+
+const GENERIC_DEVICE_PROMPT: &str = "Structure the lesson as a standard 5-Chapter linear progression. Chapter 1: Intro. Chapter 2: Concept A. Chapter 3: Concept B. Chapter 4: Practice. Chapter 5: Review.";
+
+pub fn get_lore_summary() -> String {
+    IRON_NETWORK_SUMMARY.to_string()
 }
 
 pub fn get_lore_context() -> String {
     IRON_NETWORK_CODEX.to_string()
 }
 
-pub fn get_device_prompt(device: &str) -> &'static str {
-    match device {
-        "Blueprint: Hero's Journey" => "Structure the lesson as a 5-Chapter Hero's Journey. Chapter 1: The Call (Introduction). Chapter 2: The Threshold (First Challenge). Chapter 3: The Abyss (Deep Concept). Chapter 4: The Transformation (Application). Chapter 5: The Return (Summary). Each Chapter is a Node.",
-        "Blueprint: Mystery" => "Structure the lesson as a 5-Chapter Mystery. Chapter 1: The Crime (The Problem). Chapter 2: The Clues (Key Concepts). Chapter 3: The Suspects (Analysis). Chapter 4: The Reveal (Solution). Chapter 5: The Case Closed (Conclusion). Each Chapter is a Node.",
-        "Blueprint: Collaborative" => "Structure the lesson as a Team Relay. Chapter 1: The Handoff (Briefing). Chapter 2: Leg 1 (Individual Task). Chapter 3: The Exchange (Peer Review). Chapter 4: Leg 2 (Group Synthesis). Chapter 5: The Finish Line (Presentation). Each Chapter is a Node.",
-        "Blueprint: Conflict" => "Structure the lesson as a Debate. Chapter 1: The Motion (Topic). Chapter 2: The Affirmative (Pro-Argument). Chapter 3: The Negative (Counter-Argument). Chapter 4: The Rebuttal (Critical Thinking). Chapter 5: The Verdict (Synthesis). Each Chapter is a Node.",
-        "Blueprint: Stress Relief" => "Structure the lesson as a Decompression Session. Chapter 1: The Vent (Identify Stress). Chapter 2: The Coolant (Perspective). Chapter 3: The Repair (Coping Strategy). Chapter 4: The Test (Practice). Chapter 5: The Green Light (Resilience). Each Chapter is a Node.",
-        "Blueprint: Intuition" => "Structure the lesson as a Dark Territory Run. Chapter 1: Lights Out (Uncertainty). Chapter 2: The Sound (Pattern Recognition). Chapter 3: The Obstacle (Trial & Error). Chapter 4: The Spark (Insight). Chapter 5: The Sunrise (Understanding). Each Chapter is a Node.",
-        "Blueprint: Self-Correction" => "Structure the lesson as a Governor Recalibration. Chapter 1: The Overspeed (Identifying Error). Chapter 2: The Brake (Pause/Reflect). Chapter 3: The Tuning (Adjustment). Chapter 4: The Test Run (Verification). Chapter 5: Critical Damping (Mastery). Each Chapter is a Node.",
-        _ => "Structure the lesson as a standard 5-Chapter linear progression. Chapter 1: Intro. Chapter 2: Concept A. Chapter 3: Concept B. Chapter 4: Practice. Chapter 5: Review.",
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_devices_resolve_from_client_labels() {
+        let pack = LorePack::iron_network();
+        assert!(pack
+            .device_prompt("The Derailment (Mystery)")
+            .contains("5-Chapter Mystery"));
+        assert!(pack
+            .device_prompt("Blueprint: Self-Correction")
+            .contains("Governor Recalibration"));
+        assert_eq!(pack.device_prompt("Sonnet"), GENERIC_DEVICE_PROMPT);
+    }
+
+    #[test]
+    fn test_json_pack_falls_back_to_its_default_device() {
+        let pack = LorePack::from_json(
+            r#"{
+                "id": "field_station",
+                "name": "Field Station",
+                "persona": {"name": "Pete", "role": "the station naturalist", "voice": "Curious and patient."},
+                "devices": {"Mystery": "Structure the lesson as a field investigation."},
+                "default_device": "Structure the lesson as a nature walk.",
+                "stations": {"node_1": "Mist hangs over the marsh."}
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            pack.device_prompt("Mystery"),
+            "Structure the lesson as a field investigation."
+        );
+        assert_eq!(
+            pack.device_prompt("Hero's Journey"),
+            "Structure the lesson as a nature walk."
+        );
+        assert_eq!(
+            pack.station_flavor("node_1"),
+            Some("Mist hangs over the marsh.")
+        );
+
+        let mut registry = LoreRegistry::builtin();
+        registry.insert(pack);
+        assert_eq!(
+            registry.resolve(Some("field_station")).name,
+            "Field Station"
+        );
+        assert_eq!(registry.resolve(Some("nope")).id, DEFAULT_LORE_PACK_ID);
     }
 }
@@ -0,0 +1,519 @@
+//! # Rolling Conversation Memory
+//!
+//! Long AI Mirror sessions outgrow the model's context window. The memory
+//! manager keeps the last few turns verbatim and folds everything older into
+//! a `SessionSummary`:
+//!
+//! - **Summary**: updated incrementally (previous summary + newly folded
+//!   turns), by the model when one is connected and extractively otherwise.
+//! - **Learner facts**: goals, struggles, preferences and who the learner is,
+//!   pulled from their own words so they survive folding verbatim.
+//!
+//! Each part has its own token budget and is clamped to it, so the history
+//! section of a prompt never exceeds `MemoryConfig::total_budget`.
+
+use crate::tokens::{truncate_to_tokens, HeuristicTokenCounter, TokenCounter};
+use chrono::Utc;
+use infra_db::conversation_memory::{LearnerFact, LearnerFactKind, SessionSummary, Speaker, Turn};
+use std::sync::Arc;
+
+/// Longest learner fact, in words
+const MAX_FACT_WORDS: usize = 20;
+
+#[derive(Debug, Clone)]
+pub struct MemoryConfig {
+    /// Most recent turns kept word for word
+    pub verbatim_turns: usize,
+    /// Token budget for the verbatim turns
+    pub history_budget: usize,
+    /// Token budget for the rolling summary
+    pub summary_budget: usize,
+    /// Token budget for the learner facts
+    pub facts_budget: usize,
+    pub max_facts: usize,
+}
+
+impl Default for MemoryConfig {
+    fn default() -> Self {
+        Self {
+            verbatim_turns: 6,
+            history_budget: 768,
+            summary_budget: 256,
+            facts_budget: 128,
+            max_facts: 12,
+        }
+    }
+}
+
+impl MemoryConfig {
+    /// Most tokens the managed history can take up in a prompt
+    pub fn total_budget(&self) -> usize {
+        self.history_budget + self.summary_budget + self.facts_budget
+    }
+}
+
+/// Turns that need folding before the next prompt, and those kept verbatim
+#[derive(Debug, Clone, Default)]
+pub struct FoldPlan {
+    pub fold: Vec<Turn>,
+    pub recent: Vec<Turn>,
+}
+
+/// History as it goes into a prompt
+#[derive(Debug, Clone, Default)]
+pub struct ManagedHistory {
+    pub summary: Option<String>,
+    pub learner_facts: Vec<LearnerFact>,
+    pub recent: Vec<Turn>,
+}
+
+impl ManagedHistory {
+    /// Recent turns only, no summary (short sessions and tests)
+    pub fn verbatim(recent: Vec<Turn>) -> Self {
+        Self {
+            recent,
+            ..Default::default()
+        }
+    }
+
+    /// The summary and learner facts as a prompt section (empty when there are none)
+    pub fn format_memory(&self) -> String {
+        let mut out = String::new();
+        if let Some(summary) = self.summary.as_deref().filter(|s| !s.is_empty()) {
+            out.push_str(&format!("Earlier in this conversation: {}\n", summary));
+        }
+        if !self.learner_facts.is_empty() {
+            out.push_str("What the learner has told you about themselves:\n");
+            for fact in &self.learner_facts {
+                out.push_str(&format!("- {}\n", fact.text));
+            }
+        }
+        out
+    }
+}
+
+pub struct MemoryManager {
+    config: MemoryConfig,
+    counter: Arc<dyn TokenCounter>,
+}
+
+impl Default for MemoryManager {
+    fn default() -> Self {
+        Self::new(MemoryConfig::default(), Arc::new(HeuristicTokenCounter))
+    }
+}
+
+impl MemoryManager {
+    pub fn new(config: MemoryConfig, counter: Arc<dyn TokenCounter>) -> Self {
+        Self { config, counter }
+    }
+
+    pub fn config(&self) -> &MemoryConfig {
+        &self.config
+    }
+
+    fn turn_tokens(&self, turn: &Turn) -> usize {
+        self.counter.count(&format_turn(turn))
+    }
+
+    /// Split a session's turns (oldest first) into those still to fold and
+    /// those kept verbatim. Older turns are folded until the verbatim window
+    /// fits both `verbatim_turns` and `history_budget`.
+    pub fn plan(&self, turns: &[Turn], state: &SessionSummary) -> FoldPlan {
+        // Turns up to `folded_through` are already in the summary. If that turn
+        // is no longer loaded (e.g. after a restart) everything loaded is newer.
+        let unfolded = state
+            .folded_through
+            .and_then(|id| turns.iter().position(|t| t.id == id))
+            .map(|pos| pos + 1)
+            .unwrap_or(0);
+
+        let mut keep_from = turns.len().saturating_sub(self.config.verbatim_turns);
+        keep_from = keep_from.max(unfolded);
+        let mut recent_tokens: usize = turns[keep_from..].iter().map(|t| self.turn_tokens(t)).sum();
+        while recent_tokens > self.config.history_budget && keep_from < turns.len() {
+            recent_tokens -= self.turn_tokens(&turns[keep_from]);
+            keep_from += 1;
+        }
+
+        FoldPlan {
+            fold: turns[unfolded.min(keep_from)..keep_from].to_vec(),
+            recent: turns[keep_from..].to_vec(),
+        }
+    }
+
+    /// Prompt asking a model to update the summary with the folded turns
+    pub fn summary_prompt(&self, previous: &str, fold: &[Turn]) -> String {
+        let transcript: Vec<String> = fold.iter().map(format_turn).collect();
+        format!(
+            "You keep notes on a tutoring conversation between a learner and Pete, a Socratic guide.\n\n\
+             Notes so far: {}\n\nNew turns:\n{}\n\n\
+             Rewrite the notes to cover the new turns too. Keep what the learner understood, \
+             what they are stuck on and the questions still open. Plain prose, at most {} words. \
+             Reply with the notes only.",
+            if previous.is_empty() { "(none)" } else { previous },
+            transcript.join("\n"),
+            self.config.summary_budget * 3 / 4
+        )
+    }
+
+    /// Fold `plan.fold` into the summary. `model_summary` is the model's reply
+    /// to `summary_prompt`; without one the summary is built extractively.
+    /// The result always fits `summary_budget` and `facts_budget`.
+    pub fn fold(
+        &self,
+        state: &SessionSummary,
+        fold: &[Turn],
+        model_summary: Option<String>,
+    ) -> SessionSummary {
+        let Some(last) = fold.last() else {
+            return state.clone();
+        };
+
+        let summary = match model_summary.map(|s| s.trim().to_string()) {
+            Some(text) if !text.is_empty() => {
+                truncate_to_tokens(self.counter.as_ref(), &text, self.config.summary_budget)
+            }
+            _ => self.extractive_summary(&state.summary, fold),
+        };
+
+        let mut facts = state.learner_facts.clone();
+        for fact in fold.iter().flat_map(extract_facts) {
+            let key = fact.text.to_lowercase();
+            facts.retain(|f| f.text.to_lowercase() != key);
+            facts.push(fact);
+        }
+        let overflow = facts.len().saturating_sub(self.config.max_facts);
+        facts.drain(..overflow);
+        // Oldest facts go first when the budget is tight
+        while !facts.is_empty() && self.facts_tokens(&facts) > self.config.facts_budget {
+            facts.remove(0);
+        }
+
+        SessionSummary {
+            summary,
+            learner_facts: facts,
+            folded_through: Some(last.id),
+            folded_turns: state.folded_turns + fold.len(),
+            updated_at: Some(Utc::now()),
+        }
+    }
+
+    /// Summary and verbatim turns ready for the prompt builder
+    pub fn assemble(&self, state: &SessionSummary, recent: Vec<Turn>) -> ManagedHistory {
+        ManagedHistory {
+            summary: Some(state.summary.clone()).filter(|s| !s.is_empty()),
+            learner_facts: state.learner_facts.clone(),
+            recent,
+        }
+    }
+
+    /// Tokens the managed history takes up, counted the way the budgets are
+    pub fn token_count(&self, history: &ManagedHistory) -> usize {
+        let summary = history
+            .summary
+            .as_deref()
+            .map(|s| self.counter.count(s))
+            .unwrap_or(0);
+        let recent: usize = history.recent.iter().map(|t| self.turn_tokens(t)).sum();
+        summary + self.facts_tokens(&history.learner_facts) + recent
+    }
+
+    fn facts_tokens(&self, facts: &[LearnerFact]) -> usize {
+        facts.iter().map(|f| self.counter.count(&f.text)).sum()
+    }
+
+    /// Previous summary plus one line per folded exchange; the oldest lines
+    /// are dropped when the budget runs out.
+    fn extractive_summary(&self, previous: &str, fold: &[Turn]) -> String {
+        let mut lines: Vec<String> = previous
+            .split_inclusive(['.', '!', '?'])
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect();
+        for turn in fold {
+            let gist = first_sentence(&turn.content);
+            if gist.is_empty() {
+                continue;
+            }
+            let line = match turn.speaker {
+                Speaker::User => format!("The learner said: \"{}\"", gist),
+                Speaker::AI => format!("Pete asked: \"{}\"", gist),
+            };
+            lines.push(format!("{}.", line));
+        }
+
+        while !lines.is_empty() && self.counter.count(&lines.join(" ")) > self.config.summary_budget
+        {
+            lines.remove(0);
+        }
+        let summary = lines.join(" ");
+        // A single line can still be over budget
+        truncate_to_tokens(self.counter.as_ref(), &summary, self.config.summary_budget)
+    }
+}
+
+fn format_turn(turn: &Turn) -> String {
+    let speaker = match turn.speaker {
+        Speaker::User => "Learner",
+        Speaker::AI => "You (AI Guide)",
+    };
+    format!("{}: {}", speaker, turn.content)
+}
+
+/// Start of `text` up to the first sentence end, at most 25 words
+fn first_sentence(text: &str) -> String {
+    let sentence = text
+        .split_inclusive(['.', '!', '?', '\n'])
+        .map(str::trim)
+        .find(|s| !s.is_empty())
+        .unwrap_or("");
+    let sentence = sentence.trim_end_matches(['.', '!', '\n']);
+    sentence
+        .split_whitespace()
+        .take(25)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+const FACT_CUES: &[(&str, LearnerFactKind)] = &[
+    ("my name is", LearnerFactKind::Identity),
+    ("i am a ", LearnerFactKind::Identity),
+    ("i am an ", LearnerFactKind::Identity),
+    ("i'm a ", LearnerFactKind::Identity),
+    ("i'm an ", LearnerFactKind::Identity),
+    ("i work as", LearnerFactKind::Identity),
+    ("i study", LearnerFactKind::Identity),
+    ("i want to", LearnerFactKind::Goal),
+    ("my goal is", LearnerFactKind::Goal),
+    ("i hope to", LearnerFactKind::Goal),
+    ("i'm trying to", LearnerFactKind::Goal),
+    ("i don't understand", LearnerFactKind::Struggle),
+    ("i do not understand", LearnerFactKind::Struggle),
+    ("i'm confused", LearnerFactKind::Struggle),
+    ("i am confused", LearnerFactKind::Struggle),
+    ("i struggle with", LearnerFactKind::Struggle),
+    ("i always get", LearnerFactKind::Struggle),
+    ("i prefer", LearnerFactKind::Preference),
+    ("i learn best", LearnerFactKind::Preference),
+    ("i like", LearnerFactKind::Preference),
+];
+
+/// Facts the learner stated about themselves in one turn
+pub fn extract_facts(turn: &Turn) -> Vec<LearnerFact> {
+    if turn.speaker != Speaker::User {
+        return Vec::new();
+    }
+    let mut facts = Vec::new();
+    for sentence in turn.content.split_inclusive(['.', '!', '?', '\n']) {
+        let sentence = sentence.trim();
+        // Questions are not statements about the learner
+        if sentence.ends_with('?') {
+            continue;
+        }
+        let normalized = sentence.replace('’', "'");
+        let lower = normalized.to_lowercase();
+        let cue = FACT_CUES
+            .iter()
+            .filter_map(|(cue, kind)| {
+                lower
+                    .match_indices(cue)
+                    .map(|(pos, _)| pos)
+                    .find(|&pos| {
+                        !lower[..pos]
+                            .chars()
+                            .next_back()
+                            .is_some_and(char::is_alphanumeric)
+                    })
+                    .map(|pos| (pos, *kind))
+            })
+            .min_by_key(|(pos, _)| *pos);
+        let Some((pos, kind)) = cue else {
+            continue;
+        };
+        // `pos` is a byte offset into the lowercased text; lowercasing can
+        // change lengths outside ASCII, so fall back to the whole sentence
+        let clause = normalized
+            .get(pos..)
+            .filter(|_| lower.len() == normalized.len())
+            .unwrap_or(&normalized);
+        let mut words: Vec<&str> = clause.split_whitespace().take(MAX_FACT_WORDS).collect();
+        if let Some(first) = words.first_mut() {
+            if *first == "i" {
+                *first = "I";
+            } else if *first == "i'm" {
+                *first = "I'm";
+            }
+        }
+        let text = words
+            .join(" ")
+            .trim_end_matches(['.', '!', ','])
+            .to_string();
+        if !text.is_empty() {
+            facts.push(LearnerFact {
+                kind,
+                text,
+                source_turn: turn.id,
+            });
+        }
+    }
+    facts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use infra_db::conversation_memory::TurnMetadata;
+    use uuid::Uuid;
+
+    fn turn(speaker: Speaker, content: &str) -> Turn {
+        Turn {
+            id: Uuid::new_v4(),
+            timestamp: Utc::now(),
+            speaker,
+            content: content.to_string(),
+            metadata: TurnMetadata::default(),
+        }
+    }
+
+    /// Deterministic session with turns of very different lengths
+    fn session(len: usize) -> Vec<Turn> {
+        let mut seed: u64 = 7;
+        (0..len)
+            .map(|i| {
+                seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1);
+                let words = 1 + (seed >> 33) as usize % 180;
+                let body = format!(
+                    "Turn {} about momentum and friction. {}",
+                    i,
+                    "train ".repeat(words)
+                );
+                if i % 2 == 0 {
+                    turn(
+                        Speaker::User,
+                        &format!("I want to pass exam {}. {}", i, body),
+                    )
+                } else {
+                    turn(Speaker::AI, &format!("{} What do you notice?", body))
+                }
+            })
+            .collect()
+    }
+
+    fn small_config() -> MemoryConfig {
+        MemoryConfig {
+            verbatim_turns: 4,
+            history_budget: 200,
+            summary_budget: 60,
+            facts_budget: 40,
+            max_facts: 5,
+        }
+    }
+
+    #[test]
+    fn test_token_budget_is_never_exceeded() {
+        let manager = MemoryManager::new(small_config(), Arc::new(HeuristicTokenCounter));
+        let turns = session(60);
+        let mut state = SessionSummary::default();
+
+        // Replay the session one turn at a time, as the engine does
+        for end in 1..=turns.len() {
+            let plan = manager.plan(&turns[..end], &state);
+            let model_summary = (end % 3 == 0).then(|| "word ".repeat(500));
+            state = manager.fold(&state, &plan.fold, model_summary);
+            let history = manager.assemble(&state, plan.recent);
+
+            assert!(manager.token_count(&history) <= manager.config().total_budget());
+            assert!(history.recent.len() <= 4);
+            assert!(state.learner_facts.len() <= 5);
+            assert_eq!(state.folded_turns + history.recent.len(), end);
+        }
+    }
+
+    #[test]
+    fn test_fold_is_incremental() {
+        let manager = MemoryManager::new(small_config(), Arc::new(HeuristicTokenCounter));
+        let turns: Vec<Turn> = (0..6)
+            .map(|i| turn(Speaker::User, &format!("Point {}.", i)))
+            .collect();
+
+        let plan = manager.plan(&turns, &SessionSummary::default());
+        assert_eq!(plan.fold.len(), 2);
+        assert_eq!(plan.recent.len(), 4);
+        let state = manager.fold(&SessionSummary::default(), &plan.fold, None);
+        assert_eq!(state.folded_through, Some(turns[1].id));
+        assert!(state.summary.contains("Point 0") && state.summary.contains("Point 1"));
+
+        // Nothing new to fold until the window moves again
+        assert!(manager.plan(&turns, &state).fold.is_empty());
+        let mut more = turns.clone();
+        more.push(turn(Speaker::AI, "Why does point five matter?"));
+        let plan = manager.plan(&more, &state);
+        assert_eq!(plan.fold.len(), 1);
+        assert_eq!(plan.fold[0].id, turns[2].id);
+        let state = manager.fold(&state, &plan.fold, None);
+        assert_eq!(state.folded_turns, 3);
+        assert!(state.summary.starts_with("The learner said: \"Point 0\"."));
+    }
+
+    #[test]
+    fn test_summary_survives_missing_turns() {
+        let manager = MemoryManager::default();
+        let state = SessionSummary {
+            summary: "Earlier notes.".to_string(),
+            folded_through: Some(Uuid::new_v4()),
+            folded_turns: 40,
+            ..Default::default()
+        };
+        let turns = vec![turn(Speaker::User, "Hello again.")];
+        let plan = manager.plan(&turns, &state);
+        assert!(plan.fold.is_empty());
+        assert_eq!(plan.recent.len(), 1);
+        let history = manager.assemble(&state, plan.recent);
+        assert!(history
+            .format_memory()
+            .contains("Earlier in this conversation: Earlier notes."));
+    }
+
+    #[test]
+    fn test_extract_learner_facts() {
+        let facts = extract_facts(&turn(
+            Speaker::User,
+            "Hi! I'm a second-year nursing student. I don't understand why the dose halves. \
+             Can I like ask you something? Honestly I want to pass the pharmacology exam.",
+        ));
+        let texts: Vec<(&str, LearnerFactKind)> =
+            facts.iter().map(|f| (f.text.as_str(), f.kind)).collect();
+        assert_eq!(
+            texts,
+            vec![
+                (
+                    "I'm a second-year nursing student",
+                    LearnerFactKind::Identity
+                ),
+                (
+                    "I don't understand why the dose halves",
+                    LearnerFactKind::Struggle
+                ),
+                (
+                    "I want to pass the pharmacology exam",
+                    LearnerFactKind::Goal
+                ),
+            ]
+        );
+        assert!(extract_facts(&turn(Speaker::AI, "I want to help you.")).is_empty());
+    }
+
+    #[test]
+    fn test_repeated_facts_are_deduplicated() {
+        let manager = MemoryManager::default();
+        let fold = vec![
+            turn(Speaker::User, "I want to pass the exam."),
+            turn(Speaker::AI, "What would passing mean to you?"),
+            turn(Speaker::User, "I WANT TO PASS THE EXAM."),
+        ];
+        let state = manager.fold(&SessionSummary::default(), &fold, None);
+        assert_eq!(state.learner_facts.len(), 1);
+        assert_eq!(state.learner_facts[0].source_turn, fold[2].id);
+    }
+}
@@ -0,0 +1,453 @@
+//! # Prompt Assembler (The Loading Gauge)
+//!
+//! Every prompt is built from named sections with a priority and optional
+//! min/max token budgets. Sections are measured with the active model's
+//! tokenizer (`tokens::TokenCounter`); when the prompt does not fit the
+//! context window minus the tokens reserved for the reply, the
+//! lowest-priority content is trimmed first:
+//!
+//! - `Trim::DropOldest` drops whole items from the front (older history).
+//! - `Trim::DropLowest` drops whole items from the back (lower-ranked chunks).
+//! - `Trim::Truncate` cuts text from the end at a sentence boundary.
+//! - `Trim::Fixed` is never trimmed (instructions, the learner's message).
+//!
+//! The result reports what was dropped so callers can log it and keep
+//! dependent state (e.g. citation labels) in step.
+
+use crate::error::{AiError, Result};
+use crate::tokens::{truncate_to_tokens, HeuristicTokenCounter, TokenCounter};
+use serde::Serialize;
+use std::sync::Arc;
+
+/// Context window assumed for remote backends (Gemini) and unknown models
+pub const DEFAULT_CONTEXT_WINDOW: usize = 8192;
+
+/// Section priorities shared by the call sites (higher is kept longer)
+pub mod priority {
+    pub const REQUIRED: u8 = u8::MAX;
+    pub const INSTRUCTIONS: u8 = 200;
+    pub const MEMORY: u8 = 120;
+    pub const HISTORY: u8 = 100;
+    pub const KNOWLEDGE: u8 = 80;
+    pub const VOCABULARY: u8 = 70;
+    pub const NARRATIVE: u8 = 50;
+    pub const LORE: u8 = 30;
+}
+
+/// How a section gives up tokens when the prompt is over budget
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trim {
+    Fixed,
+    Truncate,
+    DropOldest,
+    DropLowest,
+}
+
+#[derive(Debug, Clone)]
+pub struct PromptSection {
+    pub name: String,
+    pub priority: u8,
+    /// Not trimmed further once the section is at or below this size
+    pub min_tokens: usize,
+    /// Trimmed to this size even when the prompt has room
+    pub max_tokens: Option<usize>,
+    pub trim: Trim,
+    /// Rendered before the items, only when at least one item is kept
+    pub header: String,
+    pub items: Vec<String>,
+    pub separator: String,
+    pub footer: String,
+}
+
+impl PromptSection {
+    /// Text that is cut from the end when space runs out
+    pub fn text(name: &str, priority: u8, content: impl Into<String>) -> Self {
+        Self::items(name, priority, vec![content.into()], Trim::Truncate)
+    }
+
+    /// Text that must be in the prompt as-is
+    pub fn fixed(name: &str, content: impl Into<String>) -> Self {
+        Self::items(name, priority::REQUIRED, vec![content.into()], Trim::Fixed)
+    }
+
+    /// A list whose items are dropped whole
+    pub fn items(name: &str, priority: u8, items: Vec<String>, trim: Trim) -> Self {
+        Self {
+            name: name.to_string(),
+            priority,
+            min_tokens: 0,
+            max_tokens: None,
+            trim,
+            header: String::new(),
+            items: items.into_iter().filter(|i| !i.trim().is_empty()).collect(),
+            separator: "\n".to_string(),
+            footer: String::new(),
+        }
+    }
+
+    pub fn with_header(mut self, header: impl Into<String>) -> Self {
+        self.header = header.into();
+        self
+    }
+
+    pub fn with_footer(mut self, footer: impl Into<String>) -> Self {
+        self.footer = footer.into();
+        self
+    }
+
+    pub fn with_separator(mut self, separator: impl Into<String>) -> Self {
+        self.separator = separator.into();
+        self
+    }
+
+    pub fn with_min_tokens(mut self, min_tokens: usize) -> Self {
+        self.min_tokens = min_tokens;
+        self
+    }
+
+    pub fn with_max_tokens(mut self, max_tokens: usize) -> Self {
+        self.max_tokens = Some(max_tokens);
+        self
+    }
+
+    fn render(&self) -> String {
+        if self.items.is_empty() {
+            return String::new();
+        }
+        format!(
+            "{}{}{}",
+            self.header,
+            self.items.join(&self.separator),
+            self.footer
+        )
+    }
+
+    /// Give up roughly `excess` tokens. Returns false when nothing could go.
+    fn trim_once(&mut self, counter: &dyn TokenCounter, excess: usize) -> bool {
+        match self.trim {
+            Trim::Fixed => false,
+            Trim::DropOldest => {
+                if self.items.is_empty() {
+                    return false;
+                }
+                self.items.remove(0);
+                true
+            }
+            Trim::DropLowest => self.items.pop().is_some(),
+            Trim::Truncate => {
+                let Some(last) = self.items.pop() else {
+                    return false;
+                };
+                let rest = counter
+                    .count(&self.render())
+                    .max(counter.count(&format!("{}{}", self.header, self.footer)));
+                let target = counter
+                    .count(&last)
+                    .saturating_sub(excess.max(1))
+                    .max(self.min_tokens.saturating_sub(rest));
+                let cut = truncate_to_tokens(counter, &last, target);
+                if cut == last {
+                    self.items.push(last);
+                    return false;
+                }
+                if !cut.is_empty() {
+                    self.items.push(cut);
+                }
+                true
+            }
+        }
+    }
+}
+
+/// Content left out of an assembled prompt
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DroppedContent {
+    pub section: String,
+    /// Whole items removed (0 when the section was only shortened)
+    pub items: usize,
+    pub tokens: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct AssembledPrompt {
+    pub text: String,
+    pub tokens: usize,
+    pub budget: usize,
+    pub dropped: Vec<DroppedContent>,
+    /// Items kept per section, in section order
+    pub kept: Vec<(String, usize)>,
+    /// The sections as trimmed, for callers that send them separately
+    /// (e.g. as a chat's system instruction and turns)
+    pub sections: Vec<PromptSection>,
+}
+
+impl AssembledPrompt {
+    /// Items of `section` that made it into the prompt
+    pub fn kept_items(&self, section: &str) -> usize {
+        self.kept
+            .iter()
+            .find(|(name, _)| name == section)
+            .map(|(_, n)| *n)
+            .unwrap_or(0)
+    }
+}
+
+pub struct PromptAssembler {
+    counter: Arc<dyn TokenCounter>,
+    budget: usize,
+}
+
+impl PromptAssembler {
+    /// `reserved` tokens of `context_window` are kept free for the reply
+    pub fn new(counter: Arc<dyn TokenCounter>, context_window: usize, reserved: usize) -> Self {
+        Self {
+            counter,
+            budget: context_window.saturating_sub(reserved),
+        }
+    }
+
+    /// Estimated token counts, for backends without a local tokenizer
+    pub fn heuristic(context_window: usize, reserved: usize) -> Self {
+        Self::new(Arc::new(HeuristicTokenCounter), context_window, reserved)
+    }
+
+    /// Sized for a loaded local model, with its own tokenizer
+    pub fn for_local_model(model: &crate::LocalModel, reserved: usize) -> Self {
+        Self::new(model.token_counter(), model.context_window(), reserved)
+    }
+
+    /// Prompt tokens available
+    pub fn budget(&self) -> usize {
+        self.budget
+    }
+
+    pub fn counter(&self) -> Arc<dyn TokenCounter> {
+        self.counter.clone()
+    }
+
+    /// Join `sections` (in order, blank line between) within the budget.
+    /// Fails with `ContextWindowExceeded` only when the sections that may not
+    /// be trimmed do not fit on their own.
+    pub fn assemble(&self, mut sections: Vec<PromptSection>) -> Result<AssembledPrompt> {
+        let counter = self.counter.as_ref();
+        let original: Vec<(usize, usize)> = sections
+            .iter()
+            .map(|s| (s.items.len(), counter.count(&s.render())))
+            .collect();
+
+        for section in sections.iter_mut() {
+            if let Some(max) = section.max_tokens {
+                while counter.count(&section.render()) > max {
+                    let excess = counter.count(&section.render()) - max;
+                    if !section.trim_once(counter, excess) {
+                        break;
+                    }
+                }
+            }
+        }
+
+        let mut exhausted = vec![false; sections.len()];
+        let (text, tokens) = loop {
+            let text = join_sections(&sections);
+            let tokens = counter.count(&text);
+            if tokens <= self.budget {
+                break (text, tokens);
+            }
+
+            // Lowest priority first; among equals, the section furthest down
+            let candidate = sections
+                .iter()
+                .enumerate()
+                .filter(|(i, s)| {
+                    !exhausted[*i]
+                        && s.trim != Trim::Fixed
+                        && !s.items.is_empty()
+                        && counter.count(&s.render()) > s.min_tokens
+                })
+                .min_by_key(|(i, s)| (s.priority, std::cmp::Reverse(*i)))
+                .map(|(i, _)| i);
+            let Some(index) = candidate else {
+                log::warn!(
+                    "Prompt needs {} tokens but only {} fit after trimming",
+                    tokens,
+                    self.budget
+                );
+                return Err(AiError::ContextWindowExceeded);
+            };
+            if !sections[index].trim_once(counter, tokens - self.budget) {
+                exhausted[index] = true;
+            }
+        };
+
+        let dropped: Vec<DroppedContent> = sections
+            .iter()
+            .zip(&original)
+            .filter_map(|(section, (items, section_tokens))| {
+                let removed = section_tokens.saturating_sub(counter.count(&section.render()));
+                (removed > 0).then(|| DroppedContent {
+                    section: section.name.clone(),
+                    items: items.saturating_sub(section.items.len()),
+                    tokens: removed,
+                })
+            })
+            .collect();
+        if !dropped.is_empty() {
+            log::info!(
+                "Prompt trimmed to {}/{} tokens, dropped: {:?}",
+                tokens,
+                self.budget,
+                dropped
+            );
+        }
+
+        Ok(AssembledPrompt {
+            text,
+            tokens,
+            budget: self.budget,
+            dropped,
+            kept: sections
+                .iter()
+                .map(|s| (s.name.clone(), s.items.len()))
+                .collect(),
+            sections,
+        })
+    }
+}
+
+/// Sections joined with a blank line between, without any budget
+pub fn join_sections(sections: &[PromptSection]) -> String {
+    sections
+        .iter()
+        .map(PromptSection::render)
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assembler(budget: usize) -> PromptAssembler {
+        PromptAssembler::heuristic(budget + 100, 100)
+    }
+
+    fn history(n: usize) -> PromptSection {
+        PromptSection::items(
+            "history",
+            priority::HISTORY,
+            (0..n)
+                .map(|i| format!("Learner: turn number {} about friction", i))
+                .collect(),
+            Trim::DropOldest,
+        )
+        .with_header("Recent conversation:\n")
+    }
+
+    fn knowledge(n: usize) -> PromptSection {
+        PromptSection::items(
+            "knowledge",
+            priority::KNOWLEDGE,
+            (0..n)
+                .map(|i| format!("[S{}] Friction converts kinetic energy into heat.", i + 1))
+                .collect(),
+            Trim::DropLowest,
+        )
+    }
+
+    #[test]
+    fn test_fits_without_trimming() {
+        let prompt = assembler(1000)
+            .assemble(vec![
+                PromptSection::fixed("system", "You are Pete."),
+                history(2),
+                PromptSection::fixed("input", "Why does the train stop?"),
+            ])
+            .unwrap();
+        assert!(prompt.dropped.is_empty());
+        assert!(prompt
+            .text
+            .starts_with("You are Pete.\n\nRecent conversation:\n"));
+        assert!(prompt.text.ends_with("Why does the train stop?"));
+        assert_eq!(prompt.kept_items("history"), 2);
+    }
+
+    #[test]
+    fn test_lowest_priority_is_trimmed_first() {
+        let sections = vec![
+            PromptSection::fixed("system", "You are Pete, a Socratic guide."),
+            PromptSection::text("lore", priority::LORE, "The Iron Network. ".repeat(40)),
+            knowledge(4),
+            history(6),
+            PromptSection::fixed("input", "Why does the train stop?"),
+        ];
+        let full = assembler(10_000).assemble(sections.clone()).unwrap();
+        let budget = full.tokens - 60;
+        let prompt = assembler(budget).assemble(sections).unwrap();
+
+        assert!(prompt.tokens <= budget);
+        assert_eq!(prompt.dropped.len(), 1);
+        assert_eq!(prompt.dropped[0].section, "lore");
+        assert_eq!(prompt.kept_items("knowledge"), 4);
+        assert_eq!(prompt.kept_items("history"), 6);
+    }
+
+    #[test]
+    fn test_drops_lowest_ranked_chunks_then_oldest_history() {
+        let sections = vec![
+            PromptSection::fixed("system", "You are Pete."),
+            knowledge(5),
+            history(8),
+            PromptSection::fixed("input", "Why?"),
+        ];
+        let full = assembler(10_000).assemble(sections.clone()).unwrap();
+        let budget = full.tokens - 40;
+        let prompt = assembler(budget).assemble(sections.clone()).unwrap();
+        assert!(prompt.tokens <= budget);
+        assert_eq!(prompt.kept_items("history"), 8);
+        assert!(prompt.text.contains("[S1]") && !prompt.text.contains("[S5]"));
+
+        // Once the knowledge is gone, history loses its oldest turns
+        let budget = full.tokens - 100;
+        let prompt = assembler(budget).assemble(sections).unwrap();
+        assert!(prompt.tokens <= budget);
+        assert_eq!(prompt.kept_items("knowledge"), 0);
+        assert!(prompt.kept_items("history") < 8);
+        assert!(prompt.text.contains("turn number 7"));
+        assert!(!prompt.text.contains("turn number 0"));
+        let dropped: Vec<&str> = prompt.dropped.iter().map(|d| d.section.as_str()).collect();
+        assert_eq!(dropped, vec!["knowledge", "history"]);
+        assert_eq!(prompt.dropped[0].items, 5);
+    }
+
+    #[test]
+    fn test_min_and_max_budgets() {
+        let lore = "The Iron Network spans seven sectors. ".repeat(30);
+        let sections = vec![
+            PromptSection::fixed("system", "You are Pete."),
+            PromptSection::text("lore", priority::LORE, lore.clone()).with_max_tokens(50),
+            history(3),
+        ];
+        let prompt = assembler(10_000).assemble(sections).unwrap();
+        assert_eq!(prompt.dropped.len(), 1);
+        assert!(prompt.dropped[0].tokens > 0);
+        assert_eq!(prompt.kept_items("lore"), 1);
+
+        // A section at or below its minimum is never trimmed
+        let sections = vec![
+            PromptSection::text("lore", priority::LORE, lore).with_min_tokens(400),
+            history(3),
+        ];
+        let err = assembler(250).assemble(sections);
+        assert!(matches!(err, Err(AiError::ContextWindowExceeded)));
+    }
+
+    #[test]
+    fn test_fixed_sections_that_cannot_fit_are_an_error() {
+        let result = assembler(5).assemble(vec![PromptSection::fixed(
+            "system",
+            "This instruction alone is far longer than the tiny budget.",
+        )]);
+        assert!(matches!(result, Err(AiError::ContextWindowExceeded)));
+    }
+}
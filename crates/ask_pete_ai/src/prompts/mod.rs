@@ -1,3 +1,5 @@
+use super::memory_manager::ManagedHistory;
+use super::prompt_assembler::{join_sections, priority, PromptSection, Trim};
 use super::socratic_engine::SessionContext;
 use infra_db::conversation_memory::{Speaker, Turn};
 use serde::{Deserialize, Serialize};
@@ -10,6 +12,7 @@ pub enum PromptStrategy {
     Mirroring,   // Reflect their words back
     Challenging, // Logical inconsistency detected
     Affirming,   // Breakthrough moment detected
+    Support,     // Wellbeing concern → No Socratic prompting, share support resources
 }
 
 impl PromptStrategy {
@@ -40,26 +43,68 @@ impl PromptStrategy {
         Self::Mirroring
     }
 
-    /// Build the prompt for this strategy
+    /// Build the prompt for this strategy from the last three turns
     pub fn build_prompt(
         &self,
         user_input: &str,
         history: &[Turn],
         context: &SessionContext,
     ) -> String {
-        let base_system_prompt = self.get_system_prompt();
-        let conversation_context = self.format_history(history);
-        let strategy_instructions = self.get_strategy_instructions();
-
-        format!(
-            "{}\n\nContext:\n- Session Focus: {}\n- Archetype: {}\n\n{}\n\nCurrent user input: \"{}\"\n\n{}",
-            base_system_prompt,
-            context.focus_area.as_deref().unwrap_or("General reflection"),
+        let recent = history.iter().rev().take(3).rev().cloned().collect();
+        self.build_prompt_with_memory(user_input, &ManagedHistory::verbatim(recent), context)
+    }
+
+    /// Build the prompt for this strategy from budgeted history (summary,
+    /// learner facts and verbatim turns, see `memory_manager`)
+    pub fn build_prompt_with_memory(
+        &self,
+        user_input: &str,
+        memory: &ManagedHistory,
+        context: &SessionContext,
+    ) -> String {
+        join_sections(&self.prompt_sections(user_input, memory, context))
+    }
+
+    /// The prompt as sections for the `PromptAssembler`: instructions and the
+    /// learner's message are fixed, the summary and history can be trimmed
+    pub fn prompt_sections(
+        &self,
+        user_input: &str,
+        memory: &ManagedHistory,
+        context: &SessionContext,
+    ) -> Vec<PromptSection> {
+        let session_context = format!(
+            "Context:\n- Session Focus: {}\n- Archetype: {}",
+            context
+                .focus_area
+                .as_deref()
+                .unwrap_or("General reflection"),
             context.archetype.as_deref().unwrap_or("Unknown"),
-            conversation_context,
-            user_input,
-            strategy_instructions
-        )
+        );
+        let history = if memory.recent.is_empty() {
+            PromptSection::fixed("history", "No previous conversation.")
+        } else {
+            PromptSection::items(
+                "history",
+                priority::HISTORY,
+                memory.recent.iter().map(format_turn).collect(),
+                Trim::DropOldest,
+            )
+            .with_header("Recent conversation:\n")
+        };
+
+        vec![
+            PromptSection::fixed("system", self.get_system_prompt()),
+            PromptSection::fixed("context", session_context),
+            PromptSection::text(
+                "memory",
+                priority::MEMORY,
+                memory.format_memory().trim_end(),
+            ),
+            history,
+            PromptSection::fixed("input", format!("Current user input: \"{}\"", user_input)),
+            PromptSection::fixed("instructions", self.get_strategy_instructions()),
+        ]
     }
 
     fn get_system_prompt(&self) -> &str {
@@ -77,28 +122,6 @@ Guidelines:
 - Be warm, curious, never condescending"#
     }
 
-    fn format_history(&self, history: &[Turn]) -> String {
-        if history.is_empty() {
-            return "No previous conversation.".to_string();
-        }
-
-        let recent_turns: Vec<String> = history
-            .iter()
-            .rev()
-            .take(3)
-            .rev()
-            .map(|turn| {
-                let speaker = match turn.speaker {
-                    Speaker::User => "Learner",
-                    Speaker::AI => "You (AI Guide)",
-                };
-                format!("{}: {}", speaker, turn.content)
-            })
-            .collect();
-
-        format!("Recent conversation:\n{}", recent_turns.join("\n"))
-    }
-
     fn get_strategy_instructions(&self) -> &str {
         match self {
             Self::Scaffolding => {
@@ -116,10 +139,21 @@ Guidelines:
             Self::Affirming => {
                 "The learner has reached an insight. Acknowledge it and help them deepen it. Example: 'I notice you used the word \"finally.\" What makes this moment significant for you?'"
             }
+            Self::Support => {
+                "The learner may be in distress. Do not teach or ask probing questions. Respond warmly, briefly, and point them to the support resources provided."
+            }
         }
     }
 }
 
+fn format_turn(turn: &Turn) -> String {
+    let speaker = match turn.speaker {
+        Speaker::User => "Learner",
+        Speaker::AI => "You (AI Guide)",
+    };
+    format!("{}: {}", speaker, turn.content)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,153 @@
+//! # Cross-Encoder Reranker (Local, Candle)
+//!
+//! Scores (query, passage) pairs with a BERT cross-encoder such as
+//! `cross-encoder/ms-marco-MiniLM-L-6-v2` on the CPU. Slower than embeddings
+//! (one forward pass per pair) but much sharper, so it only rescores the
+//! top fused candidates of the `HybridRetriever`.
+//!
+//! The model directory must contain `config.json`, `tokenizer.json` and
+//! `model.safetensors` with a `bert.*` encoder, `bert.pooler.dense` and a
+//! single-logit `classifier` head.
+
+use crate::error::{AiError, Result};
+use crate::hybrid_retrieval::Reranker;
+use candle_core::{Device, Tensor};
+use candle_nn::{Linear, Module, VarBuilder};
+use candle_transformers::models::bert::{BertModel, Config as BertConfig, DTYPE};
+use std::path::{Path, PathBuf};
+use tokenizers::{PaddingParams, PaddingStrategy, Tokenizer, TruncationParams};
+
+#[derive(Debug, Clone)]
+pub struct RerankerConfig {
+    pub model_dir: PathBuf,
+    pub model_id: String,
+    pub max_length: usize,
+    pub batch_size: usize,
+}
+
+impl RerankerConfig {
+    pub const MS_MARCO_MINILM: &'static str = "cross-encoder/ms-marco-MiniLM-L-6-v2";
+
+    pub fn new(model_id: &str, model_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            model_dir: model_dir.into(),
+            model_id: model_id.to_string(),
+            max_length: 512,
+            batch_size: 16,
+        }
+    }
+}
+
+fn has_model_files(dir: &Path) -> bool {
+    ["config.json", "tokenizer.json", "model.safetensors"]
+        .iter()
+        .all(|file| dir.join(file).exists())
+}
+
+pub struct CrossEncoderReranker {
+    model: BertModel,
+    pooler: Linear,
+    classifier: Linear,
+    tokenizer: Tokenizer,
+    device: Device,
+    config: RerankerConfig,
+}
+
+impl CrossEncoderReranker {
+    pub fn load(config: RerankerConfig) -> Result<Self> {
+        log::info!(
+            "Loading reranker {} from {:?}",
+            config.model_id,
+            config.model_dir
+        );
+        if !has_model_files(&config.model_dir) {
+            return Err(AiError::ModelLoadFailed(format!(
+                "{:?} needs config.json, tokenizer.json and model.safetensors",
+                config.model_dir
+            )));
+        }
+
+        // Reranking runs on a handful of passages; CPU keeps the GPU free for generation
+        let device = Device::Cpu;
+
+        let bert_config: BertConfig = serde_json::from_str(&std::fs::read_to_string(
+            config.model_dir.join("config.json"),
+        )?)
+        .map_err(|e| AiError::ModelLoadFailed(format!("Invalid config.json: {}", e)))?;
+
+        let weights = config.model_dir.join("model.safetensors");
+        // SAFETY: the weights file is memory-mapped read-only and not modified while loaded
+        let vb = unsafe { VarBuilder::from_mmaped_safetensors(&[weights], DTYPE, &device)? };
+        let model = BertModel::load(vb.pp("bert"), &bert_config)?;
+        let hidden = bert_config.hidden_size;
+        let pooler = candle_nn::linear(hidden, hidden, vb.pp("bert.pooler.dense"))?;
+        let classifier = candle_nn::linear(hidden, 1, vb.pp("classifier"))?;
+
+        let mut tokenizer = Tokenizer::from_file(config.model_dir.join("tokenizer.json"))
+            .map_err(|e| AiError::ModelLoadFailed(format!("Failed to load tokenizer: {}", e)))?;
+        tokenizer.with_padding(Some(PaddingParams {
+            strategy: PaddingStrategy::BatchLongest,
+            ..Default::default()
+        }));
+        tokenizer
+            .with_truncation(Some(TruncationParams {
+                max_length: config.max_length,
+                ..Default::default()
+            }))
+            .map_err(|e| AiError::TokenizationFailed(e.to_string()))?;
+
+        log::info!("✅ Reranker loaded");
+
+        Ok(Self {
+            model,
+            pooler,
+            classifier,
+            tokenizer,
+            device,
+            config,
+        })
+    }
+
+    fn score_batch(&self, query: &str, passages: &[&str]) -> Result<Vec<f32>> {
+        let pairs: Vec<(&str, &str)> = passages.iter().map(|p| (query, *p)).collect();
+        let encodings = self
+            .tokenizer
+            .encode_batch(pairs, true)
+            .map_err(|e| AiError::TokenizationFailed(e.to_string()))?;
+
+        let stack = |rows: Vec<&[u32]>| -> Result<Tensor> {
+            let tensors: Vec<Tensor> = rows
+                .into_iter()
+                .map(|row| Tensor::new(row, &self.device))
+                .collect::<candle_core::Result<_>>()?;
+            Ok(Tensor::stack(&tensors, 0)?)
+        };
+        let input_ids = stack(encodings.iter().map(|e| e.get_ids()).collect())?;
+        // Segment ids tell the encoder which tokens are query and which are passage
+        let token_type_ids = stack(encodings.iter().map(|e| e.get_type_ids()).collect())?;
+        let attention_mask = stack(encodings.iter().map(|e| e.get_attention_mask()).collect())?;
+
+        // (batch, seq, hidden) -> [CLS] -> pooler -> one relevance logit
+        let hidden = self
+            .model
+            .forward(&input_ids, &token_type_ids, Some(&attention_mask))?;
+        let cls = hidden.narrow(1, 0, 1)?.squeeze(1)?;
+        let pooled = self.pooler.forward(&cls)?.tanh()?;
+        let logits = self.classifier.forward(&pooled)?.squeeze(1)?;
+        Ok(logits.to_vec1::<f32>()?)
+    }
+}
+
+impl Reranker for CrossEncoderReranker {
+    fn model_id(&self) -> &str {
+        &self.config.model_id
+    }
+
+    fn score(&self, query: &str, passages: &[&str]) -> anyhow::Result<Vec<f32>> {
+        let mut scores = Vec::with_capacity(passages.len());
+        for batch in passages.chunks(self.config.batch_size.max(1)) {
+            scores.extend(self.score_batch(query, batch)?);
+        }
+        Ok(scores)
+    }
+}
@@ -1,10 +1,27 @@
 use crate::architect::{BlueprintRequest, BlueprintResponse};
+use crate::citations::resolve_citations;
+use crate::guardrail::{AnswerGuardrail, AnswerKey, GuardrailIntervention, RewriteMethod};
 use crate::iron_split::IronSplitSystem;
-use crate::knowledge_retrieval::{format_chunks_for_prompt, retrieve_knowledge};
+use crate::knowledge_retrieval::{
+    knowledge_section, retrieve_knowledge, retrieve_knowledge_semantic,
+};
+use crate::llm::backend::LlmBackend;
+use crate::llm::gemini_client::{ChatRequest, ChatRole, ChatTurn, GeminiClient};
+use crate::lore::{LorePack, LoreRegistry};
+use crate::memory_manager::{ManagedHistory, MemoryManager};
+use crate::prompt_assembler::{
+    join_sections, priority, AssembledPrompt, PromptAssembler, PromptSection, Trim,
+    DEFAULT_CONTEXT_WINDOW,
+};
 use crate::prompts::PromptStrategy;
+use crate::strategy_selector::{SelectionReason, StrategySelector};
+use crate::vocabulary::{VocabularyPack, VocabularyRegistry};
+use crate::wellbeing::{WellbeingAssessment, WellbeingConfig, WellbeingMonitor};
 use anyhow::Result;
 use chrono::Utc;
-use infra_db::conversation_memory::{ConversationMemory, Speaker, Turn};
+use infra_db::conversation_memory::{Citation, ConversationMemory, Speaker, Turn, TurnMetadata};
+use infra_db::vector_store::{MetadataFilter, VectorStore};
+use pete_core::frameworks::NarrativeFramework;
 use serde::{Deserialize, Serialize};
 use sqlx::PgPool;
 use std::sync::{Arc, Mutex};
@@ -15,6 +32,14 @@ use uuid::Uuid;
 pub struct SocraticResponse {
     pub text: String,
     pub strategy_used: PromptStrategy,
+    #[serde(default)]
+    pub strategy_reason: SelectionReason,
+    /// Set when the wellbeing monitor bypassed the Socratic reply
+    #[serde(default)]
+    pub wellbeing: Option<WellbeingAssessment>,
+    /// Knowledge sources the reply cites, verified against the prompt
+    #[serde(default)]
+    pub citations: Vec<Citation>,
 }
 
 /// Context for the current session
@@ -23,6 +48,14 @@ pub struct SessionContext {
     pub user_id: i64,
     pub archetype: Option<String>,
     pub focus_area: Option<String>,
+    /// Answer key of the station the learner is on, checked by the guardrail
+    pub answer_key: Option<AnswerKey>,
+    /// Course whose knowledge corpus Pete may draw on (None = all uploads)
+    pub course_id: Option<String>,
+    /// Vocabulary pack for the course or graph (None = default pack)
+    pub vocabulary_pack: Option<String>,
+    /// Lore pack for the campaign or graph (None = the Iron Network)
+    pub lore_pack: Option<String>,
 }
 
 /// Main Socratic dialogue engine
@@ -33,7 +66,15 @@ pub struct SocraticEngine {
     // weigh_station: Option<crate::weigh_station::WeighStation>, // Removed
     memory: Arc<ConversationMemory>,
     db_pool: Option<PgPool>,
+    vector_store: Option<Arc<dyn VectorStore>>,
     iron_split: Option<Arc<Mutex<IronSplitSystem>>>,
+    backend: Option<Arc<dyn LlmBackend>>,
+    strategy_selector: StrategySelector,
+    guardrail: AnswerGuardrail,
+    wellbeing: WellbeingMonitor,
+    memory_manager: MemoryManager,
+    vocabulary: Option<Arc<VocabularyRegistry>>,
+    lore: Option<Arc<LoreRegistry>>,
 }
 
 impl SocraticEngine {
@@ -46,7 +87,15 @@ impl SocraticEngine {
             // weigh_station: None,
             memory,
             db_pool: None,
+            vector_store: None,
             iron_split: None,
+            backend: None,
+            strategy_selector: StrategySelector::default(),
+            guardrail: AnswerGuardrail::new(),
+            wellbeing: WellbeingMonitor::default(),
+            memory_manager: MemoryManager::default(),
+            vocabulary: None,
+            lore: None,
         }
     }
 
@@ -56,6 +105,12 @@ impl SocraticEngine {
         log::info!("Database pool connected to Socratic engine for RAG");
     }
 
+    /// Set the local vector store; semantic search then replaces keyword RAG
+    pub fn set_vector_store(&mut self, store: Arc<dyn VectorStore>) {
+        self.vector_store = Some(store);
+        log::info!("Vector store connected to Socratic engine for RAG");
+    }
+
     /// Set the Gemini client for LLM inference
     pub fn set_gemini_client(&mut self, client: crate::llm::gemini_client::GeminiClient) {
         self.gemini_client = Some(client);
@@ -74,16 +129,83 @@ impl SocraticEngine {
         // let weigh_station = crate::weigh_station::WeighStation::new(model.clone());
         // self.weigh_station = Some(weigh_station);
 
+        if self.iron_split.is_none() {
+            self.use_token_counter(model.token_counter());
+        }
         self.local_model = Some(model);
         log::info!("Local model connected to Socratic engine");
     }
 
     /// Set the Iron Split System (Mistral 7B)
     pub fn set_iron_split(&mut self, system: Arc<Mutex<IronSplitSystem>>) {
+        self.use_token_counter(system.lock().unwrap().token_counter());
         self.iron_split = Some(system);
         log::info!("Iron Split System connected to Socratic engine");
     }
 
+    /// Measure history budgets with the active backend's tokenizer
+    fn use_token_counter(&mut self, counter: Arc<dyn crate::tokens::TokenCounter>) {
+        let config = self.memory_manager.config().clone();
+        self.memory_manager = MemoryManager::new(config, counter);
+    }
+
+    /// Prompt assembler sized for the backend `generate_text` will use, with
+    /// `reserved` tokens left for the reply
+    pub fn prompt_assembler(&self, reserved: usize) -> PromptAssembler {
+        if self.backend.is_some() {
+            PromptAssembler::heuristic(DEFAULT_CONTEXT_WINDOW, reserved)
+        } else if let Some(ref iron_system) = self.iron_split {
+            let counter = iron_system.lock().unwrap().token_counter();
+            PromptAssembler::new(
+                counter,
+                crate::iron_split::IRON_SPLIT_CONTEXT_WINDOW,
+                reserved,
+            )
+        } else if let Some(ref model) = self.local_model {
+            PromptAssembler::for_local_model(model, reserved)
+        } else {
+            PromptAssembler::heuristic(DEFAULT_CONTEXT_WINDOW, reserved)
+        }
+    }
+
+    /// Set a text backend; it takes precedence over the built-in models
+    /// (e.g. the mock backend for offline evals)
+    pub fn set_backend(&mut self, backend: Arc<dyn LlmBackend>) {
+        log::info!(
+            "Backend '{}' connected to Socratic engine",
+            backend.model_id()
+        );
+        self.backend = Some(backend);
+    }
+
+    /// Replace the strategy selector (e.g. with a course-specific misconception list)
+    pub fn set_strategy_selector(&mut self, selector: StrategySelector) {
+        self.strategy_selector = selector;
+    }
+
+    /// Set the vocabulary packs; the session's pack then feeds key terms into
+    /// the prompt and its misconceptions into strategy selection
+    pub fn set_vocabulary_packs(&mut self, registry: Arc<VocabularyRegistry>) {
+        self.vocabulary = Some(registry);
+    }
+
+    /// Set the lore packs; the session's pack then sets Pete's persona voice
+    /// and station flavor text
+    pub fn set_lore_packs(&mut self, registry: Arc<LoreRegistry>) {
+        self.lore = Some(registry);
+    }
+
+    /// Set the institution's wellbeing thresholds and support resources
+    pub fn set_wellbeing_config(&mut self, config: WellbeingConfig) {
+        log::info!("Wellbeing monitor configured for '{}'", config.institution);
+        self.wellbeing = WellbeingMonitor::new(config);
+    }
+
+    /// Replace the history budgets / token counter (e.g. with the local model's tokenizer)
+    pub fn set_memory_manager(&mut self, manager: MemoryManager) {
+        self.memory_manager = manager;
+    }
+
     /// Generate a Socratic response to user input
     pub async fn respond(
         &mut self,
@@ -105,122 +227,434 @@ impl SocraticEngine {
         };
         self.memory.add_turn(context.session_id, user_turn).await?;
 
-        // 2. Retrieve recent conversation history
+        // 1b. Wellbeing check runs before any Socratic prompting
+        let assessment = self.wellbeing.classifier().assess(user_input);
+        if assessment.is_hit() {
+            return self.respond_with_support(context, assessment).await;
+        }
+
+        // 2. Retrieve recent conversation history, folding older turns into the summary
         let history = self.memory.get_recent(context.session_id, 10).await?;
         log::debug!(
             "Retrieved {} turns from conversation history",
             history.len()
         );
+        let managed_history = self.managed_history(context.session_id).await?;
 
-        // 3. Retrieve relevant knowledge from RAG database
-        let knowledge_context = if let Some(ref pool) = self.db_pool {
-            match retrieve_knowledge(user_input, pool, Some(3)).await {
+        // 3. Retrieve relevant knowledge (hybrid store first, keyword search as fallback),
+        //    limited to the learner's course corpus
+        let course_filter = match &context.course_id {
+            Some(course) => MetadataFilter::new().eq("course_id", course.as_str()),
+            None => MetadataFilter::default(),
+        };
+        let mut knowledge_chunks = Vec::new();
+        if let Some(ref store) = self.vector_store {
+            match retrieve_knowledge_semantic(user_input, store.as_ref(), Some(3), &course_filter)
+                .await
+            {
                 Ok(chunks) => {
-                    log::info!("Retrieved {} knowledge chunks for RAG", chunks.len());
-                    format_chunks_for_prompt(&chunks)
+                    log::info!("Retrieved {} knowledge chunks by similarity", chunks.len());
+                    knowledge_chunks = chunks;
                 }
-                Err(e) => {
-                    log::warn!("Failed to retrieve knowledge: {}", e);
-                    String::new()
+                Err(e) => log::warn!("Failed to search vector store: {}", e),
+            }
+        }
+        if knowledge_chunks.is_empty() {
+            if let Some(ref pool) = self.db_pool {
+                match retrieve_knowledge(user_input, pool, Some(3), context.course_id.as_deref())
+                    .await
+                {
+                    Ok(chunks) => {
+                        log::info!("Retrieved {} knowledge chunks for RAG", chunks.len());
+                        knowledge_chunks = chunks;
+                    }
+                    Err(e) => {
+                        log::warn!("Failed to retrieve knowledge: {}", e);
+                    }
                 }
+            } else {
+                log::debug!("No database pool available for RAG retrieval");
             }
-        } else {
-            log::debug!("No database pool available for RAG retrieval");
-            String::new()
+        }
+        // 4. Select prompting strategy from history, knowledge and user input,
+        //    watching for the vocabulary pack's misconceptions too
+        let pack = self
+            .vocabulary
+            .as_ref()
+            .map(|registry| registry.resolve(context.vocabulary_pack.as_deref()));
+        let selection = match pack {
+            Some(pack) => self
+                .strategy_selector
+                .with_misconceptions(pack.misconceptions())
+                .select(user_input, &history, &knowledge_chunks),
+            None => self
+                .strategy_selector
+                .select(user_input, &history, &knowledge_chunks),
         };
+        let strategy = selection.strategy;
+        log::debug!(
+            "Selected strategy: {:?} ({})",
+            strategy,
+            selection.reason.describe()
+        );
 
-        // 4. Select prompting strategy based on user input
-        let strategy = PromptStrategy::select_strategy(user_input, &history);
-        log::debug!("Selected strategy: {:?}", strategy);
-
-        // 5. Build prompt with template (including RAG knowledge)
-        let mut prompt = strategy.build_prompt(user_input, &history, context);
-
-        // Inject knowledge context before user question if available
-        if !knowledge_context.is_empty() {
-            prompt = format!("{}{}", knowledge_context, prompt);
-            log::debug!(
-                "Injected {} chars of knowledge into prompt",
-                knowledge_context.len()
-            );
+        // 5. Build prompt with template (including RAG knowledge), trimmed to
+        //    the model's context window
+        let mut sections = vec![knowledge_section(&knowledge_chunks)];
+        if let Some(pack) = pack {
+            sections.extend(vocabulary_section(pack, user_input, context));
+        }
+        let mut strategy_sections = strategy.prompt_sections(user_input, &managed_history, context);
+        if let Some(ref lore) = self.lore {
+            let pack = lore.resolve(context.lore_pack.as_deref());
+            // Persona and station follow the Socratic system prompt
+            let at = strategy_sections
+                .iter()
+                .position(|s| s.name == "system")
+                .map_or(0, |i| i + 1);
+            strategy_sections.splice(at..at, lore_sections(pack, context));
+        }
+        sections.extend(strategy_sections);
+
+        // Tell the model what the dispatcher noticed, so the pushback is specific
+        if selection.reason != SelectionReason::Heuristic {
+            sections.push(PromptSection::fixed(
+                "noticed",
+                format!("What you noticed: {}", selection.reason.describe()),
+            ));
         }
-        log::debug!("Built prompt: {} chars", prompt.len());
+
+        let assembled = self.prompt_assembler(1024).assemble(sections)?;
+        // Only chunks that made it into the prompt can be cited
+        knowledge_chunks.truncate(assembled.kept_items("knowledge"));
+        log::debug!(
+            "Built prompt: {}/{} tokens, {} knowledge chunks",
+            assembled.tokens,
+            assembled.budget,
+            knowledge_chunks.len()
+        );
+        let request =
+            chat_request(&assembled, &managed_history.recent, user_input).with_max_tokens(1024);
 
         // 6. Generate response using LLM
-        let response_text = if let Some(ref iron_system) = self.iron_split {
-            // Use Iron Split (Navigator)
-            let mut system = iron_system.lock().unwrap();
-            match system.ask_navigator(&prompt) {
-                Ok(text) => text,
-                Err(e) => {
-                    log::error!("Iron Split Navigator failed: {}", e);
-                    "I'm having trouble with my navigation systems.".to_string()
-                }
+        let response_text = match self.generate_reply(&assembled.text, &request).await {
+            Some(Ok(text)) => text,
+            Some(Err(fallback)) => fallback,
+            None => {
+                // Fallback if no model is connected
+                log::warn!("Gemini client not connected, using fallback response");
+                "I'm listening. Can you tell me more about that?".to_string()
             }
-        } else if let Some(ref model) = self.local_model {
-            // Use local model
-            let config = crate::local_inference::GenerationConfig {
-                max_tokens: 1024,
-                temperature: 0.7,
-                top_p: 0.9,
-                repeat_penalty: 1.1,
-            };
-            match model.generate(prompt.clone(), config).await {
-                Ok(text) => text,
-                Err(e) => {
-                    log::error!("Local model generation failed: {}", e);
-                    "I'm having trouble accessing my local memory banks.".to_string()
-                }
-            }
-        } else if let Some(ref mut gemini_client) = self.gemini_client {
-            // Actual inference using Gemini
-            match gemini_client.generate(&prompt).await {
-                Ok(text) => text,
-                Err(e) => {
-                    log::error!("Gemini generation failed: {}", e);
-                    "I'm having trouble connecting to my thoughts (Gemini API Error).".to_string()
-                }
-            }
-        } else {
-            // Fallback if Gemini client not connected
-            log::warn!("Gemini client not connected, using fallback response");
-            "I'm listening. Can you tell me more about that?".to_string()
         };
 
         // 7. Post-process response
         let processed_response = Self::post_process_response(&response_text);
 
-        // 8. Save AI's turn to memory
+        // 7b. Guardrail: never hand out the answer to the current station
+        let processed_response = self
+            .apply_guardrail(user_input, processed_response, context)
+            .await;
+
+        // 7c. Keep only source references that match the injected knowledge
+        let cited = resolve_citations(&processed_response, &knowledge_chunks);
+        if !cited.rejected.is_empty() {
+            log::warn!(
+                "Dropped {} unverified source reference(s): {:?}",
+                cited.rejected.len(),
+                cited.rejected
+            );
+        }
+        let processed_response = cited.text;
+
+        // 8. Save AI's turn to memory (with the strategy rationale and citations for researchers)
+        let mut ai_metadata = TurnMetadata::from_content(&processed_response);
+        ai_metadata.strategy = Some(format!("{:?}", strategy));
+        ai_metadata.strategy_rationale = Some(selection.reason.describe());
+        ai_metadata.citations = cited.citations.clone();
         let ai_turn = Turn {
             id: Uuid::new_v4(),
             timestamp: Utc::now(),
             speaker: Speaker::AI,
             content: processed_response.clone(),
-            metadata: Default::default(),
+            metadata: ai_metadata,
         };
         self.memory.add_turn(context.session_id, ai_turn).await?;
 
         // 9. Generate Steam (Mastery) & Sync to Antigravity
         // Simple heuristic: 1 Steam per successful turn
         let steam_earned = pete_core::economy::Steam(1.0);
-        if let Some(ref client) = self.antigravity_client {
+        if let Some(ref pool) = self.db_pool {
+            // Balance and sync event commit together; the outbox worker delivers it
+            match crate::antigravity::outbox::award_steam(
+                pool,
+                context.user_id,
+                steam_earned,
+                "socratic_dialogue",
+            )
+            .await
+            {
+                Ok(Some(event)) => log::info!(
+                    "Generated Steam: {:.2}, queued for Antigravity as {}",
+                    steam_earned.0,
+                    event.idempotency_key
+                ),
+                Ok(None) => log::warn!("No user {} to award Steam to", context.user_id),
+                Err(e) => log::warn!("Failed to record Steam award: {:#}", e),
+            }
+        } else if let Some(ref client) = self.antigravity_client {
+            // No database to make the award durable: best-effort direct sync
             let user_id_str = context.user_id.to_string();
-            // Fire and forget sync (don't block response)
-            let _ = client
+            if let Err(e) = client
                 .sync_steam(&user_id_str, steam_earned, "socratic_dialogue")
-                .await;
-            log::info!(
-                "Generated Steam: {:.2} and syncing to Antigravity",
-                steam_earned.0
-            );
+                .await
+            {
+                log::warn!("{:#}", e);
+            }
         }
 
         Ok(SocraticResponse {
             text: processed_response,
             strategy_used: strategy,
+            strategy_reason: selection.reason,
+            wellbeing: None,
+            citations: cited.citations,
         })
     }
 
+    /// The session's history within its token budget: older turns are folded
+    /// into the rolling summary (by the model when available) and saved.
+    async fn managed_history(&mut self, session_id: Uuid) -> Result<ManagedHistory> {
+        let turns = self.memory.get_recent(session_id, usize::MAX).await?;
+        let state = self
+            .memory
+            .get_summary(session_id)
+            .await?
+            .unwrap_or_default();
+        let plan = self.memory_manager.plan(&turns, &state);
+        if plan.fold.is_empty() {
+            return Ok(self.memory_manager.assemble(&state, plan.recent));
+        }
+
+        let prompt = self
+            .memory_manager
+            .summary_prompt(&state.summary, &plan.fold);
+        let max_tokens = self.memory_manager.config().summary_budget;
+        let model_summary = match self.generate_text(&prompt, max_tokens).await {
+            Some(Ok(text)) => Some(text),
+            _ => None,
+        };
+        let state = self.memory_manager.fold(&state, &plan.fold, model_summary);
+        log::debug!(
+            "Folded {} turns into the summary of session {} ({} total)",
+            plan.fold.len(),
+            session_id,
+            state.folded_turns
+        );
+        if let Err(e) = self.memory.save_summary(session_id, &state).await {
+            log::warn!("Failed to persist conversation summary: {}", e);
+        }
+
+        Ok(self.memory_manager.assemble(&state, plan.recent))
+    }
+
+    /// Bypass the gamified reply: share support resources, raise an instructor
+    /// alert and award no Steam.
+    async fn respond_with_support(
+        &mut self,
+        context: &SessionContext,
+        assessment: WellbeingAssessment,
+    ) -> Result<SocraticResponse> {
+        let reply = self.wellbeing.classifier().support_reply(assessment.level);
+
+        self.wellbeing
+            .raise_alert(
+                context.user_id,
+                context.session_id,
+                &assessment,
+                self.db_pool.as_ref(),
+            )
+            .await;
+
+        let mut metadata = TurnMetadata::from_content(&reply);
+        metadata.strategy = Some(format!("{:?}", PromptStrategy::Support));
+        metadata.strategy_rationale =
+            Some(format!("Wellbeing monitor: {}", assessment.level.as_str()));
+        let ai_turn = Turn {
+            id: Uuid::new_v4(),
+            timestamp: Utc::now(),
+            speaker: Speaker::AI,
+            content: reply.clone(),
+            metadata,
+        };
+        self.memory.add_turn(context.session_id, ai_turn).await?;
+
+        Ok(SocraticResponse {
+            text: reply,
+            strategy_used: PromptStrategy::Support,
+            strategy_reason: SelectionReason::Heuristic,
+            wellbeing: Some(assessment),
+            citations: Vec::new(),
+        })
+    }
+
+    /// Acknowledge an in-memory wellbeing alert
+    pub fn acknowledge_wellbeing_alert(&mut self, alert_id: Uuid, instructor_id: i64) -> bool {
+        self.wellbeing.acknowledge(alert_id, instructor_id)
+    }
+
+    /// The configured support resources (shown by clients in place of game UI)
+    pub fn support_resources(&self) -> crate::wellbeing::SupportResources {
+        self.wellbeing.classifier().config().resources.clone()
+    }
+
+    /// Generate the learner-facing reply. Gemini gets the conversation as a
+    /// chat (`request`); the other backends take the flat `prompt`.
+    async fn generate_reply(
+        &mut self,
+        prompt: &str,
+        request: &ChatRequest,
+    ) -> Option<std::result::Result<String, String>> {
+        let flat_backend =
+            self.backend.is_some() || self.iron_split.is_some() || self.local_model.is_some();
+        match self.gemini_client {
+            Some(ref mut gemini_client) if !flat_backend => {
+                Some(generate_with_gemini(gemini_client, request).await)
+            }
+            _ => {
+                self.generate_text(prompt, request.max_tokens.unwrap_or(1024))
+                    .await
+            }
+        }
+    }
+
+    /// Run a prompt through the best available backend.
+    ///
+    /// Returns `None` when no model is connected, and `Some(Err(..))` with an
+    /// in-character fallback message when the backend failed.
+    async fn generate_text(
+        &mut self,
+        prompt: &str,
+        max_tokens: usize,
+    ) -> Option<std::result::Result<String, String>> {
+        if let Some(ref backend) = self.backend {
+            Some(backend.generate(prompt, max_tokens).await.map_err(|e| {
+                log::error!("Backend '{}' failed: {}", backend.model_id(), e);
+                "I'm having trouble finding my words right now.".to_string()
+            }))
+        } else if let Some(ref iron_system) = self.iron_split {
+            // Use Iron Split (Navigator)
+            let mut system = iron_system.lock().unwrap();
+            Some(system.ask_navigator(prompt).map_err(|e| {
+                log::error!("Iron Split Navigator failed: {}", e);
+                "I'm having trouble with my navigation systems.".to_string()
+            }))
+        } else if let Some(ref model) = self.local_model {
+            // Use local model
+            let config = crate::local_inference::GenerationConfig {
+                max_tokens,
+                temperature: 0.7,
+                top_p: 0.9,
+                repeat_penalty: 1.1,
+            };
+            Some(
+                model
+                    .generate(prompt.to_string(), config)
+                    .await
+                    .map_err(|e| {
+                        log::error!("Local model generation failed: {}", e);
+                        "I'm having trouble accessing my local memory banks.".to_string()
+                    }),
+            )
+        } else if let Some(ref mut gemini_client) = self.gemini_client {
+            // Actual inference using Gemini
+            let request = ChatRequest::prompt(prompt).with_max_tokens(max_tokens);
+            Some(generate_with_gemini(gemini_client, &request).await)
+        } else {
+            None
+        }
+    }
+
+    /// Model `generate_text` will use, in the same order of precedence
+    pub fn model_id(&self) -> String {
+        if let Some(ref backend) = self.backend {
+            backend.model_id()
+        } else if self.iron_split.is_some() {
+            "iron-split".to_string()
+        } else if let Some(ref model) = self.local_model {
+            model.model_id()
+        } else if let Some(ref client) = self.gemini_client {
+            format!("gemini:{}", client.config().model)
+        } else {
+            "none".to_string()
+        }
+    }
+
+    /// Raw completion for non-chat callers (e.g. Pete's scenario review).
+    /// `None` when no model is connected or generation failed.
+    pub async fn complete(&mut self, prompt: &str, max_tokens: usize) -> Option<String> {
+        self.generate_text(prompt, max_tokens).await?.ok()
+    }
+
+    /// Check a draft against the station's answer key and rewrite it if it leaks.
+    async fn apply_guardrail(
+        &mut self,
+        user_input: &str,
+        draft: String,
+        context: &SessionContext,
+    ) -> String {
+        let key = match context.answer_key {
+            Some(ref key) if !key.is_empty() => key,
+            _ => return draft,
+        };
+
+        let leaks = self.guardrail.detect(&draft, key);
+        if leaks.is_empty() {
+            return draft;
+        }
+
+        // Second model pass first; fall back to a template if it fails or still leaks.
+        let rewrite_prompt = self.guardrail.rewrite_prompt(&draft, &leaks, user_input);
+        let (rewritten, method) = match self.generate_text(&rewrite_prompt, 128).await {
+            Some(Ok(text)) => {
+                let candidate = Self::post_process_response(&text);
+                if self.guardrail.detect(&candidate, key).is_empty() {
+                    (candidate, RewriteMethod::ModelPass)
+                } else {
+                    (
+                        self.guardrail.template_rewrite(&leaks, user_input),
+                        RewriteMethod::Template,
+                    )
+                }
+            }
+            _ => (
+                self.guardrail.template_rewrite(&leaks, user_input),
+                RewriteMethod::Template,
+            ),
+        };
+
+        let intervention = GuardrailIntervention {
+            id: Uuid::new_v4(),
+            session_id: context.session_id,
+            user_id: context.user_id,
+            node_id: key.node_id.clone(),
+            leaks,
+            original: draft,
+            rewritten: rewritten.clone(),
+            method,
+            timestamp: Utc::now(),
+        };
+        self.guardrail
+            .record(intervention, self.db_pool.as_ref())
+            .await;
+
+        rewritten
+    }
+
+    /// Guardrail interventions recorded since startup (newest last)
+    pub fn guardrail_interventions(&self) -> Vec<GuardrailIntervention> {
+        self.guardrail.interventions().cloned().collect()
+    }
+
     /// Analyze the cognitive load of content
     pub async fn analyze_load(
         &self,
@@ -235,7 +669,11 @@ impl SocraticEngine {
     }
 
     /// Generate a curriculum blueprint (StoryGraph)
-    pub async fn generate_blueprint(&mut self, req: BlueprintRequest) -> Result<BlueprintResponse> {
+    pub async fn generate_blueprint(
+        &mut self,
+        req: BlueprintRequest,
+        framework: Option<Arc<dyn NarrativeFramework>>,
+    ) -> Result<BlueprintResponse> {
         log::info!(
             "Socratic Engine: Generating blueprint for '{}'",
             req.subject
@@ -244,7 +682,11 @@ impl SocraticEngine {
         // Priority 1: Iron Split (Architect)
         if let Some(ref iron_system) = self.iron_split {
             let mut system = iron_system.lock().unwrap();
-            let prompt = format!("Create a curriculum for: {}", req.subject);
+            let mut prompt = format!("Create a curriculum for: {}", req.subject);
+            if let Some(ref framework) = framework {
+                prompt.push_str("\n\n");
+                prompt.push_str(&framework.prompt_guidance());
+            }
             let json_text = system.ask_architect(&prompt)?;
 
             // Parse JSON using our robust parser
@@ -256,11 +698,12 @@ impl SocraticEngine {
         let gemini = self.gemini_client.clone();
         let local = self.local_model.clone();
 
-        if gemini.is_none() && local.is_none() {
-            return Err(anyhow::anyhow!("No AI model available for Architect"));
-        }
-
-        let mut architect = crate::architect::CurriculumArchitect::new(gemini, local);
+        let vocabulary = self.vocabulary.clone().unwrap_or_default();
+        let lore = self.lore.clone().unwrap_or_default();
+        let mut architect = crate::architect::CurriculumArchitect::new(gemini, local)
+            .with_vocabulary(vocabulary)
+            .with_lore(lore)
+            .with_framework(framework);
 
         architect.generate_blueprint(req).await
     }
@@ -269,8 +712,7 @@ impl SocraticEngine {
     fn post_process_response(response: &str) -> String {
         let mut processed = response.trim().to_string();
 
-        // Ensure response doesn't give direct answers (basic heuristic)
-        // TODO: More sophisticated filtering
+        // Answer leaks are handled by the guardrail stage (see `apply_guardrail`)
 
         // Ensure response ends with a question mark
         if !processed.ends_with('?') {
@@ -282,3 +724,210 @@ impl SocraticEngine {
         processed
     }
 }
+
+/// Gemini's reply, or an in-character fallback message when the API failed
+async fn generate_with_gemini(
+    client: &mut GeminiClient,
+    request: &ChatRequest,
+) -> std::result::Result<String, String> {
+    client
+        .generate_chat(request)
+        .await
+        .map(|reply| reply.text)
+        .map_err(|e| {
+            log::error!("Gemini generation failed: {:#}", e);
+            "I'm having trouble connecting to my thoughts (Gemini API Error).".to_string()
+        })
+}
+
+/// Sections that become the chat's system instruction
+const SYSTEM_SECTIONS: [&str; 2] = ["system", "persona"];
+
+/// The assembled reply prompt as a chat: the Socratic rules and persona are
+/// the system instruction, the history that survived trimming becomes turns,
+/// and everything else (knowledge, context, instructions, the learner's
+/// message) is the final user turn.
+fn chat_request(assembled: &AssembledPrompt, recent: &[Turn], user_input: &str) -> ChatRequest {
+    let (system, rest): (Vec<PromptSection>, Vec<PromptSection>) = assembled
+        .sections
+        .iter()
+        .filter(|s| s.name != "history")
+        .cloned()
+        .partition(|s| SYSTEM_SECTIONS.contains(&s.name.as_str()));
+
+    // The assembler drops history from the front
+    let kept = assembled.kept_items("history").min(recent.len());
+    let mut history = &recent[recent.len() - kept..];
+    // The learner's message is already saved; it goes in the final turn instead
+    if let Some((last, earlier)) = history.split_last() {
+        if last.speaker == Speaker::User && last.content == user_input {
+            history = earlier;
+        }
+    }
+
+    let mut turns: Vec<ChatTurn> = Vec::new();
+    let mut push = |role: ChatRole, text: String| match turns.last_mut() {
+        // Gemini expects user and model turns to alternate
+        Some(last) if last.role == role => {
+            last.text.push_str("\n\n");
+            last.text.push_str(&text);
+        }
+        _ => turns.push(ChatTurn { role, text }),
+    };
+    for turn in history {
+        let role = match turn.speaker {
+            Speaker::User => ChatRole::User,
+            Speaker::AI => ChatRole::Model,
+        };
+        push(role, turn.content.clone());
+    }
+    push(ChatRole::User, join_sections(&rest));
+
+    ChatRequest {
+        system: Some(join_sections(&system)).filter(|s| !s.is_empty()),
+        turns,
+        max_tokens: None,
+    }
+}
+
+/// Pack terms the learner used (or that name the focus area), with their
+/// definitions, so Pete uses the course's wording. Least relevant terms go
+/// first when the prompt is tight.
+fn vocabulary_section(
+    pack: &VocabularyPack,
+    user_input: &str,
+    context: &SessionContext,
+) -> Option<PromptSection> {
+    let mut terms = pack.terms_in(user_input);
+    if let Some(ref focus) = context.focus_area {
+        for term in pack.terms_in(focus) {
+            if !terms.iter().any(|t| t.word == term.word) {
+                terms.push(term);
+            }
+        }
+    }
+    if terms.is_empty() {
+        return None;
+    }
+    Some(
+        PromptSection::items(
+            "vocabulary",
+            priority::VOCABULARY,
+            terms
+                .iter()
+                .map(|t| format!("- {}: {}", t.word, t.definition))
+                .collect(),
+            Trim::DropLowest,
+        )
+        .with_header(format!("Key vocabulary ({}):\n", pack.name)),
+    )
+}
+
+/// Pete's persona voice, plus the flavor text of the learner's station if
+/// the pack has any (dropped before anything else when space is short)
+fn lore_sections(pack: &LorePack, context: &SessionContext) -> Vec<PromptSection> {
+    let mut sections = vec![PromptSection::fixed("persona", pack.persona.prompt())];
+    let flavor = context
+        .answer_key
+        .as_ref()
+        .and_then(|key| pack.station_flavor(&key.node_id));
+    if let Some(flavor) = flavor {
+        sections
+            .push(PromptSection::text("station", priority::LORE, flavor).with_header("Station: "));
+    }
+    sections
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn turn(speaker: Speaker, content: &str) -> Turn {
+        Turn {
+            id: Uuid::new_v4(),
+            timestamp: Utc::now(),
+            speaker,
+            content: content.to_string(),
+            metadata: TurnMetadata::default(),
+        }
+    }
+
+    fn assemble(history: &[Turn], user_input: &str, budget: usize) -> AssembledPrompt {
+        let memory = ManagedHistory::verbatim(history.to_vec());
+        let context = SessionContext {
+            session_id: Uuid::new_v4(),
+            user_id: 1,
+            archetype: None,
+            focus_area: Some("chat".to_string()),
+            answer_key: None,
+            course_id: None,
+            vocabulary_pack: None,
+            lore_pack: None,
+        };
+        let mut sections = PromptStrategy::Mirroring.prompt_sections(user_input, &memory, &context);
+        sections.insert(
+            1,
+            PromptSection::fixed("persona", "You are Pete, a steam engineer."),
+        );
+        PromptAssembler::heuristic(budget + 100, 100)
+            .assemble(sections)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_chat_request_splits_system_history_and_message() {
+        let history = vec![
+            turn(Speaker::User, "Why do heavy things fall?"),
+            turn(Speaker::AI, "What have you noticed when you drop things?"),
+            turn(Speaker::User, "They all land together"),
+        ];
+        let assembled = assemble(&history, "They all land together", 10_000);
+        let request = chat_request(&assembled, &history, "They all land together");
+
+        let system = request.system.unwrap();
+        assert!(system.contains("Socratic guide"));
+        assert!(system.contains("You are Pete, a steam engineer."));
+
+        let roles: Vec<ChatRole> = request.turns.iter().map(|t| t.role).collect();
+        assert_eq!(roles, [ChatRole::User, ChatRole::Model, ChatRole::User]);
+        assert_eq!(request.turns[0].text, "Why do heavy things fall?");
+        let message = &request.turns[2].text;
+        assert!(message.contains("Current user input: \"They all land together\""));
+        assert!(!message.contains("Recent conversation"));
+        assert!(!message.contains("Socratic guide"));
+    }
+
+    #[test]
+    fn test_chat_request_keeps_only_history_that_fit() {
+        let mut history: Vec<Turn> = (0..40)
+            .map(|i| {
+                let speaker = if i % 2 == 0 {
+                    Speaker::User
+                } else {
+                    Speaker::AI
+                };
+                turn(
+                    speaker,
+                    &format!("turn {} {}", i, "about levers ".repeat(20)),
+                )
+            })
+            .collect();
+        history.push(turn(Speaker::User, "What about pulleys?"));
+        let assembled = assemble(&history, "What about pulleys?", 700);
+        let request = chat_request(&assembled, &history, "What about pulleys?");
+
+        let kept = assembled.kept_items("history");
+        assert!(kept > 1 && kept < history.len());
+        // The newest turns survive, the current message is not repeated as history
+        assert!(request.turns[request.turns.len() - 2]
+            .text
+            .starts_with("turn 39"));
+        let last = request.turns.last().unwrap();
+        assert_eq!(last.role, ChatRole::User);
+        assert!(last.text.contains("What about pulleys?"));
+        assert!(request
+            .turns
+            .windows(2)
+            .all(|pair| pair[0].role != pair[1].role));
+    }
+}
@@ -0,0 +1,656 @@
+//! # Socratic Evaluation
+//!
+//! Regression suite for Pete's replies. An `EvalSet` (YAML) lists student
+//! messages with their context; `EvalRunner` replays each one through a
+//! `SocraticEngine` with whatever backend it was given and scores the reply
+//! with deterministic checks:
+//!
+//! - `ends_with_question`: the reply hands the thinking back to the learner
+//! - `no_answer_leakage`: the guardrail finds nothing from the case's answer key
+//! - `length`: word count within the case's bounds
+//! - `reading_level`: Flesch-Kincaid grade at or below the case's maximum
+//! - `strategy`: the dispatcher picked the expected strategy
+//!
+//! An optional judge model adds 1-5 scores. Runs are saved as JSON and
+//! compared with `diff_runs` (see the `socratic_eval` binary).
+
+use crate::guardrail::{AnswerGuardrail, AnswerKey};
+use crate::llm::backend::{LlmBackend, MockBackend};
+use crate::lore::LoreRegistry;
+use crate::prompts::PromptStrategy;
+use crate::socratic_engine::{SessionContext, SocraticEngine};
+use crate::vocabulary::VocabularyRegistry;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use infra_db::conversation_memory::{ConversationMemory, Speaker, Turn};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::sync::Arc;
+use uuid::Uuid;
+
+const DEFAULT_MIN_WORDS: usize = 5;
+const DEFAULT_MAX_WORDS: usize = 120;
+const DEFAULT_MAX_GRADE: f32 = 10.0;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvalSet {
+    pub name: String,
+    pub cases: Vec<EvalCase>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvalCase {
+    pub id: String,
+    pub message: String,
+    /// Earlier turns of the session, oldest first
+    #[serde(default)]
+    pub history: Vec<EvalTurn>,
+    #[serde(default)]
+    pub focus_area: Option<String>,
+    #[serde(default)]
+    pub archetype: Option<String>,
+    #[serde(default)]
+    pub course_id: Option<String>,
+    #[serde(default)]
+    pub vocabulary_pack: Option<String>,
+    #[serde(default)]
+    pub lore_pack: Option<String>,
+    #[serde(default)]
+    pub answer_key: Option<AnswerKey>,
+    #[serde(default)]
+    pub expect: Expectations,
+    /// What the mock backend answers for this message
+    #[serde(default)]
+    pub mock_reply: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvalTurn {
+    pub speaker: Speaker,
+    pub content: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Expectations {
+    #[serde(default)]
+    pub strategy: Option<PromptStrategy>,
+    /// Defaults to true, except for wellbeing (Support) replies
+    #[serde(default)]
+    pub ends_with_question: Option<bool>,
+    #[serde(default)]
+    pub min_words: Option<usize>,
+    #[serde(default)]
+    pub max_words: Option<usize>,
+    #[serde(default)]
+    pub max_grade: Option<f32>,
+}
+
+impl EvalSet {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let raw = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read eval set {:?}", path))?;
+        Self::from_yaml(&raw).with_context(|| format!("Invalid eval set {:?}", path))
+    }
+
+    pub fn from_yaml(raw: &str) -> Result<Self> {
+        Ok(serde_yaml::from_str(raw)?)
+    }
+
+    /// Mock backend that gives each case its `mock_reply`
+    pub fn mock_backend(&self) -> MockBackend {
+        self.cases
+            .iter()
+            .filter_map(|case| {
+                let reply = case.mock_reply.as_ref()?;
+                Some((format!("Current user input: \"{}\"", case.message), reply))
+            })
+            .fold(MockBackend::default(), |mock, (needle, reply)| {
+                mock.with_rule(needle, reply.as_str())
+            })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CheckResult {
+    pub name: String,
+    pub passed: bool,
+    pub detail: String,
+}
+
+/// 1-5 ratings from the judge model
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JudgeScores {
+    pub socratic: f32,
+    pub warmth: f32,
+    pub clarity: f32,
+    #[serde(default)]
+    pub comment: String,
+}
+
+impl JudgeScores {
+    pub fn mean(&self) -> f32 {
+        (self.socratic + self.warmth + self.clarity) / 3.0
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CaseResult {
+    pub id: String,
+    pub message: String,
+    pub response: String,
+    pub strategy: PromptStrategy,
+    pub words: usize,
+    pub reading_grade: f32,
+    pub checks: Vec<CheckResult>,
+    pub passed: bool,
+    #[serde(default)]
+    pub judge: Option<JudgeScores>,
+}
+
+impl CaseResult {
+    pub fn check(&self, name: &str) -> Option<&CheckResult> {
+        self.checks.iter().find(|c| c.name == name)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvalSummary {
+    pub cases: usize,
+    pub passed: usize,
+    pub pass_rate: f32,
+    /// Pass rate of each check over the cases it applied to
+    pub checks: BTreeMap<String, f32>,
+    pub mean_reading_grade: f32,
+    #[serde(default)]
+    pub mean_judge_score: Option<f32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvalRun {
+    pub set: String,
+    pub backend: String,
+    #[serde(default)]
+    pub judge: Option<String>,
+    pub started_at: DateTime<Utc>,
+    pub cases: Vec<CaseResult>,
+    pub summary: EvalSummary,
+}
+
+impl EvalRun {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let raw = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read eval results {:?}", path))?;
+        serde_json::from_str(&raw).with_context(|| format!("Invalid eval results {:?}", path))
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        std::fs::write(path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write eval results {:?}", path))
+    }
+}
+
+/// Replays an eval set through one engine; each case gets a fresh session.
+pub struct EvalRunner {
+    engine: SocraticEngine,
+    memory: Arc<ConversationMemory>,
+    backend: String,
+    judge: Option<Arc<dyn LlmBackend>>,
+    guardrail: AnswerGuardrail,
+}
+
+impl EvalRunner {
+    /// `memory` must be the engine's conversation memory (history is seeded there)
+    pub fn new(engine: SocraticEngine, memory: Arc<ConversationMemory>, backend: &str) -> Self {
+        Self {
+            engine,
+            memory,
+            backend: backend.to_string(),
+            judge: None,
+            guardrail: AnswerGuardrail::new(),
+        }
+    }
+
+    /// Engine backed by `backend` alone, on in-memory history
+    pub fn with_backend(backend: Arc<dyn LlmBackend>) -> Self {
+        let memory = Arc::new(ConversationMemory::new_in_memory(64));
+        let mut engine = SocraticEngine::new(memory.clone());
+        let model_id = backend.model_id();
+        engine.set_backend(backend);
+        engine.set_vocabulary_packs(Arc::new(VocabularyRegistry::builtin()));
+        engine.set_lore_packs(Arc::new(LoreRegistry::builtin()));
+        Self::new(engine, memory, &model_id)
+    }
+
+    pub fn with_judge(mut self, judge: Arc<dyn LlmBackend>) -> Self {
+        self.judge = Some(judge);
+        self
+    }
+
+    pub async fn run(&mut self, set: &EvalSet) -> Result<EvalRun> {
+        let started_at = Utc::now();
+        let mut cases = Vec::with_capacity(set.cases.len());
+        for case in &set.cases {
+            log::info!("Eval case '{}'", case.id);
+            cases.push(self.run_case(case).await?);
+        }
+
+        Ok(EvalRun {
+            set: set.name.clone(),
+            backend: self.backend.clone(),
+            judge: self.judge.as_ref().map(|j| j.model_id()),
+            started_at,
+            summary: summarize(&cases),
+            cases,
+        })
+    }
+
+    async fn run_case(&mut self, case: &EvalCase) -> Result<CaseResult> {
+        let session_id = Uuid::new_v4();
+        let start = Utc::now() - chrono::Duration::minutes(case.history.len() as i64 + 1);
+        for (i, turn) in case.history.iter().enumerate() {
+            let turn = Turn {
+                id: Uuid::new_v4(),
+                timestamp: start + chrono::Duration::minutes(i as i64),
+                speaker: turn.speaker.clone(),
+                content: turn.content.clone(),
+                metadata: Default::default(),
+            };
+            self.memory.add_turn(session_id, turn).await?;
+        }
+
+        let context = SessionContext {
+            session_id,
+            user_id: 0,
+            archetype: case.archetype.clone(),
+            focus_area: case.focus_area.clone(),
+            answer_key: case.answer_key.clone(),
+            course_id: case.course_id.clone(),
+            vocabulary_pack: case.vocabulary_pack.clone(),
+            lore_pack: case.lore_pack.clone(),
+        };
+        let reply = self
+            .engine
+            .respond(&case.message, &context)
+            .await
+            .with_context(|| format!("Case '{}' failed", case.id))?;
+
+        let checks = score_reply(&self.guardrail, case, &reply.text, reply.strategy_used);
+        let judge = match &self.judge {
+            Some(judge) => judge_reply(judge.as_ref(), case, &reply.text).await,
+            None => None,
+        };
+
+        Ok(CaseResult {
+            id: case.id.clone(),
+            message: case.message.clone(),
+            words: reply.text.split_whitespace().count(),
+            reading_grade: reading_grade(&reply.text),
+            passed: checks.iter().all(|c| c.passed),
+            response: reply.text,
+            strategy: reply.strategy_used,
+            checks,
+            judge,
+        })
+    }
+}
+
+/// Deterministic checks for one reply
+pub fn score_reply(
+    guardrail: &AnswerGuardrail,
+    case: &EvalCase,
+    reply: &str,
+    strategy: PromptStrategy,
+) -> Vec<CheckResult> {
+    let expect = &case.expect;
+    let mut checks = Vec::new();
+
+    let wants_question = expect
+        .ends_with_question
+        .unwrap_or(strategy != PromptStrategy::Support);
+    if wants_question {
+        let ends = reply
+            .trim_end()
+            .trim_end_matches(['"', '\'', ')', '*', '_'])
+            .ends_with('?');
+        checks.push(CheckResult {
+            name: "ends_with_question".to_string(),
+            passed: ends,
+            detail: if ends {
+                "ends with a question".to_string()
+            } else {
+                "does not end with a question".to_string()
+            },
+        });
+    }
+
+    if let Some(key) = case.answer_key.as_ref().filter(|k| !k.is_empty()) {
+        let leaks = guardrail.detect(reply, key);
+        checks.push(CheckResult {
+            name: "no_answer_leakage".to_string(),
+            passed: leaks.is_empty(),
+            detail: format!("{} leak(s): {:?}", leaks.len(), leaks),
+        });
+    }
+
+    let words = reply.split_whitespace().count();
+    let min_words = expect.min_words.unwrap_or(DEFAULT_MIN_WORDS);
+    let max_words = expect.max_words.unwrap_or(DEFAULT_MAX_WORDS);
+    checks.push(CheckResult {
+        name: "length".to_string(),
+        passed: (min_words..=max_words).contains(&words),
+        detail: format!("{} words (allowed {}-{})", words, min_words, max_words),
+    });
+
+    let grade = reading_grade(reply);
+    let max_grade = expect.max_grade.unwrap_or(DEFAULT_MAX_GRADE);
+    checks.push(CheckResult {
+        name: "reading_level".to_string(),
+        passed: grade <= max_grade,
+        detail: format!("grade {:.1} (max {:.1})", grade, max_grade),
+    });
+
+    if let Some(expected) = expect.strategy {
+        checks.push(CheckResult {
+            name: "strategy".to_string(),
+            passed: strategy == expected,
+            detail: format!("chose {:?}, expected {:?}", strategy, expected),
+        });
+    }
+
+    checks
+}
+
+/// Flesch-Kincaid grade level (rough syllable heuristic, never below 0)
+pub fn reading_grade(text: &str) -> f32 {
+    let words: Vec<&str> = text.split_whitespace().collect();
+    if words.is_empty() {
+        return 0.0;
+    }
+    let sentences = text
+        .split(['.', '!', '?'])
+        .filter(|s| !s.trim().is_empty())
+        .count()
+        .max(1);
+    let syllables: usize = words.iter().map(|w| syllables(w)).sum();
+
+    let grade = 0.39 * (words.len() as f32 / sentences as f32)
+        + 11.8 * (syllables as f32 / words.len() as f32)
+        - 15.59;
+    grade.max(0.0)
+}
+
+fn syllables(word: &str) -> usize {
+    let word: String = word
+        .chars()
+        .filter(|c| c.is_alphabetic())
+        .flat_map(char::to_lowercase)
+        .collect();
+    let mut count = 0;
+    let mut prev_vowel = false;
+    for c in word.chars() {
+        let vowel = matches!(c, 'a' | 'e' | 'i' | 'o' | 'u' | 'y');
+        if vowel && !prev_vowel {
+            count += 1;
+        }
+        prev_vowel = vowel;
+    }
+    // Silent trailing 'e'
+    if word.ends_with('e') && !word.ends_with("le") && count > 1 {
+        count -= 1;
+    }
+    count.max(1)
+}
+
+async fn judge_reply(judge: &dyn LlmBackend, case: &EvalCase, reply: &str) -> Option<JudgeScores> {
+    let prompt = format!(
+        "You are grading a Socratic tutor. Rate the tutor's reply from 1 (poor) to 5 (excellent) on:\n\
+         - socratic: guides the learner to think instead of giving the answer\n\
+         - warmth: encouraging and never condescending\n\
+         - clarity: easy for the learner to understand\n\n\
+         Learner: \"{}\"\nTutor: \"{}\"\n\n\
+         Reply with JSON only: {{\"socratic\": n, \"warmth\": n, \"clarity\": n, \"comment\": \"...\"}}",
+        case.message, reply
+    );
+    let text = match judge.generate(&prompt, 200).await {
+        Ok(text) => text,
+        Err(e) => {
+            log::warn!("Judge failed on case '{}': {}", case.id, e);
+            return None;
+        }
+    };
+    match crate::json_utils::extract_and_parse_json::<JudgeScores>(&text) {
+        Ok(scores) => Some(JudgeScores {
+            socratic: scores.socratic.clamp(1.0, 5.0),
+            warmth: scores.warmth.clamp(1.0, 5.0),
+            clarity: scores.clarity.clamp(1.0, 5.0),
+            comment: scores.comment,
+        }),
+        Err(e) => {
+            log::warn!("Unreadable judge reply on case '{}': {}", case.id, e);
+            None
+        }
+    }
+}
+
+fn summarize(cases: &[CaseResult]) -> EvalSummary {
+    let passed = cases.iter().filter(|c| c.passed).count();
+    let mut per_check: BTreeMap<String, (usize, usize)> = BTreeMap::new();
+    for check in cases.iter().flat_map(|c| &c.checks) {
+        let entry = per_check.entry(check.name.clone()).or_default();
+        entry.1 += 1;
+        if check.passed {
+            entry.0 += 1;
+        }
+    }
+    let judged: Vec<f32> = cases
+        .iter()
+        .filter_map(|c| c.judge.as_ref().map(JudgeScores::mean))
+        .collect();
+
+    EvalSummary {
+        cases: cases.len(),
+        passed,
+        pass_rate: ratio(passed, cases.len()),
+        checks: per_check
+            .into_iter()
+            .map(|(name, (ok, total))| (name, ratio(ok, total)))
+            .collect(),
+        mean_reading_grade: mean(cases.iter().map(|c| c.reading_grade)),
+        mean_judge_score: (!judged.is_empty()).then(|| mean(judged.into_iter())),
+    }
+}
+
+fn ratio(part: usize, whole: usize) -> f32 {
+    if whole == 0 {
+        0.0
+    } else {
+        part as f32 / whole as f32
+    }
+}
+
+fn mean(values: impl Iterator<Item = f32>) -> f32 {
+    let (sum, n) = values.fold((0.0, 0usize), |(sum, n), v| (sum + v, n + 1));
+    if n == 0 {
+        0.0
+    } else {
+        sum / n as f32
+    }
+}
+
+/// A check whose outcome differs between two runs
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CheckChange {
+    pub case_id: String,
+    pub check: String,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StrategyChange {
+    pub case_id: String,
+    pub baseline: PromptStrategy,
+    pub current: PromptStrategy,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct EvalDiff {
+    /// Passed in the baseline, fails now
+    pub regressions: Vec<CheckChange>,
+    /// Failed in the baseline, passes now
+    pub fixes: Vec<CheckChange>,
+    pub strategy_changes: Vec<StrategyChange>,
+    pub added_cases: Vec<String>,
+    pub removed_cases: Vec<String>,
+    pub pass_rate_delta: f32,
+    pub judge_delta: Option<f32>,
+}
+
+impl EvalDiff {
+    pub fn has_regressions(&self) -> bool {
+        !self.regressions.is_empty()
+    }
+}
+
+/// Compare a run against a saved baseline, case by case and check by check
+pub fn diff_runs(baseline: &EvalRun, current: &EvalRun) -> EvalDiff {
+    let before: HashMap<&str, &CaseResult> =
+        baseline.cases.iter().map(|c| (c.id.as_str(), c)).collect();
+    let mut diff = EvalDiff {
+        pass_rate_delta: current.summary.pass_rate - baseline.summary.pass_rate,
+        judge_delta: current
+            .summary
+            .mean_judge_score
+            .zip(baseline.summary.mean_judge_score)
+            .map(|(now, was)| now - was),
+        ..Default::default()
+    };
+
+    for case in &current.cases {
+        let Some(old) = before.get(case.id.as_str()) else {
+            diff.added_cases.push(case.id.clone());
+            continue;
+        };
+        if case.strategy != old.strategy {
+            diff.strategy_changes.push(StrategyChange {
+                case_id: case.id.clone(),
+                baseline: old.strategy,
+                current: case.strategy,
+            });
+        }
+        for check in &case.checks {
+            let Some(was) = old.check(&check.name) else {
+                continue;
+            };
+            let change = CheckChange {
+                case_id: case.id.clone(),
+                check: check.name.clone(),
+                detail: check.detail.clone(),
+            };
+            match (was.passed, check.passed) {
+                (true, false) => diff.regressions.push(change),
+                (false, true) => diff.fixes.push(change),
+                _ => {}
+            }
+        }
+    }
+    diff.removed_cases = baseline
+        .cases
+        .iter()
+        .filter(|old| !current.cases.iter().any(|c| c.id == old.id))
+        .map(|old| old.id.clone())
+        .collect();
+
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EVAL_SET: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/socratic_eval.yaml");
+
+    #[test]
+    fn test_reading_grade() {
+        assert!(reading_grade("What do you see? Why does it move?") < 3.0);
+        assert!(
+            reading_grade(
+                "Considering the gravitational interaction, what characteristic determines \
+                 the acceleration experienced by objects of substantially different masses?"
+            ) > 12.0
+        );
+        assert_eq!(reading_grade(""), 0.0);
+    }
+
+    #[test]
+    fn test_score_reply_flags_each_check() {
+        let set = EvalSet::load(EVAL_SET).unwrap();
+        let case = set.cases.iter().find(|c| c.answer_key.is_some()).unwrap();
+        let guardrail = AnswerGuardrail::new();
+        let answer = &case.answer_key.as_ref().unwrap().quiz_answers[0];
+
+        let good = score_reply(
+            &guardrail,
+            case,
+            "What do you notice about the two balls as they fall?",
+            PromptStrategy::Scaffolding,
+        );
+        assert!(good.iter().all(|c| c.passed), "{:?}", good);
+
+        let leaky = format!("The answer is {}.", answer);
+        let bad = score_reply(&guardrail, case, &leaky, PromptStrategy::Mirroring);
+        let failed: Vec<&str> = bad
+            .iter()
+            .filter(|c| !c.passed)
+            .map(|c| c.name.as_str())
+            .collect();
+        assert!(failed.contains(&"ends_with_question"));
+        assert!(failed.contains(&"no_answer_leakage"));
+        assert!(failed.contains(&"strategy"));
+    }
+
+    #[tokio::test]
+    async fn test_mock_run_and_baseline_diff() {
+        let set = EvalSet::load(EVAL_SET).unwrap();
+        let mock = Arc::new(set.mock_backend());
+        let judge = Arc::new(MockBackend::new(
+            r#"{"socratic": 4, "warmth": 5, "clarity": 4, "comment": "fine"}"#,
+        ));
+        let mut runner = EvalRunner::with_backend(mock.clone()).with_judge(judge);
+        let baseline = runner.run(&set).await.unwrap();
+
+        assert_eq!(baseline.backend, "mock");
+        assert_eq!(baseline.cases.len(), set.cases.len());
+        assert!(
+            baseline.cases.iter().all(|c| c.passed),
+            "{:#?}",
+            baseline.cases
+        );
+        assert!((baseline.summary.mean_judge_score.unwrap() - 13.0 / 3.0).abs() < 1e-4);
+        // History was seeded into the session the reply was generated for
+        assert!(mock
+            .prompts()
+            .iter()
+            .any(|p| p.contains("Learner: I think heavier things fall faster")));
+
+        // A prompt change that makes Pete lecture shows up as regressions
+        let mut current = baseline.clone();
+        let case = &mut current.cases[0];
+        case.checks = score_reply(
+            &AnswerGuardrail::new(),
+            &set.cases[0],
+            "Gravity pulls everything down at the same rate.",
+            case.strategy,
+        );
+        let diff = diff_runs(&baseline, &current);
+        assert!(diff.has_regressions());
+        assert!(diff
+            .regressions
+            .iter()
+            .all(|r| r.case_id == set.cases[0].id));
+        assert!(diff_runs(&baseline, &baseline).regressions.is_empty());
+    }
+}
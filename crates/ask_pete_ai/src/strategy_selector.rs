@@ -0,0 +1,606 @@
+//! # Strategy Selector (The Dispatcher)
+//!
+//! Chooses a Socratic strategy by looking at the whole conversation instead of
+//! word counts alone. The dispatcher checks three signals before falling back
+//! to `PromptStrategy::select_strategy`:
+//!
+//! 1. **Misconceptions**: the learner states a known misconception.
+//! 2. **Contradictions**: the learner contradicts an earlier turn or a
+//!    retrieved knowledge chunk.
+//! 3. **Overconfidence**: the learner makes an absolute claim with no reasoning.
+//!
+//! Misconceptions and contradictions select `Challenging`; overconfidence
+//! selects `Deepening`. The rationale is recorded on the AI turn's metadata.
+
+use crate::knowledge_retrieval::KnowledgeChunk;
+use crate::prompts::PromptStrategy;
+use infra_db::conversation_memory::{Speaker, Turn};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+/// Why the dispatcher picked a strategy.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SelectionReason {
+    /// The learner repeated a known misconception.
+    Misconception { note: String },
+    /// The learner contradicted something they said earlier.
+    ContradictsEarlierTurn { earlier: String },
+    /// The learner contradicted a retrieved knowledge chunk.
+    ContradictsKnowledge { source: String, passage: String },
+    /// The learner made an absolute claim without giving reasons.
+    Overconfident { markers: Vec<String> },
+    /// No signal fired; the word-count heuristic decided.
+    #[default]
+    Heuristic,
+}
+
+impl SelectionReason {
+    /// Short human-readable summary, stored in `TurnMetadata::strategy_rationale`.
+    pub fn describe(&self) -> String {
+        match self {
+            Self::Misconception { note } => format!("Misconception detected: {}", note),
+            Self::ContradictsEarlierTurn { earlier } => {
+                format!("Contradicts earlier turn: \"{}\"", earlier)
+            }
+            Self::ContradictsKnowledge { source, passage } => {
+                format!("Contradicts knowledge source '{}': \"{}\"", source, passage)
+            }
+            Self::Overconfident { markers } => {
+                format!("Overconfident claim ({})", markers.join(", "))
+            }
+            Self::Heuristic => "Word-count heuristic".to_string(),
+        }
+    }
+}
+
+/// The dispatcher's decision.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StrategySelection {
+    pub strategy: PromptStrategy,
+    pub reason: SelectionReason,
+}
+
+/// A known misconception, matched when every term appears in an affirmed claim.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Misconception {
+    pub terms: Vec<String>,
+    pub note: String,
+}
+
+impl Misconception {
+    pub fn new(terms: &[&str], note: &str) -> Self {
+        Self {
+            terms: terms.iter().map(|t| stem(t)).collect(),
+            note: note.to_string(),
+        }
+    }
+
+    /// Builds a misconception from a plain statement such as
+    /// "heavier objects fall faster", keeping its content words and negations.
+    pub fn from_statement(statement: &str, note: &str) -> Self {
+        Self {
+            terms: statement
+                .split(|c: char| !(c.is_alphanumeric() || c == '\''))
+                .map(|t| t.to_lowercase())
+                .filter(|t| !t.is_empty() && !STOP_WORDS.contains(&t.as_str()))
+                .map(|t| stem(&t))
+                .collect(),
+            note: note.to_string(),
+        }
+    }
+}
+
+/// Default misconceptions for the physics curriculum.
+pub fn default_misconceptions() -> Vec<Misconception> {
+    vec![
+        Misconception::new(
+            &["heavier", "fall", "faster"],
+            "heavier objects do not fall faster without air resistance",
+        ),
+        Misconception::new(
+            &["force", "keep", "moving"],
+            "no net force is needed to keep an object moving at constant velocity",
+        ),
+        Misconception::new(
+            &["no", "gravity", "space"],
+            "gravity still acts on objects in orbit",
+        ),
+        Misconception::new(
+            &["mass", "weight", "same"],
+            "mass and weight are different quantities",
+        ),
+        Misconception::new(
+            &["energy", "used", "up"],
+            "energy is transformed, not used up",
+        ),
+    ]
+}
+
+/// Selects Socratic strategies from conversation history and retrieved knowledge.
+#[derive(Debug, Clone)]
+pub struct StrategySelector {
+    misconceptions: Vec<Misconception>,
+}
+
+impl Default for StrategySelector {
+    fn default() -> Self {
+        Self::new(default_misconceptions())
+    }
+}
+
+impl StrategySelector {
+    pub fn new(misconceptions: Vec<Misconception>) -> Self {
+        Self { misconceptions }
+    }
+
+    /// A copy of this selector that also watches for `extra` misconceptions
+    pub fn with_misconceptions(&self, extra: Vec<Misconception>) -> Self {
+        let mut misconceptions = self.misconceptions.clone();
+        misconceptions.extend(extra);
+        Self { misconceptions }
+    }
+
+    /// Choose a strategy for `user_input`.
+    ///
+    /// `history` may already contain the current user turn (the engine saves it
+    /// before selecting); it is skipped when comparing against earlier claims.
+    pub fn select(
+        &self,
+        user_input: &str,
+        history: &[Turn],
+        knowledge: &[KnowledgeChunk],
+    ) -> StrategySelection {
+        let claims = extract_claims(user_input);
+
+        if let Some(note) = self.find_misconception(&claims) {
+            return StrategySelection {
+                strategy: PromptStrategy::Challenging,
+                reason: SelectionReason::Misconception { note },
+            };
+        }
+
+        if let Some(earlier) = find_contradicting_turn(user_input, &claims, history) {
+            return StrategySelection {
+                strategy: PromptStrategy::Challenging,
+                reason: SelectionReason::ContradictsEarlierTurn { earlier },
+            };
+        }
+
+        if let Some((source, passage)) = find_contradicting_chunk(&claims, knowledge) {
+            return StrategySelection {
+                strategy: PromptStrategy::Challenging,
+                reason: SelectionReason::ContradictsKnowledge { source, passage },
+            };
+        }
+
+        let markers = overconfidence_markers(user_input);
+        if !markers.is_empty() {
+            return StrategySelection {
+                strategy: PromptStrategy::Deepening,
+                reason: SelectionReason::Overconfident { markers },
+            };
+        }
+
+        StrategySelection {
+            strategy: PromptStrategy::select_strategy(user_input, history),
+            reason: SelectionReason::Heuristic,
+        }
+    }
+
+    fn find_misconception(&self, claims: &[Claim]) -> Option<String> {
+        self.misconceptions
+            .iter()
+            .find(|m| {
+                // Some misconceptions are themselves negative ("no gravity in space"),
+                // so the claim's polarity has to match the misconception's.
+                let negative = m.terms.iter().any(|t| NEGATIONS.contains(&t.as_str()));
+                claims.iter().any(|claim| {
+                    claim.negated == negative
+                        && m.terms
+                            .iter()
+                            .all(|t| claim.terms.contains(t) || claim.raw.contains(t))
+                })
+            })
+            .map(|m| m.note.clone())
+    }
+}
+
+/// A single declarative statement reduced to content stems and polarity.
+#[derive(Debug, Clone, PartialEq)]
+struct Claim {
+    text: String,
+    terms: BTreeSet<String>,
+    raw: BTreeSet<String>,
+    negated: bool,
+}
+
+const NEGATIONS: &[&str] = &[
+    "not",
+    "no",
+    "never",
+    "isn't",
+    "aren't",
+    "wasn't",
+    "weren't",
+    "doesn't",
+    "don't",
+    "didn't",
+    "won't",
+    "can't",
+    "cannot",
+    "shouldn't",
+    "wouldn't",
+    "couldn't",
+    "nothing",
+    "none",
+];
+
+const STOP_WORDS: &[&str] = &[
+    "a",
+    "an",
+    "and",
+    "are",
+    "as",
+    "at",
+    "be",
+    "been",
+    "by",
+    "for",
+    "from",
+    "has",
+    "have",
+    "he",
+    "she",
+    "in",
+    "is",
+    "it",
+    "its",
+    "of",
+    "on",
+    "that",
+    "the",
+    "to",
+    "was",
+    "were",
+    "with",
+    "i",
+    "you",
+    "we",
+    "they",
+    "this",
+    "these",
+    "those",
+    "think",
+    "believe",
+    "guess",
+    "so",
+    "do",
+    "does",
+    "did",
+    "just",
+    "really",
+    "because",
+    "will",
+    "would",
+    "can",
+    "could",
+    "my",
+    "me",
+    "than",
+    "then",
+    "there",
+    "their",
+    "them",
+    "what",
+    "when",
+    "which",
+    "but",
+    "or",
+    "if",
+    "also",
+    "too",
+    "very",
+    "all",
+    "some",
+    "any",
+    "always",
+    "definitely",
+    "obviously",
+    "clearly",
+    "certainly",
+];
+
+/// Antonym pairs: the second word is treated as the negation of the first.
+const ANTONYMS: &[(&str, &str)] = &[
+    ("increase", "decrease"),
+    ("faster", "slower"),
+    ("more", "less"),
+    ("true", "false"),
+    ("heavier", "lighter"),
+    ("higher", "lower"),
+    ("larger", "smaller"),
+    ("bigger", "smaller"),
+    ("gain", "lose"),
+    ("attract", "repel"),
+    ("possible", "impossible"),
+    ("agree", "disagree"),
+    ("like", "dislike"),
+    ("right", "wrong"),
+    ("correct", "incorrect"),
+];
+
+const BOOSTERS: &[&str] = &[
+    "definitely",
+    "obviously",
+    "clearly",
+    "certainly",
+    "always",
+    "never",
+    "everyone knows",
+    "100%",
+    "no way",
+    "without a doubt",
+    "of course",
+    "undeniably",
+    "guaranteed",
+];
+
+const HEDGES: &[&str] = &[
+    "because", "maybe", "might", "perhaps", "i think", "probably", "not sure", "since", "seems",
+    "could", "wonder",
+];
+
+fn stem(word: &str) -> String {
+    let mut w = word.to_lowercase();
+    if w.len() > 4 && w.ends_with("ies") {
+        w.truncate(w.len() - 3);
+        w.push('y');
+        return w;
+    }
+    for suffix in ["ing", "ed", "es", "s"] {
+        if w.len() >= suffix.len() + 3 && w.ends_with(suffix) && !w.ends_with("ss") {
+            w.truncate(w.len() - suffix.len());
+            break;
+        }
+    }
+    // "move", "moves" and "moving" should all land on the same stem.
+    if w.len() > 3 && w.ends_with('e') {
+        w.pop();
+    }
+    w
+}
+
+fn extract_claims(text: &str) -> Vec<Claim> {
+    text.split(['.', '!', '?', ';', '\n'])
+        .filter_map(|sentence| {
+            let tokens: Vec<String> = sentence
+                .split(|c: char| !(c.is_alphanumeric() || c == '\''))
+                .filter(|t| !t.is_empty())
+                .map(|t| t.to_lowercase())
+                .collect();
+
+            let mut negated = tokens.iter().any(|t| NEGATIONS.contains(&t.as_str()));
+            let mut terms = BTreeSet::new();
+            for token in &tokens {
+                if NEGATIONS.contains(&token.as_str()) || STOP_WORDS.contains(&token.as_str()) {
+                    continue;
+                }
+                let stemmed = stem(token);
+                if let Some((base, _)) = ANTONYMS.iter().find(|(_, neg)| stem(neg) == stemmed) {
+                    negated = !negated;
+                    terms.insert(stem(base));
+                } else if stemmed.len() > 2 {
+                    terms.insert(stemmed);
+                }
+            }
+
+            if terms.len() < 2 {
+                return None;
+            }
+            Some(Claim {
+                text: sentence.trim().to_string(),
+                raw: tokens.iter().map(|t| stem(t)).collect(),
+                terms,
+                negated,
+            })
+        })
+        .collect()
+}
+
+/// Two claims contradict when they talk about (nearly) the same terms with opposite polarity.
+fn contradicts(a: &Claim, b: &Claim) -> bool {
+    if a.negated == b.negated {
+        return false;
+    }
+    let shared = a.terms.intersection(&b.terms).count();
+    let smaller = a.terms.len().min(b.terms.len());
+    shared >= 2 && shared * 4 >= smaller * 3
+}
+
+fn find_contradicting_turn(user_input: &str, claims: &[Claim], history: &[Turn]) -> Option<String> {
+    let mut earlier_turns: Vec<&Turn> = history
+        .iter()
+        .filter(|t| t.speaker == Speaker::User)
+        .collect();
+    if earlier_turns
+        .last()
+        .is_some_and(|t| t.content.trim() == user_input.trim())
+    {
+        earlier_turns.pop();
+    }
+
+    // Most recent contradictions are the most relevant to point out.
+    for turn in earlier_turns.iter().rev() {
+        for earlier in extract_claims(&turn.content) {
+            if claims.iter().any(|c| contradicts(c, &earlier)) {
+                return Some(earlier.text);
+            }
+        }
+    }
+    None
+}
+
+fn find_contradicting_chunk(
+    claims: &[Claim],
+    knowledge: &[KnowledgeChunk],
+) -> Option<(String, String)> {
+    for chunk in knowledge {
+        for fact in extract_claims(&chunk.content) {
+            if claims.iter().any(|c| contradicts(c, &fact)) {
+                return Some((chunk.title.clone(), fact.text));
+            }
+        }
+    }
+    None
+}
+
+fn overconfidence_markers(user_input: &str) -> Vec<String> {
+    let lower = user_input.to_lowercase();
+    let words: BTreeSet<&str> = lower
+        .split(|c: char| !(c.is_alphanumeric() || c == '\'' || c == '%'))
+        .filter(|t| !t.is_empty())
+        .collect();
+    let has_phrase = |phrase: &str| {
+        if phrase.contains(' ') {
+            lower.contains(phrase)
+        } else {
+            words.contains(phrase)
+        }
+    };
+
+    if HEDGES.iter().any(|h| has_phrase(h)) {
+        return Vec::new();
+    }
+    BOOSTERS
+        .iter()
+        .filter(|b| has_phrase(b))
+        .map(|b| b.to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use uuid::Uuid;
+
+    fn user_turn(content: &str) -> Turn {
+        Turn {
+            id: Uuid::new_v4(),
+            timestamp: Utc::now(),
+            speaker: Speaker::User,
+            content: content.to_string(),
+            metadata: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_contradiction_with_earlier_turn() {
+        let history = vec![
+            user_turn("Friction slows the train down on the hill."),
+            user_turn("Friction does not slow the train down at all."),
+        ];
+        let selection = StrategySelector::default().select(
+            "Friction does not slow the train down at all.",
+            &history,
+            &[],
+        );
+        assert_eq!(selection.strategy, PromptStrategy::Challenging);
+        assert!(matches!(
+            selection.reason,
+            SelectionReason::ContradictsEarlierTurn { .. }
+        ));
+    }
+
+    #[test]
+    fn test_pack_misconception_from_statement() {
+        let selector = StrategySelector::new(Vec::new()).with_misconceptions(vec![
+            Misconception::from_statement("plants get their food from the soil", "soil note"),
+        ]);
+        let selection = selector.select(
+            "I think the plant gets food from soil through its roots",
+            &[],
+            &[],
+        );
+        assert_eq!(
+            selection.reason,
+            SelectionReason::Misconception {
+                note: "soil note".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_antonym_contradiction() {
+        let history = vec![user_turn("More mass makes the train accelerate faster.")];
+        let selection = StrategySelector::default().select(
+            "More mass makes the train accelerate slower.",
+            &history,
+            &[],
+        );
+        assert_eq!(selection.strategy, PromptStrategy::Challenging);
+    }
+
+    #[test]
+    fn test_contradiction_with_knowledge() {
+        let chunks = vec![KnowledgeChunk {
+            id: "1".to_string(),
+            title: "Newton's Laws".to_string(),
+            content: "Mass does not change when an object moves to the Moon.".to_string(),
+            source_type: "txt".to_string(),
+            relevance_score: 1.0,
+            document_id: None,
+            page: None,
+            heading: None,
+        }];
+        let selection = StrategySelector::default().select(
+            "I reckon mass changes when an object moves to the Moon",
+            &[],
+            &chunks,
+        );
+        assert_eq!(selection.strategy, PromptStrategy::Challenging);
+        assert!(matches!(
+            selection.reason,
+            SelectionReason::ContradictsKnowledge { .. }
+        ));
+    }
+
+    #[test]
+    fn test_misconception_detected() {
+        let selection = StrategySelector::default().select(
+            "Heavier objects fall faster than light ones",
+            &[],
+            &[],
+        );
+        assert_eq!(selection.strategy, PromptStrategy::Challenging);
+        assert!(matches!(
+            selection.reason,
+            SelectionReason::Misconception { .. }
+        ));
+    }
+
+    #[test]
+    fn test_overconfidence_selects_deepening() {
+        let selection = StrategySelector::default().select(
+            "Momentum is obviously the same thing as velocity, everyone knows that",
+            &[],
+            &[],
+        );
+        assert_eq!(selection.strategy, PromptStrategy::Deepening);
+    }
+
+    #[test]
+    fn test_hedged_claim_is_not_overconfident() {
+        assert!(
+            overconfidence_markers("It always slows down because of friction I think").is_empty()
+        );
+    }
+
+    #[test]
+    fn test_falls_back_to_heuristic() {
+        let selection = StrategySelector::default().select("I don't know", &[], &[]);
+        assert_eq!(selection.strategy, PromptStrategy::Scaffolding);
+        assert_eq!(selection.reason, SelectionReason::Heuristic);
+    }
+}
@@ -0,0 +1,119 @@
+//! # Token Counting
+//!
+//! Prompt budgets are measured in tokens, not characters. `TokenizerCounter`
+//! uses a model's own `tokenizer.json`; `HeuristicTokenCounter` is the
+//! fallback when no tokenizer is loaded (e.g. remote backends) and errs on
+//! the side of over-counting.
+
+use crate::error::{AiError, Result};
+use std::path::Path;
+use tokenizers::Tokenizer;
+
+pub trait TokenCounter: Send + Sync {
+    /// Tokens `text` takes up in a prompt
+    fn count(&self, text: &str) -> usize;
+}
+
+/// Roughly four characters per token, never fewer tokens than words
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HeuristicTokenCounter;
+
+impl TokenCounter for HeuristicTokenCounter {
+    fn count(&self, text: &str) -> usize {
+        let chars = text.chars().count();
+        let words = text.split_whitespace().count();
+        chars.div_ceil(4).max(words)
+    }
+}
+
+/// Counts with a model's real tokenizer
+pub struct TokenizerCounter {
+    tokenizer: Tokenizer,
+}
+
+impl TokenizerCounter {
+    pub fn new(tokenizer: Tokenizer) -> Self {
+        Self { tokenizer }
+    }
+
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let tokenizer = Tokenizer::from_file(path.as_ref())
+            .map_err(|e| AiError::ModelLoadFailed(format!("Failed to load tokenizer: {}", e)))?;
+        Ok(Self::new(tokenizer))
+    }
+}
+
+impl TokenCounter for TokenizerCounter {
+    fn count(&self, text: &str) -> usize {
+        match self.tokenizer.encode(text, false) {
+            Ok(encoding) => encoding.len(),
+            Err(e) => {
+                log::warn!("Tokenizer failed ({}); estimating token count", e);
+                HeuristicTokenCounter.count(text)
+            }
+        }
+    }
+}
+
+/// Longest prefix of `text` that fits in `max_tokens`, cut at a sentence end
+/// when possible and otherwise at a word boundary.
+pub fn truncate_to_tokens(counter: &dyn TokenCounter, text: &str, max_tokens: usize) -> String {
+    if counter.count(text) <= max_tokens {
+        return text.to_string();
+    }
+
+    let sentences: Vec<&str> = text.split_inclusive(['.', '!', '?', '\n']).collect();
+    let mut kept = String::new();
+    for sentence in &sentences {
+        let candidate = format!("{}{}", kept, sentence);
+        if counter.count(candidate.trim()) > max_tokens {
+            break;
+        }
+        kept = candidate;
+    }
+    if !kept.trim().is_empty() {
+        return kept.trim().to_string();
+    }
+
+    // First sentence alone is too long: fall back to whole words
+    let mut kept = String::new();
+    for word in text.split_whitespace() {
+        let candidate = if kept.is_empty() {
+            word.to_string()
+        } else {
+            format!("{} {}", kept, word)
+        };
+        if counter.count(&candidate) > max_tokens {
+            break;
+        }
+        kept = candidate;
+    }
+    kept
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_heuristic_counts_words_and_chars() {
+        let counter = HeuristicTokenCounter;
+        assert_eq!(counter.count(""), 0);
+        assert_eq!(counter.count("a b c d e"), 5);
+        assert_eq!(counter.count("momentum"), 2);
+    }
+
+    #[test]
+    fn test_truncate_prefers_sentence_boundaries() {
+        let counter = HeuristicTokenCounter;
+        let text =
+            "Inertia keeps it moving. Friction slows it down. Momentum is mass times velocity.";
+        let cut = truncate_to_tokens(&counter, text, 14);
+        assert_eq!(cut, "Inertia keeps it moving. Friction slows it down.");
+        assert!(counter.count(&cut) <= 14);
+
+        let cut = truncate_to_tokens(&counter, text, 3);
+        assert_eq!(cut, "Inertia");
+        assert_eq!(truncate_to_tokens(&counter, text, 0), "");
+    }
+}
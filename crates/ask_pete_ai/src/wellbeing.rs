@@ -0,0 +1,531 @@
+//! # Wellbeing Monitor (The Emergency Brake)
+//!
+//! Students journal personal material into the AI Mirror. Before any Socratic
+//! prompting happens, every message passes through a local classifier:
+//!
+//! 1. **Lexicon**: weighted crisis and distress phrases, with negation dampening.
+//! 2. **Distress model**: a small logistic model over lexicon hits, absolutist
+//!    language and first-person focus.
+//!
+//! When the classifier fires, the engine skips the gamified reply (no Steam,
+//! no strategy prompt), returns the institution's support resources, and
+//! raises an alert that only instructors can read.
+//!
+//! Everything runs locally; no journal text leaves the machine.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::collections::{BTreeSet, VecDeque};
+use std::path::Path;
+use uuid::Uuid;
+
+/// How concerning a message is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum WellbeingLevel {
+    #[default]
+    None,
+    Distress,
+    Crisis,
+}
+
+impl WellbeingLevel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::Distress => "distress",
+            Self::Crisis => "crisis",
+        }
+    }
+}
+
+/// Lexicon categories, reported to instructors instead of the raw journal text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WellbeingCategory {
+    Suicidal,
+    SelfHarm,
+    Hopelessness,
+    Distress,
+}
+
+/// A phone line, text line or web page offered to the student.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SupportContact {
+    pub name: String,
+    pub detail: String,
+}
+
+/// Support text shown when the classifier fires.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SupportResources {
+    pub crisis_message: String,
+    pub distress_message: String,
+    #[serde(default)]
+    pub contacts: Vec<SupportContact>,
+}
+
+/// Per-institution thresholds and resource text.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct WellbeingConfig {
+    pub institution: String,
+    /// Minimum lexicon weight for a crisis hit (0.0 - 1.0).
+    #[serde(default = "default_crisis_threshold")]
+    pub crisis_threshold: f32,
+    /// Minimum distress-model probability for a distress hit (0.0 - 1.0).
+    #[serde(default = "default_distress_threshold")]
+    pub distress_threshold: f32,
+    pub resources: SupportResources,
+}
+
+fn default_crisis_threshold() -> f32 {
+    0.8
+}
+
+fn default_distress_threshold() -> f32 {
+    0.6
+}
+
+impl Default for WellbeingConfig {
+    fn default() -> Self {
+        Self {
+            institution: "default".to_string(),
+            crisis_threshold: default_crisis_threshold(),
+            distress_threshold: default_distress_threshold(),
+            resources: SupportResources {
+                crisis_message: "I'm really glad you told me. What you're carrying sounds heavy, and you deserve support from a real person right now. Please reach out to one of these:".to_string(),
+                distress_message: "Thank you for sharing that. It sounds like a lot right now. Let's pause the lesson; if you'd like to talk to someone, these are here for you:".to_string(),
+                contacts: vec![
+                    SupportContact {
+                        name: "988 Suicide & Crisis Lifeline (US)".to_string(),
+                        detail: "Call or text 988, or chat at 988lifeline.org".to_string(),
+                    },
+                    SupportContact {
+                        name: "Crisis Text Line".to_string(),
+                        detail: "Text HOME to 741741".to_string(),
+                    },
+                    SupportContact {
+                        name: "Emergency services".to_string(),
+                        detail: "Call 911 if you are in immediate danger".to_string(),
+                    },
+                ],
+            },
+        }
+    }
+}
+
+impl WellbeingConfig {
+    /// Load an institution's configuration from a JSON file.
+    pub fn load(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let text = std::fs::read_to_string(path.as_ref())?;
+        let config: Self = serde_json::from_str(&text)?;
+        Ok(config)
+    }
+}
+
+/// The classifier's verdict for one message.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct WellbeingAssessment {
+    pub level: WellbeingLevel,
+    pub crisis_score: f32,
+    pub distress_score: f32,
+    pub categories: Vec<WellbeingCategory>,
+}
+
+impl WellbeingAssessment {
+    pub fn is_hit(&self) -> bool {
+        self.level != WellbeingLevel::None
+    }
+}
+
+struct LexiconEntry {
+    phrase: &'static str,
+    category: WellbeingCategory,
+    weight: f32,
+}
+
+const fn entry(phrase: &'static str, category: WellbeingCategory, weight: f32) -> LexiconEntry {
+    LexiconEntry {
+        phrase,
+        category,
+        weight,
+    }
+}
+
+use WellbeingCategory::{Distress, Hopelessness, SelfHarm, Suicidal};
+
+const LEXICON: &[LexiconEntry] = &[
+    entry("kill myself", Suicidal, 1.0),
+    entry("killing myself", Suicidal, 1.0),
+    // Past attempts and self-reports: "kill myself" alone misses "killed myself"
+    entry("killed myself", Suicidal, 1.0),
+    entry("tried to kill myself", Suicidal, 1.0),
+    entry("tried to end my life", Suicidal, 1.0),
+    entry("ended my life", Suicidal, 0.95),
+    entry("tried to end it", Suicidal, 0.9),
+    entry("attempted suicide", Suicidal, 1.0),
+    entry("end my life", Suicidal, 1.0),
+    entry("take my own life", Suicidal, 1.0),
+    entry("want to die", Suicidal, 0.95),
+    entry("wanna die", Suicidal, 0.95),
+    entry("suicide", Suicidal, 0.9),
+    entry("suicidal", Suicidal, 1.0),
+    entry("better off dead", Suicidal, 1.0),
+    entry("better off without me", Suicidal, 0.95),
+    entry("end it all", Suicidal, 0.9),
+    entry("no reason to live", Suicidal, 0.95),
+    entry("don't want to be here anymore", Suicidal, 0.85),
+    entry("don't want to wake up", Suicidal, 0.85),
+    entry("hurt myself", SelfHarm, 0.9),
+    entry("hurting myself", SelfHarm, 0.9),
+    entry("cut myself", SelfHarm, 0.95),
+    entry("cutting myself", SelfHarm, 0.95),
+    entry("harmed myself", SelfHarm, 0.9),
+    entry("self harm", SelfHarm, 0.9),
+    entry("self-harm", SelfHarm, 0.9),
+    entry("overdose", SelfHarm, 0.85),
+    entry("hopeless", Hopelessness, 0.5),
+    entry("no point in anything", Hopelessness, 0.6),
+    entry("no future", Hopelessness, 0.5),
+    entry("can't go on", Hopelessness, 0.7),
+    entry("give up on everything", Hopelessness, 0.6),
+    entry("nothing matters", Hopelessness, 0.5),
+    entry("worthless", Hopelessness, 0.5),
+    entry("hate myself", Distress, 0.6),
+    entry("can't cope", Distress, 0.5),
+    entry("can't take it anymore", Distress, 0.6),
+    entry("panic attack", Distress, 0.45),
+    entry("depressed", Distress, 0.45),
+    entry("overwhelmed", Distress, 0.3),
+    entry("nobody cares", Distress, 0.45),
+    entry("all alone", Distress, 0.35),
+    entry("so alone", Distress, 0.4),
+    entry("crying every", Distress, 0.4),
+    entry("can't sleep", Distress, 0.25),
+    entry("scared all the time", Distress, 0.4),
+    entry("falling apart", Distress, 0.4),
+];
+
+const NEGATIONS: &[&str] = &["not", "never", "don't", "wouldn't", "won't", "no"];
+const ABSOLUTIST: &[&str] = &[
+    "always",
+    "never",
+    "nothing",
+    "everything",
+    "completely",
+    "totally",
+    "entire",
+    "forever",
+    "nobody",
+    "everyone",
+    "constantly",
+];
+const FIRST_PERSON: &[&str] = &["i", "i'm", "me", "my", "myself", "i've", "i'd"];
+
+/// Weights of the distress model: bias, lexicon sum, absolutist density, first-person density.
+const MODEL_WEIGHTS: [f32; 4] = [-3.2, 5.0, 6.0, 2.5];
+
+/// The local two-stage classifier.
+#[derive(Debug, Clone, Default)]
+pub struct WellbeingClassifier {
+    config: WellbeingConfig,
+}
+
+impl WellbeingClassifier {
+    pub fn new(config: WellbeingConfig) -> Self {
+        Self { config }
+    }
+
+    pub fn config(&self) -> &WellbeingConfig {
+        &self.config
+    }
+
+    pub fn assess(&self, message: &str) -> WellbeingAssessment {
+        let lower = message.to_lowercase().replace('’', "'");
+        let words: Vec<&str> = lower
+            .split(|c: char| !(c.is_alphanumeric() || c == '\'' || c == '-'))
+            .filter(|w| !w.is_empty())
+            .collect();
+
+        // Stage 1: lexicon
+        let mut crisis_score: f32 = 0.0;
+        let mut distress_sum: f32 = 0.0;
+        let mut categories = BTreeSet::new();
+        for item in LEXICON {
+            if let Some(pos) = lower.find(item.phrase) {
+                // "I would never hurt myself" still counts, just less.
+                let weight = if negated_before(&lower[..pos]) {
+                    item.weight * 0.5
+                } else {
+                    item.weight
+                };
+                categories.insert(item.category);
+                match item.category {
+                    Suicidal | SelfHarm => crisis_score = crisis_score.max(weight),
+                    Hopelessness | Distress => distress_sum += weight,
+                }
+            }
+        }
+
+        // Stage 2: small distress model
+        let total = words.len().max(1) as f32;
+        let absolutist = words.iter().filter(|w| ABSOLUTIST.contains(w)).count() as f32 / total;
+        let first_person = words.iter().filter(|w| FIRST_PERSON.contains(w)).count() as f32 / total;
+        let [bias, w_lexicon, w_absolutist, w_first_person] = MODEL_WEIGHTS;
+        let logit = bias
+            + w_lexicon * (distress_sum + crisis_score)
+            + w_absolutist * absolutist
+            + w_first_person * first_person;
+        let distress_score = 1.0 / (1.0 + (-logit).exp());
+
+        let level = if crisis_score >= self.config.crisis_threshold {
+            WellbeingLevel::Crisis
+        } else if distress_score >= self.config.distress_threshold && !categories.is_empty() {
+            WellbeingLevel::Distress
+        } else {
+            WellbeingLevel::None
+        };
+
+        WellbeingAssessment {
+            level,
+            crisis_score,
+            distress_score,
+            categories: categories.into_iter().collect(),
+        }
+    }
+
+    /// The reply shown instead of a Socratic turn.
+    pub fn support_reply(&self, level: WellbeingLevel) -> String {
+        let resources = &self.config.resources;
+        let message = match level {
+            WellbeingLevel::Crisis => &resources.crisis_message,
+            _ => &resources.distress_message,
+        };
+        let contacts: Vec<String> = resources
+            .contacts
+            .iter()
+            .map(|c| format!("- {}: {}", c.name, c.detail))
+            .collect();
+        if contacts.is_empty() {
+            message.clone()
+        } else {
+            format!("{}\n\n{}", message, contacts.join("\n"))
+        }
+    }
+}
+
+fn negated_before(prefix: &str) -> bool {
+    prefix
+        .split_whitespace()
+        .rev()
+        .take(3)
+        .any(|w| NEGATIONS.contains(&w.trim_matches(|c: char| !c.is_alphanumeric() && c != '\'')))
+}
+
+/// An instructor-visible alert. Only categories and scores are stored, never the journal text.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WellbeingAlert {
+    pub id: Uuid,
+    pub user_id: i64,
+    pub session_id: Uuid,
+    pub institution: String,
+    pub level: WellbeingLevel,
+    pub categories: Vec<WellbeingCategory>,
+    pub crisis_score: f32,
+    pub distress_score: f32,
+    pub created_at: DateTime<Utc>,
+    pub acknowledged_by: Option<i64>,
+}
+
+/// How many alerts are kept in memory when no database is connected.
+const MAX_IN_MEMORY_ALERTS: usize = 200;
+
+/// Classifier plus the alert log, owned by the Socratic engine.
+#[derive(Debug, Default)]
+pub struct WellbeingMonitor {
+    classifier: WellbeingClassifier,
+    alerts: VecDeque<WellbeingAlert>,
+}
+
+impl WellbeingMonitor {
+    pub fn new(config: WellbeingConfig) -> Self {
+        Self {
+            classifier: WellbeingClassifier::new(config),
+            alerts: VecDeque::new(),
+        }
+    }
+
+    pub fn classifier(&self) -> &WellbeingClassifier {
+        &self.classifier
+    }
+
+    /// Record an alert for instructors, persisting it when a pool is available.
+    pub async fn raise_alert(
+        &mut self,
+        user_id: i64,
+        session_id: Uuid,
+        assessment: &WellbeingAssessment,
+        pool: Option<&PgPool>,
+    ) -> WellbeingAlert {
+        let alert = WellbeingAlert {
+            id: Uuid::new_v4(),
+            user_id,
+            session_id,
+            institution: self.classifier.config.institution.clone(),
+            level: assessment.level,
+            categories: assessment.categories.clone(),
+            crisis_score: assessment.crisis_score,
+            distress_score: assessment.distress_score,
+            created_at: Utc::now(),
+            acknowledged_by: None,
+        };
+
+        // Never log message content, only that an alert exists.
+        log::warn!(
+            target: "wellbeing",
+            "Wellbeing alert {} raised ({}) for session {}",
+            alert.id,
+            alert.level.as_str(),
+            alert.session_id
+        );
+
+        if let Some(pool) = pool {
+            let categories = serde_json::to_value(&alert.categories).unwrap_or_default();
+            let result = sqlx::query(
+                r#"
+                INSERT INTO wellbeing_alerts
+                (id, user_id, session_id, institution, level, categories, crisis_score, distress_score, created_at)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+                "#,
+            )
+            .bind(alert.id)
+            .bind(alert.user_id)
+            .bind(alert.session_id)
+            .bind(&alert.institution)
+            .bind(alert.level.as_str())
+            .bind(categories)
+            .bind(alert.crisis_score)
+            .bind(alert.distress_score)
+            .bind(alert.created_at)
+            .execute(pool)
+            .await;
+            if let Err(e) = result {
+                log::error!("Failed to persist wellbeing alert {}: {}", alert.id, e);
+            }
+        }
+
+        if self.alerts.len() >= MAX_IN_MEMORY_ALERTS {
+            self.alerts.pop_front();
+        }
+        self.alerts.push_back(alert.clone());
+        alert
+    }
+
+    /// Alerts raised since startup, newest last.
+    pub fn alerts(&self) -> impl Iterator<Item = &WellbeingAlert> {
+        self.alerts.iter()
+    }
+
+    /// Mark an in-memory alert as handled. Returns false if the alert is unknown.
+    pub fn acknowledge(&mut self, alert_id: Uuid, instructor_id: i64) -> bool {
+        match self.alerts.iter_mut().find(|a| a.id == alert_id) {
+            Some(alert) => {
+                alert.acknowledged_by = Some(instructor_id);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Deserialize)]
+    struct LabeledExample {
+        text: String,
+        label: WellbeingLevel,
+    }
+
+    fn labeled_examples() -> Vec<LabeledExample> {
+        serde_json::from_str(include_str!("../fixtures/wellbeing_labeled.json"))
+            .expect("valid labeled examples")
+    }
+
+    #[test]
+    fn test_labeled_examples() {
+        let classifier = WellbeingClassifier::default();
+        let mut failures = Vec::new();
+        for example in labeled_examples() {
+            let assessment = classifier.assess(&example.text);
+            if assessment.level != example.label {
+                failures.push(format!(
+                    "{:?} expected {:?}, got {:?} (crisis {:.2}, distress {:.2})",
+                    example.text,
+                    example.label,
+                    assessment.level,
+                    assessment.crisis_score,
+                    assessment.distress_score
+                ));
+            }
+        }
+        assert!(
+            failures.is_empty(),
+            "misclassified:\n{}",
+            failures.join("\n")
+        );
+    }
+
+    #[test]
+    fn test_past_attempts_are_crisis() {
+        let classifier = WellbeingClassifier::default();
+        for text in [
+            "I almost killed myself last spring",
+            "I tried to kill myself in March",
+            "Last winter I tried to end my life",
+            "I nearly ended my life after finals",
+            "I tried to end it last night",
+            "I harmed myself after the exam",
+        ] {
+            let assessment = classifier.assess(text);
+            assert_eq!(assessment.level, WellbeingLevel::Crisis, "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn test_thresholds_are_configurable() {
+        let strict = WellbeingClassifier::new(WellbeingConfig {
+            distress_threshold: 0.2,
+            ..Default::default()
+        });
+        let text = "I feel overwhelmed by this unit";
+        assert_eq!(
+            WellbeingClassifier::default().assess(text).level,
+            WellbeingLevel::None
+        );
+        assert_eq!(strict.assess(text).level, WellbeingLevel::Distress);
+    }
+
+    #[test]
+    fn test_support_reply_lists_contacts() {
+        let classifier = WellbeingClassifier::default();
+        let reply = classifier.support_reply(WellbeingLevel::Crisis);
+        assert!(reply.contains("988"));
+        assert!(reply.starts_with(&classifier.config().resources.crisis_message));
+    }
+
+    #[tokio::test]
+    async fn test_alerts_store_no_message_text() {
+        let mut monitor = WellbeingMonitor::default();
+        let assessment = monitor.classifier().assess("I want to die");
+        let alert = monitor
+            .raise_alert(7, Uuid::new_v4(), &assessment, None)
+            .await;
+        let json = serde_json::to_string(&alert).unwrap();
+        assert!(!json.contains("want to die"));
+        assert!(monitor.acknowledge(alert.id, 1));
+        assert_eq!(monitor.alerts().next().unwrap().acknowledged_by, Some(1));
+    }
+}
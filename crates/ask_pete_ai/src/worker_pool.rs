@@ -0,0 +1,648 @@
+//! Priority-aware admission for model calls.
+//!
+//! Every inference asks the pool for a [`WorkerSlot`] before it runs and
+//! gives it back on drop. Free slots go to the highest [`Priority`] class
+//! with a runnable job, round-robin across users inside a class, and never
+//! to more jobs per model than that model's limit. A full class is refused
+//! up front with a retry hint rather than left to pile up.
+
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+use thiserror::Error;
+use tokio::sync::oneshot;
+
+/// Weight of the newest sample in the wait/run averages
+const EMA_ALPHA: f64 = 0.2;
+/// Assumed job length before any job has finished
+const DEFAULT_RUN_SECS: f64 = 5.0;
+const MAX_RETRY_SECS: u64 = 60;
+
+/// Scheduling classes, most urgent first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Priority {
+    /// A student waiting on Pete's chat reply
+    Interactive,
+    /// AI Mirror turns inside a reflection session
+    Mirror,
+    /// Vocabulary and node weighing
+    WeighStation,
+    /// Blueprints and stories; nobody is watching a spinner
+    Batch,
+}
+
+impl Priority {
+    pub const ALL: [Priority; 4] = [
+        Priority::Interactive,
+        Priority::Mirror,
+        Priority::WeighStation,
+        Priority::Batch,
+    ];
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum PoolError {
+    #[error("Inference queue for {priority:?} jobs is full; retry in {}s", retry_after.as_secs())]
+    Saturated {
+        priority: Priority,
+        retry_after: Duration,
+    },
+}
+
+/// What a job is, for scheduling purposes
+#[derive(Debug, Clone)]
+pub struct JobSpec {
+    pub priority: Priority,
+    /// Key for the per-model limit, e.g. `SocraticEngine::model_id()`
+    pub model_id: String,
+    /// Jobs without a user share one anonymous turn
+    pub user_id: Option<i64>,
+}
+
+impl JobSpec {
+    pub fn new(priority: Priority, model_id: impl Into<String>) -> Self {
+        Self {
+            priority,
+            model_id: model_id.into(),
+            user_id: None,
+        }
+    }
+
+    pub fn for_user(mut self, user_id: i64) -> Self {
+        self.user_id = Some(user_id);
+        self
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PoolConfig {
+    /// Jobs running at once across all models
+    pub workers: usize,
+    /// Jobs waiting per priority class before new ones are refused
+    pub max_queued: usize,
+    /// Jobs running at once on a model without its own limit
+    pub default_model_limit: usize,
+    pub model_limits: HashMap<String, usize>,
+}
+
+impl Default for PoolConfig {
+    fn default() -> Self {
+        Self {
+            workers: 4,
+            max_queued: 64,
+            // Local models share one set of weights and a CPU; one at a time
+            default_model_limit: 1,
+            model_limits: HashMap::new(),
+        }
+    }
+}
+
+impl PoolConfig {
+    /// Reads `INFERENCE_WORKERS`, `INFERENCE_QUEUE_DEPTH`, `INFERENCE_MODEL_LIMIT`
+    /// and `INFERENCE_MODEL_LIMITS` ("gemini:gemini-pro=8,local:x.gguf=1").
+    /// Zero workers or queue depth is raised to one.
+    pub fn from_env() -> Self {
+        let number = |key: &str| std::env::var(key).ok().and_then(|v| v.parse().ok());
+        let defaults = Self::default();
+
+        let mut config = Self {
+            workers: number("INFERENCE_WORKERS")
+                .unwrap_or(defaults.workers)
+                .max(1),
+            max_queued: number("INFERENCE_QUEUE_DEPTH")
+                .unwrap_or(defaults.max_queued)
+                .max(1),
+            default_model_limit: number("INFERENCE_MODEL_LIMIT")
+                .unwrap_or(defaults.default_model_limit),
+            model_limits: HashMap::new(),
+        };
+        if let Ok(limits) = std::env::var("INFERENCE_MODEL_LIMITS") {
+            for entry in limits.split(',') {
+                match entry.trim().rsplit_once('=') {
+                    Some((model_id, limit)) => match limit.trim().parse() {
+                        Ok(limit) => config = config.with_model_limit(model_id.trim(), limit),
+                        Err(_) => log::warn!("Ignoring model limit '{}'", entry),
+                    },
+                    None if entry.trim().is_empty() => {}
+                    None => log::warn!("Ignoring model limit '{}'", entry),
+                }
+            }
+        }
+        config
+    }
+
+    pub fn with_model_limit(mut self, model_id: impl Into<String>, limit: usize) -> Self {
+        self.model_limits.insert(model_id.into(), limit);
+        self
+    }
+
+    fn model_limit(&self, model_id: &str) -> usize {
+        self.model_limits
+            .get(model_id)
+            .copied()
+            .unwrap_or(self.default_model_limit)
+            .max(1)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct QueueDepth {
+    pub priority: Priority,
+    pub queued: usize,
+    /// Distinct users with jobs waiting in this class
+    pub users: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PoolMetrics {
+    pub workers: usize,
+    pub max_queued: usize,
+    pub running: usize,
+    pub running_by_model: BTreeMap<String, usize>,
+    pub queues: Vec<QueueDepth>,
+    pub admitted: u64,
+    pub rejected: u64,
+    /// Moving averages over recent jobs
+    pub avg_wait_ms: f64,
+    pub avg_run_ms: f64,
+}
+
+struct Waiter {
+    id: u64,
+    model_id: String,
+    enqueued_at: Instant,
+    admit: oneshot::Sender<()>,
+}
+
+/// One priority class: a FIFO per user, served in turn order
+#[derive(Default)]
+struct ClassQueue {
+    turns: VecDeque<Option<i64>>,
+    waiting: HashMap<Option<i64>, VecDeque<Waiter>>,
+    len: usize,
+}
+
+impl ClassQueue {
+    fn push(&mut self, user_id: Option<i64>, waiter: Waiter) {
+        let queue = self.waiting.entry(user_id).or_default();
+        if queue.is_empty() {
+            self.turns.push_back(user_id);
+        }
+        queue.push_back(waiter);
+        self.len += 1;
+    }
+
+    /// False when the job is no longer waiting (it was admitted)
+    fn remove(&mut self, user_id: Option<i64>, id: u64) -> bool {
+        let Some(queue) = self.waiting.get_mut(&user_id) else {
+            return false;
+        };
+        let Some(pos) = queue.iter().position(|w| w.id == id) else {
+            return false;
+        };
+        queue.remove(pos);
+        if queue.is_empty() {
+            self.waiting.remove(&user_id);
+            self.turns.retain(|turn| *turn != user_id);
+        }
+        self.len -= 1;
+        true
+    }
+
+    /// Oldest job of the first user in turn whose model has room. That user
+    /// goes to the back; users skipped over keep their place.
+    fn next(&mut self, has_room: impl Fn(&str) -> bool) -> Option<Waiter> {
+        let pos = self.turns.iter().position(|user_id| {
+            self.waiting[user_id]
+                .front()
+                .is_some_and(|w| has_room(&w.model_id))
+        })?;
+        let user_id = self.turns.remove(pos)?;
+        let queue = self.waiting.get_mut(&user_id)?;
+        let waiter = queue.pop_front()?;
+        if queue.is_empty() {
+            self.waiting.remove(&user_id);
+        } else {
+            self.turns.push_back(user_id);
+        }
+        self.len -= 1;
+        Some(waiter)
+    }
+}
+
+#[derive(Default)]
+struct Scheduler {
+    classes: [ClassQueue; 4],
+    running: usize,
+    running_by_model: HashMap<String, usize>,
+    next_id: u64,
+    admitted: u64,
+    rejected: u64,
+    avg_wait_secs: Option<f64>,
+    avg_run_secs: Option<f64>,
+}
+
+impl Scheduler {
+    /// Hand out free slots until workers or runnable jobs run out
+    fn dispatch(&mut self, config: &PoolConfig) {
+        while self.running < config.workers {
+            let running_by_model = &self.running_by_model;
+            let has_room = |model_id: &str| {
+                running_by_model.get(model_id).copied().unwrap_or(0) < config.model_limit(model_id)
+            };
+            let Some(waiter) = self
+                .classes
+                .iter_mut()
+                .find_map(|class| class.next(has_room))
+            else {
+                break;
+            };
+
+            let waited = waiter.enqueued_at.elapsed();
+            // The ticket removes itself under this lock, so it is still listening
+            if waiter.admit.send(()).is_ok() {
+                self.running += 1;
+                *self.running_by_model.entry(waiter.model_id).or_default() += 1;
+                self.admitted += 1;
+                ema(&mut self.avg_wait_secs, waited.as_secs_f64());
+            }
+        }
+    }
+
+    fn release(&mut self, model_id: &str, ran: Option<Duration>) {
+        self.running -= 1;
+        if let Some(count) = self.running_by_model.get_mut(model_id) {
+            *count -= 1;
+            if *count == 0 {
+                self.running_by_model.remove(model_id);
+            }
+        }
+        if let Some(ran) = ran {
+            ema(&mut self.avg_run_secs, ran.as_secs_f64());
+        }
+    }
+
+    /// Time until the jobs ahead of a new `priority` job have likely run
+    fn retry_after(&self, priority: Priority, config: &PoolConfig) -> Duration {
+        let ahead: usize = self.classes[..=priority.index()]
+            .iter()
+            .map(|class| class.len)
+            .sum();
+        let run_secs = self.avg_run_secs.unwrap_or(DEFAULT_RUN_SECS);
+        let secs = (ahead as f64 * run_secs / config.workers.max(1) as f64).ceil() as u64;
+        Duration::from_secs(secs.clamp(1, MAX_RETRY_SECS))
+    }
+}
+
+fn ema(average: &mut Option<f64>, sample: f64) {
+    *average = Some(match *average {
+        Some(avg) => avg + EMA_ALPHA * (sample - avg),
+        None => sample,
+    });
+}
+
+struct Inner {
+    config: PoolConfig,
+    scheduler: Mutex<Scheduler>,
+}
+
+impl Inner {
+    /// Slots are released from `Drop`, so a poisoned lock must not panic again
+    fn scheduler(&self) -> MutexGuard<'_, Scheduler> {
+        self.scheduler
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Shared handle; clones schedule against the same slots
+#[derive(Clone)]
+pub struct InferencePool {
+    inner: Arc<Inner>,
+}
+
+impl InferencePool {
+    pub fn new(mut config: PoolConfig) -> Self {
+        // Zero would admit nothing and leave every caller waiting forever
+        config.workers = config.workers.max(1);
+        config.max_queued = config.max_queued.max(1);
+        Self {
+            inner: Arc::new(Inner {
+                config,
+                scheduler: Mutex::new(Scheduler::default()),
+            }),
+        }
+    }
+
+    /// Join the queue without waiting. Fails at once when the job's class
+    /// is full, so callers can answer 503 before doing any work.
+    pub fn enqueue(&self, spec: JobSpec) -> Result<Ticket, PoolError> {
+        let config = &self.inner.config;
+        let mut scheduler = self.inner.scheduler();
+
+        if scheduler.classes[spec.priority.index()].len >= config.max_queued {
+            scheduler.rejected += 1;
+            return Err(PoolError::Saturated {
+                priority: spec.priority,
+                retry_after: scheduler.retry_after(spec.priority, config),
+            });
+        }
+
+        let id = scheduler.next_id;
+        scheduler.next_id += 1;
+        let (admit, admitted) = oneshot::channel();
+        scheduler.classes[spec.priority.index()].push(
+            spec.user_id,
+            Waiter {
+                id,
+                model_id: spec.model_id.clone(),
+                enqueued_at: Instant::now(),
+                admit,
+            },
+        );
+        scheduler.dispatch(config);
+
+        Ok(Ticket {
+            inner: self.inner.clone(),
+            id,
+            spec,
+            admitted,
+            holds_slot: false,
+        })
+    }
+
+    /// Queue and wait for a slot; hold the slot for the length of the call
+    pub async fn acquire(&self, spec: JobSpec) -> Result<WorkerSlot, PoolError> {
+        Ok(self.enqueue(spec)?.wait().await)
+    }
+
+    pub fn metrics(&self) -> PoolMetrics {
+        let config = &self.inner.config;
+        let scheduler = self.inner.scheduler();
+
+        PoolMetrics {
+            workers: config.workers,
+            max_queued: config.max_queued,
+            running: scheduler.running,
+            running_by_model: scheduler
+                .running_by_model
+                .iter()
+                .map(|(model_id, count)| (model_id.clone(), *count))
+                .collect(),
+            queues: Priority::ALL
+                .into_iter()
+                .map(|priority| {
+                    let class = &scheduler.classes[priority.index()];
+                    QueueDepth {
+                        priority,
+                        queued: class.len,
+                        users: class.turns.len(),
+                    }
+                })
+                .collect(),
+            admitted: scheduler.admitted,
+            rejected: scheduler.rejected,
+            avg_wait_ms: scheduler.avg_wait_secs.unwrap_or(0.0) * 1000.0,
+            avg_run_ms: scheduler.avg_run_secs.unwrap_or(0.0) * 1000.0,
+        }
+    }
+}
+
+/// A place in the queue. Dropping it before `wait` finishes gives the place
+/// (or the slot, if it was just granted) back.
+pub struct Ticket {
+    inner: Arc<Inner>,
+    id: u64,
+    spec: JobSpec,
+    admitted: oneshot::Receiver<()>,
+    holds_slot: bool,
+}
+
+impl Ticket {
+    pub async fn wait(mut self) -> WorkerSlot {
+        (&mut self.admitted)
+            .await
+            .expect("the pool keeps a waiter until it is admitted or its ticket is dropped");
+        self.holds_slot = true;
+        WorkerSlot {
+            inner: self.inner.clone(),
+            model_id: self.spec.model_id.clone(),
+            started: Instant::now(),
+        }
+    }
+}
+
+impl Drop for Ticket {
+    fn drop(&mut self) {
+        if self.holds_slot {
+            return;
+        }
+        let mut scheduler = self.inner.scheduler();
+        let class = &mut scheduler.classes[self.spec.priority.index()];
+        if !class.remove(self.spec.user_id, self.id) {
+            // Admitted, but nobody is left to run it
+            scheduler.release(&self.spec.model_id, None);
+            scheduler.dispatch(&self.inner.config);
+        }
+    }
+}
+
+/// Permission to run one job; frees the slot for the next job on drop
+pub struct WorkerSlot {
+    inner: Arc<Inner>,
+    model_id: String,
+    started: Instant,
+}
+
+impl Drop for WorkerSlot {
+    fn drop(&mut self) {
+        let mut scheduler = self.inner.scheduler();
+        scheduler.release(&self.model_id, Some(self.started.elapsed()));
+        scheduler.dispatch(&self.inner.config);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pool(workers: usize, max_queued: usize) -> InferencePool {
+        InferencePool::new(PoolConfig {
+            workers,
+            max_queued,
+            default_model_limit: workers,
+            model_limits: HashMap::new(),
+        })
+    }
+
+    fn job(priority: Priority) -> JobSpec {
+        JobSpec::new(priority, "m")
+    }
+
+    #[tokio::test]
+    async fn test_zero_workers_still_admit_jobs() {
+        let pool = pool(0, 0);
+        let slot = tokio::time::timeout(
+            std::time::Duration::from_secs(1),
+            pool.acquire(job(Priority::Interactive)),
+        )
+        .await
+        .expect("a zero-worker pool must not hang")
+        .unwrap();
+        assert_eq!(pool.metrics().workers, 1);
+        drop(slot);
+    }
+
+    /// Runs tickets in admission order (one worker) and returns their labels
+    async fn admission_order(tickets: Vec<(&'static str, Ticket)>) -> Vec<&'static str> {
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let handles: Vec<_> = tickets
+            .into_iter()
+            .map(|(label, ticket)| {
+                let order = order.clone();
+                tokio::spawn(async move {
+                    let _slot = ticket.wait().await;
+                    order.lock().unwrap().push(label);
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.await.unwrap();
+        }
+        let order = order.lock().unwrap().clone();
+        order
+    }
+
+    #[tokio::test]
+    async fn test_higher_priority_classes_go_first() {
+        let pool = pool(1, 8);
+        let busy = pool.acquire(job(Priority::Batch)).await.unwrap();
+
+        let enqueue = |priority| pool.enqueue(job(priority)).unwrap();
+        let tickets = vec![
+            ("architect", enqueue(Priority::Batch)),
+            ("weigh", enqueue(Priority::WeighStation)),
+            ("mirror", enqueue(Priority::Mirror)),
+            ("chat", enqueue(Priority::Interactive)),
+        ];
+        drop(busy);
+
+        assert_eq!(
+            admission_order(tickets).await,
+            vec!["chat", "mirror", "weigh", "architect"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_users_take_turns_within_a_class() {
+        let pool = pool(1, 8);
+        let busy = pool.acquire(job(Priority::Batch)).await.unwrap();
+
+        let job = |user| job(Priority::Interactive).for_user(user);
+        let tickets = vec![
+            ("a1", pool.enqueue(job(1)).unwrap()),
+            ("a2", pool.enqueue(job(1)).unwrap()),
+            ("a3", pool.enqueue(job(1)).unwrap()),
+            ("b1", pool.enqueue(job(2)).unwrap()),
+            ("b2", pool.enqueue(job(2)).unwrap()),
+        ];
+        drop(busy);
+
+        assert_eq!(
+            admission_order(tickets).await,
+            vec!["a1", "b1", "a2", "b2", "a3"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_model_limit_lets_other_models_through() {
+        let pool = InferencePool::new(
+            PoolConfig {
+                workers: 2,
+                max_queued: 8,
+                default_model_limit: 2,
+                model_limits: HashMap::new(),
+            }
+            .with_model_limit("local", 1),
+        );
+        let _busy = pool
+            .acquire(JobSpec::new(Priority::Batch, "local"))
+            .await
+            .unwrap();
+
+        let blocked = pool
+            .enqueue(JobSpec::new(Priority::Interactive, "local"))
+            .unwrap();
+        let other = pool
+            .enqueue(JobSpec::new(Priority::Batch, "gemini"))
+            .unwrap();
+
+        let slot = tokio::time::timeout(Duration::from_millis(200), other.wait())
+            .await
+            .expect("a different model is not held back");
+        assert!(
+            tokio::time::timeout(Duration::from_millis(50), blocked.wait())
+                .await
+                .is_err()
+        );
+
+        let metrics = pool.metrics();
+        assert_eq!(metrics.running, 2);
+        assert_eq!(metrics.running_by_model.get("local"), Some(&1));
+        assert_eq!(
+            metrics.queues[0].queued, 0,
+            "timed-out ticket left the queue"
+        );
+        drop(slot);
+    }
+
+    #[tokio::test]
+    async fn test_full_class_is_refused_with_retry_hint() {
+        let pool = pool(1, 1);
+        let _busy = pool.acquire(job(Priority::Batch)).await.unwrap();
+        let _queued = pool.enqueue(job(Priority::Batch)).unwrap();
+
+        match pool.enqueue(job(Priority::Batch)) {
+            Err(PoolError::Saturated {
+                priority,
+                retry_after,
+            }) => {
+                assert_eq!(priority, Priority::Batch);
+                assert!(retry_after >= Duration::from_secs(1));
+            }
+            Ok(_) => panic!("queue should be saturated"),
+        }
+        // Chat has its own room
+        let _chat = pool.enqueue(job(Priority::Interactive)).unwrap();
+
+        let metrics = pool.metrics();
+        assert_eq!(metrics.rejected, 1);
+        assert_eq!(metrics.queues[Priority::Batch.index()].queued, 1);
+        assert_eq!(metrics.queues[Priority::Interactive.index()].queued, 1);
+    }
+
+    #[tokio::test]
+    async fn test_dropped_tickets_return_their_place_or_slot() {
+        let pool = pool(1, 8);
+        let busy = pool.acquire(job(Priority::Batch)).await.unwrap();
+        let waiting = pool.enqueue(job(Priority::Batch)).unwrap();
+        drop(waiting);
+        assert_eq!(pool.metrics().queues[Priority::Batch.index()].queued, 0);
+        drop(busy);
+
+        // Admitted straight away, then abandoned before running
+        let abandoned = pool.enqueue(job(Priority::Batch)).unwrap();
+        assert_eq!(pool.metrics().running, 1);
+        drop(abandoned);
+        assert_eq!(pool.metrics().running, 0);
+
+        let _slot = pool.acquire(job(Priority::Batch)).await.unwrap();
+        assert_eq!(pool.metrics().admitted, 3);
+    }
+}
@@ -19,10 +19,25 @@ impl Coal {
         Coal((tokens as f64) * Self::GEMMA_COST_PER_TOKEN)
     }
 
+    /// Cost for cloud Gemini inference per 1,000 prompt tokens
+    pub const GEMINI_COST_PER_1K_INPUT: f64 = 1.5;
+
+    /// Cost for cloud Gemini inference per 1,000 generated tokens
+    pub const GEMINI_COST_PER_1K_OUTPUT: f64 = 6.0;
+
     /// Calculate cost for a cloud request
     pub fn cost_cloud() -> Self {
         Coal(Self::GEMINI_COST_PER_REQUEST)
     }
+
+    /// Calculate cost for a cloud request from its reported token usage.
+    /// A typical tutoring turn (~2k in, ~300 out) costs about one request's worth.
+    pub fn cost_cloud_tokens(prompt_tokens: usize, output_tokens: usize) -> Self {
+        Coal(
+            (prompt_tokens as f64) / 1000.0 * Self::GEMINI_COST_PER_1K_INPUT
+                + (output_tokens as f64) / 1000.0 * Self::GEMINI_COST_PER_1K_OUTPUT,
+        )
+    }
 }
 
 /// Represents "Steam" (Mastery/Progress).
@@ -0,0 +1,321 @@
+//! # Narrative Frameworks
+//!
+//! A narrative framework is a lesson shape: an ordered list of named stages,
+//! each with guidance for the AI writing it. Frameworks can describe
+//! themselves to the Architect and emit a skeleton `StoryGraph` (one station
+//! per stage, chained in order) for a subject.
+
+use crate::expert::{Connection, StoryGraph, StoryNode};
+use crate::layout::{AutoLayout, LayoutConfig};
+use serde::{Deserialize, Serialize};
+
+/// One stage of a framework
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FrameworkStage {
+    pub id: String,
+    pub name: String,
+    /// What the stage does for the learner
+    pub purpose: String,
+    /// How the AI should write content for this stage
+    pub guidance: String,
+    /// 1-3, used as the skeleton station's complexity level
+    pub complexity: u8,
+}
+
+impl FrameworkStage {
+    pub fn new(id: &str, name: &str, purpose: &str, guidance: &str, complexity: u8) -> Self {
+        Self {
+            id: id.to_string(),
+            name: name.to_string(),
+            purpose: purpose.to_string(),
+            guidance: guidance.to_string(),
+            complexity,
+        }
+    }
+}
+
+pub trait NarrativeFramework: Send + Sync {
+    /// Stable id used in URLs and requests, e.g. "heros_journey"
+    fn id(&self) -> &str;
+
+    fn name(&self) -> &str;
+
+    fn description(&self) -> &str;
+
+    /// Stages in the order learners meet them
+    fn stages(&self) -> &[FrameworkStage];
+
+    fn stage(&self, stage_id: &str) -> Option<&FrameworkStage> {
+        self.stages().iter().find(|s| s.id == stage_id)
+    }
+
+    /// Instructions for an AI structuring a lesson with this framework
+    fn prompt_guidance(&self) -> String {
+        let mut guidance = format!(
+            "Structure the lesson as {} ({} stages). Each stage is a Node, in this order:\n",
+            self.name(),
+            self.stages().len()
+        );
+        for (i, stage) in self.stages().iter().enumerate() {
+            guidance.push_str(&format!(
+                "{}. {} - {} {}\n",
+                i + 1,
+                stage.name,
+                stage.purpose,
+                stage.guidance
+            ));
+        }
+        guidance
+    }
+
+    /// One station per stage, connected in order and laid out left to right
+    fn skeleton(&self, subject: &str) -> StoryGraph {
+        let nodes: Vec<StoryNode> = self
+            .stages()
+            .iter()
+            .enumerate()
+            .map(|(i, stage)| StoryNode {
+                id: format!("{:02}_{}", i + 1, stage.id),
+                title: stage.name.clone(),
+                content: format!("{}\n\nSubject: {}", stage.purpose, subject),
+                x: 0.0,
+                y: 0.0,
+                passenger_count: 0,
+                complexity_level: stage.complexity.clamp(1, 3),
+                learner_profiles: vec![],
+                gardens_active: vec![],
+                required_stats: Default::default(),
+                logic: Default::default(),
+                style: Default::default(),
+                quest: None,
+                mass: None,
+                analysis_hash: None,
+            })
+            .collect();
+        let connections = nodes
+            .windows(2)
+            .map(|pair| Connection {
+                id: format!("edge_{}_{}", pair[0].id, pair[1].id),
+                from_node: pair[0].id.clone(),
+                to_node: pair[1].id.clone(),
+            })
+            .collect();
+
+        let mut graph = StoryGraph {
+            id: format!("{}_{}", self.id(), slug(subject)),
+            title: format!("{}: {}", subject, self.name()),
+            nodes,
+            connections,
+        };
+        graph.auto_layout(&LayoutConfig::default());
+        graph
+    }
+}
+
+/// "Newton's Laws" -> "newtons_laws"
+fn slug(text: &str) -> String {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join("_")
+}
+
+/// A framework defined entirely by its stage list
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StagedFramework {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub stages: Vec<FrameworkStage>,
+}
+
+impl NarrativeFramework for StagedFramework {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    fn stages(&self) -> &[FrameworkStage] {
+        &self.stages
+    }
+}
+
+impl StagedFramework {
+    pub fn heros_journey() -> Self {
+        Self {
+            id: "heros_journey".to_string(),
+            name: "Hero's Journey".to_string(),
+            description: "The learner leaves familiar ground, faces a trial, and returns changed."
+                .to_string(),
+            stages: vec![
+                FrameworkStage::new(
+                    "call",
+                    "The Call",
+                    "Introduce the subject through a problem the learner cannot yet solve.",
+                    "Open with a concrete hook and end on the question the lesson will answer.",
+                    1,
+                ),
+                FrameworkStage::new(
+                    "threshold",
+                    "The Threshold",
+                    "The first challenge: apply prior knowledge and find where it falls short.",
+                    "Pose a task that is solvable with effort; surface one common misconception.",
+                    1,
+                ),
+                FrameworkStage::new(
+                    "abyss",
+                    "The Abyss",
+                    "Confront the core concept at its hardest.",
+                    "Introduce the key idea and its vocabulary; ask the learner to reason, not recall.",
+                    3,
+                ),
+                FrameworkStage::new(
+                    "transformation",
+                    "The Transformation",
+                    "Apply the new understanding to a fresh situation.",
+                    "Give a transfer task in a different context from the Abyss.",
+                    2,
+                ),
+                FrameworkStage::new(
+                    "return",
+                    "The Return",
+                    "Bring the insight home and reflect on what changed.",
+                    "Ask the learner to revisit the opening problem and explain it in their own words.",
+                    1,
+                ),
+            ],
+        }
+    }
+
+    pub fn freytag() -> Self {
+        Self {
+            id: "freytag".to_string(),
+            name: "Freytag's Pyramid".to_string(),
+            description: "Tension builds to a climax, then resolves.".to_string(),
+            stages: vec![
+                FrameworkStage::new(
+                    "exposition",
+                    "Exposition",
+                    "Set the scene: the situation, the people involved and what is at stake.",
+                    "Establish context and the key terms without explaining the central idea yet.",
+                    1,
+                ),
+                FrameworkStage::new(
+                    "rising_action",
+                    "Rising Action",
+                    "Complications pile up as the learner gathers evidence.",
+                    "Add one complication per step; each should need a little more of the concept.",
+                    2,
+                ),
+                FrameworkStage::new(
+                    "climax",
+                    "Climax",
+                    "The decisive moment where the concept must be used to resolve the conflict.",
+                    "Make this the hardest task; the learner commits to an answer and justifies it.",
+                    3,
+                ),
+                FrameworkStage::new(
+                    "falling_action",
+                    "Falling Action",
+                    "Consequences of the decision play out.",
+                    "Show what follows from the learner's choice and let them check it against evidence.",
+                    2,
+                ),
+                FrameworkStage::new(
+                    "denouement",
+                    "Denouement",
+                    "Resolve loose ends and consolidate.",
+                    "Summarise through the learner's own reflection, not a lecture.",
+                    1,
+                ),
+            ],
+        }
+    }
+
+    pub fn kishotenketsu() -> Self {
+        Self {
+            id: "kishotenketsu".to_string(),
+            name: "Kishōtenketsu".to_string(),
+            description:
+                "Four acts without conflict: introduce, develop, twist, and reconcile the twist."
+                    .to_string(),
+            stages: vec![
+                FrameworkStage::new(
+                    "ki",
+                    "Ki (Introduction)",
+                    "Introduce the subject in a familiar setting.",
+                    "Keep it calm and observational; invite the learner to notice details.",
+                    1,
+                ),
+                FrameworkStage::new(
+                    "sho",
+                    "Shō (Development)",
+                    "Develop the idea so the learner builds a working model.",
+                    "Extend the same setting; the learner predicts and checks small variations.",
+                    2,
+                ),
+                FrameworkStage::new(
+                    "ten",
+                    "Ten (Twist)",
+                    "Present something unexpected that the model does not seem to explain.",
+                    "Introduce a surprising case from a different angle, not a conflict or villain.",
+                    3,
+                ),
+                FrameworkStage::new(
+                    "ketsu",
+                    "Ketsu (Reconciliation)",
+                    "Reconcile the twist with the earlier idea into a richer understanding.",
+                    "Ask the learner how both the familiar and the surprising cases fit together.",
+                    2,
+                ),
+            ],
+        }
+    }
+}
+
+/// The frameworks Ask Pete ships with
+pub fn builtin_frameworks() -> Vec<StagedFramework> {
+    vec![
+        StagedFramework::heros_journey(),
+        StagedFramework::freytag(),
+        StagedFramework::kishotenketsu(),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_skeleton_has_one_station_per_stage_in_order() {
+        for framework in builtin_frameworks() {
+            let graph = framework.skeleton("Newton's Laws");
+            assert_eq!(graph.nodes.len(), framework.stages.len());
+            assert_eq!(graph.connections.len(), framework.stages.len() - 1);
+            assert_eq!(graph.id, format!("{}_newton_s_laws", framework.id));
+            for (edge, pair) in graph.connections.iter().zip(graph.nodes.windows(2)) {
+                assert_eq!(edge.from_node, pair[0].id);
+                assert_eq!(edge.to_node, pair[1].id);
+                assert!(pair[0].x < pair[1].x);
+            }
+        }
+    }
+
+    #[test]
+    fn test_prompt_guidance_lists_every_stage() {
+        let framework = StagedFramework::kishotenketsu();
+        let guidance = framework.prompt_guidance();
+        for stage in framework.stages() {
+            assert!(guidance.contains(&stage.name));
+        }
+        assert_eq!(framework.stage("ten").unwrap().complexity, 3);
+    }
+}
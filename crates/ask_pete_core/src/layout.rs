@@ -0,0 +1,603 @@
+//! # Rail Yard Surveyor (Auto-Layout)
+//!
+//! Deterministic layered (Sugiyama-style) layout for story graphs.
+//! AI blueprints and Twine imports often arrive with every station at (0, 0),
+//! so the Trainyard canvas needs a way to lay the track out again.
+//!
+//! Pipeline:
+//! 1. Break cycles (loops back to earlier stations) by reversing back edges.
+//! 2. Assign layers with the longest-path rule, so every track runs left to right.
+//! 3. Split long edges into virtual waypoints, one per layer they cross.
+//! 4. Reduce crossings with alternating barycenter sweeps.
+//! 5. Assign y coordinates that pull each station toward its neighbours,
+//!    which keeps a branch in its own lane.
+//!
+//! The same input (node order + edge order) always yields the same output.
+
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+/// Spacing and iteration settings for the layout engine.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct LayoutConfig {
+    /// Horizontal distance between layers (station width + track gap).
+    pub layer_spacing: f64,
+    /// Vertical distance between stations in the same layer.
+    pub node_spacing: f64,
+    pub origin_x: f64,
+    pub origin_y: f64,
+    /// Number of down/up barycenter sweep pairs.
+    pub sweeps: usize,
+}
+
+impl Default for LayoutConfig {
+    fn default() -> Self {
+        Self {
+            // Stations render as w-72 (288px) by ~150px on the canvas.
+            layer_spacing: 400.0,
+            node_spacing: 200.0,
+            origin_x: 100.0,
+            origin_y: 100.0,
+            sweeps: 8,
+        }
+    }
+}
+
+/// What the layout engine did, for logging and API responses.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct LayoutSummary {
+    pub layers: usize,
+    pub crossings: usize,
+    pub reversed_edges: usize,
+}
+
+/// Computed positions keyed by node id.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GraphLayout {
+    pub positions: HashMap<String, (f64, f64)>,
+    pub summary: LayoutSummary,
+}
+
+/// Graph types that can be re-positioned by the layout engine.
+pub trait AutoLayout {
+    fn auto_layout(&mut self, config: &LayoutConfig) -> LayoutSummary;
+}
+
+impl AutoLayout for crate::expert::StoryGraph {
+    fn auto_layout(&mut self, config: &LayoutConfig) -> LayoutSummary {
+        layout_graph(
+            &mut self.nodes,
+            &self.connections,
+            |n| n.id.as_str(),
+            |c| (c.from_node.as_str(), c.to_node.as_str()),
+            |n| (&mut n.x, &mut n.y),
+            config,
+        )
+    }
+}
+
+impl AutoLayout for crate::trainyard::StoryGraph {
+    fn auto_layout(&mut self, config: &LayoutConfig) -> LayoutSummary {
+        layout_graph(
+            &mut self.nodes,
+            &self.connections,
+            |n| n.id.as_str(),
+            |c| (c.from_node.as_str(), c.to_node.as_str()),
+            |n| (&mut n.x, &mut n.y),
+            config,
+        )
+    }
+}
+
+/// Shared body of the [`AutoLayout`] impls: lays out the nodes and writes
+/// each computed position back through `position`.
+fn layout_graph<N, C>(
+    nodes: &mut [N],
+    connections: &[C],
+    id: impl Fn(&N) -> &str,
+    endpoints: impl Fn(&C) -> (&str, &str),
+    position: impl Fn(&mut N) -> (&mut f64, &mut f64),
+    config: &LayoutConfig,
+) -> LayoutSummary {
+    let layout = compute_layout(
+        nodes.iter().map(&id),
+        connections.iter().map(endpoints),
+        config,
+    );
+    for node in nodes.iter_mut() {
+        if let Some(&(x, y)) = layout.positions.get(id(node)) {
+            let (node_x, node_y) = position(node);
+            *node_x = x;
+            *node_y = y;
+        }
+    }
+    layout.summary
+}
+
+/// Lays out an arbitrary directed graph given node ids and (from, to) edges.
+///
+/// Duplicate node ids keep their first occurrence. Self-loops, duplicate edges
+/// and edges that reference unknown nodes are ignored.
+pub fn compute_layout<'a>(
+    node_ids: impl IntoIterator<Item = &'a str>,
+    edges: impl IntoIterator<Item = (&'a str, &'a str)>,
+    config: &LayoutConfig,
+) -> GraphLayout {
+    let mut ids: Vec<&str> = Vec::new();
+    let mut index: HashMap<&str, usize> = HashMap::new();
+    for id in node_ids {
+        if !index.contains_key(id) {
+            index.insert(id, ids.len());
+            ids.push(id);
+        }
+    }
+    let n = ids.len();
+    if n == 0 {
+        return GraphLayout::default();
+    }
+
+    let mut seen = HashSet::new();
+    let mut edge_list = Vec::new();
+    for (from, to) in edges {
+        if let (Some(&u), Some(&v)) = (index.get(from), index.get(to)) {
+            if u != v && seen.insert((u, v)) {
+                edge_list.push((u, v));
+            }
+        }
+    }
+
+    let (dag, reversed_edges) = break_cycles(n, &edge_list);
+    let layer_of = assign_layers(n, &dag);
+    let (succ, pred, node_layer) = insert_virtual_nodes(&dag, &layer_of);
+    let layer_count = node_layer.iter().copied().max().unwrap_or(0) + 1;
+
+    let mut layers = initial_order(n, &succ, &node_layer, layer_count);
+    let crossings = minimize_crossings(&mut layers, &succ, &pred, config.sweeps);
+    let ys = assign_y(&layers, &succ, &pred, config);
+
+    let mut positions = HashMap::with_capacity(n);
+    for (layer_idx, layer) in layers.iter().enumerate() {
+        for &v in layer {
+            if v < n {
+                let x = config.origin_x + layer_idx as f64 * config.layer_spacing;
+                positions.insert(ids[v].to_string(), (x, ys[v]));
+            }
+        }
+    }
+
+    GraphLayout {
+        positions,
+        summary: LayoutSummary {
+            layers: layer_count,
+            crossings,
+            reversed_edges,
+        },
+    }
+}
+
+/// Reverses DFS back edges so the graph becomes acyclic.
+/// Sources are explored first, then any remaining nodes in input order.
+fn break_cycles(n: usize, edges: &[(usize, usize)]) -> (Vec<(usize, usize)>, usize) {
+    let mut out: Vec<Vec<usize>> = vec![Vec::new(); n];
+    let mut in_degree = vec![0usize; n];
+    for &(u, v) in edges {
+        out[u].push(v);
+        in_degree[v] += 1;
+    }
+
+    // 0 = unvisited, 1 = on stack, 2 = done
+    let mut state = vec![0u8; n];
+    let mut back_edges = HashSet::new();
+    let roots = (0..n)
+        .filter(|&v| in_degree[v] == 0)
+        .chain((0..n).filter(|&v| in_degree[v] != 0));
+
+    for root in roots {
+        if state[root] != 0 {
+            continue;
+        }
+        let mut stack = vec![(root, 0usize)];
+        state[root] = 1;
+        while let Some(&mut (v, ref mut next)) = stack.last_mut() {
+            if let Some(&w) = out[v].get(*next) {
+                *next += 1;
+                match state[w] {
+                    0 => {
+                        state[w] = 1;
+                        stack.push((w, 0));
+                    }
+                    1 => {
+                        back_edges.insert((v, w));
+                    }
+                    _ => {}
+                }
+            } else {
+                state[v] = 2;
+                stack.pop();
+            }
+        }
+    }
+
+    let mut dag = Vec::with_capacity(edges.len());
+    let mut dag_seen = HashSet::new();
+    for &(u, v) in edges {
+        let e = if back_edges.contains(&(u, v)) {
+            (v, u)
+        } else {
+            (u, v)
+        };
+        // A reversed edge may duplicate an existing forward edge.
+        if dag_seen.insert(e) {
+            dag.push(e);
+        }
+    }
+    (dag, back_edges.len())
+}
+
+/// Longest-path layering: each node sits one layer right of its furthest predecessor.
+fn assign_layers(n: usize, dag: &[(usize, usize)]) -> Vec<usize> {
+    let mut out: Vec<Vec<usize>> = vec![Vec::new(); n];
+    let mut in_degree = vec![0usize; n];
+    for &(u, v) in dag {
+        out[u].push(v);
+        in_degree[v] += 1;
+    }
+
+    let mut layer = vec![0usize; n];
+    let mut ready: BinaryHeap<Reverse<usize>> =
+        (0..n).filter(|&v| in_degree[v] == 0).map(Reverse).collect();
+    while let Some(Reverse(v)) = ready.pop() {
+        for &w in &out[v] {
+            layer[w] = layer[w].max(layer[v] + 1);
+            in_degree[w] -= 1;
+            if in_degree[w] == 0 {
+                ready.push(Reverse(w));
+            }
+        }
+    }
+    layer
+}
+
+type Adjacency = Vec<Vec<usize>>;
+
+/// Splits edges spanning several layers into chains through virtual nodes.
+/// Virtual nodes are numbered after the real ones.
+fn insert_virtual_nodes(
+    dag: &[(usize, usize)],
+    layer_of: &[usize],
+) -> (Adjacency, Adjacency, Vec<usize>) {
+    let mut node_layer = layer_of.to_vec();
+    let mut succ: Adjacency = vec![Vec::new(); node_layer.len()];
+    let mut pred: Adjacency = vec![Vec::new(); node_layer.len()];
+
+    for &(u, v) in dag {
+        let mut prev = u;
+        for layer in layer_of[u] + 1..layer_of[v] {
+            let dummy = node_layer.len();
+            node_layer.push(layer);
+            succ.push(Vec::new());
+            pred.push(Vec::new());
+            succ[prev].push(dummy);
+            pred[dummy].push(prev);
+            prev = dummy;
+        }
+        succ[prev].push(v);
+        pred[v].push(prev);
+    }
+    (succ, pred, node_layer)
+}
+
+/// Depth-first discovery order, so each branch starts out contiguous.
+fn initial_order(
+    real_nodes: usize,
+    succ: &Adjacency,
+    node_layer: &[usize],
+    layer_count: usize,
+) -> Vec<Vec<usize>> {
+    let mut layers = vec![Vec::new(); layer_count];
+    let mut visited = vec![false; node_layer.len()];
+    for root in 0..real_nodes {
+        if visited[root] {
+            continue;
+        }
+        let mut stack = vec![root];
+        while let Some(v) = stack.pop() {
+            if visited[v] {
+                continue;
+            }
+            visited[v] = true;
+            layers[node_layer[v]].push(v);
+            // Push in reverse so the first child is visited first.
+            for &w in succ[v].iter().rev() {
+                if !visited[w] {
+                    stack.push(w);
+                }
+            }
+        }
+    }
+    layers
+}
+
+/// Alternating down/up barycenter sweeps, keeping the best ordering seen.
+fn minimize_crossings(
+    layers: &mut [Vec<usize>],
+    succ: &Adjacency,
+    pred: &Adjacency,
+    sweeps: usize,
+) -> usize {
+    let mut best = layers.to_vec();
+    let mut best_crossings = total_crossings(layers, succ);
+
+    for _ in 0..sweeps {
+        if best_crossings == 0 {
+            break;
+        }
+        for i in 1..layers.len() {
+            let (fixed, rest) = layers.split_at_mut(i);
+            reorder_by_barycenter(&mut rest[0], &fixed[i - 1], pred);
+        }
+        for i in (0..layers.len().saturating_sub(1)).rev() {
+            let (rest, fixed) = layers.split_at_mut(i + 1);
+            reorder_by_barycenter(&mut rest[i], &fixed[0], succ);
+        }
+        let crossings = total_crossings(layers, succ);
+        if crossings < best_crossings {
+            best_crossings = crossings;
+            best = layers.to_vec();
+        }
+    }
+
+    layers.clone_from_slice(&best);
+    best_crossings
+}
+
+fn reorder_by_barycenter(layer: &mut [usize], fixed: &[usize], neighbours: &Adjacency) {
+    let position: HashMap<usize, usize> = fixed.iter().enumerate().map(|(i, &v)| (v, i)).collect();
+    let mut keyed: Vec<(f64, usize, usize)> = layer
+        .iter()
+        .enumerate()
+        .map(|(current, &v)| {
+            let adjacent: Vec<usize> = neighbours[v]
+                .iter()
+                .filter_map(|w| position.get(w).copied())
+                .collect();
+            let barycenter = if adjacent.is_empty() {
+                current as f64
+            } else {
+                adjacent.iter().sum::<usize>() as f64 / adjacent.len() as f64
+            };
+            (barycenter, current, v)
+        })
+        .collect();
+    // Ties keep their current relative order, which makes the sort deterministic.
+    keyed.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)));
+    for (slot, (_, _, v)) in layer.iter_mut().zip(keyed) {
+        *slot = v;
+    }
+}
+
+fn total_crossings(layers: &[Vec<usize>], succ: &Adjacency) -> usize {
+    layers
+        .windows(2)
+        .map(|pair| {
+            let lower: HashMap<usize, usize> =
+                pair[1].iter().enumerate().map(|(i, &v)| (v, i)).collect();
+            let mut edges = Vec::new();
+            for (i, &u) in pair[0].iter().enumerate() {
+                for w in &succ[u] {
+                    if let Some(&j) = lower.get(w) {
+                        edges.push((i, j));
+                    }
+                }
+            }
+            let mut count = 0;
+            for a in 0..edges.len() {
+                for b in a + 1..edges.len() {
+                    let (u1, v1) = edges[a];
+                    let (u2, v2) = edges[b];
+                    if (u1 < u2 && v1 > v2) || (u1 > u2 && v1 < v2) {
+                        count += 1;
+                    }
+                }
+            }
+            count
+        })
+        .sum()
+}
+
+/// Pulls each node toward the mean y of its neighbours while keeping the
+/// layer order and at least `node_spacing` between stations.
+fn assign_y(
+    layers: &[Vec<usize>],
+    succ: &Adjacency,
+    pred: &Adjacency,
+    config: &LayoutConfig,
+) -> Vec<f64> {
+    let total = succ.len();
+    let spacing = config.node_spacing;
+    let mut y = vec![0.0; total];
+    for layer in layers {
+        for (i, &v) in layer.iter().enumerate() {
+            y[v] = i as f64 * spacing;
+        }
+    }
+
+    for _ in 0..4 {
+        for layer in layers.iter().skip(1) {
+            place_layer(layer, pred, &mut y, spacing);
+        }
+        for layer in layers.iter().rev().skip(1) {
+            place_layer(layer, succ, &mut y, spacing);
+        }
+    }
+
+    let min = y.iter().copied().fold(f64::INFINITY, f64::min);
+    y.iter()
+        .map(|v| (config.origin_y + v - min).round())
+        .collect()
+}
+
+fn place_layer(layer: &[usize], neighbours: &Adjacency, y: &mut [f64], spacing: f64) {
+    if layer.is_empty() {
+        return;
+    }
+    let desired: Vec<f64> = layer
+        .iter()
+        .map(|&v| {
+            if neighbours[v].is_empty() {
+                y[v]
+            } else {
+                neighbours[v].iter().map(|&w| y[w]).sum::<f64>() / neighbours[v].len() as f64
+            }
+        })
+        .collect();
+
+    // Push overlaps downward, push overlaps upward, then average the two.
+    // Both passes respect the spacing, so their mean does too.
+    let len = layer.len();
+    let mut down = desired.clone();
+    for i in 1..len {
+        down[i] = down[i].max(down[i - 1] + spacing);
+    }
+    let mut up = desired;
+    for i in (0..len - 1).rev() {
+        up[i] = up[i].min(up[i + 1] - spacing);
+    }
+    for (i, &v) in layer.iter().enumerate() {
+        y[v] = (down[i] + up[i]) / 2.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layout(nodes: &[&str], edges: &[(&str, &str)]) -> GraphLayout {
+        compute_layout(
+            nodes.iter().copied(),
+            edges.iter().copied(),
+            &LayoutConfig::default(),
+        )
+    }
+
+    #[test]
+    fn test_layout_is_deterministic() {
+        let nodes = ["a", "b", "c", "d", "e", "f"];
+        let edges = [
+            ("a", "b"),
+            ("a", "c"),
+            ("b", "d"),
+            ("c", "d"),
+            ("d", "e"),
+            ("c", "f"),
+        ];
+        let first = layout(&nodes, &edges);
+        for _ in 0..5 {
+            assert_eq!(first, layout(&nodes, &edges));
+        }
+    }
+
+    #[test]
+    fn test_edges_flow_left_to_right() {
+        let nodes = ["start", "mid", "end", "side"];
+        let edges = [("start", "mid"), ("mid", "end"), ("start", "side")];
+        let result = layout(&nodes, &edges);
+        for (from, to) in edges {
+            assert!(result.positions[from].0 < result.positions[to].0);
+        }
+        assert_eq!(result.summary.layers, 3);
+    }
+
+    #[test]
+    fn test_linear_chain_stays_in_one_lane() {
+        let nodes = ["1", "2", "3", "4"];
+        let edges = [("1", "2"), ("2", "3"), ("3", "4")];
+        let result = layout(&nodes, &edges);
+        let y = result.positions["1"].1;
+        assert!(nodes.iter().all(|n| result.positions[*n].1 == y));
+    }
+
+    #[test]
+    fn test_no_overlaps_within_a_layer() {
+        let nodes = ["root", "a", "b", "c", "d"];
+        let edges = [("root", "a"), ("root", "b"), ("root", "c"), ("root", "d")];
+        let result = layout(&nodes, &edges);
+        let mut ys: Vec<f64> = ["a", "b", "c", "d"]
+            .iter()
+            .map(|n| result.positions[*n].1)
+            .collect();
+        ys.sort_by(f64::total_cmp);
+        for pair in ys.windows(2) {
+            assert!(pair[1] - pair[0] >= LayoutConfig::default().node_spacing);
+        }
+    }
+
+    #[test]
+    fn test_crossings_are_removed() {
+        // Input order forces a crossing (a->d, b->c) that reordering can fix.
+        let nodes = ["a", "b", "c", "d"];
+        let edges = [("a", "d"), ("b", "c")];
+        let result = layout(&nodes, &edges);
+        assert_eq!(result.summary.crossings, 0);
+        let a_above_b = result.positions["a"].1 < result.positions["b"].1;
+        let d_above_c = result.positions["d"].1 < result.positions["c"].1;
+        assert_eq!(a_above_b, d_above_c);
+    }
+
+    #[test]
+    fn test_cycles_are_broken() {
+        let nodes = ["a", "b", "c"];
+        let edges = [("a", "b"), ("b", "c"), ("c", "a")];
+        let result = layout(&nodes, &edges);
+        assert_eq!(result.summary.reversed_edges, 1);
+        assert_eq!(result.positions.len(), 3);
+        assert!(result.positions["a"].0 < result.positions["b"].0);
+    }
+
+    #[test]
+    fn test_unknown_edges_and_duplicates_are_ignored() {
+        let nodes = ["a", "b", "a"];
+        let edges = [("a", "b"), ("a", "b"), ("a", "ghost"), ("b", "b")];
+        let result = layout(&nodes, &edges);
+        assert_eq!(result.positions.len(), 2);
+        assert_eq!(result.summary.layers, 2);
+    }
+
+    #[test]
+    fn test_story_graph_positions_are_overwritten() {
+        let node = |id: &str| crate::trainyard::StoryNode {
+            id: id.to_string(),
+            title: id.to_string(),
+            content: String::new(),
+            x: 0.0,
+            y: 0.0,
+            station_type: Default::default(),
+            passenger_count: 0,
+            complexity_level: 1,
+            context_prompt: String::new(),
+            completion_criteria: String::new(),
+            required_stats: HashMap::new(),
+            logic: Default::default(),
+            style: Default::default(),
+        };
+        let mut graph = crate::trainyard::StoryGraph {
+            id: "g".to_string(),
+            title: "Imported".to_string(),
+            nodes: vec![node("a"), node("b")],
+            connections: vec![crate::trainyard::Connection {
+                id: "c1".to_string(),
+                from_node: "a".to_string(),
+                to_node: "b".to_string(),
+                connection_type: Default::default(),
+            }],
+            metadata: HashMap::new(),
+        };
+        let summary = graph.auto_layout(&LayoutConfig::default());
+        assert_eq!(summary.layers, 2);
+        assert_ne!(
+            (graph.nodes[0].x, graph.nodes[0].y),
+            (graph.nodes[1].x, graph.nodes[1].y)
+        );
+    }
+}
@@ -5,7 +5,9 @@ pub mod ai;
 pub mod db;
 pub mod economy;
 pub mod expert;
+pub mod frameworks;
 pub mod graph_manager; // [NEW] MVP Repair: Simple Graph Manager
+pub mod layout;
 pub mod locomotive;
 pub mod models;
 pub mod narrative_graph;
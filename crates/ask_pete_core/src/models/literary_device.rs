@@ -1,7 +1,7 @@
 use serde::{Deserialize, Serialize};
 use std::fmt;
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub enum LiteraryDevice {
     HerosJourney,
     Mystery,
@@ -43,4 +43,58 @@ impl LiteraryDevice {
             LiteraryDevice::SelfCorrection,
         ]
     }
+
+    /// Resolves the names clients send: the variant name ("HerosJourney"),
+    /// the display label ("The Derailment (Mystery)"), the legacy
+    /// "Blueprint: Mystery" form or the plain name ("hero's journey").
+    pub fn from_label(label: &str) -> Option<LiteraryDevice> {
+        let label = label.trim();
+        let label = label.strip_prefix("Blueprint:").unwrap_or(label);
+        // "The Derailment (Mystery)" -> "Mystery"
+        let label = match (label.rfind('('), label.rfind(')')) {
+            (Some(open), Some(close)) if open < close => &label[open + 1..close],
+            _ => label,
+        };
+        let key = normalize(label);
+        Self::all()
+            .into_iter()
+            .find(|device| normalize(&format!("{:?}", device)) == key)
+    }
+}
+
+/// Lowercase letters only, so "Self-Correction" matches "SelfCorrection"
+fn normalize(label: &str) -> String {
+    label
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_label_accepts_every_client_spelling() {
+        for device in LiteraryDevice::all() {
+            assert_eq!(
+                LiteraryDevice::from_label(&device.to_string()),
+                Some(device.clone())
+            );
+            assert_eq!(
+                LiteraryDevice::from_label(&format!("{:?}", device)),
+                Some(device)
+            );
+        }
+        assert_eq!(
+            LiteraryDevice::from_label("Blueprint: Hero's Journey"),
+            Some(LiteraryDevice::HerosJourney)
+        );
+        assert_eq!(
+            LiteraryDevice::from_label("self-correction"),
+            Some(LiteraryDevice::SelfCorrection)
+        );
+        assert_eq!(LiteraryDevice::from_label("Sonnet"), None);
+    }
 }
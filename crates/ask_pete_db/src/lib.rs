@@ -2,4 +2,7 @@ pub mod conversation_memory;
 pub mod vector_store;
 
 pub use conversation_memory::ConversationMemory;
-pub use vector_store::{Document, LanceDbConnection, VectorStore};
+pub use vector_store::{
+    Document, Embedder, FileVectorStore, HashingEmbedder, MetadataFilter, OpenedIndex,
+    ScoredDocument, VectorStore,
+};
@@ -1,6 +1,35 @@
-use anyhow::Result;
+//! # Vector Store (The Memory Bank)
+//!
+//! A persistent, file-backed flat index for sentence embeddings.
+//!
+//! Layout of an index directory:
+//! - `manifest.json`: embedding model id and dimension. [`FileVectorStore::open`]
+//!   refuses an index built with a different model, since the stored vectors
+//!   would be meaningless; [`FileVectorStore::open_or_reindex`] re-embeds it instead,
+//!   except onto the [`HashingEmbedder`] fallback, where it hands back the stored
+//!   documents for keyword search and leaves the index for the real model.
+//! - `vectors.f32`: append-only, little-endian rows of `dimension` floats,
+//!   memory-mapped rather than read into the heap.
+//! - `documents.jsonl`: append-only log of `add` / `delete` records pointing at rows.
+//!
+//! Vectors are L2-normalised on insert, so cosine similarity is a dot product.
+//! Deleted rows are skipped at search time and reclaimed by `compact()`, which
+//! also runs automatically once more than half of the rows are dead.
+//!
+//! Embeddings come from an [`Embedder`]. The real one (Candle MiniLM/BGE) lives
+//! in `infra_ai::embeddings`; [`HashingEmbedder`] is a model-free fallback.
+
+use anyhow::{bail, Context, Result};
 use async_trait::async_trait;
+use memmap2::Mmap;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::RwLock;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Document {
@@ -9,54 +38,974 @@ pub struct Document {
     pub metadata: String, // JSON string
 }
 
+/// A search result with its cosine similarity to the query.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ScoredDocument {
+    pub document: Document,
+    pub score: f32,
+}
+
+/// Turns text into fixed-size vectors. Implementations may block (model inference),
+/// so the store calls them from `spawn_blocking`.
+pub trait Embedder: Send + Sync {
+    /// Stable identifier written to the index manifest
+    fn model_id(&self) -> &str;
+    fn dimension(&self) -> usize;
+    fn embed(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>>;
+}
+
+/// Exact-match filter on top-level keys of a document's metadata JSON.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MetadataFilter {
+    pub equals: HashMap<String, Value>,
+}
+
+impl MetadataFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn eq(mut self, key: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.equals.insert(key.into(), value.into());
+        self
+    }
+
+    pub fn matches(&self, metadata: &Value) -> bool {
+        self.equals
+            .iter()
+            .all(|(key, expected)| metadata.get(key) == Some(expected))
+    }
+}
+
 #[async_trait]
 pub trait VectorStore: Send + Sync {
+    /// Insert or replace a document (same id replaces the old vector).
     async fn add_document(&self, doc: Document) -> Result<()>;
-    async fn search(&self, query: &str, limit: usize) -> Result<Vec<Document>>;
+
+    /// Insert many documents. Stores that embed should override this to batch.
+    async fn add_documents(&self, docs: Vec<Document>) -> Result<()> {
+        for doc in docs {
+            self.add_document(doc).await?;
+        }
+        Ok(())
+    }
+
+    /// Remove a document. Returns false if it was not indexed.
+    async fn delete_document(&self, id: &str) -> Result<bool>;
+
+    /// Top-k cosine search restricted to documents matching `filter`.
+    async fn search_filtered(
+        &self,
+        query: &str,
+        limit: usize,
+        filter: &MetadataFilter,
+    ) -> Result<Vec<ScoredDocument>>;
+
+    async fn search(&self, query: &str, limit: usize) -> Result<Vec<Document>> {
+        Ok(self
+            .search_filtered(query, limit, &MetadataFilter::default())
+            .await?
+            .into_iter()
+            .map(|hit| hit.document)
+            .collect())
+    }
+}
+
+// ============================================================================
+// Hashing embedder (no model required)
+// ============================================================================
+
+/// Feature-hashed bag of words and word bigrams. No semantics, but deterministic
+/// and dependency-free: used when no sentence model is cached, and in tests.
+pub struct HashingEmbedder {
+    dimension: usize,
+}
+
+impl HashingEmbedder {
+    pub const MODEL_ID: &'static str = "hashing-bow-v1";
+
+    pub fn new(dimension: usize) -> Self {
+        Self { dimension }
+    }
+}
+
+impl Default for HashingEmbedder {
+    fn default() -> Self {
+        Self::new(384)
+    }
+}
+
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325u64, |hash, b| {
+        (hash ^ *b as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+impl Embedder for HashingEmbedder {
+    fn model_id(&self) -> &str {
+        Self::MODEL_ID
+    }
+
+    fn dimension(&self) -> usize {
+        self.dimension
+    }
+
+    fn embed(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>> {
+        Ok(texts
+            .iter()
+            .map(|text| {
+                let words: Vec<String> = text
+                    .to_lowercase()
+                    .split(|c: char| !c.is_alphanumeric())
+                    .filter(|w| w.len() > 1)
+                    .map(|w| w.trim_end_matches('s').to_string())
+                    .collect();
+
+                let mut vector = vec![0.0f32; self.dimension];
+                let mut add = |feature: &str, weight: f32| {
+                    let hash = fnv1a(feature.as_bytes());
+                    let bucket = (hash % self.dimension as u64) as usize;
+                    let sign = if hash >> 63 == 0 { 1.0 } else { -1.0 };
+                    vector[bucket] += sign * weight;
+                };
+                for word in &words {
+                    add(word, 1.0);
+                }
+                for pair in words.windows(2) {
+                    add(&format!("{} {}", pair[0], pair[1]), 0.5);
+                }
+                vector
+            })
+            .collect())
+    }
+}
+
+fn normalize(vector: &mut [f32]) {
+    let norm = vector.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        vector.iter_mut().for_each(|v| *v /= norm);
+    }
+}
+
+// ============================================================================
+// File-backed flat index
+// ============================================================================
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+struct Manifest {
+    model_id: String,
+    dimension: usize,
 }
 
-pub struct LanceDbConnection {
-    // conn: lancedb::Connection,
-    // embedding_model: Mutex<TextEmbedding>, // Wrap in Mutex because embed needs &mut self
-    _table_name: String,
+impl Manifest {
+    fn of(embedder: &dyn Embedder) -> Self {
+        Self {
+            model_id: embedder.model_id().to_string(),
+            dimension: embedder.dimension(),
+        }
+    }
+
+    fn read(dir: &Path) -> Result<Option<Self>> {
+        let path = dir.join(MANIFEST_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+        Ok(Some(serde_json::from_str(&fs::read_to_string(path)?)?))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+enum LogRecord {
+    Add { row: usize, document: Document },
+    Delete { id: String },
+}
+
+struct Entry {
+    document: Document,
+    metadata: Value,
+    row: usize,
+}
+
+struct Index {
+    dir: PathBuf,
+    dimension: usize,
+    /// `vectors.f32`: all rows written since the last compaction, including
+    /// dead ones. `None` while the file is empty (zero-length maps are invalid).
+    vectors: Option<Mmap>,
+    entries: HashMap<String, Entry>,
+}
+
+impl Index {
+    /// Open the index files in `dir`, replaying the document log
+    fn load(dir: PathBuf, dimension: usize) -> Result<Self> {
+        let vectors_path = dir.join(VECTORS_FILE);
+        if vectors_path.exists() {
+            // Drop a torn trailing row from an interrupted write
+            let len = fs::metadata(&vectors_path)?.len();
+            let row_bytes = (dimension * 4).max(1) as u64;
+            if len % row_bytes != 0 {
+                OpenOptions::new()
+                    .write(true)
+                    .open(&vectors_path)?
+                    .set_len(len - len % row_bytes)?;
+            }
+        }
+
+        let mut index = Index {
+            dir,
+            dimension,
+            vectors: None,
+            entries: HashMap::new(),
+        };
+        index.remap()?;
+
+        let rows = index.rows();
+        for record in read_log(&index.dir)? {
+            match record {
+                LogRecord::Add { row, document } if row < rows => {
+                    let metadata = parse_metadata(&document.metadata);
+                    index.entries.insert(
+                        document.id.clone(),
+                        Entry {
+                            document,
+                            metadata,
+                            row,
+                        },
+                    );
+                }
+                LogRecord::Add { document, .. } => {
+                    log::warn!("🧠 [Memory] Dropping '{}': vector row missing", document.id);
+                }
+                LogRecord::Delete { id } => {
+                    index.entries.remove(&id);
+                }
+            }
+        }
+        Ok(index)
+    }
+
+    /// Map `vectors.f32` again after it grew or was replaced
+    fn remap(&mut self) -> Result<()> {
+        let path = self.dir.join(VECTORS_FILE);
+        self.vectors = match File::open(&path) {
+            Ok(file) if file.metadata()?.len() > 0 => {
+                // SAFETY: only this store writes the file, and only by appending
+                // or by renaming a new file over it; existing bytes never change
+                // under a live map.
+                Some(unsafe { Mmap::map(&file)? })
+            }
+            Ok(_) => None,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => return Err(e.into()),
+        };
+        Ok(())
+    }
+
+    fn row_bytes(&self) -> usize {
+        self.dimension * 4
+    }
+
+    fn rows(&self) -> usize {
+        self.vectors
+            .as_ref()
+            .map_or(0, |map| map.len() / self.row_bytes().max(1))
+    }
+
+    /// Little-endian bytes of one stored row
+    fn row(&self, row: usize) -> &[u8] {
+        let map = self.vectors.as_deref().unwrap_or_default();
+        &map[row * self.row_bytes()..(row + 1) * self.row_bytes()]
+    }
+
+    fn append_log(&self, record: &LogRecord) -> Result<()> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.dir.join(DOCUMENTS_FILE))?;
+        writeln!(file, "{}", serde_json::to_string(record)?)?;
+        Ok(())
+    }
+
+    /// Append the vectors in one write, log the documents, then map the new rows
+    fn insert(&mut self, documents: Vec<Document>, vectors: &[Vec<f32>]) -> Result<()> {
+        let first_row = self.rows();
+        let bytes: Vec<u8> = vectors
+            .iter()
+            .flatten()
+            .flat_map(|v| v.to_le_bytes())
+            .collect();
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.dir.join(VECTORS_FILE))?
+            .write_all(&bytes)?;
+        self.remap()?;
+
+        for (offset, document) in documents.into_iter().enumerate() {
+            let row = first_row + offset;
+            self.append_log(&LogRecord::Add {
+                row,
+                document: document.clone(),
+            })?;
+            let metadata = parse_metadata(&document.metadata);
+            self.entries.insert(
+                document.id.clone(),
+                Entry {
+                    document,
+                    metadata,
+                    row,
+                },
+            );
+        }
+        Ok(())
+    }
+
+    fn remove(&mut self, id: &str) -> Result<bool> {
+        if self.entries.remove(id).is_none() {
+            return Ok(false);
+        }
+        self.append_log(&LogRecord::Delete { id: id.to_string() })?;
+        Ok(true)
+    }
+
+    fn dead_rows(&self) -> usize {
+        self.rows().saturating_sub(self.entries.len())
+    }
+
+    /// Rewrite both files with only live rows. Writes to temp files and renames,
+    /// so a crash mid-compaction leaves the previous index intact.
+    fn compact(&mut self) -> Result<()> {
+        let mut ids: Vec<String> = self.entries.keys().cloned().collect();
+        ids.sort();
+
+        let mut bytes = Vec::with_capacity(ids.len() * self.row_bytes());
+        let mut log = String::new();
+        for (row, id) in ids.iter().enumerate() {
+            let entry = &self.entries[id];
+            bytes.extend_from_slice(self.row(entry.row));
+            log.push_str(&serde_json::to_string(&LogRecord::Add {
+                row,
+                document: entry.document.clone(),
+            })?);
+            log.push('\n');
+        }
+
+        let vectors_tmp = self.dir.join(format!("{}.tmp", VECTORS_FILE));
+        let documents_tmp = self.dir.join(format!("{}.tmp", DOCUMENTS_FILE));
+        fs::write(&vectors_tmp, bytes)?;
+        fs::write(&documents_tmp, log)?;
+        // Unmap first: Windows won't replace a file that is still mapped
+        self.vectors = None;
+        let swapped = fs::rename(vectors_tmp, self.dir.join(VECTORS_FILE))
+            .and_then(|_| fs::rename(documents_tmp, self.dir.join(DOCUMENTS_FILE)));
+        if let Err(e) = swapped {
+            self.remap()?;
+            return Err(e.into());
+        }
+
+        for (row, id) in ids.iter().enumerate() {
+            if let Some(entry) = self.entries.get_mut(id) {
+                entry.row = row;
+            }
+        }
+        self.remap()
+    }
+}
+
+fn read_log(dir: &Path) -> Result<Vec<LogRecord>> {
+    let path = dir.join(DOCUMENTS_FILE);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let mut records = Vec::new();
+    for line in BufReader::new(File::open(&path)?).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<LogRecord>(&line) {
+            Ok(record) => records.push(record),
+            Err(e) => log::warn!("🧠 [Memory] Skipping corrupt index record: {}", e),
+        }
+    }
+    Ok(records)
 }
 
-impl LanceDbConnection {
-    pub async fn new(db_path: &str) -> Result<Self> {
-        // Fix InitOptions construction
+/// Live documents of an index, from its log alone (no vectors needed)
+fn live_documents(dir: &Path) -> Result<Vec<Document>> {
+    let mut documents = HashMap::new();
+    for record in read_log(dir)? {
+        match record {
+            LogRecord::Add { document, .. } => {
+                documents.insert(document.id.clone(), document);
+            }
+            LogRecord::Delete { id } => {
+                documents.remove(&id);
+            }
+        }
+    }
+    let mut documents: Vec<Document> = documents.into_values().collect();
+    documents.sort_by(|a, b| a.id.cmp(&b.id));
+    Ok(documents)
+}
 
-        let _uri = format!("data/{}", db_path);
-        // let conn = connect(&uri).execute().await?;
+/// Finish a re-index swap that was interrupted. With the rebuild already in
+/// place only the parked old copy is left to delete; without it, the old copy
+/// goes back and any half-built rebuild is dropped.
+fn recover_swap(dir: &Path) -> Result<()> {
+    let old = dir.with_extension("old");
+    if old.exists() {
+        if dir.exists() {
+            fs::remove_dir_all(&old)?;
+        } else {
+            log::warn!(
+                "🧠 [Memory] Restoring {:?} after an interrupted re-index",
+                dir
+            );
+            fs::rename(&old, dir)?;
+        }
+    }
+    let rebuild = dir.with_extension("reindex");
+    if rebuild.exists() {
+        fs::remove_dir_all(&rebuild)?;
+    }
+    Ok(())
+}
+
+/// Embed on the calling thread and L2-normalise, checking the dimension
+fn embed_normalized(embedder: &dyn Embedder, texts: &[&str]) -> Result<Vec<Vec<f32>>> {
+    let mut vectors = embedder.embed(texts)?;
+    for vector in &mut vectors {
+        if vector.len() != embedder.dimension() {
+            bail!(
+                "Embedder returned {} dims, expected {}",
+                vector.len(),
+                embedder.dimension()
+            );
+        }
+        normalize(vector);
+    }
+    Ok(vectors)
+}
+
+fn dot(row: &[u8], query: &[f32]) -> f32 {
+    row.chunks_exact(4)
+        .zip(query)
+        .map(|(b, q)| f32::from_le_bytes([b[0], b[1], b[2], b[3]]) * q)
+        .sum()
+}
+
+fn parse_metadata(metadata: &str) -> Value {
+    serde_json::from_str(metadata).unwrap_or(Value::Null)
+}
+
+const MANIFEST_FILE: &str = "manifest.json";
+const VECTORS_FILE: &str = "vectors.f32";
+const DOCUMENTS_FILE: &str = "documents.jsonl";
+/// Documents embedded per model call while re-indexing
+const REINDEX_BATCH: usize = 64;
+
+/// What [`FileVectorStore::open_or_reindex`] could open
+pub enum OpenedIndex {
+    /// Vectors match the embedder
+    Ready(FileVectorStore),
+    /// The index was built by `model_id`, but only the hashing fallback is
+    /// loaded. Re-embedding would throw the real vectors away until the next
+    /// re-index, so they stay on disk and `documents` serve keyword search.
+    KeywordOnly {
+        model_id: String,
+        documents: Vec<Document>,
+    },
+}
+
+/// Persistent flat vector index. Exact top-k search; fine for classroom-sized
+/// corpora (tens of thousands of chunks) without an ANN structure.
+pub struct FileVectorStore {
+    embedder: Arc<dyn Embedder>,
+    index: RwLock<Index>,
+}
+
+impl FileVectorStore {
+    /// Open (or create) the index at `dir`.
+    pub fn open(dir: impl AsRef<Path>, embedder: Arc<dyn Embedder>) -> Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        recover_swap(&dir)?;
+        fs::create_dir_all(&dir)
+            .with_context(|| format!("Failed to create vector store at {:?}", dir))?;
+
+        let manifest = Manifest::of(embedder.as_ref());
+        match Manifest::read(&dir)? {
+            Some(existing) if existing != manifest => bail!(
+                "Vector store at {:?} was built with '{}' ({} dims), not '{}' ({} dims); re-index it",
+                dir,
+                existing.model_id,
+                existing.dimension,
+                manifest.model_id,
+                manifest.dimension
+            ),
+            Some(_) => {}
+            None => fs::write(
+                dir.join(MANIFEST_FILE),
+                serde_json::to_string_pretty(&manifest)?,
+            )?,
+        }
+
+        let index = Index::load(dir, manifest.dimension)?;
+
+        log::info!(
+            "🧠 [Memory] Opened vector store with {} documents ({})",
+            index.entries.len(),
+            manifest.model_id
+        );
 
         Ok(Self {
-            // conn,
-            // embedding_model: Mutex::new(model),
-            _table_name: "memory_bank".to_string(),
+            embedder,
+            index: RwLock::new(index),
+        })
+    }
+
+    /// Like [`open`](Self::open), but an index built with another model (e.g.
+    /// one built while only the hashing embedder was available) is re-embedded
+    /// from its stored texts instead of refused. A real model's index is never
+    /// re-embedded onto the hashing fallback; see [`OpenedIndex::KeywordOnly`].
+    /// Blocks while embedding; call it from `spawn_blocking` when the corpus is large.
+    pub fn open_or_reindex(
+        dir: impl AsRef<Path>,
+        embedder: Arc<dyn Embedder>,
+    ) -> Result<OpenedIndex> {
+        let dir = dir.as_ref();
+        recover_swap(dir)?;
+        let manifest = Manifest::of(embedder.as_ref());
+        let existing = match Manifest::read(dir)? {
+            Some(existing) if existing != manifest => existing,
+            _ => return Self::open(dir, embedder).map(OpenedIndex::Ready),
+        };
+
+        let documents = live_documents(dir)?;
+        if manifest.model_id == HashingEmbedder::MODEL_ID
+            && existing.model_id != HashingEmbedder::MODEL_ID
+        {
+            log::warn!(
+                "🧠 [Memory] Not re-indexing {} documents at {:?} from '{}' onto the hashing fallback",
+                documents.len(),
+                dir,
+                existing.model_id
+            );
+            return Ok(OpenedIndex::KeywordOnly {
+                model_id: existing.model_id,
+                documents,
+            });
+        }
+
+        log::warn!(
+            "🧠 [Memory] Re-indexing {} documents at {:?} from '{}' to '{}'",
+            documents.len(),
+            dir,
+            existing.model_id,
+            manifest.model_id
+        );
+
+        // Build next to the old index and swap, so a failure leaves it intact
+        let rebuild = dir.with_extension("reindex");
+        if rebuild.exists() {
+            fs::remove_dir_all(&rebuild)?;
+        }
+        fs::create_dir_all(&rebuild)?;
+        fs::write(
+            rebuild.join(MANIFEST_FILE),
+            serde_json::to_string_pretty(&manifest)?,
+        )?;
+        let mut index = Index::load(rebuild.clone(), manifest.dimension)?;
+        for batch in documents.chunks(REINDEX_BATCH) {
+            let texts: Vec<&str> = batch.iter().map(|d| d.text.as_str()).collect();
+            let vectors = embed_normalized(embedder.as_ref(), &texts)?;
+            index.insert(batch.to_vec(), &vectors)?;
+        }
+        drop(index);
+
+        // Park the old index rather than delete it, so there is always a
+        // complete copy on disk; `recover_swap` finishes an interrupted swap
+        let old = dir.with_extension("old");
+        if old.exists() {
+            fs::remove_dir_all(&old)?;
+        }
+        fs::rename(dir, &old)?;
+        fs::rename(&rebuild, dir)?;
+        if let Err(e) = fs::remove_dir_all(&old) {
+            log::warn!("🧠 [Memory] Failed to remove old index {:?}: {}", old, e);
+        }
+        Self::open(dir, embedder).map(OpenedIndex::Ready)
+    }
+
+    pub async fn len(&self) -> usize {
+        self.index.read().await.entries.len()
+    }
+
+    pub async fn is_empty(&self) -> bool {
+        self.len().await == 0
+    }
+
+    /// Reclaim space held by deleted and replaced documents.
+    pub async fn compact(&self) -> Result<()> {
+        self.index.write().await.compact()
+    }
+
+    async fn embed(&self, texts: Vec<String>) -> Result<Vec<Vec<f32>>> {
+        let embedder = self.embedder.clone();
+        tokio::task::spawn_blocking(move || {
+            let refs: Vec<&str> = texts.iter().map(String::as_str).collect();
+            embed_normalized(embedder.as_ref(), &refs)
         })
+        .await?
+    }
+
+    /// Snapshot of all live documents, e.g. to build a keyword index alongside.
+    pub async fn documents(&self) -> Vec<Document> {
+        let index = self.index.read().await;
+        let mut docs: Vec<Document> = index
+            .entries
+            .values()
+            .map(|entry| entry.document.clone())
+            .collect();
+        docs.sort_by(|a, b| a.id.cmp(&b.id));
+        docs
     }
 }
 
 #[async_trait]
-impl VectorStore for LanceDbConnection {
+impl VectorStore for FileVectorStore {
     async fn add_document(&self, doc: Document) -> Result<()> {
-        // 1. Generate Embedding
-        let embedding: Vec<f32> = vec![0.0; 384]; // Dummy embedding
-
-        println!(
-            "🧠 [Memory] (MOCKED) Embedding generated (Dim: {}). Storing: {}",
-            embedding.len(),
-            doc.id
-        );
+        self.add_documents(vec![doc]).await
+    }
 
-        // TODO: Actual LanceDB Insert
-        // For now, we just print to verify the pipeline works.
+    /// Embed and insert many documents with one model call.
+    async fn add_documents(&self, docs: Vec<Document>) -> Result<()> {
+        if docs.is_empty() {
+            return Ok(());
+        }
+        let vectors = self
+            .embed(docs.iter().map(|d| d.text.clone()).collect())
+            .await?;
 
+        let mut index = self.index.write().await;
+        index.insert(docs, &vectors)?;
+        if index.dead_rows() > index.entries.len() {
+            index.compact()?;
+        }
         Ok(())
     }
 
-    async fn search(&self, query: &str, _limit: usize) -> Result<Vec<Document>> {
-        println!("🧠 [Memory] (MOCKED) Searching for: '{}'", query);
+    async fn delete_document(&self, id: &str) -> Result<bool> {
+        let mut index = self.index.write().await;
+        let removed = index.remove(id)?;
+        if index.dead_rows() > index.entries.len() {
+            index.compact()?;
+        }
+        Ok(removed)
+    }
+
+    async fn search_filtered(
+        &self,
+        query: &str,
+        limit: usize,
+        filter: &MetadataFilter,
+    ) -> Result<Vec<ScoredDocument>> {
+        if limit == 0 {
+            return Ok(Vec::new());
+        }
+        let query_vector = self
+            .embed(vec![query.to_string()])
+            .await?
+            .pop()
+            .unwrap_or_default();
+
+        let index = self.index.read().await;
+        let mut hits: Vec<(f32, &Entry)> = index
+            .entries
+            .values()
+            .filter(|entry| filter.matches(&entry.metadata))
+            .map(|entry| (dot(index.row(entry.row), &query_vector), entry))
+            .collect();
+
+        hits.sort_by(|a, b| {
+            b.0.partial_cmp(&a.0)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| a.1.document.id.cmp(&b.1.document.id))
+        });
+        hits.truncate(limit);
+
+        Ok(hits
+            .into_iter()
+            .map(|(score, entry)| ScoredDocument {
+                document: entry.document.clone(),
+                score,
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Deserialize)]
+    struct Corpus {
+        documents: Vec<CorpusDoc>,
+        queries: Vec<CorpusQuery>,
+    }
+
+    #[derive(Deserialize)]
+    struct CorpusDoc {
+        id: String,
+        topic: String,
+        text: String,
+    }
+
+    #[derive(Deserialize)]
+    struct CorpusQuery {
+        query: String,
+        relevant: String,
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("pete_vectors_{}_{}", name, uuid::Uuid::new_v4()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn corpus() -> Corpus {
+        serde_json::from_str(include_str!("../fixtures/recall_corpus.json")).unwrap()
+    }
+
+    fn doc(id: &str, topic: &str, text: &str) -> Document {
+        Document {
+            id: id.to_string(),
+            text: text.to_string(),
+            metadata: serde_json::json!({ "topic": topic }).to_string(),
+        }
+    }
+
+    async fn corpus_store(dir: &Path) -> (FileVectorStore, Corpus) {
+        let corpus = corpus();
+        let store = FileVectorStore::open(dir, Arc::new(HashingEmbedder::default())).unwrap();
+        store
+            .add_documents(
+                corpus
+                    .documents
+                    .iter()
+                    .map(|d| doc(&d.id, &d.topic, &d.text))
+                    .collect(),
+            )
+            .await
+            .unwrap();
+        (store, corpus)
+    }
+
+    #[tokio::test]
+    async fn test_recall_at_3_on_fixture_corpus() {
+        let dir = temp_dir("recall");
+        let (store, corpus) = corpus_store(&dir).await;
+
+        let mut found = 0;
+        for q in &corpus.queries {
+            let hits = store.search(&q.query, 3).await.unwrap();
+            if hits.iter().any(|d| d.id == q.relevant) {
+                found += 1;
+            }
+        }
+        let recall = found as f32 / corpus.queries.len() as f32;
+        assert!(recall >= 0.9, "recall@3 was {}", recall);
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn test_index_persists_across_reopen() {
+        let dir = temp_dir("reopen");
+        let (store, corpus) = corpus_store(&dir).await;
+        let before = store.search(&corpus.queries[0].query, 3).await.unwrap();
+        drop(store);
+
+        let reopened = FileVectorStore::open(&dir, Arc::new(HashingEmbedder::default())).unwrap();
+        assert_eq!(reopened.len().await, corpus.documents.len());
+        let after = reopened.search(&corpus.queries[0].query, 3).await.unwrap();
+        let ids = |docs: &[Document]| docs.iter().map(|d| d.id.clone()).collect::<Vec<_>>();
+        assert_eq!(ids(&before), ids(&after));
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn test_delete_and_compact() {
+        let dir = temp_dir("delete");
+        let (store, corpus) = corpus_store(&dir).await;
+        let target = &corpus.queries[0];
+
+        assert!(store.delete_document(&target.relevant).await.unwrap());
+        assert!(!store.delete_document(&target.relevant).await.unwrap());
+        let hits = store.search(&target.query, 10).await.unwrap();
+        assert!(hits.iter().all(|d| d.id != target.relevant));
+
+        store.compact().await.unwrap();
+        drop(store);
+        let reopened = FileVectorStore::open(&dir, Arc::new(HashingEmbedder::default())).unwrap();
+        assert_eq!(reopened.len().await, corpus.documents.len() - 1);
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn test_metadata_filter() {
+        let dir = temp_dir("filter");
+        let (store, _) = corpus_store(&dir).await;
+
+        let filter = MetadataFilter::new().eq("topic", "biology");
+        let hits = store
+            .search_filtered("energy transfer in motion", 5, &filter)
+            .await
+            .unwrap();
+        assert!(!hits.is_empty());
+        assert!(hits
+            .iter()
+            .all(|h| h.document.metadata.contains("\"biology\"")));
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn test_replacing_a_document_keeps_one_copy() {
+        let dir = temp_dir("replace");
+        let store = FileVectorStore::open(&dir, Arc::new(HashingEmbedder::default())).unwrap();
+        store
+            .add_document(doc("a", "x", "old text about gears"))
+            .await
+            .unwrap();
+        store
+            .add_document(doc("a", "x", "new text about levers"))
+            .await
+            .unwrap();
+
+        assert_eq!(store.len().await, 1);
+        let hits = store.search("levers", 5).await.unwrap();
+        assert_eq!(hits[0].text, "new text about levers");
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_model_mismatch_is_rejected() {
+        let dir = temp_dir("mismatch");
+        FileVectorStore::open(&dir, Arc::new(HashingEmbedder::new(384))).unwrap();
+        assert!(FileVectorStore::open(&dir, Arc::new(HashingEmbedder::new(128))).is_err());
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn test_model_change_reindexes_stored_documents() {
+        let dir = temp_dir("reindex");
+        let (store, corpus) = corpus_store(&dir).await;
+        store
+            .delete_document(&corpus.documents[0].id)
+            .await
+            .unwrap();
+        drop(store);
+
+        let reindexed = tokio::task::spawn_blocking({
+            let dir = dir.clone();
+            move || FileVectorStore::open_or_reindex(&dir, Arc::new(HashingEmbedder::new(128)))
+        })
+        .await
+        .unwrap()
+        .unwrap();
+        let OpenedIndex::Ready(reindexed) = reindexed else {
+            panic!("hashing to hashing should re-index");
+        };
+        assert_eq!(reindexed.len().await, corpus.documents.len() - 1);
+        let query = corpus
+            .queries
+            .iter()
+            .find(|q| q.relevant != corpus.documents[0].id)
+            .unwrap();
+        let hits = reindexed.search(&query.query, 3).await.unwrap();
+        assert!(hits.iter().any(|d| d.id == query.relevant));
+        drop(reindexed);
+
+        // The manifest moved to the new model; the old one is now the mismatch
+        assert!(FileVectorStore::open(&dir, Arc::new(HashingEmbedder::new(384))).is_err());
+        assert!(!dir.with_extension("reindex").exists());
+        assert!(!dir.with_extension("old").exists());
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    /// Stands in for a sentence model: a real model id over hashed vectors
+    struct NamedEmbedder(HashingEmbedder);
+
+    impl Embedder for NamedEmbedder {
+        fn model_id(&self) -> &str {
+            "test-sentence-model"
+        }
+
+        fn dimension(&self) -> usize {
+            self.0.dimension()
+        }
+
+        fn embed(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>> {
+            self.0.embed(texts)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fallback_embedder_does_not_reindex_a_model_index() {
+        let dir = temp_dir("fallback");
+        let store =
+            FileVectorStore::open(&dir, Arc::new(NamedEmbedder(HashingEmbedder::default())))
+                .unwrap();
+        store
+            .add_document(doc("a", "physics", "levers trade force for distance"))
+            .await
+            .unwrap();
+        drop(store);
+
+        let opened =
+            FileVectorStore::open_or_reindex(&dir, Arc::new(HashingEmbedder::default())).unwrap();
+        let OpenedIndex::KeywordOnly {
+            model_id,
+            documents,
+        } = opened
+        else {
+            panic!("the model's index must not be re-embedded");
+        };
+        assert_eq!(model_id, "test-sentence-model");
+        assert_eq!(documents.len(), 1);
+
+        // The model's vectors are still there for the next boot
+        let store =
+            FileVectorStore::open(&dir, Arc::new(NamedEmbedder(HashingEmbedder::default())))
+                .unwrap();
+        assert_eq!(store.search("levers", 1).await.unwrap()[0].id, "a");
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn test_interrupted_swap_is_recovered() {
+        let dir = temp_dir("swap");
+        let (store, corpus) = corpus_store(&dir).await;
+        drop(store);
+
+        // Crash after parking the old index but before moving the rebuild in
+        let old = dir.with_extension("old");
+        let rebuild = dir.with_extension("reindex");
+        fs::rename(&dir, &old).unwrap();
+        fs::create_dir_all(&rebuild).unwrap();
+
+        let store = FileVectorStore::open(&dir, Arc::new(HashingEmbedder::default())).unwrap();
+        assert_eq!(store.len().await, corpus.documents.len());
+        assert!(!old.exists());
+        assert!(!rebuild.exists());
+        drop(store);
 
-        Ok(vec![])
+        // Crash after the swap but before the old copy was deleted
+        fs::create_dir_all(&old).unwrap();
+        let store = FileVectorStore::open(&dir, Arc::new(HashingEmbedder::default())).unwrap();
+        assert_eq!(store.len().await, corpus.documents.len());
+        assert!(!old.exists());
+        let _ = fs::remove_dir_all(dir);
     }
 }
@@ -0,0 +1,348 @@
+//! ConversationMemory against a real Postgres. Each test creates its own
+//! database from `TEST_DATABASE_URL` (any database on a server the user may
+//! `CREATE DATABASE` on), applies the server migrations and drops it again.
+//! The tests are ignored by default; CI runs them with
+//! `cargo test -p ask_pete_db --test conversation_memory_pg -- --ignored`.
+
+use ask_pete_db::conversation_memory::{Citation, SessionSummary, Speaker, Turn, TurnMetadata};
+use ask_pete_db::ConversationMemory;
+use chrono::{Duration, Utc};
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+use sqlx::PgPool;
+use std::str::FromStr;
+use uuid::Uuid;
+
+const MIGRATIONS: &[&str] = &[
+    include_str!(
+        "../../ask_pete_server/migrations/20251204140000_create_conversation_summaries.sql"
+    ),
+    include_str!("../../ask_pete_server/migrations/20251204200000_create_conversation_turns.sql"),
+];
+
+struct TestDb {
+    admin: PgPool,
+    name: String,
+    pool: PgPool,
+}
+
+impl TestDb {
+    async fn create() -> Self {
+        let url = std::env::var("TEST_DATABASE_URL")
+            .expect("TEST_DATABASE_URL must point at a Postgres server for these tests");
+        let admin = PgPoolOptions::new()
+            .max_connections(1)
+            .connect(&url)
+            .await
+            .expect("Failed to connect to TEST_DATABASE_URL");
+        let name = format!("ask_pete_test_{}", Uuid::new_v4().simple());
+        sqlx::query(&format!("CREATE DATABASE {}", name))
+            .execute(&admin)
+            .await
+            .expect("Failed to create test database");
+
+        let options = PgConnectOptions::from_str(&url).unwrap().database(&name);
+        let pool = PgPoolOptions::new()
+            .max_connections(4)
+            .connect_with(options)
+            .await
+            .expect("Failed to connect to test database");
+        for migration in MIGRATIONS {
+            sqlx::raw_sql(migration)
+                .execute(&pool)
+                .await
+                .expect("Failed to apply migration");
+        }
+
+        Self { admin, name, pool }
+    }
+
+    async fn count(&self, session_id: Uuid) -> i64 {
+        sqlx::query_scalar("SELECT COUNT(*) FROM conversation_turns WHERE session_id = $1")
+            .bind(session_id)
+            .fetch_one(&self.pool)
+            .await
+            .unwrap()
+    }
+
+    /// Make every write to `conversation_turns` fail (reads of cached
+    /// sessions don't touch it), or undo that
+    async fn break_turns_table(&self, broken: bool) {
+        let sql = if broken {
+            "ALTER TABLE conversation_turns RENAME TO conversation_turns_away"
+        } else {
+            "ALTER TABLE conversation_turns_away RENAME TO conversation_turns"
+        };
+        sqlx::query(sql).execute(&self.pool).await.unwrap();
+    }
+
+    async fn drop(self) {
+        self.pool.close().await;
+        sqlx::query(&format!(
+            "DROP DATABASE IF EXISTS {} WITH (FORCE)",
+            self.name
+        ))
+        .execute(&self.admin)
+        .await
+        .expect("Failed to drop test database");
+    }
+}
+
+/// Turns a second apart, so their order doesn't depend on insert timing
+fn turn(i: i64, content: &str) -> Turn {
+    Turn {
+        id: Uuid::nil(),
+        timestamp: Utc::now() + Duration::seconds(i),
+        speaker: if i % 2 == 0 {
+            Speaker::User
+        } else {
+            Speaker::AI
+        },
+        content: content.to_string(),
+        metadata: TurnMetadata::default(),
+    }
+}
+
+#[tokio::test]
+#[ignore = "needs TEST_DATABASE_URL"]
+async fn test_turns_are_written_in_batches() {
+    let db = TestDb::create().await;
+    let memory = ConversationMemory::new(db.pool.clone(), 10).with_batch_size(3);
+    let session = Uuid::new_v4();
+
+    memory
+        .add_turn(session, turn(0, "What is torque?"))
+        .await
+        .unwrap();
+    memory
+        .add_turn(session, turn(1, "What makes a door easy to open?"))
+        .await
+        .unwrap();
+    assert_eq!(db.count(session).await, 0);
+
+    memory
+        .add_turn(session, turn(2, "Pushing far from the hinge"))
+        .await
+        .unwrap();
+    assert_eq!(db.count(session).await, 3);
+
+    memory
+        .add_turn(session, turn(3, "Why does distance matter?"))
+        .await
+        .unwrap();
+    assert_eq!(memory.flush().await.unwrap(), 1);
+    assert_eq!(db.count(session).await, 4);
+    assert_eq!(memory.flush().await.unwrap(), 0);
+
+    db.drop().await;
+}
+
+#[tokio::test]
+#[ignore = "needs TEST_DATABASE_URL"]
+async fn test_cache_miss_loads_full_session_with_metadata() {
+    let db = TestDb::create().await;
+    let session = Uuid::new_v4();
+    let writer = ConversationMemory::new(db.pool.clone(), 10);
+    let mut answer = turn(1, "Torque is force times lever arm [S1].");
+    answer.metadata = TurnMetadata {
+        word_count: 7,
+        strategy: Some("clarify".to_string()),
+        citations: vec![Citation {
+            label: "S1".to_string(),
+            chunk_id: "chunk-1".to_string(),
+            document_id: None,
+            title: "Rotational Dynamics".to_string(),
+            page: Some(12),
+            heading: None,
+            snippet: "Torque is the product of force and lever arm.".to_string(),
+        }],
+        ..TurnMetadata::default()
+    };
+    writer
+        .add_turn(session, turn(0, "What is torque?"))
+        .await
+        .unwrap();
+    writer.add_turn(session, answer.clone()).await.unwrap();
+    writer
+        .add_turn(session, turn(2, "So a longer wrench helps?"))
+        .await
+        .unwrap();
+    writer.flush().await.unwrap();
+
+    // A fresh cache, as after a restart
+    let reader = ConversationMemory::new(db.pool.clone(), 10);
+    let recent = reader.get_recent(session, 2).await.unwrap();
+    assert_eq!(recent.len(), 2);
+    assert_eq!(recent[0].content, answer.content);
+    assert_eq!(recent[0].speaker, Speaker::AI);
+    assert_eq!(recent[0].metadata.strategy.as_deref(), Some("clarify"));
+    assert_eq!(recent[0].metadata.citations, answer.metadata.citations);
+    // The whole session was cached, not just the two turns asked for
+    assert_eq!(
+        reader.get_recent(session, usize::MAX).await.unwrap().len(),
+        3
+    );
+
+    // A turn added to an uncached session keeps the earlier history
+    let other = ConversationMemory::new(db.pool.clone(), 10);
+    other
+        .add_turn(session, turn(3, "Yes, more lever arm."))
+        .await
+        .unwrap();
+    let all = other.get_recent(session, usize::MAX).await.unwrap();
+    let contents: Vec<_> = all.iter().map(|t| t.content.as_str()).collect();
+    assert_eq!(
+        contents,
+        [
+            "What is torque?",
+            "Torque is force times lever arm [S1].",
+            "So a longer wrench helps?",
+            "Yes, more lever arm.",
+        ]
+    );
+
+    db.drop().await;
+}
+
+#[tokio::test]
+#[ignore = "needs TEST_DATABASE_URL"]
+async fn test_pages_come_from_the_database_in_order() {
+    let db = TestDb::create().await;
+    let session = Uuid::new_v4();
+    let writer = ConversationMemory::new(db.pool.clone(), 10);
+    for i in 0..5 {
+        writer
+            .add_turn(session, turn(i, &format!("turn {}", i)))
+            .await
+            .unwrap();
+    }
+    writer.flush().await.unwrap();
+
+    let uncached = ConversationMemory::new(db.pool.clone(), 10);
+    let mut offset = Some(0);
+    let mut seen = Vec::new();
+    while let Some(at) = offset {
+        let page = uncached.get_page(session, at, 2).await.unwrap();
+        assert_eq!(page.total, 5);
+        seen.extend(page.turns.into_iter().map(|t| t.content));
+        offset = page.next_offset;
+    }
+    assert_eq!(seen, ["turn 0", "turn 1", "turn 2", "turn 3", "turn 4"]);
+
+    db.drop().await;
+}
+
+#[tokio::test]
+#[ignore = "needs TEST_DATABASE_URL"]
+async fn test_delete_session_removes_turns_and_summary() {
+    let db = TestDb::create().await;
+    let memory = ConversationMemory::new(db.pool.clone(), 10).with_batch_size(2);
+    let (gone, kept) = (Uuid::new_v4(), Uuid::new_v4());
+    for session in [gone, kept] {
+        memory
+            .add_turn(session, turn(0, "What is torque?"))
+            .await
+            .unwrap();
+        memory
+            .add_turn(session, turn(1, "What turns a door?"))
+            .await
+            .unwrap();
+    }
+    // One more for `gone` that is still waiting to be written
+    memory.add_turn(gone, turn(2, "The handle")).await.unwrap();
+    let summary = SessionSummary {
+        summary: "Torque basics.".to_string(),
+        ..SessionSummary::default()
+    };
+    memory.save_summary(gone, &summary).await.unwrap();
+
+    assert_eq!(memory.delete_session(gone).await.unwrap(), 3);
+    memory.flush().await.unwrap();
+    assert_eq!(db.count(gone).await, 0);
+    assert_eq!(db.count(kept).await, 2);
+
+    let fresh = ConversationMemory::new(db.pool.clone(), 10);
+    assert!(fresh.get_recent(gone, 10).await.unwrap().is_empty());
+    assert!(fresh.get_summary(gone).await.unwrap().is_none());
+    assert_eq!(fresh.get_recent(kept, 10).await.unwrap().len(), 2);
+
+    db.drop().await;
+}
+
+#[tokio::test]
+#[ignore = "needs TEST_DATABASE_URL"]
+async fn test_failed_write_keeps_the_turn_and_retries() {
+    let db = TestDb::create().await;
+    let memory = ConversationMemory::new(db.pool.clone(), 10).with_batch_size(1);
+    let session = Uuid::new_v4();
+    memory
+        .add_turn(session, turn(0, "What is torque?"))
+        .await
+        .unwrap();
+    assert_eq!(db.count(session).await, 1);
+
+    // The write fails, but the turn is kept and the caller sees no error
+    db.break_turns_table(true).await;
+    memory
+        .add_turn(session, turn(1, "What turns a door?"))
+        .await
+        .unwrap();
+    assert_eq!(memory.get_recent(session, 10).await.unwrap().len(), 2);
+    assert!(memory.flush().await.is_err());
+
+    db.break_turns_table(false).await;
+    assert_eq!(memory.flush().await.unwrap(), 1);
+    assert_eq!(db.count(session).await, 2);
+
+    db.drop().await;
+}
+
+#[tokio::test]
+#[ignore = "needs TEST_DATABASE_URL"]
+async fn test_first_turn_is_kept_when_history_cannot_load() {
+    let db = TestDb::create().await;
+    let memory = ConversationMemory::new(db.pool.clone(), 10);
+    let session = Uuid::new_v4();
+
+    // Nothing cached, so adding the turn has to load the session first
+    db.break_turns_table(true).await;
+    memory
+        .add_turn(session, turn(0, "Why do gears have teeth?"))
+        .await
+        .unwrap();
+    assert_eq!(memory.get_recent(session, 10).await.unwrap().len(), 1);
+
+    db.break_turns_table(false).await;
+    assert_eq!(memory.flush().await.unwrap(), 1);
+    assert_eq!(db.count(session).await, 1);
+
+    db.drop().await;
+}
+
+#[tokio::test]
+#[ignore = "needs TEST_DATABASE_URL"]
+async fn test_unwritten_turns_are_capped_oldest_first() {
+    let db = TestDb::create().await;
+    let memory = ConversationMemory::new(db.pool.clone(), 10)
+        .with_batch_size(1)
+        .with_max_pending(2);
+    let session = Uuid::new_v4();
+
+    db.break_turns_table(true).await;
+    for i in 0..4 {
+        memory
+            .add_turn(session, turn(i, &format!("turn {}", i)))
+            .await
+            .unwrap();
+    }
+    db.break_turns_table(false).await;
+    assert_eq!(memory.flush().await.unwrap(), 2);
+
+    let stored = ConversationMemory::new(db.pool.clone(), 10)
+        .get_recent(session, 10)
+        .await
+        .unwrap();
+    let contents: Vec<_> = stored.iter().map(|t| t.content.as_str()).collect();
+    assert_eq!(contents, ["turn 2", "turn 3"]);
+
+    db.drop().await;
+}
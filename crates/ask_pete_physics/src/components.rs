@@ -213,6 +213,9 @@ pub struct StartDownloadEvent {
 
 #[derive(Event, Debug, Clone)]
 pub struct DownloadProgressEvent {
+    pub id: infra_services::downloader::DownloadId,
+    pub filename: String,
+    pub state: infra_services::downloader::DownloadState,
     pub percent: f32,
     pub downloaded_bytes: u64,
     pub total_bytes: u64,
@@ -224,6 +227,10 @@ pub struct SharedDownloadStateResource(pub Arc<RwLock<Option<DownloadProgressEve
 #[derive(Resource, Clone)]
 pub struct DownloadCommandInbox(pub Arc<RwLock<Vec<StartDownloadEvent>>>);
 
+/// Runs model downloads concurrently; shared with the API for listing and cancelling
+#[derive(Resource, Clone)]
+pub struct DownloadQueueResource(pub Arc<infra_services::downloader::DownloadQueue>);
+
 // --- Pete AI Events & Resources ---
 
 #[derive(Event, Debug, Clone)]
@@ -156,7 +156,7 @@ pub fn whistle_system(
     }
 }
 
-// Resource to hold the channel receiver for download progress
+// Resource to hold the channel receiver for download progress (one feed for every job)
 #[derive(Resource)]
 pub struct DownloadChannel(
     pub tokio::sync::mpsc::Receiver<infra_services::downloader::DownloadProgress>,
@@ -165,27 +165,23 @@ pub struct DownloadChannel(
 // System to handle StartDownloadEvent
 pub fn download_manager_system(
     mut events: EventReader<StartDownloadEvent>,
-    mut commands: Commands,
+    queue: Res<crate::components::DownloadQueueResource>,
 ) {
     for event in events.read() {
-        info!("Starting download for: {}", event.model_config.name);
-
-        let (tx, rx) = tokio::sync::mpsc::channel(100);
-        commands.insert_resource(DownloadChannel(rx));
-
-        let url = event.model_config.hf_repo.clone(); // In real implementation, construct full URL
-        let filename = event.model_config.filename.clone();
+        let model = &event.model_config;
 
         // Construct full URL (simplified for now)
-        let full_url = format!("https://huggingface.co/{}/resolve/main/{}", url, filename);
-        let path = std::path::PathBuf::from("models").join(filename);
-
-        // Spawn async task
-        tokio::spawn(async move {
-            if let Err(e) = infra_services::downloader::download_file(full_url, path, tx).await {
-                error!("Download failed: {}", e);
-            }
-        });
+        let full_url = format!(
+            "https://huggingface.co/{}/resolve/main/{}",
+            model.hf_repo, model.filename
+        );
+        let path = queue.0.store().dir().join(&model.filename);
+        let request = infra_services::downloader::DownloadRequest::new(full_url, path)
+            .with_sha256(model.sha256.clone())
+            .with_size((model.size_mb as f64 * 1024.0 * 1024.0) as u64);
+
+        let id = queue.0.enqueue(request);
+        info!("Queued download {} for: {}", id, model.name);
     }
 }
 
@@ -217,6 +213,9 @@ pub fn progress_update_system(
             // info!("Download Progress: {:.2}%", progress.percent); // Reduce log spam
 
             let event = DownloadProgressEvent {
+                id: progress.id,
+                filename: progress.filename.clone(),
+                state: progress.state,
                 percent: progress.percent,
                 downloaded_bytes: progress.downloaded_bytes,
                 total_bytes: progress.total_bytes,
@@ -230,8 +229,18 @@ pub fn progress_update_system(
                 *guard = Some(event);
             }
 
-            if progress.percent >= 100.0 {
-                info!("Download Complete!");
+            match progress.state {
+                infra_services::downloader::DownloadState::Completed => {
+                    info!("Download Complete: {}", progress.filename);
+                }
+                infra_services::downloader::DownloadState::Failed => {
+                    error!(
+                        "Download failed: {}: {}",
+                        progress.filename,
+                        progress.error.as_deref().unwrap_or("unknown error")
+                    );
+                }
+                _ => {}
             }
         }
     }
@@ -0,0 +1,16 @@
+-- Answer-leak guardrail interventions, for researcher review
+CREATE TABLE IF NOT EXISTS guardrail_interventions (
+    id UUID PRIMARY KEY,
+    session_id UUID NOT NULL,
+    user_id BIGINT NOT NULL,
+    node_id TEXT NOT NULL,
+    leaks JSONB NOT NULL DEFAULT '[]'::jsonb,
+    -- e.g. [{"kind": "direct_answer", "answer": "42"}]
+    original_response TEXT NOT NULL,
+    rewritten_response TEXT NOT NULL,
+    method TEXT NOT NULL,
+    -- 'model_pass' or 'template'
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+CREATE INDEX idx_guardrail_interventions_node_id ON guardrail_interventions(node_id);
+CREATE INDEX idx_guardrail_interventions_created_at ON guardrail_interventions(created_at);
@@ -0,0 +1,19 @@
+-- Wellbeing alerts raised by the AI Mirror, readable by instructors only.
+-- Journal text is never stored here, only categories and scores.
+CREATE TABLE IF NOT EXISTS wellbeing_alerts (
+    id UUID PRIMARY KEY,
+    user_id BIGINT NOT NULL,
+    session_id UUID NOT NULL,
+    institution TEXT NOT NULL,
+    level TEXT NOT NULL,
+    -- 'distress' or 'crisis'
+    categories JSONB NOT NULL DEFAULT '[]'::jsonb,
+    crisis_score REAL NOT NULL,
+    distress_score REAL NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    acknowledged_by BIGINT REFERENCES users(id),
+    acknowledged_at TIMESTAMPTZ
+);
+CREATE INDEX idx_wellbeing_alerts_created_at ON wellbeing_alerts(created_at);
+CREATE INDEX idx_wellbeing_alerts_unacknowledged ON wellbeing_alerts(created_at)
+WHERE acknowledged_by IS NULL;
@@ -0,0 +1,23 @@
+-- Uploaded knowledge documents; their chunks live in knowledge_sources
+CREATE TABLE IF NOT EXISTS knowledge_documents (
+    id UUID PRIMARY KEY,
+    title TEXT NOT NULL,
+    source_type TEXT NOT NULL,
+    content TEXT NOT NULL,
+    -- Normalized full text, kept for re-indexing
+    content_hash TEXT NOT NULL,
+    -- SHA-256 of content, used to dedupe uploads
+    status TEXT NOT NULL DEFAULT 'pending',
+    -- 'pending', 'processing', 'indexed' or 'failed'
+    chunk_count INTEGER NOT NULL DEFAULT 0,
+    error TEXT,
+    uploaded_by BIGINT REFERENCES users(id),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+CREATE INDEX idx_knowledge_documents_content_hash ON knowledge_documents(content_hash);
+ALTER TABLE knowledge_sources
+ADD COLUMN IF NOT EXISTS document_id UUID REFERENCES knowledge_documents(id) ON DELETE CASCADE,
+    ADD COLUMN IF NOT EXISTS chunk_index INTEGER,
+    ADD COLUMN IF NOT EXISTS content_hash TEXT;
+CREATE INDEX idx_knowledge_sources_document_id ON knowledge_sources(document_id);
@@ -0,0 +1,3 @@
+-- Extracted heading/paragraph blocks with page numbers, used when (re)chunking
+ALTER TABLE knowledge_documents
+ADD COLUMN IF NOT EXISTS blocks JSONB;
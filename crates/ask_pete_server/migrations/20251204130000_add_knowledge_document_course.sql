@@ -0,0 +1,5 @@
+-- Per-course knowledge corpora: chunks carry course_id in their metadata too
+ALTER TABLE knowledge_documents
+ADD COLUMN IF NOT EXISTS course_id TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_knowledge_documents_course_id ON knowledge_documents(course_id);
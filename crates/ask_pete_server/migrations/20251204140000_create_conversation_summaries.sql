@@ -0,0 +1,10 @@
+-- Rolling summaries of long AI Mirror sessions (older turns folded by the memory manager)
+CREATE TABLE IF NOT EXISTS conversation_summaries (
+    session_id UUID PRIMARY KEY,
+    summary TEXT NOT NULL DEFAULT '',
+    learner_facts JSONB NOT NULL DEFAULT '[]'::jsonb,
+    -- e.g. [{"kind": "goal", "text": "I want to pass the exam", "source_turn": "..."}]
+    folded_through UUID,
+    folded_turns INTEGER NOT NULL DEFAULT 0,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
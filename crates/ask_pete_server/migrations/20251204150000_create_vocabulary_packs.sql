@@ -0,0 +1,16 @@
+-- Vocabulary packs: seeded words remember their pack, related terms and misconceptions
+ALTER TABLE vocabulary_words
+ADD COLUMN IF NOT EXISTS pack_id TEXT,
+ADD COLUMN IF NOT EXISTS related_terms TEXT [] NOT NULL DEFAULT '{}',
+ADD COLUMN IF NOT EXISTS misconceptions TEXT [] NOT NULL DEFAULT '{}';
+
+CREATE INDEX IF NOT EXISTS idx_vocabulary_words_pack_id ON vocabulary_words(pack_id);
+
+-- Which pack a course or story graph uses (graph beats course beats the default pack)
+CREATE TABLE IF NOT EXISTS vocabulary_pack_assignments (
+    scope TEXT NOT NULL CHECK (scope IN ('course', 'graph')),
+    scope_id TEXT NOT NULL,
+    pack_id TEXT NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (scope, scope_id)
+);
@@ -0,0 +1,8 @@
+-- Which lore pack a campaign or story graph uses (graph beats campaign beats the Iron Network)
+CREATE TABLE IF NOT EXISTS lore_pack_assignments (
+    scope TEXT NOT NULL CHECK (scope IN ('campaign', 'graph')),
+    scope_id TEXT NOT NULL,
+    pack_id TEXT NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (scope, scope_id)
+);
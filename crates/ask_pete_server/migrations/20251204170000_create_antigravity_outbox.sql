@@ -0,0 +1,20 @@
+-- Steam sync events for Antigravity, written in the same transaction as the
+-- balance change and delivered by the outbox worker. Rows that exhaust their
+-- retries (or are rejected outright) stay as 'dead' until requeued.
+CREATE TABLE IF NOT EXISTS antigravity_outbox (
+    id BIGSERIAL PRIMARY KEY,
+    idempotency_key UUID NOT NULL UNIQUE,
+    user_id BIGINT NOT NULL,
+    amount DOUBLE PRECISION NOT NULL,
+    source TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'delivered', 'dead')),
+    attempts INTEGER NOT NULL DEFAULT 0,
+    last_error TEXT,
+    next_attempt_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    delivered_at TIMESTAMPTZ
+);
+CREATE INDEX idx_antigravity_outbox_due ON antigravity_outbox(next_attempt_at)
+WHERE status = 'pending';
+CREATE INDEX idx_antigravity_outbox_dead ON antigravity_outbox(created_at)
+WHERE status = 'dead';
@@ -0,0 +1,14 @@
+-- Second tier of the AI response cache: model output for deterministic calls
+-- (word/node weighing, scenario review), keyed by a SHA-256 of the prompt
+-- template version, model id, sampling parameters and input.
+CREATE TABLE IF NOT EXISTS ai_response_cache (
+    cache_key TEXT PRIMARY KEY,
+    template TEXT NOT NULL,
+    model_id TEXT NOT NULL,
+    response TEXT NOT NULL,
+    hit_count BIGINT NOT NULL DEFAULT 0,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMPTZ NOT NULL
+);
+CREATE INDEX idx_ai_response_cache_template ON ai_response_cache(template);
+CREATE INDEX idx_ai_response_cache_expires ON ai_response_cache(expires_at);
@@ -0,0 +1,19 @@
+-- Pete chat jobs submitted through /api/pete/chat. Status moves
+-- queued -> running -> done | failed, or to cancelled from either of the
+-- first two. Finished rows are purged once expires_at passes.
+CREATE TABLE IF NOT EXISTS chat_jobs (
+    id UUID PRIMARY KEY,
+    user_id BIGINT NOT NULL,
+    session_id UUID NOT NULL,
+    message TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'queued' CHECK (status IN ('queued', 'running', 'done', 'failed', 'cancelled')),
+    response JSONB,
+    error TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMPTZ NOT NULL
+);
+CREATE INDEX idx_chat_jobs_user ON chat_jobs(user_id, created_at DESC);
+CREATE INDEX idx_chat_jobs_session ON chat_jobs(session_id);
+CREATE INDEX idx_chat_jobs_expires ON chat_jobs(expires_at)
+WHERE status IN ('done', 'failed', 'cancelled');
@@ -0,0 +1,14 @@
+-- AI Mirror conversation history, written in batches by ConversationMemory and
+-- loaded back into its LRU cache on a miss. `seq` breaks ties between turns
+-- with the same timestamp so sessions always replay in insertion order.
+CREATE TABLE IF NOT EXISTS conversation_turns (
+    id UUID PRIMARY KEY,
+    seq BIGSERIAL NOT NULL,
+    session_id UUID NOT NULL,
+    speaker TEXT NOT NULL CHECK (speaker IN ('user', 'ai')),
+    content TEXT NOT NULL,
+    metadata JSONB NOT NULL DEFAULT '{}'::jsonb,
+    -- word_count, sentiment, depth_level, virtue_signals, strategy, citations
+    timestamp TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+CREATE INDEX IF NOT EXISTS idx_conversation_turns_session ON conversation_turns(session_id, timestamp, seq);
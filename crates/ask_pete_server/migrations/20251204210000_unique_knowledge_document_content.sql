@@ -0,0 +1,20 @@
+-- One live copy of each uploaded text per course, so concurrent uploads of the
+-- same file can't both insert. Failed uploads stay out of the index and may be retried.
+-- Existing duplicates are marked failed first (the earliest upload is kept).
+UPDATE knowledge_documents AS d
+SET status = 'failed',
+    error = 'Duplicate of an earlier upload',
+    updated_at = NOW()
+WHERE d.status <> 'failed'
+    AND EXISTS (
+        SELECT 1
+        FROM knowledge_documents AS o
+        WHERE o.content_hash = d.content_hash
+            AND COALESCE(o.course_id, '') = COALESCE(d.course_id, '')
+            AND o.status <> 'failed'
+            AND (o.created_at, o.id) < (d.created_at, d.id)
+    );
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_knowledge_documents_unique_content
+ON knowledge_documents(content_hash, COALESCE(course_id, ''))
+WHERE status <> 'failed';
@@ -0,0 +1,8 @@
+-- Who owns each AI Mirror session opened through /api/ai-mirror/create-session,
+-- so only that learner can page or delete its conversation_turns.
+CREATE TABLE IF NOT EXISTS conversation_sessions (
+    session_id UUID PRIMARY KEY,
+    user_id BIGINT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+CREATE INDEX IF NOT EXISTS idx_conversation_sessions_user ON conversation_sessions(user_id);
@@ -1,4 +1,3 @@
-pub mod conversation_memory;
 pub mod llm;
 pub mod memory;
 pub mod prompts;
@@ -1,5 +1,5 @@
-use super::conversation_memory::{Speaker, Turn};
 use infra_ai::socratic_engine::SessionContext;
+use infra_db::conversation_memory::{Speaker, Turn};
 use serde::{Deserialize, Serialize};
 
 /// Strategies for Socratic prompting
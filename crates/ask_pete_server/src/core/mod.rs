@@ -1,6 +0,0 @@
-pub mod traits {
-    pub trait AssessmentPlugin {}
-    pub trait NarrativeFramework {}
-    pub trait NodeTypeExtension {}
-    pub trait ThemeProvider {}
-}
@@ -1,8 +1,9 @@
 use axum::{
-    http::StatusCode,
+    http::{header, HeaderValue, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
+use infra_ai::worker_pool::PoolError;
 use serde_json::json;
 use thiserror::Error;
 
@@ -13,6 +14,9 @@ pub enum AppError {
     #[error("Authentication required")]
     AuthError,
 
+    #[error("Insufficient role")]
+    Forbidden,
+
     #[error("User not found")]
     NotFound,
 
@@ -25,10 +29,23 @@ pub enum AppError {
     #[error("Internal Server Error")]
     InternalServerError,
 
+    #[error("Inference queue is full")]
+    Busy { retry_after_secs: u64 },
+
     #[error("Unexpected error: {0}")]
     Anyhow(#[from] anyhow::Error),
 }
 
+impl From<PoolError> for AppError {
+    fn from(err: PoolError) -> Self {
+        match err {
+            PoolError::Saturated { retry_after, .. } => AppError::Busy {
+                retry_after_secs: retry_after.as_secs(),
+            },
+        }
+    }
+}
+
 /// A custom Result type for our application.
 pub type Result<T> = std::result::Result<T, AppError>;
 
@@ -37,8 +54,14 @@ pub type Result<T> = std::result::Result<T, AppError>;
 /// This satisfies the "Privacy-First" Directive.
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
+        let retry_after = match &self {
+            AppError::Busy { retry_after_secs } => Some(*retry_after_secs),
+            _ => None,
+        };
+
         let (status, error_message) = match self {
             AppError::AuthError => (StatusCode::UNAUTHORIZED, "Authentication required"),
+            AppError::Forbidden => (StatusCode::FORBIDDEN, "Insufficient permissions"),
             AppError::NotFound => (StatusCode::NOT_FOUND, "Resource not found"),
             AppError::ValidationError(msg) => (StatusCode::BAD_REQUEST, msg),
 
@@ -50,6 +73,10 @@ impl IntoResponse for AppError {
             AppError::InternalServerError => {
                 (StatusCode::INTERNAL_SERVER_ERROR, "Internal Server Error")
             }
+            AppError::Busy { .. } => (
+                StatusCode::SERVICE_UNAVAILABLE,
+                "Pete is busy right now, please retry shortly",
+            ),
             AppError::Anyhow(e) => {
                 tracing::error!("Unexpected Error: {:?}", e);
                 (StatusCode::INTERNAL_SERVER_ERROR, "Internal Server Error")
@@ -61,6 +88,13 @@ impl IntoResponse for AppError {
             "code": status.as_u16(),
         }));
 
-        (status, body).into_response()
+        let mut response = (status, body).into_response();
+        // Tells clients (and the frontend's poller) when a slot is likely free
+        if let Some(secs) = retry_after {
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, HeaderValue::from(secs));
+        }
+        response
     }
 }
@@ -0,0 +1,65 @@
+use crate::error::Result;
+use crate::middleware::auth::AuthUser;
+use crate::services::response_cache::CacheStats;
+use crate::state::AppState;
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use pete_core::UserRole;
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize)]
+pub struct InvalidateQuery {
+    /// e.g. "weigh_node"; all templates when omitted
+    pub template: Option<String>,
+    /// e.g. "gemini:gemini-1.5-flash"; all models when omitted
+    pub model_id: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct InvalidateResponse {
+    pub removed: u64,
+}
+
+/// GET /api/ai_cache/stats - Hit rates since startup, overall and per prompt template
+pub async fn cache_stats(
+    State(state): State<AppState>,
+    user: AuthUser,
+) -> Result<Json<CacheStats>> {
+    user.require(UserRole::Instructor)?;
+    Ok(Json(state.response_cache.stats().await))
+}
+
+/// DELETE /api/ai_cache?template=&model_id= - Drop cached replies matching the filters
+pub async fn invalidate_cache(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Query(query): Query<InvalidateQuery>,
+) -> Result<Json<InvalidateResponse>> {
+    user.require(UserRole::Instructor)?;
+    let removed = state
+        .response_cache
+        .invalidate_where(query.template.as_deref(), query.model_id.as_deref())
+        .await?;
+
+    log::info!(
+        "Instructor {} invalidated {} cached AI replies (template {:?}, model {:?})",
+        user.user_id,
+        removed,
+        query.template,
+        query.model_id
+    );
+    Ok(Json(InvalidateResponse { removed }))
+}
+
+/// DELETE /api/ai_cache/:key - Drop one cached reply by its hash
+pub async fn invalidate_cache_entry(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(key): Path<String>,
+) -> Result<Json<InvalidateResponse>> {
+    user.require(UserRole::Instructor)?;
+    let removed = state.response_cache.invalidate(&key).await?;
+    Ok(Json(InvalidateResponse { removed }))
+}
@@ -1,46 +1,142 @@
-use crate::error::Result;
+use crate::error::{AppError, Result};
+use crate::middleware::auth::{AuthUser, SimulationAuth};
 use crate::state::AppState;
-use axum::{extract::State, http::StatusCode, Json};
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use infra_ai::guardrail::AnswerKey;
 use infra_ai::socratic_engine::SessionContext;
+use infra_ai::wellbeing::SupportResources;
+use infra_ai::worker_pool::{JobSpec, Priority};
+use infra_db::conversation_memory::{Citation, Speaker};
 use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
 use uuid::Uuid;
 
 #[derive(Deserialize)]
 pub struct SendMessageRequest {
     pub session_id: Uuid,
+    /// Must be the signed-in caller's id
     pub user_id: i64,
     pub message: String,
     pub archetype: Option<String>,
     pub focus_area: Option<String>,
+    /// Story graph and station the learner is on, so Pete won't give away its answer
+    #[serde(default)]
+    pub graph_id: Option<i32>,
+    #[serde(default)]
+    pub node_id: Option<String>,
+    /// Limits Pete's knowledge retrieval to this course's uploads
+    #[serde(default)]
+    pub course_id: Option<String>,
+    /// Campaign whose story world Pete speaks from
+    #[serde(default)]
+    pub campaign_id: Option<Uuid>,
 }
 
 #[derive(Serialize)]
 pub struct SendMessageResponse {
     pub ai_response: String,
     pub session_id: Uuid,
+    /// Present when the wellbeing monitor replaced the Socratic reply.
+    /// Clients should hide game UI (Steam, quests) and show these resources.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub support: Option<SupportResources>,
+    /// Knowledge sources behind the reply, for faculty to verify
+    pub citations: Vec<Citation>,
 }
 
 /// Handle a message from the user and return AI's Socratic response
 pub async fn handle_send_message(
     State(app_state): State<AppState>,
+    SimulationAuth(user): SimulationAuth,
     Json(payload): Json<SendMessageRequest>,
-) -> std::result::Result<Json<SendMessageResponse>, (StatusCode, String)> {
+) -> std::result::Result<Json<SendMessageResponse>, Response> {
+    // Wellbeing alerts are filed against this id, so it must be the caller's
+    let user_id = match user {
+        Some(user) if user.user_id != payload.user_id => {
+            return Err(AppError::Forbidden.into_response());
+        }
+        Some(user) => {
+            // Only the session's owner may add turns to it
+            claim_session(&app_state, &user, payload.session_id)
+                .await
+                .map_err(IntoResponse::into_response)?;
+            user.user_id
+        }
+        // Simulation mode: nobody can sign in
+        None => payload.user_id,
+    };
     log::info!(
         "Received message from user {} in session {}",
-        payload.user_id,
+        user_id,
         payload.session_id
     );
 
+    // Look up the current station's answer key for the guardrail
+    let answer_key = match (app_state.pool.as_ref(), payload.graph_id, &payload.node_id) {
+        (Some(pool), Some(graph_id), Some(node_id)) => {
+            load_answer_key(pool, graph_id, node_id).await
+        }
+        _ => None,
+    };
+
+    // Graph's vocabulary pack, else the course's, else the default
+    let vocabulary_pack = match app_state.pool.as_ref() {
+        Some(pool) => crate::services::vocabulary_packs::resolve_pack_id(
+            pool,
+            payload.graph_id,
+            payload.course_id.as_deref(),
+        )
+        .await
+        .unwrap_or_else(|e| {
+            log::warn!("Failed to resolve vocabulary pack: {}", e);
+            None
+        }),
+        None => None,
+    };
+
+    // Graph's lore pack, else the campaign's, else the Iron Network
+    let lore_pack = match app_state.pool.as_ref() {
+        Some(pool) => crate::services::lore_packs::resolve_pack_id(
+            pool,
+            payload.graph_id,
+            payload.campaign_id,
+        )
+        .await
+        .unwrap_or_else(|e| {
+            log::warn!("Failed to resolve lore pack: {}", e);
+            None
+        }),
+        None => None,
+    };
+
     // Build session context
     let context = SessionContext {
         session_id: payload.session_id,
-        user_id: payload.user_id,
+        user_id,
         archetype: payload.archetype,
         focus_area: payload.focus_area,
+        answer_key,
+        course_id: payload.course_id,
+        vocabulary_pack,
+        lore_pack,
     };
 
+    // Wait behind live chat, ahead of weighing and batch jobs; 503 when saturated
+    let _slot = app_state
+        .inference_pool
+        .acquire(
+            JobSpec::new(Priority::Mirror, app_state.socratic_model_id.clone()).for_user(user_id),
+        )
+        .await
+        .map_err(|e| AppError::from(e).into_response())?;
+
     // Get Socratic engine and generate response
-    let response_text = {
+    let (response_text, support, citations) = {
         let mut engine = app_state.socratic_engine.write().await;
 
         match engine.respond(&payload.message, &context).await {
@@ -49,14 +145,19 @@ pub async fn handle_send_message(
                     "Generated Socratic response using strategy: {:?}",
                     response.strategy_used
                 );
-                response.text
+                let support = response
+                    .wellbeing
+                    .as_ref()
+                    .map(|_| engine.support_resources());
+                (response.text, support, response.citations)
             }
             Err(e) => {
                 log::error!("Failed to generate Socratic response: {}", e);
                 return Err((
                     StatusCode::INTERNAL_SERVER_ERROR,
                     format!("Failed to generate response: {}", e),
-                ));
+                )
+                    .into_response());
             }
         }
     };
@@ -66,51 +167,221 @@ pub async fn handle_send_message(
     Ok(Json(SendMessageResponse {
         ai_response: response_text,
         session_id: payload.session_id,
+        support,
+        citations,
     }))
 }
 
-/// Create a new conversation session
+/// Build the guardrail's answer key from a stored story graph node.
+/// The graph's vocabulary list doubles as the target words Pete must not define.
+pub(crate) async fn load_answer_key(
+    pool: &PgPool,
+    graph_id: i32,
+    node_id: &str,
+) -> Option<AnswerKey> {
+    let row: Option<(serde_json::Value, Vec<String>)> =
+        sqlx::query_as("SELECT graph_data, vocabulary FROM story_graphs WHERE id = $1")
+            .bind(graph_id)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| log::warn!("Failed to load story graph {}: {}", graph_id, e))
+            .ok()
+            .flatten();
+    let (graph_data, vocabulary) = row?;
+
+    let node_json = graph_data
+        .get("nodes")?
+        .as_array()?
+        .iter()
+        .find(|n| n.get("id").and_then(|id| id.as_str()) == Some(node_id))?
+        .clone();
+    let node: pete_core::trainyard::StoryNode = serde_json::from_value(node_json).ok()?;
+
+    Some(AnswerKey::from_node(&node, vocabulary))
+}
+
+/// Create a new conversation session, owned by the caller when signed in
 #[derive(Serialize)]
 pub struct CreateSessionResponse {
     pub session_id: Uuid,
 }
 
 pub async fn handle_create_session(
-    State(_app_state): State<AppState>,
-) -> std::result::Result<Json<CreateSessionResponse>, (StatusCode, String)> {
+    State(app_state): State<AppState>,
+    user: Option<AuthUser>,
+) -> Result<Json<CreateSessionResponse>> {
     let session_id = Uuid::new_v4();
+    if let (Some(pool), Some(user)) = (app_state.pool.as_ref(), user) {
+        sqlx::query("INSERT INTO conversation_sessions (session_id, user_id) VALUES ($1, $2)")
+            .bind(session_id)
+            .bind(user.user_id)
+            .execute(pool)
+            .await?;
+    }
     log::info!("Created new conversation session: {}", session_id);
 
     Ok(Json(CreateSessionResponse { session_id }))
 }
 
-/// Get conversation history for a session
+/// Fail unless `user` owns the session. Mirror sessions are recorded at
+/// creation; Pete chat sessions are owned by whoever queued their jobs.
+async fn check_session_owner(
+    app_state: &AppState,
+    user: &AuthUser,
+    session_id: Uuid,
+) -> Result<()> {
+    let mut owner = None;
+    if let Some(pool) = app_state.pool.as_ref() {
+        owner = sqlx::query_scalar::<_, i64>(
+            "SELECT user_id FROM conversation_sessions WHERE session_id = $1",
+        )
+        .bind(session_id)
+        .fetch_optional(pool)
+        .await?;
+    }
+    if owner.is_none() {
+        owner = app_state.chat_queue.session_owner(session_id).await?;
+    }
+
+    match owner {
+        Some(owner) if owner == user.user_id => Ok(()),
+        Some(_) => Err(AppError::Forbidden),
+        None => Err(AppError::NotFound),
+    }
+}
+
+/// Like [`check_session_owner`], but a session nobody owns yet (opened before
+/// signing in, or a fresh id) becomes the caller's
+async fn claim_session(app_state: &AppState, user: &AuthUser, session_id: Uuid) -> Result<()> {
+    match check_session_owner(app_state, user, session_id).await {
+        Err(AppError::NotFound) => {}
+        checked => return checked,
+    }
+    if let Some(pool) = app_state.pool.as_ref() {
+        sqlx::query(
+            r#"
+            INSERT INTO conversation_sessions (session_id, user_id)
+            VALUES ($1, $2)
+            ON CONFLICT (session_id) DO NOTHING
+            "#,
+        )
+        .bind(session_id)
+        .bind(user.user_id)
+        .execute(pool)
+        .await?;
+    }
+    // Someone else may have claimed it first
+    check_session_owner(app_state, user, session_id).await
+}
+
+/// Get one page of a session's conversation history, oldest turn first.
+/// Owners only, except in simulation mode where nobody can sign in
 #[derive(Deserialize)]
 pub struct GetHistoryRequest {
     pub session_id: Uuid,
+    #[serde(default)]
+    pub offset: usize,
     pub limit: Option<usize>,
 }
 
+const DEFAULT_HISTORY_PAGE: usize = 50;
+const MAX_HISTORY_PAGE: usize = 200;
+
 #[derive(Serialize)]
 pub struct ConversationTurn {
     pub speaker: String,
     pub content: String,
     pub timestamp: String,
+    pub citations: Vec<Citation>,
 }
 
 #[derive(Serialize)]
 pub struct GetHistoryResponse {
     pub turns: Vec<ConversationTurn>,
+    /// Turns in the whole session
+    pub total: usize,
+    /// Offset to request for the following page, absent on the last one
+    pub next_offset: Option<usize>,
 }
 
 pub async fn handle_get_history(
-    State(_app_state): State<AppState>,
+    State(app_state): State<AppState>,
+    SimulationAuth(user): SimulationAuth,
     Json(payload): Json<GetHistoryRequest>,
-) -> std::result::Result<Json<GetHistoryResponse>, (StatusCode, String)> {
+) -> Result<Json<GetHistoryResponse>> {
+    // Simulation mode has no owners; the in-memory history is served as is
+    if let Some(user) = &user {
+        check_session_owner(&app_state, user, payload.session_id).await?;
+    }
     log::info!("Fetching history for session {}", payload.session_id);
 
-    // TODO: Implement actual history retrieval from ConversationMemory
-    // For Phase 1, return empty history
+    let limit = payload
+        .limit
+        .unwrap_or(DEFAULT_HISTORY_PAGE)
+        .clamp(1, MAX_HISTORY_PAGE);
+    let page = app_state
+        .conversation_memory
+        .get_page(payload.session_id, payload.offset, limit)
+        .await?;
+
+    let turns = page
+        .turns
+        .into_iter()
+        .map(|turn| ConversationTurn {
+            speaker: match turn.speaker {
+                Speaker::User => "user",
+                Speaker::AI => "ai",
+            }
+            .to_string(),
+            content: turn.content,
+            timestamp: turn.timestamp.to_rfc3339(),
+            citations: turn.metadata.citations,
+        })
+        .collect();
+
+    Ok(Json(GetHistoryResponse {
+        turns,
+        total: page.total,
+        next_offset: page.next_offset,
+    }))
+}
+
+/// Delete a session's history, summary and ownership record. Owners only,
+/// except in simulation mode where nobody can sign in
+#[derive(Deserialize)]
+pub struct DeleteSessionRequest {
+    pub session_id: Uuid,
+}
+
+#[derive(Serialize)]
+pub struct DeleteSessionResponse {
+    pub deleted_turns: usize,
+}
+
+pub async fn handle_delete_session(
+    State(app_state): State<AppState>,
+    SimulationAuth(user): SimulationAuth,
+    Json(payload): Json<DeleteSessionRequest>,
+) -> Result<Json<DeleteSessionResponse>> {
+    if let Some(user) = &user {
+        check_session_owner(&app_state, user, payload.session_id).await?;
+    }
+
+    let deleted_turns = app_state
+        .conversation_memory
+        .delete_session(payload.session_id)
+        .await?;
+    if let Some(pool) = app_state.pool.as_ref() {
+        sqlx::query("DELETE FROM conversation_sessions WHERE session_id = $1")
+            .bind(payload.session_id)
+            .execute(pool)
+            .await?;
+    }
+    log::info!(
+        "Deleted session {} ({} turns)",
+        payload.session_id,
+        deleted_turns
+    );
 
-    Ok(Json(GetHistoryResponse { turns: vec![] }))
+    Ok(Json(DeleteSessionResponse { deleted_turns }))
 }
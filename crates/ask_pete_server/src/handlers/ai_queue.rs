@@ -0,0 +1,15 @@
+use crate::error::Result;
+use crate::middleware::auth::AuthUser;
+use crate::state::AppState;
+use axum::{extract::State, Json};
+use infra_ai::worker_pool::PoolMetrics;
+use pete_core::UserRole;
+
+/// GET /api/ai_queue/stats - Inference pool depth per priority class, running jobs per model
+pub async fn queue_stats(
+    State(state): State<AppState>,
+    user: AuthUser,
+) -> Result<Json<PoolMetrics>> {
+    user.require(UserRole::Instructor)?;
+    Ok(Json(state.inference_pool.metrics()))
+}
@@ -0,0 +1,46 @@
+use crate::error::{AppError, Result};
+use crate::middleware::auth::AuthUser;
+use crate::state::AppState;
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use infra_ai::antigravity::outbox::{self, OutboxEntry};
+use pete_core::UserRole;
+use serde::Serialize;
+
+#[derive(Serialize)]
+pub struct RequeueResponse {
+    pub id: i64,
+    pub requeued: bool,
+}
+
+/// GET /api/antigravity/outbox/dead_letters - Steam syncs that gave up, newest first
+pub async fn list_dead_letters(
+    State(state): State<AppState>,
+    user: AuthUser,
+) -> Result<Json<Vec<OutboxEntry>>> {
+    user.require(UserRole::Instructor)?;
+    let pool = state.pool.as_ref().ok_or(AppError::InternalServerError)?;
+    Ok(Json(outbox::dead_letters(pool, 200).await?))
+}
+
+/// POST /api/antigravity/outbox/:id/requeue - Retry a dead-lettered sync
+pub async fn requeue_dead_letter(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(id): Path<i64>,
+) -> Result<Json<RequeueResponse>> {
+    user.require(UserRole::Instructor)?;
+    let pool = state.pool.as_ref().ok_or(AppError::InternalServerError)?;
+    if !outbox::requeue(pool, id).await? {
+        return Err(AppError::NotFound);
+    }
+
+    log::info!(
+        "Instructor {} requeued Antigravity sync {}",
+        user.user_id,
+        id
+    );
+    Ok(Json(RequeueResponse { id, requeued: true }))
+}
@@ -1,17 +1,38 @@
-use crate::error::Result;
+use crate::error::{AppError, Result};
 use crate::state::AppState;
 use axum::{extract::State, Json};
 use infra_ai::architect::{BlueprintRequest, BlueprintResponse, CurriculumArchitect};
+use infra_ai::worker_pool::{JobSpec, Priority};
 
 pub async fn generate_blueprint(
     State(state): State<AppState>,
     Json(payload): Json<BlueprintRequest>,
 ) -> Result<Json<BlueprintResponse>> {
+    // Resolve the requested narrative framework plugin before taking the engine lock
+    let framework = match payload.framework.as_deref() {
+        Some(id) => Some(
+            state
+                .plugins
+                .get_framework(id)
+                .ok_or(AppError::ValidationError("Unknown narrative framework"))?,
+        ),
+        None => None,
+    };
+
+    // Blueprints are batch work: they wait until chat and weighing are served
+    let _slot = state
+        .inference_pool
+        .acquire(JobSpec::new(
+            Priority::Batch,
+            state.socratic_model_id.clone(),
+        ))
+        .await?;
+
     // Use the shared Socratic Engine (The AI Mirror)
     let mut engine = state.socratic_engine.write().await;
 
     // Generate blueprint using the engine's available model (Gemma or Gemini)
-    let response = engine.generate_blueprint(payload).await?;
+    let response = engine.generate_blueprint(payload, framework).await?;
 
     Ok(Json(response))
 }
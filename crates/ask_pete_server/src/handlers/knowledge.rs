@@ -1,22 +1,25 @@
 // Knowledge Base Management API
-// Handles document upload, chunking, and embedding for RAG with Gemma 27B
+// Handles document upload, chunking, and embedding for RAG
 
-use crate::error::AppError;
-use axum::{extract::State, Json};
+use crate::error::{AppError, Result};
+use crate::middleware::auth::AuthUser;
+use crate::services::extractors::extractor_for;
+use crate::services::knowledge_ingest::{KnowledgeDocument, KnowledgeIngestor};
+use crate::state::AppState;
+use axum::{
+    extract::{Multipart, Path, State},
+    Json,
+};
+use infra_db::vector_store::{MetadataFilter, VectorStore};
+use pete_core::UserRole;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-#[derive(Debug, Deserialize)]
-pub struct UploadKnowledgeRequest {
-    pub title: String,
-    pub content: String,
-    pub source_type: String, // "pdf", "txt", "md"
-}
-
 #[derive(Debug, Serialize)]
 pub struct UploadKnowledgeResponse {
-    pub source_id: Uuid,
-    pub chunks_created: usize,
+    pub document: KnowledgeDocument,
+    /// False when identical content was already in the library
+    pub created: bool,
     pub message: String,
 }
 
@@ -31,90 +34,224 @@ pub struct SearchResult {
 pub struct SearchRequest {
     pub query: String,
     pub limit: Option<usize>,
+    /// Only search documents uploaded for this course
+    #[serde(default)]
+    pub course_id: Option<String>,
+}
+
+fn ingestor(state: &AppState) -> Result<KnowledgeIngestor> {
+    let pool = state.pool.clone().ok_or(AppError::InternalServerError)?;
+    Ok(KnowledgeIngestor::new(pool, state.memory_store.clone()))
 }
 
-/// Upload a document and process it into searchable chunks
+/// Upload a document (multipart) and index it in the background.
+///
+/// Fields: `file` (PDF, DOCX, HTML, Markdown or text) or `content` (raw text),
+/// plus optional `title`, `source_type` (defaults to the file extension) and
+/// `course_id` (limits retrieval of the document to that course).
+/// Poll `GET /api/knowledge/documents/:id` for status. Instructors only.
 pub async fn upload_knowledge(
-    Json(req): Json<UploadKnowledgeRequest>,
-) -> Result<Json<UploadKnowledgeResponse>, AppError> {
-    // TODO Phase 2: Implement document storage and chunking
-    // 1. Store document in knowledge_sources table
-    // 2. Chunk the content (500 tokens, 50 token overlap)
-    // 3. Generate embeddings using fastembed
-    // 4. Store vectors in knowledge_vectors table
+    State(state): State<AppState>,
+    user: AuthUser,
+    mut multipart: Multipart,
+) -> Result<Json<UploadKnowledgeResponse>> {
+    user.require(UserRole::Instructor)?;
+    let ingestor = ingestor(&state)?;
+
+    let mut title: Option<String> = None;
+    let mut source_type: Option<String> = None;
+    let mut course_id: Option<String> = None;
+    let mut file_name: Option<String> = None;
+    let mut content: Option<Vec<u8>> = None;
+
+    while let Some(field) = multipart
+        .next_field()
+        .await
+        .map_err(|_| AppError::ValidationError("Malformed multipart body"))?
+    {
+        let name = field.name().unwrap_or_default().to_string();
+        match name.as_str() {
+            "file" | "content" => {
+                if file_name.is_none() {
+                    file_name = field.file_name().map(str::to_string);
+                }
+                let bytes = field
+                    .bytes()
+                    .await
+                    .map_err(|_| AppError::ValidationError("Failed to read upload"))?;
+                content = Some(bytes.to_vec());
+            }
+            "title" | "source_type" | "course_id" => {
+                let value = field
+                    .text()
+                    .await
+                    .map_err(|_| AppError::ValidationError("Invalid text field"))?;
+                let value = Some(value.trim().to_string()).filter(|v| !v.is_empty());
+                match name.as_str() {
+                    "title" => title = value,
+                    "source_type" => source_type = value,
+                    _ => course_id = value,
+                }
+            }
+            _ => {}
+        }
+    }
 
-    let source_id = Uuid::new_v4();
+    let bytes = content.ok_or(AppError::ValidationError("Missing file or content field"))?;
+    let source_type = source_type
+        .or_else(|| {
+            file_name
+                .as_deref()
+                .and_then(|name| name.rsplit_once('.'))
+                .map(|(_, ext)| ext.to_lowercase())
+        })
+        .unwrap_or_else(|| "txt".to_string());
+
+    let extractor =
+        extractor_for(&source_type).ok_or(AppError::ValidationError("Unsupported source type"))?;
+    // PDF parsing is CPU-bound; keep it off the async workers
+    let extracted = tokio::task::spawn_blocking(move || extractor.extract(&bytes))
+        .await
+        .map_err(|_| AppError::InternalServerError)?
+        .map_err(|e| {
+            log::warn!("Failed to extract {} upload: {}", source_type, e);
+            AppError::ValidationError("Could not read text from the uploaded file")
+        })?;
+    if extracted.is_empty() {
+        return Err(AppError::ValidationError(
+            "No text found in the uploaded file",
+        ));
+    }
+
+    let title = title
+        .or_else(|| extracted.title.clone())
+        .or_else(|| file_name.clone())
+        .unwrap_or_else(|| "Untitled".to_string());
+
+    let (document, created) = ingestor
+        .create_document(
+            &title,
+            &source_type,
+            &extracted,
+            Some(user.user_id),
+            course_id.as_deref(),
+        )
+        .await
+        .map_err(|e| {
+            log::warn!("Knowledge upload '{}' rejected: {}", title, e);
+            AppError::ValidationError("Document could not be stored")
+        })?;
+
+    if created {
+        let document_id = document.id;
+        tokio::spawn(async move { ingestor.index_document(document_id).await });
+    }
 
     Ok(Json(UploadKnowledgeResponse {
-        source_id,
-        chunks_created: 0,
-        message: "Knowledge upload endpoint ready (implementation pending database setup)"
-            .to_string(),
+        message: if created {
+            "Document accepted; indexing in the background".to_string()
+        } else {
+            "Document already in the knowledge library".to_string()
+        },
+        document,
+        created,
     }))
 }
 
-/// Search for relevant knowledge chunks using vector similarity
-/// Uses Gemma 27B's synthesize_from_rag() for long-context orchestration
-pub async fn search_knowledge(
-    Json(req): Json<SearchRequest>,
-) -> Result<Json<Vec<SearchResult>>, AppError> {
-    // TODO Phase 2: Implement vector search with Gemma 27B synthesis
-    // 1. Generate embedding for query using FastEmbed
-    // 2. Perform cosine similarity search using pgvector
-    // 3. Use Gemma 27B's synthesize_from_rag() to create coherent answer (8K context!)
-    // 4. Return synthesized response with source attribution
-
-    log::info!(
-        "RAG search query: {} (limit: {})",
-        req.query,
-        req.limit.unwrap_or(5)
-    );
-
-    // Placeholder: In production, call:
-    // let sources = vector_search(&req.query, req.limit.unwrap_or(5)).await?;
-    // let synthesized = gemma_server.synthesize_from_rag(&req.query, &sources, 10)?;
-
-    Ok(Json(vec![]))
+/// GET /api/knowledge/documents - All uploaded documents with indexing status
+pub async fn list_documents(State(state): State<AppState>) -> Result<Json<Vec<KnowledgeDocument>>> {
+    Ok(Json(ingestor(&state)?.list_documents().await?))
 }
 
-/// Utility: Chunk text using sliding window approach
-/// TODO: Upgrade to semantic chunking (sentence boundaries)
-pub fn chunk_text(text: &str, chunk_size: usize, overlap: usize) -> Vec<String> {
-    let words: Vec<&str> = text.split_whitespace().collect();
-    let mut chunks = Vec::new();
-
-    if words.is_empty() {
-        return chunks;
-    }
-
-    let mut i = 0;
-    while i < words.len() {
-        let end = (i + chunk_size).min(words.len());
-        let chunk = words[i..end].join(" ");
-        chunks.push(chunk);
+/// GET /api/knowledge/documents/:id
+pub async fn get_document(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<KnowledgeDocument>> {
+    ingestor(&state)?
+        .get_document(id)
+        .await?
+        .map(Json)
+        .ok_or(AppError::NotFound)
+}
 
-        // Move forward by (chunk_size - overlap) to create overlap
-        i += if chunk_size > overlap {
-            chunk_size - overlap
-        } else {
-            chunk_size
-        };
+/// DELETE /api/knowledge/documents/:id - Remove a document, its chunks and vectors
+pub async fn delete_document(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<serde_json::Value>> {
+    user.require(UserRole::Instructor)?;
+    if !ingestor(&state)?.delete_document(id).await? {
+        return Err(AppError::NotFound);
     }
-
-    chunks
+    Ok(Json(serde_json::json!({ "deleted": id })))
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+/// POST /api/knowledge/documents/:id/reindex - Re-chunk and re-embed a document
+pub async fn reindex_document(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<KnowledgeDocument>> {
+    user.require(UserRole::Instructor)?;
+    let ingestor = ingestor(&state)?;
+    let document = ingestor.get_document(id).await?.ok_or(AppError::NotFound)?;
+    tokio::spawn(async move { ingestor.index_document(id).await });
+    Ok(Json(document))
+}
 
-    #[test]
-    fn test_chunk_text() {
-        let text = "This is a test sentence with multiple words for chunking.";
-        let chunks = chunk_text(text, 3, 1);
+/// Search for relevant knowledge chunks with hybrid (BM25 + vector) retrieval,
+/// falling back to keyword search when no vector store is available
+pub async fn search_knowledge(
+    State(state): State<AppState>,
+    Json(req): Json<SearchRequest>,
+) -> Result<Json<Vec<SearchResult>>> {
+    let limit = req.limit.unwrap_or(5);
+    log::info!("RAG search query: {} (limit: {})", req.query, limit);
 
-        assert!(!chunks.is_empty());
-        assert_eq!(chunks[0], "This is a");
-        assert_eq!(chunks[1], "a test sentence");
+    if let Some(store) = &state.memory_store {
+        let filter = match &req.course_id {
+            Some(course) => MetadataFilter::new().eq("course_id", course.as_str()),
+            None => MetadataFilter::default(),
+        };
+        let hits = store.search_filtered(&req.query, limit, &filter).await?;
+        return Ok(Json(
+            hits.into_iter()
+                .map(|hit| {
+                    let metadata: serde_json::Value =
+                        serde_json::from_str(&hit.document.metadata).unwrap_or_default();
+                    SearchResult {
+                        chunk_text: hit.document.text,
+                        similarity: hit.score,
+                        source_title: metadata
+                            .get("title")
+                            .and_then(|t| t.as_str())
+                            .unwrap_or("Untitled")
+                            .to_string(),
+                    }
+                })
+                .collect(),
+        ));
     }
+
+    let pool = state.pool.as_ref().ok_or(AppError::InternalServerError)?;
+    let chunks = infra_ai::knowledge_retrieval::retrieve_knowledge(
+        &req.query,
+        pool,
+        Some(limit),
+        req.course_id.as_deref(),
+    )
+    .await
+    .map_err(|e| anyhow::anyhow!("Keyword search failed: {}", e))?;
+    Ok(Json(
+        chunks
+            .into_iter()
+            .map(|chunk| SearchResult {
+                chunk_text: chunk.content,
+                similarity: chunk.relevance_score,
+                source_title: chunk.title,
+            })
+            .collect(),
+    ))
 }
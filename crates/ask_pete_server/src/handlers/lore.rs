@@ -0,0 +1,125 @@
+use crate::error::{AppError, Result};
+use crate::middleware::auth::AuthUser;
+use crate::services::lore_packs::{assign_pack, LoreScope};
+use crate::state::AppState;
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use infra_ai::lore::LorePack;
+use pete_core::UserRole;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Serialize)]
+pub struct LorePackSummary {
+    pub id: String,
+    pub name: String,
+    pub summary: String,
+    pub persona: String,
+    pub station_count: usize,
+}
+
+#[derive(Deserialize)]
+pub struct AssignLorePackRequest {
+    pub pack_id: String,
+}
+
+#[derive(Serialize)]
+pub struct LorePackAssignment {
+    pub scope: LoreScope,
+    pub scope_id: String,
+    pub pack_id: String,
+}
+
+/// GET /api/lore/packs - Story worlds faculty can choose from
+pub async fn list_packs(State(state): State<AppState>) -> Json<Vec<LorePackSummary>> {
+    Json(
+        state
+            .lore_packs
+            .packs()
+            .map(|p| LorePackSummary {
+                id: p.id.clone(),
+                name: p.name.clone(),
+                summary: p.summary.clone(),
+                persona: format!("{}, {}", p.persona.name, p.persona.role),
+                station_count: p.stations.len(),
+            })
+            .collect(),
+    )
+}
+
+/// GET /api/lore/packs/:id - A pack with its codex, persona and device structures
+pub async fn get_pack(
+    State(state): State<AppState>,
+    Path(pack_id): Path<String>,
+) -> Result<Json<LorePack>> {
+    state
+        .lore_packs
+        .get(&pack_id)
+        .cloned()
+        .map(Json)
+        .ok_or(AppError::NotFound)
+}
+
+/// PUT /api/campaigns/:id/lore_pack - Choose a campaign's story world
+pub async fn assign_campaign_pack(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(campaign_id): Path<Uuid>,
+    Json(payload): Json<AssignLorePackRequest>,
+) -> Result<Json<LorePackAssignment>> {
+    assign(
+        &state,
+        user,
+        LoreScope::Campaign,
+        campaign_id.to_string(),
+        payload.pack_id,
+    )
+    .await
+}
+
+/// PUT /api/story_graphs/:id/lore_pack - Choose a graph's story world (overrides the campaign's)
+pub async fn assign_graph_pack(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(graph_id): Path<i32>,
+    Json(payload): Json<AssignLorePackRequest>,
+) -> Result<Json<LorePackAssignment>> {
+    assign(
+        &state,
+        user,
+        LoreScope::Graph,
+        graph_id.to_string(),
+        payload.pack_id,
+    )
+    .await
+}
+
+async fn assign(
+    state: &AppState,
+    user: AuthUser,
+    scope: LoreScope,
+    scope_id: String,
+    pack_id: String,
+) -> Result<Json<LorePackAssignment>> {
+    user.require(UserRole::Instructor)?;
+    let pool = state.pool.as_ref().ok_or(AppError::InternalServerError)?;
+    if state.lore_packs.get(&pack_id).is_none() {
+        return Err(AppError::ValidationError("Unknown lore pack"));
+    }
+
+    assign_pack(pool, scope, &scope_id, &pack_id).await?;
+    log::info!(
+        "Instructor {} set {} {} to lore pack '{}'",
+        user.user_id,
+        scope.as_str(),
+        scope_id,
+        pack_id
+    );
+    Ok(Json(LorePackAssignment {
+        scope,
+        scope_id,
+        pack_id,
+    }))
+}
@@ -1,14 +1,22 @@
+pub mod ai_cache;
 pub mod ai_mirror;
+pub mod ai_queue;
+pub mod antigravity;
 pub mod architect;
 pub mod auth; // [NEW]
 pub mod campaign;
 pub mod expert;
 pub mod knowledge;
+pub mod lore;
+pub mod models;
 pub mod persona;
 pub mod player;
+pub mod plugins;
 pub mod quest; // [NEW] Quest management (start/complete)
 pub mod recharge;
 pub mod research;
 pub mod simulation;
 pub mod telemetry;
+pub mod vocabulary;
 pub mod weigh_station;
+pub mod wellbeing;
@@ -0,0 +1,132 @@
+use crate::error::{AppError, Result};
+use crate::middleware::auth::AuthUser;
+use crate::services::model_recommender::{CatalogModel, ModelRecommender, RecommendationReport};
+use crate::state::AppState;
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use infra_services::downloader::{
+    DiskReport, DownloadId, DownloadProgress, StoredModel, VerifyReport,
+};
+use infra_services::hardware_scanner::HardwareScanner;
+use pete_core::UserRole;
+use serde::Serialize;
+
+#[derive(Serialize)]
+pub struct CancelResponse {
+    pub id: DownloadId,
+    pub cancelled: bool,
+}
+
+#[derive(Serialize)]
+pub struct DeleteModelResponse {
+    pub filename: String,
+    pub deleted: bool,
+}
+
+/// GET /api/models/downloads - Every download since startup with its latest progress
+pub async fn list_downloads(State(state): State<AppState>) -> Json<Vec<DownloadProgress>> {
+    Json(state.download_queue.jobs())
+}
+
+/// DELETE /api/models/downloads/:id - Cancel a queued or running download
+pub async fn cancel_download(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(id): Path<DownloadId>,
+) -> Result<Json<CancelResponse>> {
+    user.require(UserRole::Instructor)?;
+    if state.download_queue.job(id).is_none() {
+        return Err(AppError::NotFound);
+    }
+    let cancelled = state.download_queue.cancel(id);
+    Ok(Json(CancelResponse { id, cancelled }))
+}
+
+/// GET /api/models/installed - Downloaded model files with their recorded checksums
+pub async fn list_installed(State(state): State<AppState>) -> Result<Json<Vec<StoredModel>>> {
+    Ok(Json(state.download_queue.store().list()?))
+}
+
+/// GET /api/models/disk - Space used by models, the quota and free space on the volume
+pub async fn disk_usage(State(state): State<AppState>) -> Json<DiskReport> {
+    Json(state.download_queue.store().disk_report())
+}
+
+/// DELETE /api/models/installed/:filename - Remove a model and its checksum entry
+pub async fn delete_installed(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(filename): Path<String>,
+) -> Result<Json<DeleteModelResponse>> {
+    user.require(UserRole::Instructor)?;
+    let store = state.download_queue.store();
+    store
+        .path_for(&filename)
+        .map_err(|_| AppError::ValidationError("Invalid model file name"))?;
+    if state.download_queue.is_active(&filename) {
+        return Err(AppError::ValidationError(
+            "Model is still downloading; cancel it first",
+        ));
+    }
+
+    let deleted = store.delete(&filename)?;
+    if !deleted {
+        return Err(AppError::NotFound);
+    }
+    log::info!("Instructor {} deleted model {}", user.user_id, filename);
+    Ok(Json(DeleteModelResponse { filename, deleted }))
+}
+
+/// POST /api/models/installed/:filename/verify - Re-hash a model against the manifest
+pub async fn verify_installed(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(filename): Path<String>,
+) -> Result<Json<VerifyReport>> {
+    user.require(UserRole::Instructor)?;
+    let store = state.download_queue.store();
+    let path = store
+        .path_for(&filename)
+        .map_err(|_| AppError::ValidationError("Invalid model file name"))?;
+    if !path.is_file() {
+        return Err(AppError::NotFound);
+    }
+    Ok(Json(store.verify(&filename).await?))
+}
+
+/// DELETE /api/models/cache/:alias - Remove a Hugging Face cached model (e.g. "pete")
+pub async fn delete_cached(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(alias): Path<String>,
+) -> Result<Json<DeleteModelResponse>> {
+    user.require(UserRole::Instructor)?;
+    let deleted = state.model_manager.lock().await.delete_model(&alias)?;
+    if !deleted {
+        return Err(AppError::NotFound);
+    }
+    log::info!("Instructor {} deleted cached model {}", user.user_id, alias);
+    Ok(Json(DeleteModelResponse {
+        filename: alias,
+        deleted,
+    }))
+}
+
+/// GET /api/models/recommendations - Best model and quantization per AI role on this machine
+pub async fn recommendations(State(state): State<AppState>) -> Result<Json<RecommendationReport>> {
+    let catalog = CatalogModel::from_manager(
+        &*state.model_manager.lock().await,
+        state.download_queue.store().dir(),
+    );
+
+    // Hardware scan and GGUF headers both touch the disk
+    let report = tokio::task::spawn_blocking(move || {
+        ModelRecommender::new(HardwareScanner::scan(), catalog).report()
+    })
+    .await
+    .map_err(|_| AppError::InternalServerError)?;
+
+    Ok(Json(report))
+}
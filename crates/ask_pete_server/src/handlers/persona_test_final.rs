@@ -28,12 +28,10 @@ mod tests {
             leptos_options,
             shared_research_log: Arc::new(RwLock::new(Default::default())),
             shared_virtues: Arc::new(RwLock::new(Default::default())),
-            conversation_memory: Arc::new(
-                crate::ai::conversation_memory::ConversationMemory::new_in_memory(10),
-            ),
+            conversation_memory: Arc::new(infra_db::ConversationMemory::new_in_memory(10)),
             socratic_engine: Arc::new(tokio::sync::RwLock::new(
-                crate::ai::socratic_engine::SocraticEngine::new(Arc::new(
-                    crate::ai::conversation_memory::ConversationMemory::new_in_memory(10),
+                infra_ai::socratic_engine::SocraticEngine::new(Arc::new(
+                    infra_db::ConversationMemory::new_in_memory(10),
                 )),
             )),
             model_manager: Arc::new(tokio::sync::Mutex::new(
@@ -0,0 +1,73 @@
+use crate::error::{AppError, Result};
+use crate::state::AppState;
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use pete_core::expert::StoryGraph;
+use pete_core::frameworks::{FrameworkStage, NarrativeFramework};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize)]
+pub struct FrameworkSummary {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub stages: Vec<FrameworkStage>,
+}
+
+#[derive(Deserialize)]
+pub struct SkeletonQuery {
+    pub subject: String,
+}
+
+impl FrameworkSummary {
+    fn from_framework(framework: &dyn NarrativeFramework) -> Self {
+        Self {
+            id: framework.id().to_string(),
+            name: framework.name().to_string(),
+            description: framework.description().to_string(),
+            stages: framework.stages().to_vec(),
+        }
+    }
+}
+
+/// GET /api/plugins/frameworks - Narrative frameworks the Architect can build with
+pub async fn list_frameworks(State(state): State<AppState>) -> Json<Vec<FrameworkSummary>> {
+    Json(
+        state
+            .plugins
+            .frameworks()
+            .iter()
+            .map(|f| FrameworkSummary::from_framework(f.as_ref()))
+            .collect(),
+    )
+}
+
+/// GET /api/plugins/frameworks/:id - A framework and its stages
+pub async fn get_framework(
+    State(state): State<AppState>,
+    Path(framework_id): Path<String>,
+) -> Result<Json<FrameworkSummary>> {
+    state
+        .plugins
+        .get_framework(&framework_id)
+        .map(|f| Json(FrameworkSummary::from_framework(f.as_ref())))
+        .ok_or(AppError::NotFound)
+}
+
+/// GET /api/plugins/frameworks/:id/skeleton?subject=... - One station per stage
+pub async fn framework_skeleton(
+    State(state): State<AppState>,
+    Path(framework_id): Path<String>,
+    Query(query): Query<SkeletonQuery>,
+) -> Result<Json<StoryGraph>> {
+    if query.subject.trim().is_empty() {
+        return Err(AppError::ValidationError("Subject is required"));
+    }
+    let framework = state
+        .plugins
+        .get_framework(&framework_id)
+        .ok_or(AppError::NotFound)?;
+    Ok(Json(framework.skeleton(query.subject.trim())))
+}
@@ -1,13 +1,26 @@
 use crate::error::Result;
+use crate::middleware::auth::AuthUser;
 use crate::AppState;
 use axum::{extract::State, Json};
 use domain_physics::components::{ResearchLog, VirtueTopology};
+use pete_core::UserRole;
 
 pub async fn get_research_log(State(state): State<AppState>) -> Result<Json<ResearchLog>> {
     let log = state.shared_research_log.read().unwrap().clone();
     Ok(Json(log))
 }
 
+/// GET /api/research/guardrail - Answer-leak interventions recorded since startup.
+/// Instructor-only: entries carry the original model reply and student excerpts.
+pub async fn get_guardrail_interventions(
+    State(state): State<AppState>,
+    user: AuthUser,
+) -> Result<Json<Vec<infra_ai::guardrail::GuardrailIntervention>>> {
+    user.require(UserRole::Instructor)?;
+    let engine = state.socratic_engine.read().await;
+    Ok(Json(engine.guardrail_interventions()))
+}
+
 pub async fn get_virtue_topology(State(state): State<AppState>) -> Result<Json<VirtueTopology>> {
     let virtues = state.shared_virtues.read().unwrap().clone();
     Ok(Json(virtues))
@@ -1,8 +1,21 @@
 use crate::error::Result;
 use crate::state::AppState;
 use axum::{extract::State, Json};
+use infra_ai::prompt_assembler::{priority, PromptSection};
+use infra_ai::worker_pool::{JobSpec, Priority};
 use serde::{Deserialize, Serialize};
 
+/// Room for a 200-300 word story
+const STORY_MAX_TOKENS: usize = 600;
+const STORY_THEME_TOKENS: usize = 200;
+
+const STORY_INSTRUCTIONS: &str = r#"Write a short, engaging story (approx. 200-300 words) that naturally incorporates ALL of the provided vocabulary words.
+The story should be fun, slightly gamified (referencing "Operators", "Trains", or "The Static" if appropriate for the theme, otherwise stick to the requested theme), and educational.
+
+Highlight the vocabulary words in the story by wrapping them in **bold**.
+
+Generate the story now:"#;
+
 #[derive(Debug, Deserialize)]
 pub struct GenerateStoryRequest {
     pub vaam_words: Vec<String>,
@@ -24,35 +37,40 @@ pub async fn generate_story(
         payload.theme, payload.vaam_words
     );
 
-    let words_list = payload.vaam_words.join(", ");
-    let prompt = format!(
-        r#"You are a Storyteller for the Iron Network.
+    // Every vocabulary word must make it into the prompt; a long theme is cut
+    let sections = vec![
+        PromptSection::fixed("role", "You are a Storyteller for the Iron Network."),
+        PromptSection::text("theme", priority::NARRATIVE, payload.theme.clone())
+            .with_header("THEME: ")
+            .with_max_tokens(STORY_THEME_TOKENS),
+        PromptSection::fixed(
+            "vocabulary",
+            format!("VOCABULARY WORDS: {}", payload.vaam_words.join(", ")),
+        ),
+        PromptSection::fixed("instructions", STORY_INSTRUCTIONS),
+    ];
 
-THEME: "{}"
-VOCABULARY WORDS: {}
-
-Write a short, engaging story (approx. 200-300 words) that naturally incorporates ALL of the provided vocabulary words.
-The story should be fun, slightly gamified (referencing "Operators", "Trains", or "The Static" if appropriate for the theme, otherwise stick to the requested theme), and educational.
-
-Highlight the vocabulary words in the story by wrapping them in **bold**.
-
-Generate the story now:"#,
-        payload.theme, words_list
-    );
+    // Story generation is batch work on the same model as chat
+    let _slot = state
+        .inference_pool
+        .acquire(JobSpec::new(
+            Priority::Batch,
+            state.socratic_model_id.clone(),
+        ))
+        .await?;
 
-    // Use Socratic Engine's LLM client
-    let engine = state.socratic_engine.read().await;
+    // Use the Socratic Engine's active model
+    let mut engine = state.socratic_engine.write().await;
+    let prompt = engine
+        .prompt_assembler(STORY_MAX_TOKENS)
+        .assemble(sections)
+        .map_err(anyhow::Error::from)?
+        .text;
 
-    // Generate using LLM
-    let response_text = match engine.llm_client() {
-        Some(client) => client
-            .generate_text(&prompt)
-            .await
-            .map_err(|e| anyhow::anyhow!("LLM generation failed: {}", e))?,
-        None => {
-            return Err(anyhow::anyhow!("LLM client not available").into());
-        }
-    };
+    let response_text = engine
+        .complete(&prompt, STORY_MAX_TOKENS)
+        .await
+        .ok_or_else(|| anyhow::anyhow!("LLM generation failed or no model is available"))?;
 
     println!(
         "Story Handler: Generated story of length {}",
@@ -0,0 +1,117 @@
+use crate::error::{AppError, Result};
+use crate::middleware::auth::AuthUser;
+use crate::services::vocabulary_packs::{assign_pack, PackScope};
+use crate::state::AppState;
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use infra_ai::vocabulary::VocabularyPack;
+use pete_core::UserRole;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize)]
+pub struct VocabularyPackSummary {
+    pub id: String,
+    pub name: String,
+    pub domain: String,
+    pub description: String,
+    pub term_count: usize,
+}
+
+#[derive(Deserialize)]
+pub struct AssignPackRequest {
+    pub pack_id: String,
+}
+
+#[derive(Serialize)]
+pub struct PackAssignment {
+    pub scope: PackScope,
+    pub scope_id: String,
+    pub pack_id: String,
+}
+
+/// GET /api/vocabulary/packs - Packs faculty can choose from
+pub async fn list_packs(State(state): State<AppState>) -> Json<Vec<VocabularyPackSummary>> {
+    Json(
+        state
+            .vocabulary_packs
+            .packs()
+            .map(|p| VocabularyPackSummary {
+                id: p.id.clone(),
+                name: p.name.clone(),
+                domain: p.domain.clone(),
+                description: p.description.clone(),
+                term_count: p.terms.len(),
+            })
+            .collect(),
+    )
+}
+
+/// GET /api/vocabulary/packs/:id - A pack with all its terms
+pub async fn get_pack(
+    State(state): State<AppState>,
+    Path(pack_id): Path<String>,
+) -> Result<Json<VocabularyPack>> {
+    state
+        .vocabulary_packs
+        .get(&pack_id)
+        .cloned()
+        .map(Json)
+        .ok_or(AppError::NotFound)
+}
+
+/// PUT /api/courses/:course_id/vocabulary_pack - Choose a course's pack
+pub async fn assign_course_pack(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(course_id): Path<String>,
+    Json(payload): Json<AssignPackRequest>,
+) -> Result<Json<PackAssignment>> {
+    assign(&state, user, PackScope::Course, course_id, payload.pack_id).await
+}
+
+/// PUT /api/story_graphs/:id/vocabulary_pack - Choose a graph's pack (overrides the course's)
+pub async fn assign_graph_pack(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(graph_id): Path<i32>,
+    Json(payload): Json<AssignPackRequest>,
+) -> Result<Json<PackAssignment>> {
+    assign(
+        &state,
+        user,
+        PackScope::Graph,
+        graph_id.to_string(),
+        payload.pack_id,
+    )
+    .await
+}
+
+async fn assign(
+    state: &AppState,
+    user: AuthUser,
+    scope: PackScope,
+    scope_id: String,
+    pack_id: String,
+) -> Result<Json<PackAssignment>> {
+    user.require(UserRole::Instructor)?;
+    let pool = state.pool.as_ref().ok_or(AppError::InternalServerError)?;
+    if state.vocabulary_packs.get(&pack_id).is_none() {
+        return Err(AppError::ValidationError("Unknown vocabulary pack"));
+    }
+
+    assign_pack(pool, scope, &scope_id, &pack_id).await?;
+    log::info!(
+        "Instructor {} set {} {} to vocabulary pack '{}'",
+        user.user_id,
+        scope.as_str(),
+        scope_id,
+        pack_id
+    );
+    Ok(Json(PackAssignment {
+        scope,
+        scope_id,
+        pack_id,
+    }))
+}
@@ -0,0 +1,88 @@
+use crate::error::{AppError, Result};
+use crate::middleware::auth::AuthUser;
+use crate::state::AppState;
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use pete_core::UserRole;
+use serde::Serialize;
+use uuid::Uuid;
+
+#[derive(Serialize, sqlx::FromRow)]
+pub struct WellbeingAlertRow {
+    pub id: Uuid,
+    pub user_id: i64,
+    pub session_id: Uuid,
+    pub institution: String,
+    pub level: String,
+    pub categories: serde_json::Value,
+    pub crisis_score: f32,
+    pub distress_score: f32,
+    pub created_at: DateTime<Utc>,
+    pub acknowledged_by: Option<i64>,
+    pub acknowledged_at: Option<DateTime<Utc>>,
+}
+
+/// GET /api/wellbeing/alerts - Unacknowledged alerts first, instructors only
+pub async fn list_alerts(
+    State(state): State<AppState>,
+    user: AuthUser,
+) -> Result<Json<Vec<WellbeingAlertRow>>> {
+    user.require(UserRole::Instructor)?;
+    let pool = state.pool.as_ref().ok_or(AppError::InternalServerError)?;
+
+    let alerts = sqlx::query_as::<_, WellbeingAlertRow>(
+        r#"
+        SELECT id, user_id, session_id, institution, level, categories,
+               crisis_score, distress_score, created_at, acknowledged_by, acknowledged_at
+        FROM wellbeing_alerts
+        ORDER BY (acknowledged_by IS NOT NULL), created_at DESC
+        LIMIT 200
+        "#,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    log::info!(
+        target: "wellbeing",
+        "Instructor {} viewed {} wellbeing alerts",
+        user.user_id,
+        alerts.len()
+    );
+    Ok(Json(alerts))
+}
+
+/// POST /api/wellbeing/alerts/:id/acknowledge - Mark an alert as handled
+pub async fn acknowledge_alert(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(alert_id): Path<Uuid>,
+) -> Result<Json<WellbeingAlertRow>> {
+    user.require(UserRole::Instructor)?;
+    let pool = state.pool.as_ref().ok_or(AppError::InternalServerError)?;
+
+    let alert = sqlx::query_as::<_, WellbeingAlertRow>(
+        r#"
+        UPDATE wellbeing_alerts
+        SET acknowledged_by = $2, acknowledged_at = NOW()
+        WHERE id = $1
+        RETURNING id, user_id, session_id, institution, level, categories,
+                  crisis_score, distress_score, created_at, acknowledged_by, acknowledged_at
+        "#,
+    )
+    .bind(alert_id)
+    .bind(user.user_id)
+    .fetch_optional(pool)
+    .await?
+    .ok_or(AppError::NotFound)?;
+
+    state
+        .socratic_engine
+        .write()
+        .await
+        .acknowledge_wellbeing_alert(alert_id, user.user_id);
+
+    Ok(Json(alert))
+}
@@ -29,6 +29,7 @@ mod domain;
 mod error;
 mod handlers;
 mod middleware;
+mod plugins;
 mod repositories; // [NEW]
 mod routes;
 mod services;
@@ -46,6 +47,7 @@ use crate::routes::pete::pete_routes;
 use crate::routes::player::player_routes;
 use crate::routes::research::research_routes;
 use crate::routes::weigh_station_routes::weigh_station_routes;
+use crate::services::model_recommender::{load_warning, ModelRole};
 use crate::static_assets::Assets as StaticAssets;
 use axum::response::IntoResponse;
 use axum::Router;
@@ -61,6 +63,7 @@ use domain_physics::components::{
     CognitiveLoad,
     DownloadCommandInbox,
     DownloadProgressEvent,
+    DownloadQueueResource,
     EnginePower,
     Experience,
     Level,
@@ -106,6 +109,8 @@ fn run_bevy_app(
     shared_physics: SharedPhysicsResource,
     download_inbox: DownloadCommandInbox,
     download_state: SharedDownloadStateResource,
+    download_queue: DownloadQueueResource,
+    download_progress: DownloadChannel,
     pete_command_inbox: PeteCommandInbox,
     pete_response_outbox: PeteResponseOutbox,
     shared_campaign_state: SharedCampaignStateResource,
@@ -139,6 +144,8 @@ fn run_bevy_app(
     app.insert_resource(shared_physics);
     app.insert_resource(download_inbox);
     app.insert_resource(download_state);
+    app.insert_resource(download_queue);
+    app.insert_resource(download_progress);
     app.insert_resource(pete_command_inbox.clone());
     app.insert_resource(pete_response_outbox.clone());
     app.insert_resource(shared_campaign_state);
@@ -258,6 +265,92 @@ async fn static_handler(uri: axum::http::Uri) -> impl axum::response::IntoRespon
     }
 }
 
+/// Pick the sentence embedder for the vector store:
+/// `EMBEDDING_MODEL_DIR`, then the model cache (downloading MiniLM if missing),
+/// then the model-free hashing embedder.
+async fn load_embedder(
+    model_manager: &tokio::sync::Mutex<crate::services::model_manager::ModelManager>,
+) -> Arc<dyn infra_db::Embedder> {
+    use infra_ai::embeddings::{CandleEmbedder, EmbeddingConfig};
+
+    let model_id =
+        env::var("EMBEDDING_MODEL_ID").unwrap_or_else(|_| EmbeddingConfig::MINILM.to_string());
+
+    let config = match env::var("EMBEDDING_MODEL_DIR") {
+        Ok(dir) => Some(EmbeddingConfig::new(&model_id, dir)),
+        Err(_) => {
+            let manager = model_manager.lock().await;
+            match EmbeddingConfig::from_hf_cache(manager.cache_dir(), &model_id) {
+                Some(config) => Some(config),
+                None => manager
+                    .download_embedding_model(&model_id)
+                    .await
+                    .map_err(|e| eprintln!("⚠️ [Memory] Embedding model download failed: {}", e))
+                    .ok()
+                    .map(|dir| EmbeddingConfig::new(&model_id, dir)),
+            }
+        }
+    };
+
+    if let Some(config) = config {
+        match tokio::task::spawn_blocking(move || CandleEmbedder::load(config)).await {
+            Ok(Ok(embedder)) => return Arc::new(embedder),
+            Ok(Err(e)) => eprintln!("⚠️ [Memory] Failed to load embedding model: {}", e),
+            Err(e) => eprintln!("⚠️ [Memory] Embedding model loader panicked: {}", e),
+        }
+    }
+
+    println!("🧠 [Memory] Using hashing embedder (no sentence model available)");
+    Arc::new(infra_db::HashingEmbedder::default())
+}
+
+/// Optional cross-encoder for reranking hybrid search results. Off unless
+/// `RERANKER_MODEL_DIR` or `RERANKER_MODEL_ID` is set, since it costs a forward
+/// pass per candidate on every query.
+async fn load_reranker(
+    model_manager: &tokio::sync::Mutex<crate::services::model_manager::ModelManager>,
+) -> Option<Arc<dyn infra_ai::hybrid_retrieval::Reranker>> {
+    use infra_ai::reranker::{CrossEncoderReranker, RerankerConfig};
+
+    let model_id = env::var("RERANKER_MODEL_ID").ok();
+    let model_dir = match env::var("RERANKER_MODEL_DIR") {
+        Ok(dir) => std::path::PathBuf::from(dir),
+        Err(_) => {
+            let model_id = model_id.as_deref()?;
+            match model_manager
+                .lock()
+                .await
+                .download_embedding_model(model_id)
+                .await
+            {
+                Ok(dir) => dir,
+                Err(e) => {
+                    eprintln!("⚠️ [Memory] Reranker download failed: {}", e);
+                    return None;
+                }
+            }
+        }
+    };
+    let config = RerankerConfig::new(
+        model_id
+            .as_deref()
+            .unwrap_or(RerankerConfig::MS_MARCO_MINILM),
+        model_dir,
+    );
+
+    match tokio::task::spawn_blocking(move || CrossEncoderReranker::load(config)).await {
+        Ok(Ok(reranker)) => Some(Arc::new(reranker)),
+        Ok(Err(e)) => {
+            eprintln!("⚠️ [Memory] Failed to load reranker: {}", e);
+            None
+        }
+        Err(e) => {
+            eprintln!("⚠️ [Memory] Reranker loader panicked: {}", e);
+            None
+        }
+    }
+}
+
 #[tokio::main]
 async fn main() {
     dotenv::dotenv().ok();
@@ -325,6 +418,14 @@ async fn main() {
     let model_path = std::env::var("LOCAL_MODEL_PATH")
         .unwrap_or_else(|_| "assets/models/mistral-7b-instruct-v0.1.Q4_K_M.gguf".to_string());
 
+    // Loading a model bigger than free memory "works" but swaps every token
+    if let Some(warning) = load_warning(
+        std::path::Path::new(&model_path),
+        ModelRole::Navigator.context_tokens(),
+    ) {
+        println!("⚠️ Local AI Model {}", warning);
+    }
+
     let shared_local_model: Option<LocalModel> = match LocalModel::load(LocalConfigWrapper {
         model_path: std::path::PathBuf::from(&model_path),
         ..Default::default()
@@ -343,6 +444,12 @@ async fn main() {
     };
 
     // Initialize Iron Split System (Mistral 7B)
+    if let Some(warning) = load_warning(
+        std::path::Path::new("assets/models/mistral-7b-instruct-v0.1.Q4_K_M.gguf"),
+        ModelRole::Architect.context_tokens(),
+    ) {
+        println!("⚠️ Iron Split System {}", warning);
+    }
     let iron_split = match infra_ai::iron_split::IronSplitSystem::new() {
         Ok(system) => {
             println!("✅ Iron Split System (Mistral 7B) Loaded Successfully");
@@ -362,14 +469,37 @@ async fn main() {
             ConversationMemory::new_in_memory(100)
         }
     });
+    // Turns are written to conversation_turns in batches; flush partial ones regularly
+    conversation_memory.spawn_flusher(std::time::Duration::from_secs(2));
 
     let mut socratic_engine_instance = SocraticEngine::new(conversation_memory.clone());
     socratic_engine_instance.set_gemini_client(gemini_client);
 
-    // Initialize Antigravity Client (Enterprise Bridge)
+    // Initialize Antigravity Client (Enterprise Bridge). Steam awards go
+    // through the outbox; the worker delivers them once ANTIGRAVITY_SYNC_URL is set.
     let antigravity_client = infra_ai::antigravity::AntigravityClient::new();
+    if let Some(ref db_pool) = pool {
+        if antigravity_client.is_configured() {
+            infra_ai::antigravity::outbox::OutboxWorker::new(
+                db_pool.clone(),
+                antigravity_client.clone(),
+            )
+            .spawn();
+        } else {
+            println!("ANTIGRAVITY_SYNC_URL not set; Steam syncs will wait in the outbox");
+        }
+    }
     socratic_engine_instance.set_antigravity_client(antigravity_client);
 
+    // OpenAI-compatible inference server (llama.cpp, Ollama, vLLM) takes precedence when configured
+    if let Some(config) = infra_ai::llm::OpenAiConfig::from_env() {
+        println!(
+            "Using OpenAI-compatible backend at {} (model {})",
+            config.base_url, config.model
+        );
+        socratic_engine_instance.set_backend(Arc::new(infra_ai::llm::OpenAiBackend::new(config)));
+    }
+
     // Pass shared Local model to Socratic Engine
     if let Some(ref model) = shared_local_model {
         socratic_engine_instance.set_local_model(model.clone());
@@ -385,13 +515,56 @@ async fn main() {
         socratic_engine_instance.set_db_pool(db_pool.clone());
     }
 
+    // Wellbeing monitor: institution thresholds and support resources
+    let wellbeing_path = env::var("WELLBEING_CONFIG_PATH")
+        .unwrap_or_else(|_| "data/wellbeing/default.json".to_string());
+    match infra_ai::wellbeing::WellbeingConfig::load(&wellbeing_path) {
+        Ok(config) => socratic_engine_instance.set_wellbeing_config(config),
+        Err(e) => println!(
+            "Wellbeing config not loaded from {} ({}); using built-in resources",
+            wellbeing_path, e
+        ),
+    }
+
+    // Vocabulary packs: built-in physics plus faculty packs (JSON/CSV)
+    let vocabulary_dir =
+        env::var("VOCABULARY_PACKS_DIR").unwrap_or_else(|_| "data/vocabulary".to_string());
+    let vocabulary_packs = Arc::new(crate::services::vocabulary_packs::load_registry(
+        &vocabulary_dir,
+    ));
+    if let Some(ref db_pool) = pool {
+        match crate::services::vocabulary_packs::seed_registry(db_pool, &vocabulary_packs).await {
+            Ok(count) => println!("📚 [Vocabulary] Seeded {} pack words", count),
+            Err(e) => eprintln!("⚠️ [Vocabulary] Failed to seed packs: {}", e),
+        }
+    }
+    socratic_engine_instance.set_vocabulary_packs(vocabulary_packs.clone());
+
+    // Lore packs: the Iron Network plus faculty story worlds
+    let lore_dir = env::var("LORE_PACKS_DIR").unwrap_or_else(|_| "data/lore".to_string());
+    let lore_packs = Arc::new(crate::services::lore_packs::load_registry(&lore_dir));
+    socratic_engine_instance.set_lore_packs(lore_packs.clone());
+
+    // Plugins: narrative frameworks the Architect structures blueprints with
+    let plugins = Arc::new(crate::plugins::registry::PluginRegistry::with_defaults());
+
+    // One pool schedules every model call: chat > Mirror > Weigh Station > batch
+    let inference_pool =
+        infra_ai::worker_pool::InferencePool::new(infra_ai::worker_pool::PoolConfig::from_env());
+    let socratic_model_id = socratic_engine_instance.model_id();
+
     let socratic_engine = Arc::new(tokio::sync::RwLock::new(socratic_engine_instance));
 
     println!("AI Mirror Socratic Engine initialized and connected to Gemini 3 Ultra");
 
+    // Model downloads: resumable, checksummed, quota-limited, several at once.
+    // The model manager shares the store, so its downloads count against the
+    // same quota and reservations as the download queue's.
+    let model_store = infra_services::downloader::ModelStore::from_env("models");
     let model_manager = Arc::new(tokio::sync::Mutex::new(
         crate::services::model_manager::ModelManager::new()
-            .expect("Failed to initialize ModelManager"),
+            .expect("Failed to initialize ModelManager")
+            .with_store(model_store.clone()),
     ));
 
     // Auto-download "pete" model if missing
@@ -411,26 +584,89 @@ async fn main() {
         }
     }
 
+    // Replies to deterministic prompts (weighing, scenario review), memory then Postgres
+    let response_cache = Arc::new(crate::services::response_cache::ResponseCache::from_env(
+        pool.clone(),
+    ));
+    {
+        let cache = response_cache.clone();
+        tokio::spawn(async move {
+            match cache.purge_expired().await {
+                Ok(0) => {}
+                Ok(n) => println!("🧹 [AI Cache] Purged {} expired replies", n),
+                Err(e) => eprintln!("⚠️ [AI Cache] Failed to purge expired replies: {}", e),
+            }
+        });
+    }
+
     let pete_assistant = Arc::new(
-        crate::services::pete::PeteAssistant::new().expect("Failed to initialize PeteAssistant"),
+        crate::services::pete::PeteAssistant::new()
+            .expect("Failed to initialize PeteAssistant")
+            .with_engine(socratic_engine.clone())
+            .with_response_cache(response_cache.clone())
+            .with_worker_pool(inference_pool.clone()),
     );
 
-    // Initialize Local Vector DB (LanceDB)
-    let lancedb_path =
-        std::env::var("LANCEDB_PATH").unwrap_or_else(|_| "data/brain_vectors".to_string());
-    let memory_store = match infra_db::LanceDbConnection::new(&lancedb_path).await {
-        Ok(store) => {
+    // Initialize Local Vector DB (sentence embeddings computed on this machine)
+    let embedder = load_embedder(&model_manager).await;
+    let vector_store_path =
+        std::env::var("VECTOR_STORE_PATH").unwrap_or_else(|_| "data/brain_vectors".to_string());
+    // An index built by a different embedder is re-embedded from its stored
+    // texts, except onto the hashing fallback (the model failed to load this
+    // time): then the model's vectors are kept and search is keyword-only
+    let opened = {
+        let path = vector_store_path.clone();
+        tokio::task::spawn_blocking(move || {
+            infra_db::FileVectorStore::open_or_reindex(path, embedder)
+        })
+        .await
+        .unwrap_or_else(|e| Err(anyhow::anyhow!("Vector store loader panicked: {}", e)))
+    };
+    let retriever = match opened {
+        Ok(infra_db::OpenedIndex::Ready(store)) => {
             println!(
                 "🧠 [Memory] Local Vector DB initialized at '{}'",
-                lancedb_path
+                vector_store_path
             );
-            Some(Arc::new(store))
+            // BM25 keyword index over the same chunks, fused with vector search
+            let corpus = store.documents().await;
+            Some(infra_ai::hybrid_retrieval::HybridRetriever::new(
+                Arc::new(store),
+                corpus,
+            ))
+        }
+        Ok(infra_db::OpenedIndex::KeywordOnly {
+            model_id,
+            documents,
+        }) => {
+            eprintln!(
+                "⚠️ [Memory] Vector store at '{}' needs '{}', which didn't load; keyword search only, uploads disabled",
+                vector_store_path, model_id
+            );
+            Some(infra_ai::hybrid_retrieval::HybridRetriever::keyword_only(
+                documents,
+            ))
         }
         Err(e) => {
-            eprintln!("⚠️ [Memory] Failed to initialize LanceDB: {}", e);
+            eprintln!("⚠️ [Memory] Failed to open vector store: {}", e);
             None
         }
     };
+    let memory_store: Option<Arc<dyn infra_db::VectorStore>> = match retriever {
+        Some(mut retriever) => {
+            if let Some(reranker) = load_reranker(&model_manager).await {
+                retriever = retriever.with_reranker(reranker);
+            }
+            Some(Arc::new(retriever))
+        }
+        None => None,
+    };
+    if let Some(ref store) = memory_store {
+        socratic_engine
+            .write()
+            .await
+            .set_vector_store(store.clone());
+    }
 
     // Initialize Weigh Station & Shared Local Model
     let weigh_station = if let Some(db_pool) = pool.clone() {
@@ -439,7 +675,10 @@ async fn main() {
             crate::services::weigh_station::WeighStationService::new(
                 Some(db_pool),
                 shared_local_model.clone(),
-            ),
+            )
+            .with_vocabulary_packs(vocabulary_packs.clone())
+            .with_response_cache(response_cache.clone())
+            .with_worker_pool(inference_pool.clone()),
         ))
     } else {
         println!("⚠️ Database not available, using Heuristic Weigh Station.");
@@ -447,7 +686,10 @@ async fn main() {
             crate::services::weigh_station::WeighStationService::new(
                 None,
                 shared_local_model.clone(),
-            ),
+            )
+            .with_vocabulary_packs(vocabulary_packs.clone())
+            .with_response_cache(response_cache.clone())
+            .with_worker_pool(inference_pool.clone()),
         ))
     };
 
@@ -460,8 +702,42 @@ async fn main() {
         }
     };
 
-    // Initialize Chat Queue Service
-    let chat_queue = crate::services::chat_queue::ChatQueueService::new(socratic_engine.clone());
+    // Initialize Chat Queue Service (jobs persisted in chat_jobs, expired after CHAT_JOB_TTL_SECS)
+    let chat_queue = crate::services::chat_queue::ChatQueueService::new(
+        socratic_engine.clone(),
+        inference_pool.clone(),
+        socratic_model_id.clone(),
+        pool.clone(),
+    );
+    {
+        let queue = chat_queue.clone();
+        tokio::spawn(async move {
+            // Jobs left queued/running by a previous process will never finish
+            match queue.recover().await {
+                Ok(0) => {}
+                Ok(n) => println!("🧹 [Chat Queue] Marked {} interrupted jobs as failed", n),
+                Err(e) => eprintln!("⚠️ [Chat Queue] Failed to recover jobs: {}", e),
+            }
+            let mut interval = tokio::time::interval(std::time::Duration::from_secs(300));
+            loop {
+                interval.tick().await;
+                match queue.purge_expired().await {
+                    Ok(0) => {}
+                    Ok(n) => println!("🧹 [Chat Queue] Purged {} expired jobs", n),
+                    Err(e) => eprintln!("⚠️ [Chat Queue] Failed to purge expired jobs: {}", e),
+                }
+            }
+        });
+    }
+
+    let max_downloads = env::var("MODEL_DOWNLOAD_CONCURRENCY")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(2);
+    let (download_queue, download_progress) = infra_services::downloader::DownloadQueue::new(
+        infra_services::downloader::Downloader::new(model_store),
+        max_downloads,
+    );
 
     // --- Spawn Bevy Thread ---
     let state_clone = download_state.clone();
@@ -478,6 +754,7 @@ async fn main() {
     let log_clone = shared_research_log.clone();
     let virtues_clone = shared_virtues.clone();
     let inbox_clone = download_inbox.clone();
+    let download_queue_clone = download_queue.clone();
 
     // Capture the handle to the Tokio runtime
     let tokio_handle = tokio::runtime::Handle::current();
@@ -489,6 +766,8 @@ async fn main() {
             physics_clone,
             inbox_clone,
             state_clone,
+            DownloadQueueResource(download_queue_clone),
+            DownloadChannel(download_progress),
             pete_inbox_clone,
             pete_outbox_clone,
             campaign_clone,
@@ -524,7 +803,14 @@ async fn main() {
         quest_command_inbox,
         shared_graph_manager: shared_graph_manager.0, // [NEW]
         quest_repo,                                   // [NEW]
-                                                      // memory_store,
+        memory_store,
+        vocabulary_packs,
+        lore_packs,
+        plugins,
+        response_cache,
+        download_queue,
+        inference_pool,
+        socratic_model_id,
     };
 
     // Create Model App State
@@ -568,6 +854,14 @@ async fn main() {
         .nest("/api/weigh_station", weigh_station_routes())
         .merge(crate::routes::scenarios::scenarios_routes(&app_state))
         .merge(crate::routes::story_graphs::story_graph_routes(&app_state)) // [NEW] Story graph persistence
+        .merge(crate::routes::wellbeing::wellbeing_routes(&app_state))
+        .merge(crate::routes::vocabulary::vocabulary_routes(&app_state))
+        .merge(crate::routes::lore::lore_routes(&app_state))
+        .merge(crate::routes::plugins::plugin_routes(&app_state))
+        .merge(crate::routes::ai_cache::ai_cache_routes(&app_state))
+        .merge(crate::routes::ai_queue::ai_queue_routes(&app_state))
+        .merge(crate::routes::models::model_storage_routes(&app_state))
+        .merge(crate::routes::antigravity::antigravity_routes(&app_state))
         .merge(crate::routes::campaign_routes::campaign_routes())
         .merge(crate::routes::character_routes::character_routes(
             &app_state,
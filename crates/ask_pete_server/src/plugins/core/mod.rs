@@ -0,0 +1,6 @@
+pub mod traits {
+    pub trait AssessmentPlugin: Send + Sync {}
+    pub use pete_core::frameworks::NarrativeFramework;
+    pub trait NodeTypeExtension: Send + Sync {}
+    pub trait ThemeProvider: Send + Sync {}
+}
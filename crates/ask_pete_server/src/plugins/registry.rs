@@ -1,4 +1,5 @@
-use crate::core::traits::{AssessmentPlugin, NarrativeFramework, NodeTypeExtension, ThemeProvider};
+use super::core::traits::{AssessmentPlugin, NarrativeFramework, NodeTypeExtension, ThemeProvider};
+use pete_core::frameworks::builtin_frameworks;
 use std::collections::HashMap;
 use std::sync::Arc;
 
@@ -9,18 +10,18 @@ use std::sync::Arc;
 ///
 /// # Example
 ///
-/// ```rust
+/// ```rust,ignore
 /// let mut registry = PluginRegistry::new();
 ///
 /// // Register a narrative framework
 /// registry.register_framework(
 ///     "heros_journey",
-///     Box::new(HerosJourneyFramework::new())
+///     Arc::new(StagedFramework::heros_journey())
 /// );
 ///
 /// // Later, retrieve it
 /// if let Some(framework) = registry.get_framework("heros_journey") {
-///     let stages = framework.get_stages();
+///     let stages = framework.stages();
 /// }
 /// ```
 #[derive(Default)]
@@ -64,6 +65,13 @@ impl PluginRegistry {
         self.narrative_frameworks.keys().cloned().collect()
     }
 
+    /// All registered narrative frameworks, ordered by ID
+    pub fn frameworks(&self) -> Vec<Arc<dyn NarrativeFramework>> {
+        let mut frameworks: Vec<_> = self.narrative_frameworks.values().cloned().collect();
+        frameworks.sort_by(|a, b| a.id().cmp(b.id()));
+        frameworks
+    }
+
     // ========================================================================
     // THEMES
     // ========================================================================
@@ -130,20 +138,16 @@ impl PluginRegistry {
 
     /// Load all built-in plugins
     ///
-    /// This will be expanded as we create built-in plugins:
-    /// - Hero's Journey framework
-    /// - Freytag's Pyramid framework
-    /// - Blank framework
-    /// - Anime theme
-    /// - Military theme
-    /// - Fantasy theme
+    /// Currently the narrative frameworks from `pete_core::frameworks`
+    /// (Hero's Journey, Freytag's Pyramid, Kishōtenketsu). Themes,
+    /// assessments and node extensions will follow.
     fn load_builtin_plugins(&mut self) {
         log::info!("Loading built-in plugins...");
 
-        // TODO: Register built-in plugins here as they're implemented
-        // Example:
-        // self.register_framework("heros_journey", Arc::new(HerosJourneyFramework::new()));
-        // self.register_theme("anime", Arc::new(AnimeTheme::new()));
+        for framework in builtin_frameworks() {
+            let id = framework.id.clone();
+            self.register_framework(&id, Arc::new(framework));
+        }
 
         log::info!("Built-in plugins loaded");
     }
@@ -174,4 +178,21 @@ mod tests {
         assert!(registry.list_themes().is_empty());
         assert!(registry.list_assessments().is_empty());
     }
+
+    #[test]
+    fn test_defaults_register_builtin_frameworks() {
+        let registry = PluginRegistry::with_defaults();
+        let ids: Vec<String> = registry
+            .frameworks()
+            .iter()
+            .map(|f| f.id().to_string())
+            .collect();
+        assert_eq!(ids, vec!["freytag", "heros_journey", "kishotenketsu"]);
+
+        let skeleton = registry
+            .get_framework("heros_journey")
+            .unwrap()
+            .skeleton("Photosynthesis");
+        assert_eq!(skeleton.nodes.len(), 5);
+    }
 }
@@ -1,5 +1,7 @@
 use crate::error::{AppError, Result};
 use async_trait::async_trait;
+use infra_ai::antigravity::{outbox, SteamSyncEvent};
+use pete_core::economy::Steam;
 use pete_core::expert::StoryGraph;
 use sqlx::{PgPool, Row};
 
@@ -74,6 +76,11 @@ impl QuestRepository for PostgresQuestRepository {
         .execute(&mut *tx)
         .await?;
 
+        // 3. Queue the Antigravity sync alongside the balance change
+        let event =
+            SteamSyncEvent::new(user_id, Steam(steam_earned), &format!("quest:{}", quest_id));
+        outbox::enqueue(&mut tx, &event).await?;
+
         tx.commit().await?;
 
         Ok(new_balance)
@@ -0,0 +1,15 @@
+use crate::handlers::ai_cache::{cache_stats, invalidate_cache, invalidate_cache_entry};
+use crate::AppState;
+use axum::{
+    routing::{delete, get},
+    Router,
+};
+
+/// AI response cache metrics and invalidation. Instructor-only, enforced per handler by `AuthUser`.
+pub fn ai_cache_routes(state: &AppState) -> Router<AppState> {
+    Router::new()
+        .route("/api/ai_cache", delete(invalidate_cache))
+        .route("/api/ai_cache/stats", get(cache_stats))
+        .route("/api/ai_cache/:key", delete(invalidate_cache_entry))
+        .with_state(state.clone())
+}
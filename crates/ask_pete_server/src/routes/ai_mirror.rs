@@ -1,4 +1,6 @@
-use crate::handlers::ai_mirror::{handle_create_session, handle_get_history, handle_send_message};
+use crate::handlers::ai_mirror::{
+    handle_create_session, handle_delete_session, handle_get_history, handle_send_message,
+};
 use crate::AppState;
 use axum::{routing::post, Router};
 
@@ -7,4 +9,5 @@ pub fn ai_mirror_routes() -> Router<AppState> {
         .route("/create-session", post(handle_create_session))
         .route("/send-message", post(handle_send_message))
         .route("/get-history", post(handle_get_history))
+        .route("/delete-session", post(handle_delete_session))
 }
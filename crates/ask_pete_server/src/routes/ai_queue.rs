@@ -0,0 +1,10 @@
+use crate::handlers::ai_queue::queue_stats;
+use crate::AppState;
+use axum::{routing::get, Router};
+
+/// Inference pool metrics. Instructor-only, enforced by `AuthUser` in the handler.
+pub fn ai_queue_routes(state: &AppState) -> Router<AppState> {
+    Router::new()
+        .route("/api/ai_queue/stats", get(queue_stats))
+        .with_state(state.clone())
+}
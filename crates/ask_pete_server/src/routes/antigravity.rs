@@ -0,0 +1,20 @@
+use crate::handlers::antigravity::{list_dead_letters, requeue_dead_letter};
+use crate::AppState;
+use axum::{
+    routing::{get, post},
+    Router,
+};
+
+/// Antigravity Steam sync dead-letter view. Instructor-only, enforced per handler by `AuthUser`.
+pub fn antigravity_routes(state: &AppState) -> Router<AppState> {
+    Router::new()
+        .route(
+            "/api/antigravity/outbox/dead_letters",
+            get(list_dead_letters),
+        )
+        .route(
+            "/api/antigravity/outbox/:id/requeue",
+            post(requeue_dead_letter),
+        )
+        .with_state(state.clone())
+}
@@ -1,13 +1,32 @@
 // Knowledge Base / RAG Routes
-use crate::handlers::knowledge::{search_knowledge, upload_knowledge};
+use crate::handlers::knowledge::{
+    delete_document, get_document, list_documents, reindex_document, search_knowledge,
+    upload_knowledge,
+};
 use crate::state::AppState;
 use axum::{
+    extract::DefaultBodyLimit,
     routing::{get, post},
     Router,
 };
 
+/// Instructor documents (PDFs, slide exports) are well over axum's 2 MB default
+const MAX_UPLOAD_BYTES: usize = 25 * 1024 * 1024;
+
 pub fn knowledge_routes() -> Router<AppState> {
     Router::new()
-        .route("/api/knowledge/upload", post(upload_knowledge))
+        .route(
+            "/api/knowledge/upload",
+            post(upload_knowledge).layer(DefaultBodyLimit::max(MAX_UPLOAD_BYTES)),
+        )
         .route("/api/knowledge/search", post(search_knowledge))
+        .route("/api/knowledge/documents", get(list_documents))
+        .route(
+            "/api/knowledge/documents/:id",
+            get(get_document).delete(delete_document),
+        )
+        .route(
+            "/api/knowledge/documents/:id/reindex",
+            post(reindex_document),
+        )
 }
@@ -0,0 +1,17 @@
+use crate::handlers::lore::{assign_campaign_pack, assign_graph_pack, get_pack, list_packs};
+use crate::AppState;
+use axum::{
+    routing::{get, put},
+    Router,
+};
+
+/// Lore pack catalogue and per-campaign / per-graph selection.
+/// Assignments are instructor-only, enforced per handler by `AuthUser`.
+pub fn lore_routes(state: &AppState) -> Router<AppState> {
+    Router::new()
+        .route("/api/lore/packs", get(list_packs))
+        .route("/api/lore/packs/:id", get(get_pack))
+        .route("/api/campaigns/:id/lore_pack", put(assign_campaign_pack))
+        .route("/api/story_graphs/:id/lore_pack", put(assign_graph_pack))
+        .with_state(state.clone())
+}
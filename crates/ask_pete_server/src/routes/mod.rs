@@ -1,19 +1,27 @@
 // pub mod ai;
+pub mod ai_cache;
 pub mod ai_mirror;
+pub mod ai_queue;
+pub mod antigravity;
 pub mod architect; // [NEW] Blueprint AI generation
 pub mod expert;
 pub mod knowledge; // [NEW] - RAG Knowledge Base routes
+pub mod lore;
 pub mod persona;
 pub mod player;
+pub mod plugins;
 pub mod research;
 // pub mod vaam;
 pub mod campaign_routes;
 pub mod character_routes;
 // pub mod debug;
 pub mod model_routes;
+pub mod models;
 pub mod pete; // [NEW]
 pub mod recharge;
 pub mod scenarios;
 pub mod simulation; // [NEW] // [NEW] // [NEW]
 pub mod story_graphs; // [NEW] Story graph persistence
+pub mod vocabulary;
 pub mod weigh_station_routes; // Enabled // [NEW] // [NEW]
+pub mod wellbeing;
@@ -0,0 +1,28 @@
+use crate::handlers::models::{
+    cancel_download, delete_cached, delete_installed, disk_usage, list_downloads, list_installed,
+    recommendations, verify_installed,
+};
+use crate::AppState;
+use axum::{
+    routing::{delete, get, post},
+    Router,
+};
+
+/// Model storage: download queue, installed files, disk usage and hardware
+/// recommendations. Reads are public like `/api/models/progress`; cancel,
+/// delete and verify are instructor-only.
+pub fn model_storage_routes(state: &AppState) -> Router<AppState> {
+    Router::new()
+        .route("/api/models/downloads", get(list_downloads))
+        .route("/api/models/downloads/:id", delete(cancel_download))
+        .route("/api/models/installed", get(list_installed))
+        .route("/api/models/installed/:filename", delete(delete_installed))
+        .route(
+            "/api/models/installed/:filename/verify",
+            post(verify_installed),
+        )
+        .route("/api/models/cache/:alias", delete(delete_cached))
+        .route("/api/models/disk", get(disk_usage))
+        .route("/api/models/recommendations", get(recommendations))
+        .with_state(state.clone())
+}
@@ -1,7 +1,11 @@
+use crate::error::{AppError, Result};
+use crate::middleware::auth::AuthUser;
+use crate::services::chat_queue::{ChatJob, ChatScope};
 use crate::services::model_manager::ModelDefinition;
 use crate::AppState;
 use axum::{
     extract::{Json, Path, State},
+    http::StatusCode,
     response::IntoResponse,
     routing::{get, post},
     Router,
@@ -14,7 +18,10 @@ pub fn pete_routes(state: &AppState) -> Router<AppState> {
         .route("/api/pete/models", get(list_models))
         .route("/api/pete/models/download", post(download_model))
         .route("/api/pete/chat", post(submit_chat)) // [MODIFIED] Async Submit
-        .route("/api/pete/chat/:job_id", get(check_chat)) // [NEW] Poll Status
+        .route(
+            "/api/pete/chat/:job_id",
+            get(check_chat).delete(cancel_chat),
+        )
         .with_state(state.clone())
 }
 
@@ -64,37 +71,132 @@ async fn download_model(
 #[derive(Deserialize)]
 struct ChatRequest {
     message: String,
+    /// Continue an earlier conversation; defaults to the user's latest session
+    #[serde(default)]
+    session_id: Option<Uuid>,
+    /// Story graph and station the learner is on, so Pete won't give away its answer
+    #[serde(default)]
+    graph_id: Option<i32>,
+    #[serde(default)]
+    node_id: Option<String>,
+    /// Limits Pete's knowledge retrieval to this course's uploads
+    #[serde(default)]
+    course_id: Option<String>,
+    /// Campaign whose story world Pete speaks from
+    #[serde(default)]
+    campaign_id: Option<Uuid>,
 }
 
 // 1. Submit (Fast)
 async fn submit_chat(
     State(state): State<AppState>,
+    user: AuthUser,
     Json(payload): Json<ChatRequest>,
-) -> impl IntoResponse {
-    // Immediately enqueue and return the Ticket ID
-    let job_id = state.chat_queue.enqueue(payload.message).await;
+) -> Result<impl IntoResponse> {
+    if payload.message.trim().is_empty() {
+        return Err(AppError::ValidationError("Message must not be empty"));
+    }
+
+    let session_id = match payload.session_id {
+        Some(id) => id,
+        None => state
+            .chat_queue
+            .latest_session(user.user_id)
+            .await?
+            .unwrap_or_else(Uuid::new_v4),
+    };
+    if let Some(owner) = state.chat_queue.session_owner(session_id).await? {
+        if owner != user.user_id {
+            return Err(AppError::Forbidden);
+        }
+    }
+
+    // Look up the current station's answer key for the guardrail
+    let answer_key = match (state.pool.as_ref(), payload.graph_id, &payload.node_id) {
+        (Some(pool), Some(graph_id), Some(node_id)) => {
+            crate::handlers::ai_mirror::load_answer_key(pool, graph_id, node_id).await
+        }
+        _ => None,
+    };
+    // Graph's vocabulary pack, else the course's, else the default
+    let vocabulary_pack = match state.pool.as_ref() {
+        Some(pool) => crate::services::vocabulary_packs::resolve_pack_id(
+            pool,
+            payload.graph_id,
+            payload.course_id.as_deref(),
+        )
+        .await
+        .unwrap_or_else(|e| {
+            log::warn!("Failed to resolve vocabulary pack: {}", e);
+            None
+        }),
+        None => None,
+    };
+    // Graph's lore pack, else the campaign's, else the Iron Network
+    let lore_pack = match state.pool.as_ref() {
+        Some(pool) => crate::services::lore_packs::resolve_pack_id(
+            pool,
+            payload.graph_id,
+            payload.campaign_id,
+        )
+        .await
+        .unwrap_or_else(|e| {
+            log::warn!("Failed to resolve lore pack: {}", e);
+            None
+        }),
+        None => None,
+    };
+    let scope = ChatScope {
+        answer_key,
+        course_id: payload.course_id,
+        vocabulary_pack,
+        lore_pack,
+    };
+
+    // Immediately enqueue and return the Ticket ID (503 + Retry-After if saturated)
+    let job = state
+        .chat_queue
+        .enqueue(user.user_id, session_id, payload.message, scope)
+        .await?;
 
     // Return 202 Accepted
-    (
-        axum::http::StatusCode::ACCEPTED,
+    Ok((
+        StatusCode::ACCEPTED,
         Json(serde_json::json!({
-            "job_id": job_id,
-            "status": "Queued",
+            "job_id": job.id,
+            "session_id": job.session_id,
+            "status": job.status,
             "message": "Pete is thinking..."
         })),
-    )
+    ))
 }
 
 // 2. Poll (Fast)
-async fn check_chat(State(state): State<AppState>, Path(job_id): Path<Uuid>) -> impl IntoResponse {
-    match state.chat_queue.get_status(&job_id) {
-        Some(status) => Json::<crate::services::chat_queue::JobStatus>(status).into_response(),
-        None => (
-            axum::http::StatusCode::NOT_FOUND,
-            Json(serde_json::json!({"error": "Job not found"})),
-        )
-            .into_response(),
-    }
+async fn check_chat(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(job_id): Path<Uuid>,
+) -> Result<Json<ChatJob>> {
+    state
+        .chat_queue
+        .get(job_id, user.user_id)
+        .await?
+        .map(Json)
+        .ok_or(AppError::NotFound)
+}
+
+// 3. Cancel - queued or running jobs stop; finished jobs are returned unchanged
+async fn cancel_chat(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(job_id): Path<Uuid>,
+) -> Result<Json<ChatJob>> {
+    state
+        .chat_queue
+        .cancel(job_id, user.user_id)
+        .await?
+        .map(Json)
+        .ok_or(AppError::NotFound)
 }
 
 #[derive(Serialize)]
@@ -0,0 +1,15 @@
+use crate::handlers::plugins::{framework_skeleton, get_framework, list_frameworks};
+use crate::AppState;
+use axum::{routing::get, Router};
+
+/// Plugin catalogue. Narrative frameworks are read-only and public, like the pack catalogues.
+pub fn plugin_routes(state: &AppState) -> Router<AppState> {
+    Router::new()
+        .route("/api/plugins/frameworks", get(list_frameworks))
+        .route("/api/plugins/frameworks/:id", get(get_framework))
+        .route(
+            "/api/plugins/frameworks/:id/skeleton",
+            get(framework_skeleton),
+        )
+        .with_state(state.clone())
+}
@@ -1,11 +1,16 @@
 use crate::error::{AppError, Result};
+use crate::middleware::auth::AuthUser;
+use crate::services::pete::{PeteSuggestion, ScenarioData};
+use crate::services::response_cache::CachePolicy;
 use crate::AppState;
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     routing::{get, post, put},
     Json, Router,
 };
 use pete_core::expert::StoryGraph;
+use pete_core::layout::{AutoLayout, LayoutConfig, LayoutSummary};
+use pete_core::UserRole;
 use serde::{Deserialize, Serialize};
 use sqlx::types::JsonValue;
 
@@ -55,6 +60,11 @@ pub fn story_graph_routes(state: &AppState) -> Router<AppState> {
             "/api/story_graphs/:id",
             get(get_story_graph).put(update_story_graph),
         )
+        .route("/api/story_graphs/:id/layout", post(layout_story_graph))
+        .route(
+            "/api/story_graphs/:id/suggestions",
+            get(story_graph_suggestions),
+        )
         .with_state(state.clone())
 }
 
@@ -215,3 +225,125 @@ async fn update_story_graph(
         updated_at: row.updated_at,
     }))
 }
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LayoutStoryGraphResponse {
+    pub graph: StoryGraphResponse,
+    pub summary: LayoutSummary,
+}
+
+/// POST /api/story_graphs/:id/layout - Auto-arrange a stored graph's stations
+async fn layout_story_graph(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+) -> Result<Json<LayoutStoryGraphResponse>> {
+    let pool = state.pool.as_ref().ok_or(AppError::InternalServerError)?;
+
+    let row = sqlx::query_as::<_, StoryGraphRow>(
+        r#"
+        SELECT id, title, subject, literary_device, focus, vocabulary, graph_data, created_at, updated_at
+        FROM story_graphs
+        WHERE id = $1
+        "#,
+    )
+    .bind(id)
+    .fetch_one(pool)
+    .await?;
+
+    let mut graph_data: StoryGraph = serde_json::from_value(row.graph_data)
+        .map_err(|e| anyhow::anyhow!("Failed to deserialize graph: {}", e))?;
+
+    let summary = graph_data.auto_layout(&LayoutConfig::default());
+
+    let graph_json = serde_json::to_value(&graph_data)
+        .map_err(|e| anyhow::anyhow!("Failed to serialize graph: {}", e))?;
+
+    let row = sqlx::query_as::<_, StoryGraphRow>(
+        r#"
+        UPDATE story_graphs
+        SET graph_data = $1
+        WHERE id = $2
+        RETURNING id, title, subject, literary_device, focus, vocabulary, graph_data, created_at, updated_at
+        "#,
+    )
+    .bind(&graph_json)
+    .bind(id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(Json(LayoutStoryGraphResponse {
+        graph: StoryGraphResponse {
+            id: row.id,
+            title: row.title,
+            subject: row.subject,
+            literary_device: row.literary_device,
+            focus: row.focus,
+            vocabulary: row.vocabulary,
+            graph_data,
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+        },
+        summary,
+    }))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StoryGraphSuggestionsResponse {
+    pub graph_id: i32,
+    pub suggestions: Vec<PeteSuggestion>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SuggestionsQuery {
+    /// Ask the model again and replace the cached review
+    #[serde(default)]
+    pub fresh: bool,
+}
+
+/// GET /api/story_graphs/:id/suggestions?fresh= - Pete's pedagogical review of a stored graph
+///
+/// Instructor-only: the review may run a model generation.
+async fn story_graph_suggestions(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(id): Path<i32>,
+    Query(query): Query<SuggestionsQuery>,
+) -> Result<Json<StoryGraphSuggestionsResponse>> {
+    user.require(UserRole::Instructor)?;
+    let pool = state.pool.as_ref().ok_or(AppError::InternalServerError)?;
+
+    let row = sqlx::query_as::<_, StoryGraphRow>(
+        r#"
+        SELECT id, title, subject, literary_device, focus, vocabulary, graph_data, created_at, updated_at
+        FROM story_graphs
+        WHERE id = $1
+        "#,
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await?
+    .ok_or(AppError::NotFound)?;
+
+    let graph: StoryGraph = serde_json::from_value(row.graph_data)
+        .map_err(|e| anyhow::anyhow!("Failed to deserialize graph: {}", e))?;
+
+    let scenario = ScenarioData {
+        graph,
+        vocabulary: row.vocabulary,
+        framework: row.literary_device,
+    };
+    let policy = if query.fresh {
+        CachePolicy::Refresh
+    } else {
+        CachePolicy::Use
+    };
+    let suggestions = state
+        .pete_assistant
+        .analyze_scenario(&scenario, policy)
+        .await?;
+
+    Ok(Json(StoryGraphSuggestionsResponse {
+        graph_id: row.id,
+        suggestions,
+    }))
+}
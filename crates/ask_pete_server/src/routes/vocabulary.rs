@@ -0,0 +1,23 @@
+use crate::handlers::vocabulary::{assign_course_pack, assign_graph_pack, get_pack, list_packs};
+use crate::AppState;
+use axum::{
+    routing::{get, put},
+    Router,
+};
+
+/// Vocabulary pack catalogue and per-course / per-graph selection.
+/// Assignments are instructor-only, enforced per handler by `AuthUser`.
+pub fn vocabulary_routes(state: &AppState) -> Router<AppState> {
+    Router::new()
+        .route("/api/vocabulary/packs", get(list_packs))
+        .route("/api/vocabulary/packs/:id", get(get_pack))
+        .route(
+            "/api/courses/:course_id/vocabulary_pack",
+            put(assign_course_pack),
+        )
+        .route(
+            "/api/story_graphs/:id/vocabulary_pack",
+            put(assign_graph_pack),
+        )
+        .with_state(state.clone())
+}
@@ -1,3 +1,4 @@
+use crate::error::AppError;
 use crate::services::weigh_station::{WeighStationService, WordPhysics};
 use crate::AppState;
 use axum::{
@@ -6,6 +7,7 @@ use axum::{
     routing::post,
     Router,
 };
+use infra_ai::worker_pool::PoolError;
 use serde::Deserialize;
 
 #[derive(Deserialize)]
@@ -32,9 +34,13 @@ async fn weigh_word(
         // No lock needed for Arc<WeighStationService> as it uses internal mutability (PgPool) or immutable state
         match station.weigh_word(&payload.word).await {
             Ok(physics) => Json::<WordPhysics>(physics).into_response(),
-            Err(e) => {
-                (axum::http::StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response()
-            }
+            // Saturated pool: 503 with Retry-After instead of a generic failure
+            Err(e) => match e.downcast_ref::<PoolError>() {
+                Some(busy) => AppError::from(busy.clone()).into_response(),
+                None => {
+                    (axum::http::StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response()
+                }
+            },
         }
     } else {
         (
@@ -0,0 +1,17 @@
+use crate::handlers::wellbeing::{acknowledge_alert, list_alerts};
+use crate::AppState;
+use axum::{
+    routing::{get, post},
+    Router,
+};
+
+/// Instructor-only wellbeing alert routes. Access is enforced per handler by `AuthUser`.
+pub fn wellbeing_routes(state: &AppState) -> Router<AppState> {
+    Router::new()
+        .route("/api/wellbeing/alerts", get(list_alerts))
+        .route(
+            "/api/wellbeing/alerts/:id/acknowledge",
+            post(acknowledge_alert),
+        )
+        .with_state(state.clone())
+}
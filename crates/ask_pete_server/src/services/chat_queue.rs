@@ -1,109 +1,633 @@
 use crate::services::pete::PeteResponse;
-use infra_ai::socratic_engine::SocraticEngine;
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use infra_ai::guardrail::AnswerKey;
+use infra_ai::socratic_engine::{SessionContext, SocraticEngine};
+use infra_ai::worker_pool::{InferencePool, JobSpec, PoolError, Priority, Ticket};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
 use std::collections::HashMap;
 use std::sync::{Arc, RwLock};
-use tokio::sync::mpsc;
+use tokio::task::AbortHandle;
 use uuid::Uuid;
 
 // 1. Define the States
-#[derive(Debug, Clone, serde::Serialize)]
-#[serde(tag = "status", content = "data")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum JobStatus {
     Queued,
-    Processing,
-    Completed(PeteResponse),
-    Failed(String),
+    Running,
+    Done,
+    Failed,
+    Cancelled,
 }
 
-struct ChatJob {
+impl JobStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            JobStatus::Queued => "queued",
+            JobStatus::Running => "running",
+            JobStatus::Done => "done",
+            JobStatus::Failed => "failed",
+            JobStatus::Cancelled => "cancelled",
+        }
+    }
+
+    fn parse(status: &str) -> Option<Self> {
+        match status {
+            "queued" => Some(JobStatus::Queued),
+            "running" => Some(JobStatus::Running),
+            "done" => Some(JobStatus::Done),
+            "failed" => Some(JobStatus::Failed),
+            "cancelled" => Some(JobStatus::Cancelled),
+            _ => None,
+        }
+    }
+
+    /// Finished jobs never change again and expire after the TTL
+    pub fn is_finished(self) -> bool {
+        matches!(
+            self,
+            JobStatus::Done | JobStatus::Failed | JobStatus::Cancelled
+        )
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ChatJob {
+    pub id: Uuid,
+    pub user_id: i64,
+    pub session_id: Uuid,
+    pub message: String,
+    pub status: JobStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response: Option<PeteResponse>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(sqlx::FromRow)]
+struct ChatJobRow {
     id: Uuid,
+    user_id: i64,
+    session_id: Uuid,
     message: String,
-    // Add user_id or context here if needed
+    status: String,
+    response: Option<serde_json::Value>,
+    error: Option<String>,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+    expires_at: DateTime<Utc>,
+}
+
+impl From<ChatJobRow> for ChatJob {
+    fn from(row: ChatJobRow) -> Self {
+        Self {
+            id: row.id,
+            user_id: row.user_id,
+            session_id: row.session_id,
+            message: row.message,
+            status: JobStatus::parse(&row.status).unwrap_or(JobStatus::Failed),
+            response: row
+                .response
+                .and_then(|value| serde_json::from_value(value).ok()),
+            error: row.error,
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+            expires_at: row.expires_at,
+        }
+    }
+}
+
+/// Where the learner is chatting from, resolved by the route before queueing
+#[derive(Debug, Clone, Default)]
+pub struct ChatScope {
+    /// Answer key of the current station, so the guardrail can stop leaks
+    pub answer_key: Option<AnswerKey>,
+    /// Limits knowledge retrieval to this course's uploads
+    pub course_id: Option<String>,
+    /// Vocabulary pack of the graph or course (`None` for the default pack)
+    pub vocabulary_pack: Option<String>,
+    /// Lore pack of the graph or campaign (`None` for the Iron Network)
+    pub lore_pack: Option<String>,
 }
 
 // 2. The Service Struct
+/// Pete chat jobs: scheduled on the shared inference pool at
+/// `Priority::Interactive`, kept in memory for polling and, with a database,
+/// in `chat_jobs` so status survives restarts. Finished jobs expire after the TTL.
 #[derive(Clone)]
 pub struct ChatQueueService {
-    sender: mpsc::Sender<ChatJob>,
-    results: Arc<RwLock<HashMap<Uuid, JobStatus>>>,
+    engine: Arc<tokio::sync::RwLock<SocraticEngine>>,
+    pool: InferencePool,
+    model_id: String,
+    db: Option<PgPool>,
+    ttl: Duration,
+    jobs: Arc<RwLock<HashMap<Uuid, ChatJob>>>,
+    tasks: Arc<RwLock<HashMap<Uuid, AbortHandle>>>,
 }
 
 impl ChatQueueService {
-    pub fn new(engine: Arc<tokio::sync::RwLock<SocraticEngine>>) -> Self {
-        let (sender, mut receiver) = mpsc::channel::<ChatJob>(100); // Buffer of 100 jobs
-        let results = Arc::new(RwLock::new(HashMap::new()));
-        let results_clone = results.clone();
-
-        // 3. Spawn the Background Worker
-        tokio::spawn(async move {
-            println!("🤖 Chat Queue Worker Started...");
-            while let Some(job) = receiver.recv().await {
-                // A. Mark as Processing
-                {
-                    let mut map = results_clone.write().unwrap();
-                    map.insert(job.id, JobStatus::Processing);
-                }
+    pub const DEFAULT_TTL_SECS: i64 = 60 * 60;
+
+    /// TTL from `CHAT_JOB_TTL_SECS`, default one hour
+    pub fn new(
+        engine: Arc<tokio::sync::RwLock<SocraticEngine>>,
+        pool: InferencePool,
+        model_id: String,
+        db: Option<PgPool>,
+    ) -> Self {
+        let ttl_secs = std::env::var("CHAT_JOB_TTL_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(Self::DEFAULT_TTL_SECS);
+        Self {
+            engine,
+            pool,
+            model_id,
+            db,
+            ttl: Duration::seconds(ttl_secs),
+            jobs: Arc::new(RwLock::new(HashMap::new())),
+            tasks: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
 
-                // B. Perform the Heavy Lifting (AI Inference)
-                // We lock the engine only for the duration of this specific generation
-                let response = {
-                    let mut engine_guard = engine.write().await;
-                    // Mock context for now
-                    let context = infra_ai::socratic_engine::SessionContext {
-                        session_id: job.id,
-                        user_id: 1,
-                        archetype: None,
-                        focus_area: Some("chat".to_string()),
-                    };
-                    engine_guard.respond(&job.message, &context).await
+    /// Take a ticket for the message, or refuse at once if chat is saturated.
+    /// The reply goes into `session_id`, so turns carry over between messages.
+    pub async fn enqueue(
+        &self,
+        user_id: i64,
+        session_id: Uuid,
+        message: String,
+        scope: ChatScope,
+    ) -> std::result::Result<ChatJob, PoolError> {
+        let ticket = self.pool.enqueue(
+            JobSpec::new(Priority::Interactive, self.model_id.clone()).for_user(user_id),
+        )?;
+
+        let now = Utc::now();
+        let job = ChatJob {
+            id: Uuid::new_v4(),
+            user_id,
+            session_id,
+            message,
+            status: JobStatus::Queued,
+            response: None,
+            error: None,
+            created_at: now,
+            updated_at: now,
+            expires_at: now + self.ttl,
+        };
+        self.jobs.write().unwrap().insert(job.id, job.clone());
+        if let Some(db) = &self.db {
+            if let Err(e) = insert_job(db, &job).await {
+                log::warn!("Failed to persist chat job {}: {}", job.id, e);
+            }
+        }
+
+        // 3. Run once the pool hands us a worker slot
+        let task = tokio::spawn(self.clone().run(job.clone(), scope, ticket));
+        self.tasks
+            .write()
+            .unwrap()
+            .insert(job.id, task.abort_handle());
+        if task.is_finished() {
+            // Finished before we could record it; don't keep a stale handle
+            self.tasks.write().unwrap().remove(&job.id);
+        }
+
+        Ok(job)
+    }
+
+    async fn run(self, job: ChatJob, scope: ChatScope, ticket: Ticket) {
+        let _slot = ticket.wait().await;
+
+        // A. Mark as Running (unless it was cancelled while queued)
+        if !self
+            .transition(job.id, &[JobStatus::Queued], JobStatus::Running, None, None)
+            .await
+        {
+            return;
+        }
+
+        // B. Perform the Heavy Lifting (AI Inference)
+        // We lock the engine only for the duration of this specific generation
+        let context = SessionContext {
+            session_id: job.session_id,
+            user_id: job.user_id,
+            archetype: None,
+            focus_area: Some("chat".to_string()),
+            answer_key: scope.answer_key,
+            course_id: scope.course_id,
+            vocabulary_pack: scope.vocabulary_pack,
+            lore_pack: scope.lore_pack,
+        };
+        let response = {
+            let mut engine_guard = self.engine.write().await;
+            engine_guard.respond(&job.message, &context).await
+        };
+
+        // C. Save Result
+        match response {
+            Ok(data) => {
+                // Convert SocraticResponse to PeteResponse
+                let pete_response = PeteResponse {
+                    answer: data.text,
+                    citations: data.citations,
+                    confidence: 1.0, // TODO: Get confidence
+                    suggestions: vec![],
                 };
+                self.transition(
+                    job.id,
+                    &[JobStatus::Running],
+                    JobStatus::Done,
+                    Some(pete_response),
+                    None,
+                )
+                .await;
+            }
+            Err(e) => {
+                self.transition(
+                    job.id,
+                    &[JobStatus::Running],
+                    JobStatus::Failed,
+                    None,
+                    Some(e.to_string()),
+                )
+                .await;
+            }
+        }
+        self.tasks.write().unwrap().remove(&job.id);
+    }
 
-                // C. Save Result
-                let mut map = results_clone.write().unwrap();
-                match response {
-                    Ok(data) => {
-                        // Convert SocraticResponse to PeteResponse
-                        let pete_response = PeteResponse {
-                            answer: data.text,
-                            citations: vec![], // TODO: Extract citations
-                            confidence: 1.0,   // TODO: Get confidence
-                            suggestions: vec![],
-                        };
-                        map.insert(job.id, JobStatus::Completed(pete_response));
-                    }
-                    Err(e) => {
-                        map.insert(job.id, JobStatus::Failed(e.to_string()));
+    /// Move a job to `to` if it is still in one of `from`. False when it
+    /// already moved on (e.g. cancelled), so late results are dropped.
+    async fn transition(
+        &self,
+        id: Uuid,
+        from: &[JobStatus],
+        to: JobStatus,
+        response: Option<PeteResponse>,
+        error: Option<String>,
+    ) -> bool {
+        let now = Utc::now();
+        let updated = {
+            let mut jobs = self.jobs.write().unwrap();
+            match jobs.get_mut(&id) {
+                Some(job) if from.contains(&job.status) => {
+                    job.status = to;
+                    job.response = response;
+                    job.error = error;
+                    job.updated_at = now;
+                    if to.is_finished() {
+                        job.expires_at = now + self.ttl;
                     }
+                    Some(job.clone())
                 }
+                _ => None,
             }
+        };
+
+        match (updated, &self.db) {
+            (Some(job), Some(db)) => {
+                let from: Vec<&str> = from.iter().map(|status| status.as_str()).collect();
+                if let Err(e) = update_job(db, &job, &from).await {
+                    log::warn!(
+                        "Failed to persist chat job {} as {}: {}",
+                        id,
+                        to.as_str(),
+                        e
+                    );
+                }
+                true
+            }
+            (Some(_), None) => true,
+            (None, _) => false,
+        }
+    }
+
+    /// The caller's job, unless it belongs to someone else or has expired
+    pub async fn get(&self, id: Uuid, user_id: i64) -> Result<Option<ChatJob>> {
+        let cached = self.jobs.read().unwrap().get(&id).cloned();
+        let job = match (cached, &self.db) {
+            (Some(job), _) => Some(job),
+            (None, Some(db)) => load_job(db, id).await?,
+            (None, None) => None,
+        };
+        let expired = |job: &ChatJob| job.status.is_finished() && job.expires_at <= Utc::now();
+        Ok(job.filter(|job| job.user_id == user_id && !expired(job)))
+    }
+
+    /// Stop a queued or running job. Finished jobs are returned unchanged.
+    pub async fn cancel(&self, id: Uuid, user_id: i64) -> Result<Option<ChatJob>> {
+        let Some(job) = self.get(id, user_id).await? else {
+            return Ok(None);
+        };
+        if job.status.is_finished() {
+            return Ok(Some(job));
+        }
+
+        // Abort first so the task can't write a result after we mark it;
+        // dropping its ticket or slot hands the worker to the next job.
+        if let Some(task) = self.tasks.write().unwrap().remove(&id) {
+            task.abort();
+        }
+        self.transition(
+            id,
+            &[JobStatus::Queued, JobStatus::Running],
+            JobStatus::Cancelled,
+            None,
+            None,
+        )
+        .await;
+        self.get(id, user_id).await
+    }
+
+    /// Session of the caller's most recent chat, so a client that doesn't
+    /// send `session_id` still continues its conversation
+    pub async fn latest_session(&self, user_id: i64) -> Result<Option<Uuid>> {
+        if let Some(db) = &self.db {
+            let session = sqlx::query_scalar(
+                "SELECT session_id FROM chat_jobs WHERE user_id = $1 ORDER BY created_at DESC LIMIT 1",
+            )
+            .bind(user_id)
+            .fetch_optional(db)
+            .await?;
+            return Ok(session);
+        }
+        Ok(self
+            .jobs
+            .read()
+            .unwrap()
+            .values()
+            .filter(|job| job.user_id == user_id)
+            .max_by_key(|job| job.created_at)
+            .map(|job| job.session_id))
+    }
+
+    /// Who chats in `session_id`, so one user can't write into another's session
+    pub async fn session_owner(&self, session_id: Uuid) -> Result<Option<i64>> {
+        if let Some(db) = &self.db {
+            let owner =
+                sqlx::query_scalar("SELECT user_id FROM chat_jobs WHERE session_id = $1 LIMIT 1")
+                    .bind(session_id)
+                    .fetch_optional(db)
+                    .await?;
+            return Ok(owner);
+        }
+        Ok(self
+            .jobs
+            .read()
+            .unwrap()
+            .values()
+            .find(|job| job.session_id == session_id)
+            .map(|job| job.user_id))
+    }
+
+    /// Drop finished jobs past their TTL, in memory and in Postgres
+    pub async fn purge_expired(&self) -> Result<u64> {
+        let now = Utc::now();
+        let mut removed = {
+            let mut jobs = self.jobs.write().unwrap();
+            let before = jobs.len();
+            jobs.retain(|_, job| !(job.status.is_finished() && job.expires_at <= now));
+            (before - jobs.len()) as u64
+        };
+
+        if let Some(db) = &self.db {
+            let result = sqlx::query(
+                "DELETE FROM chat_jobs WHERE expires_at <= NOW() AND status IN ('done', 'failed', 'cancelled')",
+            )
+            .execute(db)
+            .await?;
+            removed = removed.max(result.rows_affected());
+        }
+        Ok(removed)
+    }
+
+    /// Jobs a previous process left queued or running will never finish;
+    /// fail them so their clients stop polling.
+    pub async fn recover(&self) -> Result<u64> {
+        let Some(db) = &self.db else {
+            return Ok(0);
+        };
+        let result = sqlx::query(
+            r#"
+            UPDATE chat_jobs
+            SET status = 'failed', error = 'Interrupted by a server restart',
+                updated_at = NOW(), expires_at = $1
+            WHERE status IN ('queued', 'running')
+            "#,
+        )
+        .bind(Utc::now() + self.ttl)
+        .execute(db)
+        .await?;
+        Ok(result.rows_affected())
+    }
+}
+
+async fn insert_job(db: &PgPool, job: &ChatJob) -> Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO chat_jobs (id, user_id, session_id, message, status, created_at, updated_at, expires_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        "#,
+    )
+    .bind(job.id)
+    .bind(job.user_id)
+    .bind(job.session_id)
+    .bind(&job.message)
+    .bind(job.status.as_str())
+    .bind(job.created_at)
+    .bind(job.updated_at)
+    .bind(job.expires_at)
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+async fn update_job(db: &PgPool, job: &ChatJob, from: &[&str]) -> Result<()> {
+    let response = job
+        .response
+        .as_ref()
+        .map(serde_json::to_value)
+        .transpose()?;
+    sqlx::query(
+        r#"
+        UPDATE chat_jobs
+        SET status = $3, response = $4, error = $5, updated_at = $6, expires_at = $7
+        WHERE id = $1 AND status = ANY($2)
+        "#,
+    )
+    .bind(job.id)
+    .bind(from)
+    .bind(job.status.as_str())
+    .bind(response)
+    .bind(&job.error)
+    .bind(job.updated_at)
+    .bind(job.expires_at)
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+async fn load_job(db: &PgPool, id: Uuid) -> Result<Option<ChatJob>> {
+    let row = sqlx::query_as::<_, ChatJobRow>(
+        r#"
+        SELECT id, user_id, session_id, message, status, response, error,
+               created_at, updated_at, expires_at
+        FROM chat_jobs WHERE id = $1
+        "#,
+    )
+    .bind(id)
+    .fetch_optional(db)
+    .await?;
+    Ok(row.map(ChatJob::from))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use infra_ai::worker_pool::PoolConfig;
+    use infra_db::conversation_memory::Speaker;
+    use infra_db::ConversationMemory;
+    use std::time::Duration as StdDuration;
+
+    fn service(workers: usize) -> (ChatQueueService, Arc<ConversationMemory>) {
+        let memory = Arc::new(ConversationMemory::new_in_memory(100));
+        let engine = Arc::new(tokio::sync::RwLock::new(SocraticEngine::new(
+            memory.clone(),
+        )));
+        let pool = InferencePool::new(PoolConfig {
+            workers,
+            default_model_limit: workers,
+            ..PoolConfig::default()
         });
+        let service = ChatQueueService::new(engine, pool, "none".to_string(), None);
+        (service, memory)
+    }
 
-        Self { sender, results }
+    async fn wait_until_finished(service: &ChatQueueService, job: &ChatJob) -> ChatJob {
+        for _ in 0..200 {
+            let current = service.get(job.id, job.user_id).await.unwrap().unwrap();
+            if current.status.is_finished() {
+                return current;
+            }
+            tokio::time::sleep(StdDuration::from_millis(10)).await;
+        }
+        panic!("chat job {} never finished", job.id);
     }
 
-    pub async fn enqueue(&self, message: String) -> Uuid {
-        let id = Uuid::new_v4();
-        let job = ChatJob { id, message };
+    #[tokio::test]
+    async fn test_concurrent_users_keep_their_own_jobs_and_sessions() {
+        let (service, memory) = service(2);
+        let sessions: Vec<(i64, Uuid)> = (1..=4).map(|user| (user, Uuid::new_v4())).collect();
 
-        // Initialize status
-        {
-            let mut map = self.results.write().unwrap();
-            map.insert(id, JobStatus::Queued);
+        let submissions = sessions.iter().flat_map(|&(user_id, session_id)| {
+            let service = service.clone();
+            (0..2).map(move |turn| {
+                let service = service.clone();
+                async move {
+                    service
+                        .enqueue(
+                            user_id,
+                            session_id,
+                            format!("user {} turn {}", user_id, turn),
+                            ChatScope::default(),
+                        )
+                        .await
+                        .unwrap()
+                }
+            })
+        });
+        let jobs = futures::future::join_all(submissions).await;
+
+        for job in &jobs {
+            let done = wait_until_finished(&service, job).await;
+            assert_eq!(done.status, JobStatus::Done);
+            assert!(done.response.is_some());
+
+            // Other users can't see it
+            let stranger = job.user_id + 100;
+            assert!(service.get(job.id, stranger).await.unwrap().is_none());
+            assert!(service.cancel(job.id, stranger).await.unwrap().is_none());
         }
 
-        // Send to worker (fire and forget)
-        if let Err(e) = self.sender.send(job).await {
-            let mut map = self.results.write().unwrap();
-            map.insert(
-                id,
-                JobStatus::Failed(format!("Queue full or closed: {}", e)),
+        // Both turns of each user landed in that user's session
+        for (user_id, session_id) in sessions {
+            let turns = memory.get_recent(session_id, 10).await.unwrap();
+            let asked: Vec<&str> = turns
+                .iter()
+                .filter(|turn| matches!(turn.speaker, Speaker::User))
+                .map(|turn| turn.content.as_str())
+                .collect();
+            assert_eq!(asked.len(), 2, "session of user {}", user_id);
+            assert!(asked
+                .iter()
+                .all(|content| content.starts_with(&format!("user {} ", user_id))));
+            assert_eq!(
+                service.latest_session(user_id).await.unwrap(),
+                Some(session_id)
+            );
+            assert_eq!(
+                service.session_owner(session_id).await.unwrap(),
+                Some(user_id)
             );
         }
+    }
 
-        id
+    #[tokio::test]
+    async fn test_cancel_queued_job_frees_its_place() {
+        let (service, _) = service(1);
+        // Hold the only worker so the job stays queued
+        let busy = service
+            .pool
+            .acquire(JobSpec::new(Priority::Batch, "none"))
+            .await
+            .unwrap();
+
+        let job = service
+            .enqueue(7, Uuid::new_v4(), "hello".into(), ChatScope::default())
+            .await
+            .unwrap();
+        assert_eq!(job.status, JobStatus::Queued);
+
+        let cancelled = service.cancel(job.id, 7).await.unwrap().unwrap();
+        assert_eq!(cancelled.status, JobStatus::Cancelled);
+        // The aborted task drops its ticket on the runtime's next pass
+        tokio::time::sleep(StdDuration::from_millis(20)).await;
+        assert_eq!(service.pool.metrics().queues[0].queued, 0);
+
+        // Nothing runs it later
+        drop(busy);
+        tokio::time::sleep(StdDuration::from_millis(50)).await;
+        let after = service.get(job.id, 7).await.unwrap().unwrap();
+        assert_eq!(after.status, JobStatus::Cancelled);
+        assert!(after.response.is_none());
     }
 
-    pub fn get_status(&self, id: &Uuid) -> Option<JobStatus> {
-        self.results.read().unwrap().get(id).cloned()
+    #[tokio::test]
+    async fn test_finished_jobs_expire_after_ttl() {
+        let (service, _) = service(1);
+        let service = service.with_ttl(Duration::milliseconds(50));
+
+        let job = service
+            .enqueue(3, Uuid::new_v4(), "hi".into(), ChatScope::default())
+            .await
+            .unwrap();
+        wait_until_finished(&service, &job).await;
+
+        tokio::time::sleep(StdDuration::from_millis(80)).await;
+        assert!(service.get(job.id, 3).await.unwrap().is_none());
+        assert_eq!(service.purge_expired().await.unwrap(), 1);
+        assert!(service.jobs.read().unwrap().is_empty());
     }
 }
@@ -0,0 +1,167 @@
+use super::{DocumentExtractor, ExtractedDocument, TextBlock};
+use anyhow::{Context, Result};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use std::io::{Cursor, Read};
+
+/// Word documents: reads `word/document.xml` from the ZIP container.
+///
+/// Paragraph styles `Title` and `HeadingN` become headings. DOCX has no fixed
+/// pages, so page numbers follow explicit page breaks and the page breaks Word
+/// recorded at last save (`w:lastRenderedPageBreak`).
+pub struct DocxExtractor;
+
+fn read_entry(archive: &mut zip::ZipArchive<Cursor<&[u8]>>, name: &str) -> Result<Option<String>> {
+    let mut file = match archive.by_name(name) {
+        Ok(file) => file,
+        Err(zip::result::ZipError::FileNotFound) => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    let mut xml = String::new();
+    file.read_to_string(&mut xml)?;
+    Ok(Some(xml))
+}
+
+fn attribute(element: &BytesStart, local_name: &[u8]) -> Option<String> {
+    element
+        .attributes()
+        .flatten()
+        .find(|a| a.key.local_name().as_ref() == local_name)
+        .and_then(|a| a.unescape_value().ok())
+        .map(|v| v.into_owned())
+}
+
+/// Heading level from a paragraph style id such as "Heading2" or "Title".
+fn style_level(style: &str) -> Option<u8> {
+    let style = style.to_lowercase();
+    if style == "title" {
+        return Some(1);
+    }
+    style
+        .strip_prefix("heading")
+        .and_then(|n| n.trim().parse::<u8>().ok())
+        .map(|n| n.clamp(1, 6))
+}
+
+fn parse_body(xml: &str) -> Result<Vec<TextBlock>> {
+    let mut reader = Reader::from_str(xml);
+    let mut blocks = Vec::new();
+    let mut page = 1u32;
+    let mut paragraph_page = 1u32;
+    let mut text = String::new();
+    let mut level: Option<u8> = None;
+    let mut in_text = false;
+
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) => match e.local_name().as_ref() {
+                b"p" => {
+                    text.clear();
+                    level = None;
+                    paragraph_page = page;
+                }
+                b"t" => in_text = true,
+                _ => {}
+            },
+            Event::Empty(e) => match e.local_name().as_ref() {
+                b"pStyle" => level = attribute(&e, b"val").as_deref().and_then(style_level),
+                b"tab" => text.push('\t'),
+                b"br" if attribute(&e, b"type").as_deref() == Some("page") => page += 1,
+                b"br" => text.push(' '),
+                b"lastRenderedPageBreak" => {
+                    page += 1;
+                    if text.trim().is_empty() {
+                        paragraph_page = page;
+                    }
+                }
+                _ => {}
+            },
+            Event::Text(t) if in_text => text.push_str(&t.unescape()?),
+            Event::End(e) => match e.local_name().as_ref() {
+                b"t" => in_text = false,
+                b"p" => {
+                    let content = std::mem::take(&mut text);
+                    blocks.push(match level {
+                        Some(level) => TextBlock::heading(level, content, Some(paragraph_page)),
+                        None => TextBlock::paragraph(content, Some(paragraph_page)),
+                    });
+                }
+                _ => {}
+            },
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(blocks)
+}
+
+/// `dc:title` from `docProps/core.xml`
+fn parse_title(xml: &str) -> Option<String> {
+    let mut reader = Reader::from_str(xml);
+    let mut in_title = false;
+    loop {
+        match reader.read_event().ok()? {
+            Event::Start(e) if e.local_name().as_ref() == b"title" => in_title = true,
+            Event::Text(t) if in_title => {
+                let title = t.unescape().ok()?.trim().to_string();
+                return (!title.is_empty()).then_some(title);
+            }
+            Event::End(e) if e.local_name().as_ref() == b"title" => return None,
+            Event::Eof => return None,
+            _ => {}
+        }
+    }
+}
+
+impl DocumentExtractor for DocxExtractor {
+    fn source_types(&self) -> &'static [&'static str] {
+        &["docx"]
+    }
+
+    fn extract(&self, bytes: &[u8]) -> Result<ExtractedDocument> {
+        let mut archive =
+            zip::ZipArchive::new(Cursor::new(bytes)).context("Not a DOCX (ZIP) file")?;
+        let body = read_entry(&mut archive, "word/document.xml")?
+            .context("DOCX is missing word/document.xml")?;
+        let title = read_entry(&mut archive, "docProps/core.xml")?
+            .as_deref()
+            .and_then(parse_title);
+
+        Ok(ExtractedDocument {
+            title,
+            blocks: parse_body(&body)?,
+        }
+        .tidy())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_docx_fixture() {
+        let doc = DocxExtractor
+            .extract(include_bytes!("../../../fixtures/knowledge/energy.docx"))
+            .unwrap();
+
+        assert_eq!(doc.title.as_deref(), Some("Energy Unit"));
+        assert_eq!(
+            doc.blocks,
+            vec![
+                TextBlock::heading(1, "Kinetic Energy", Some(1)),
+                TextBlock::paragraph(
+                    "Kinetic energy depends on mass and the square of speed.",
+                    Some(1)
+                ),
+                TextBlock::heading(2, "Potential Energy", Some(2)),
+                TextBlock::paragraph("Potential energy depends on height & gravity.", Some(2)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rejects_non_zip() {
+        assert!(DocxExtractor.extract(b"not a zip").is_err());
+    }
+}
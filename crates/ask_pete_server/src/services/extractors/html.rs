@@ -0,0 +1,133 @@
+use super::{DocumentExtractor, ExtractedDocument, TextBlock};
+use anyhow::Result;
+use scraper::{ElementRef, Html, Node, Selector};
+
+/// HTML via `scraper`. Navigation, scripts and styles are skipped; block-level
+/// elements become paragraphs and `h1`-`h6` become headings.
+pub struct HtmlExtractor;
+
+const SKIPPED: &[&str] = &[
+    "script", "style", "noscript", "nav", "header", "footer", "aside", "form", "svg", "template",
+];
+const BLOCKS: &[&str] = &[
+    "p",
+    "div",
+    "section",
+    "article",
+    "main",
+    "li",
+    "ul",
+    "ol",
+    "table",
+    "tr",
+    "td",
+    "th",
+    "blockquote",
+    "pre",
+    "figcaption",
+    "dt",
+    "dd",
+    "br",
+];
+
+fn walk(element: ElementRef, blocks: &mut Vec<TextBlock>, text: &mut String) {
+    for child in element.children() {
+        match child.value() {
+            Node::Text(t) => text.push_str(t),
+            Node::Element(e) => {
+                let name = e.name();
+                if SKIPPED.contains(&name) {
+                    continue;
+                }
+                let Some(child) = ElementRef::wrap(child) else {
+                    continue;
+                };
+
+                if let Some(level) = name
+                    .strip_prefix('h')
+                    .and_then(|n| n.parse::<u8>().ok())
+                    .filter(|n| (1..=6).contains(n))
+                {
+                    flush(blocks, text);
+                    let heading: String = child.text().collect();
+                    blocks.push(TextBlock::heading(level, heading, None));
+                } else if BLOCKS.contains(&name) {
+                    flush(blocks, text);
+                    walk(child, blocks, text);
+                    flush(blocks, text);
+                } else {
+                    walk(child, blocks, text);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn flush(blocks: &mut Vec<TextBlock>, text: &mut String) {
+    if !text.trim().is_empty() {
+        blocks.push(TextBlock::paragraph(text.trim(), None));
+    }
+    text.clear();
+}
+
+impl DocumentExtractor for HtmlExtractor {
+    fn source_types(&self) -> &'static [&'static str] {
+        &["html", "htm"]
+    }
+
+    fn extract(&self, bytes: &[u8]) -> Result<ExtractedDocument> {
+        let source = String::from_utf8_lossy(bytes);
+        let document = Html::parse_document(&source);
+
+        let title_selector = Selector::parse("title").expect("valid selector");
+        let title = document
+            .select(&title_selector)
+            .next()
+            .map(|t| t.text().collect::<String>().trim().to_string())
+            .filter(|t| !t.is_empty());
+
+        let body_selector = Selector::parse("body").expect("valid selector");
+        let root = document
+            .select(&body_selector)
+            .next()
+            .unwrap_or_else(|| document.root_element());
+
+        let mut blocks = Vec::new();
+        let mut text = String::new();
+        walk(root, &mut blocks, &mut text);
+        flush(&mut blocks, &mut text);
+
+        Ok(ExtractedDocument { title, blocks }.tidy())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_html_fixture() {
+        let doc = HtmlExtractor
+            .extract(include_bytes!("../../../fixtures/knowledge/friction.html"))
+            .unwrap();
+
+        assert_eq!(doc.title.as_deref(), Some("Friction Notes"));
+        assert_eq!(
+            doc.blocks,
+            vec![
+                TextBlock::heading(1, "Friction", None),
+                TextBlock::paragraph(
+                    "Friction opposes sliding motion & turns kinetic energy into heat.",
+                    None
+                ),
+                TextBlock::heading(2, "Types", None),
+                TextBlock::paragraph("Static friction", None),
+                TextBlock::paragraph("Kinetic friction", None),
+            ]
+        );
+        let text = doc.plain_text();
+        assert!(!text.contains("Home"));
+        assert!(!text.contains("console.log"));
+    }
+}
@@ -0,0 +1,86 @@
+use super::{DocumentExtractor, ExtractedDocument, TextBlock};
+use anyhow::Result;
+use pulldown_cmark::{Event, HeadingLevel, Parser, Tag, TagEnd};
+
+/// Markdown via `pulldown-cmark`; list items and code blocks become paragraphs.
+pub struct MarkdownExtractor;
+
+fn heading_level(level: HeadingLevel) -> u8 {
+    match level {
+        HeadingLevel::H1 => 1,
+        HeadingLevel::H2 => 2,
+        HeadingLevel::H3 => 3,
+        HeadingLevel::H4 => 4,
+        HeadingLevel::H5 => 5,
+        HeadingLevel::H6 => 6,
+    }
+}
+
+impl DocumentExtractor for MarkdownExtractor {
+    fn source_types(&self) -> &'static [&'static str] {
+        &["md", "markdown"]
+    }
+
+    fn extract(&self, bytes: &[u8]) -> Result<ExtractedDocument> {
+        let source = std::str::from_utf8(bytes)?;
+        let mut blocks = Vec::new();
+        let mut text = String::new();
+
+        for event in Parser::new(source) {
+            match event {
+                Event::Text(t) | Event::Code(t) => text.push_str(&t),
+                Event::SoftBreak | Event::HardBreak => text.push(' '),
+                Event::End(TagEnd::Heading(level)) => {
+                    blocks.push(TextBlock::heading(
+                        heading_level(level),
+                        std::mem::take(&mut text),
+                        None,
+                    ));
+                }
+                Event::End(TagEnd::Paragraph | TagEnd::Item | TagEnd::CodeBlock) => {
+                    blocks.push(TextBlock::paragraph(std::mem::take(&mut text), None));
+                }
+                // A nested list or paragraph inside an item starts a new block
+                Event::Start(Tag::List(_) | Tag::Paragraph) if !text.trim().is_empty() => {
+                    blocks.push(TextBlock::paragraph(std::mem::take(&mut text), None));
+                }
+                _ => {}
+            }
+        }
+        if !text.trim().is_empty() {
+            blocks.push(TextBlock::paragraph(text, None));
+        }
+
+        Ok(ExtractedDocument {
+            title: None,
+            blocks,
+        }
+        .tidy())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::extractors::BlockKind;
+
+    #[test]
+    fn test_markdown_fixture() {
+        let doc = MarkdownExtractor
+            .extract(include_bytes!("../../../fixtures/knowledge/momentum.md"))
+            .unwrap();
+
+        assert_eq!(doc.title.as_deref(), Some("Momentum"));
+        assert_eq!(doc.blocks[0], TextBlock::heading(1, "Momentum", None));
+        assert!(doc
+            .blocks
+            .iter()
+            .any(|b| b.kind == BlockKind::Heading { level: 2 } && b.text == "Conservation"));
+        assert!(doc
+            .blocks
+            .iter()
+            .any(|b| b.text == "Momentum is mass times velocity (p = mv)."));
+        assert!(doc.blocks.iter().any(|b| b.text == "Elastic collisions"));
+        assert!(!doc.plain_text().contains("**"));
+    }
+}
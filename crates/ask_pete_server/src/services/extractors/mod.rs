@@ -0,0 +1,201 @@
+//! # Document Extractors
+//!
+//! Pure-Rust text extraction for knowledge uploads. Every extractor produces the
+//! same shape: an ordered list of [`TextBlock`]s (headings and paragraphs) with
+//! page numbers where the format has them. The ingestion pipeline chunks these
+//! blocks and keeps the page/heading on each chunk for citations.
+
+mod docx;
+mod html;
+mod markdown;
+mod pdf;
+
+pub use docx::DocxExtractor;
+pub use html::HtmlExtractor;
+pub use markdown::MarkdownExtractor;
+pub use pdf::PdfExtractor;
+
+use crate::services::knowledge_ingest::normalize_text;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum BlockKind {
+    Heading { level: u8 },
+    Paragraph,
+}
+
+/// One structural unit of an extracted document.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TextBlock {
+    #[serde(flatten)]
+    pub kind: BlockKind,
+    pub text: String,
+    /// 1-based page number, when the format has pages
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub page: Option<u32>,
+}
+
+impl TextBlock {
+    pub fn heading(level: u8, text: impl Into<String>, page: Option<u32>) -> Self {
+        Self {
+            kind: BlockKind::Heading { level },
+            text: text.into(),
+            page,
+        }
+    }
+
+    pub fn paragraph(text: impl Into<String>, page: Option<u32>) -> Self {
+        Self {
+            kind: BlockKind::Paragraph,
+            text: text.into(),
+            page,
+        }
+    }
+
+    pub fn is_heading(&self) -> bool {
+        matches!(self.kind, BlockKind::Heading { .. })
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ExtractedDocument {
+    /// Title from document metadata (`<title>`, DOCX core properties, first H1)
+    pub title: Option<String>,
+    pub blocks: Vec<TextBlock>,
+}
+
+impl ExtractedDocument {
+    /// Markdown-style plain text: headings as `#` lines, blocks separated by blank lines.
+    pub fn plain_text(&self) -> String {
+        self.blocks
+            .iter()
+            .map(|block| match block.kind {
+                BlockKind::Heading { level } => {
+                    format!("{} {}", "#".repeat(level.clamp(1, 6) as usize), block.text)
+                }
+                BlockKind::Paragraph => block.text.clone(),
+            })
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.blocks.iter().all(|b| b.text.trim().is_empty())
+    }
+
+    /// Drop empty blocks and collapse whitespace inside each block.
+    fn tidy(mut self) -> Self {
+        for block in &mut self.blocks {
+            block.text = block.text.split_whitespace().collect::<Vec<_>>().join(" ");
+        }
+        self.blocks.retain(|b| !b.text.is_empty());
+        if self.title.is_none() {
+            self.title = self
+                .blocks
+                .iter()
+                .find(|b| b.kind == BlockKind::Heading { level: 1 })
+                .map(|b| b.text.clone());
+        }
+        self
+    }
+}
+
+/// Converts the raw bytes of one file format into structured text.
+pub trait DocumentExtractor: Send + Sync {
+    /// Source types (file extensions) this extractor handles, lowercase
+    fn source_types(&self) -> &'static [&'static str];
+    fn extract(&self, bytes: &[u8]) -> Result<ExtractedDocument>;
+}
+
+/// Plain UTF-8 text; headings are recognised with the same heuristics as PDFs.
+pub struct PlainTextExtractor;
+
+impl DocumentExtractor for PlainTextExtractor {
+    fn source_types(&self) -> &'static [&'static str] {
+        &["txt", "text"]
+    }
+
+    fn extract(&self, bytes: &[u8]) -> Result<ExtractedDocument> {
+        let text = std::str::from_utf8(bytes)?;
+        Ok(ExtractedDocument {
+            title: None,
+            blocks: blocks_from_text(text, None),
+        }
+        .tidy())
+    }
+}
+
+/// All built-in extractors.
+pub fn extractors() -> Vec<Box<dyn DocumentExtractor>> {
+    vec![
+        Box::new(PlainTextExtractor),
+        Box::new(MarkdownExtractor),
+        Box::new(HtmlExtractor),
+        Box::new(DocxExtractor),
+        Box::new(PdfExtractor),
+    ]
+}
+
+/// Find the extractor for a source type such as "pdf" or "docx".
+pub fn extractor_for(source_type: &str) -> Option<Box<dyn DocumentExtractor>> {
+    let source_type = source_type.trim_start_matches('.').to_lowercase();
+    extractors()
+        .into_iter()
+        .find(|e| e.source_types().contains(&source_type.as_str()))
+}
+
+/// Normalize loose text (a PDF page, a .txt file) into heading and paragraph blocks.
+pub fn blocks_from_text(text: &str, page: Option<u32>) -> Vec<TextBlock> {
+    normalize_text(text)
+        .split("\n\n")
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+        .map(|paragraph| {
+            let hashes = paragraph.chars().take_while(|c| *c == '#').count();
+            if (1..=6).contains(&hashes) && paragraph[hashes..].starts_with(' ') {
+                TextBlock::heading(hashes as u8, paragraph[hashes..].trim(), page)
+            } else if looks_like_heading(paragraph) {
+                TextBlock::heading(2, paragraph, page)
+            } else {
+                TextBlock::paragraph(paragraph, page)
+            }
+        })
+        .collect()
+}
+
+/// A short standalone line without sentence punctuation, e.g. "Newton's Laws".
+pub fn looks_like_heading(paragraph: &str) -> bool {
+    let words = paragraph.split_whitespace().count();
+    (1..=8).contains(&words)
+        && !paragraph.ends_with(['.', '!', '?', ',', ';', ':'])
+        && paragraph.starts_with(|c: char| c.is_uppercase() || c.is_ascii_digit())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extractor_lookup() {
+        for source_type in ["pdf", "DOCX", ".md", "html", "htm", "txt"] {
+            assert!(extractor_for(source_type).is_some(), "{}", source_type);
+        }
+        assert!(extractor_for("pptx").is_none());
+    }
+
+    #[test]
+    fn test_plain_text_headings() {
+        let doc = PlainTextExtractor
+            .extract(b"Newton's Laws\n\nForce equals mass times\nacceleration.")
+            .unwrap();
+        assert_eq!(
+            doc.blocks,
+            vec![
+                TextBlock::heading(2, "Newton's Laws", None),
+                TextBlock::paragraph("Force equals mass times acceleration.", None),
+            ]
+        );
+    }
+}
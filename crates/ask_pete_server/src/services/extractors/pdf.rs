@@ -0,0 +1,65 @@
+use super::{blocks_from_text, DocumentExtractor, ExtractedDocument};
+use anyhow::{anyhow, Result};
+
+/// PDFs via `pdf-extract`, one pass per page so every block keeps its page number.
+/// Headings are inferred from short, unpunctuated standalone lines.
+pub struct PdfExtractor;
+
+impl DocumentExtractor for PdfExtractor {
+    fn source_types(&self) -> &'static [&'static str] {
+        &["pdf"]
+    }
+
+    fn extract(&self, bytes: &[u8]) -> Result<ExtractedDocument> {
+        let pages = pdf_extract::extract_text_from_mem_by_pages(bytes)
+            .map_err(|e| anyhow!("Failed to read PDF: {}", e))?;
+
+        let blocks = pages
+            .iter()
+            .enumerate()
+            .flat_map(|(i, page)| blocks_from_text(page, Some(i as u32 + 1)))
+            .collect();
+
+        Ok(ExtractedDocument {
+            title: None,
+            blocks,
+        }
+        .tidy())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::extractors::BlockKind;
+
+    #[test]
+    fn test_pdf_fixture_keeps_pages() {
+        let doc = PdfExtractor
+            .extract(include_bytes!("../../../fixtures/knowledge/newton.pdf"))
+            .unwrap();
+
+        let heading = doc
+            .blocks
+            .iter()
+            .find(|b| b.text == "Newton's Laws")
+            .expect("heading on page 1");
+        assert_eq!(heading.kind, BlockKind::Heading { level: 2 });
+        assert_eq!(heading.page, Some(1));
+
+        let second = doc
+            .blocks
+            .iter()
+            .find(|b| b.text.contains("equal and opposite"))
+            .expect("text on page 2");
+        assert_eq!(second.page, Some(2));
+        assert!(doc
+            .plain_text()
+            .contains("Force equals mass times acceleration."));
+    }
+
+    #[test]
+    fn test_rejects_garbage() {
+        assert!(PdfExtractor.extract(b"%PDF-1.4 broken").is_err());
+    }
+}
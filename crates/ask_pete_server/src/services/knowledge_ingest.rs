@@ -0,0 +1,668 @@
+//! # Knowledge Ingestion
+//!
+//! Turns an uploaded document into searchable chunks:
+//!
+//! 0. **Extract**: a `DocumentExtractor` turns PDF/DOCX/HTML/Markdown bytes into
+//!    heading and paragraph blocks with page numbers (see `extractors`).
+//! 1. **Normalize**: unify line endings, re-join hyphenated and hard-wrapped lines.
+//! 2. **Chunk**: split on headings and sentence boundaries, packing sentences up to
+//!    a token budget with a sentence-level overlap between neighbours.
+//! 3. **Dedupe**: identical documents (by SHA-256 of the normalized text) are not
+//!    stored twice, and repeated chunks inside a document are dropped.
+//! 4. **Store**: one `knowledge_sources` row per chunk (so keyword retrieval keeps
+//!    working) plus one vector store entry per chunk. Chunk metadata carries the
+//!    document's `course_id` so retrieval can be limited to one course.
+//!
+//! Each document row in `knowledge_documents` tracks its indexing status.
+
+use crate::services::extractors::{blocks_from_text, ExtractedDocument, TextBlock};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use infra_db::vector_store::VectorStore;
+use infra_db::Document;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use std::collections::HashSet;
+use std::sync::Arc;
+use uuid::Uuid;
+
+// ============================================================================
+// Normalization
+// ============================================================================
+
+/// Clean extracted text while keeping paragraph and heading structure.
+pub fn normalize_text(raw: &str) -> String {
+    let text = raw
+        .replace("\r\n", "\n")
+        .replace('\r', "\n")
+        .replace('\u{a0}', " ")
+        .replace(['\u{2018}', '\u{2019}'], "'")
+        .replace(['\u{201c}', '\u{201d}'], "\"");
+
+    let mut paragraphs: Vec<String> = Vec::new();
+    let mut current = String::new();
+
+    for line in text.lines() {
+        let line: String = line
+            .chars()
+            .filter(|c| !c.is_control() || *c == '\t')
+            .collect::<String>()
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        if line.is_empty() {
+            if !current.is_empty() {
+                paragraphs.push(std::mem::take(&mut current));
+            }
+            continue;
+        }
+
+        // Headings and list items always start a new block
+        if is_markdown_heading(&line) || is_list_item(&line) {
+            if !current.is_empty() {
+                paragraphs.push(std::mem::take(&mut current));
+            }
+            if is_markdown_heading(&line) {
+                paragraphs.push(line);
+            } else {
+                current = line;
+            }
+            continue;
+        }
+
+        if current.is_empty() {
+            current = line;
+        } else if current.ends_with('-')
+            && !current.ends_with(" -")
+            && line.starts_with(|c: char| c.is_lowercase())
+        {
+            // "photo-\nsynthesis" → "photosynthesis"
+            current.pop();
+            current.push_str(&line);
+        } else {
+            current.push(' ');
+            current.push_str(&line);
+        }
+    }
+    if !current.is_empty() {
+        paragraphs.push(current);
+    }
+
+    paragraphs.join("\n\n")
+}
+
+fn is_markdown_heading(line: &str) -> bool {
+    let hashes = line.chars().take_while(|c| *c == '#').count();
+    (1..=6).contains(&hashes) && line[hashes..].starts_with(' ')
+}
+
+fn is_list_item(line: &str) -> bool {
+    line.starts_with("- ")
+        || line.starts_with("* ")
+        || line
+            .split_once(". ")
+            .map(|(n, _)| !n.is_empty() && n.len() <= 3 && n.chars().all(|c| c.is_ascii_digit()))
+            .unwrap_or(false)
+}
+
+/// SHA-256 of the text, hex encoded.
+pub fn content_hash(text: &str) -> String {
+    Sha256::digest(text.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+// ============================================================================
+// Chunking
+// ============================================================================
+
+/// Rough token count for budgeting (≈ 0.75 words per token for English prose).
+pub fn estimate_tokens(text: &str) -> usize {
+    (text.split_whitespace().count() * 4).div_ceil(3)
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ChunkingConfig {
+    pub max_tokens: usize,
+    pub overlap_tokens: usize,
+}
+
+impl Default for ChunkingConfig {
+    fn default() -> Self {
+        Self {
+            max_tokens: 400,
+            overlap_tokens: 50,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Chunk {
+    pub index: usize,
+    pub text: String,
+    /// Nearest heading above the chunk
+    pub heading: Option<String>,
+    /// Page of the chunk's first sentence, for paged formats
+    pub page: Option<u32>,
+    pub token_count: usize,
+    pub content_hash: String,
+}
+
+const ABBREVIATIONS: &[&str] = &[
+    "dr.", "mr.", "mrs.", "ms.", "prof.", "st.", "vs.", "etc.", "e.g.", "i.e.", "fig.", "eq.",
+    "no.", "approx.", "cf.", "al.",
+];
+
+/// Split a paragraph into sentences, keeping abbreviations and decimals intact.
+pub fn split_sentences(paragraph: &str) -> Vec<String> {
+    let mut sentences = Vec::new();
+    let mut current = String::new();
+    let words: Vec<&str> = paragraph.split_whitespace().collect();
+
+    for (i, word) in words.iter().enumerate() {
+        if !current.is_empty() {
+            current.push(' ');
+        }
+        current.push_str(word);
+
+        let trimmed = word.trim_end_matches(['"', '\'', ')']);
+        let ends_sentence = trimmed.ends_with(['.', '!', '?'])
+            && !ABBREVIATIONS.contains(&trimmed.to_lowercase().as_str());
+        let next_starts_sentence = words
+            .get(i + 1)
+            .map(|next| {
+                next.starts_with(|c: char| {
+                    c.is_uppercase() || c.is_ascii_digit() || c == '"' || c == '('
+                })
+            })
+            .unwrap_or(true);
+
+        if ends_sentence && next_starts_sentence {
+            sentences.push(std::mem::take(&mut current));
+        }
+    }
+    if !current.is_empty() {
+        sentences.push(current);
+    }
+    sentences
+}
+
+/// Chunk normalized text on heading and sentence boundaries.
+pub fn chunk_document(text: &str, config: &ChunkingConfig) -> Vec<Chunk> {
+    chunk_blocks(&blocks_from_text(text, None), config)
+}
+
+/// Chunk extracted blocks on heading and sentence boundaries.
+///
+/// Chunks never span two sections. Within a section, sentences are packed up to
+/// `max_tokens`; the next chunk starts with the trailing sentences of the previous
+/// one, up to `overlap_tokens`. A single sentence longer than the budget is split
+/// on word boundaries. Each chunk keeps the page of its first sentence.
+pub fn chunk_blocks(blocks: &[TextBlock], config: &ChunkingConfig) -> Vec<Chunk> {
+    let max_tokens = config.max_tokens.max(1);
+
+    // Group (page, sentence) pairs into sections under their heading
+    type Section = (Option<String>, Vec<(Option<u32>, String)>);
+    let mut sections: Vec<Section> = vec![(None, Vec::new())];
+    for block in blocks {
+        if block.is_heading() {
+            sections.push((Some(block.text.clone()), Vec::new()));
+            continue;
+        }
+        let (_, sentences) = sections.last_mut().expect("at least one section");
+        for sentence in split_sentences(&block.text) {
+            if estimate_tokens(&sentence) > max_tokens {
+                sentences.extend(
+                    split_long_sentence(&sentence, max_tokens)
+                        .into_iter()
+                        .map(|piece| (block.page, piece)),
+                );
+            } else {
+                sentences.push((block.page, sentence));
+            }
+        }
+    }
+
+    let mut chunks = Vec::new();
+    for (heading, sentences) in sections {
+        let mut start = 0;
+        while start < sentences.len() {
+            let mut end = start;
+            let mut tokens = 0;
+            while end < sentences.len() {
+                let next = estimate_tokens(&sentences[end].1);
+                if end > start && tokens + next > max_tokens {
+                    break;
+                }
+                tokens += next;
+                end += 1;
+            }
+
+            let text = sentences[start..end]
+                .iter()
+                .map(|(_, sentence)| sentence.as_str())
+                .collect::<Vec<_>>()
+                .join(" ");
+            chunks.push(Chunk {
+                index: chunks.len(),
+                content_hash: content_hash(&text),
+                token_count: tokens,
+                heading: heading.clone(),
+                page: sentences[start].0,
+                text,
+            });
+
+            if end >= sentences.len() {
+                break;
+            }
+
+            // Step back over trailing sentences that fit in the overlap budget
+            let mut next_start = end;
+            let mut overlap = 0;
+            while next_start > start + 1 {
+                let size = estimate_tokens(&sentences[next_start - 1].1);
+                if overlap + size > config.overlap_tokens {
+                    break;
+                }
+                overlap += size;
+                next_start -= 1;
+            }
+            start = next_start;
+        }
+    }
+    chunks
+}
+
+fn split_long_sentence(sentence: &str, max_tokens: usize) -> Vec<String> {
+    let words_per_piece = (max_tokens * 3 / 4).max(1);
+    sentence
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .chunks(words_per_piece)
+        .map(|words| words.join(" "))
+        .collect()
+}
+
+// ============================================================================
+// Storage
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DocumentStatus {
+    Pending,
+    Processing,
+    Indexed,
+    Failed,
+}
+
+impl DocumentStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Processing => "processing",
+            Self::Indexed => "indexed",
+            Self::Failed => "failed",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct KnowledgeDocument {
+    pub id: Uuid,
+    pub title: String,
+    pub source_type: String,
+    pub content_hash: String,
+    pub status: String,
+    pub chunk_count: i32,
+    pub error: Option<String>,
+    pub uploaded_by: Option<i64>,
+    pub course_id: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+const DOCUMENT_COLUMNS: &str = "id, title, source_type, content_hash, status, chunk_count, error, uploaded_by, course_id, created_at, updated_at";
+
+/// Stores documents, chunks them and keeps the vector store in sync.
+#[derive(Clone)]
+pub struct KnowledgeIngestor {
+    pool: PgPool,
+    store: Option<Arc<dyn VectorStore>>,
+    config: ChunkingConfig,
+}
+
+impl KnowledgeIngestor {
+    pub fn new(pool: PgPool, store: Option<Arc<dyn VectorStore>>) -> Self {
+        Self {
+            pool,
+            store,
+            config: ChunkingConfig::default(),
+        }
+    }
+
+    /// Store an extracted document for indexing. Returns the existing document
+    /// (and `false`) when identical content was already uploaded to the same course.
+    pub async fn create_document(
+        &self,
+        title: &str,
+        source_type: &str,
+        extracted: &ExtractedDocument,
+        uploaded_by: Option<i64>,
+        course_id: Option<&str>,
+    ) -> Result<(KnowledgeDocument, bool)> {
+        let content = normalize_text(&extracted.plain_text());
+        if content.is_empty() {
+            anyhow::bail!("Document has no text content");
+        }
+        let hash = content_hash(&content);
+
+        // The partial unique index on (content_hash, course) settles concurrent
+        // uploads of the same file: one inserts, the others get the winner back
+        let inserted = sqlx::query_as::<_, KnowledgeDocument>(&format!(
+            r#"
+            INSERT INTO knowledge_documents
+            (id, title, source_type, content, content_hash, status, uploaded_by, blocks, course_id)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            ON CONFLICT (content_hash, COALESCE(course_id, '')) WHERE status <> 'failed'
+            DO NOTHING
+            RETURNING {}
+            "#,
+            DOCUMENT_COLUMNS
+        ))
+        .bind(Uuid::new_v4())
+        .bind(title)
+        .bind(source_type)
+        .bind(&content)
+        .bind(&hash)
+        .bind(DocumentStatus::Pending.as_str())
+        .bind(uploaded_by)
+        .bind(serde_json::to_value(&extracted.blocks)?)
+        .bind(course_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        if let Some(doc) = inserted {
+            return Ok((doc, true));
+        }
+
+        let existing = sqlx::query_as::<_, KnowledgeDocument>(&format!(
+            r#"
+            SELECT {} FROM knowledge_documents
+            WHERE content_hash = $1 AND COALESCE(course_id, '') = COALESCE($2, '') AND status <> 'failed'
+            "#,
+            DOCUMENT_COLUMNS
+        ))
+        .bind(&hash)
+        .bind(course_id)
+        .fetch_optional(&self.pool)
+        .await?
+        // The conflicting upload failed in between; the client can simply retry
+        .ok_or_else(|| anyhow::anyhow!("A concurrent upload of this document failed"))?;
+        log::info!(
+            "Knowledge upload '{}' duplicates document {}",
+            title,
+            existing.id
+        );
+        Ok((existing, false))
+    }
+
+    /// Chunk and index a stored document, replacing any previous chunks.
+    /// Failures are recorded on the document row rather than returned.
+    pub async fn index_document(&self, document_id: Uuid) {
+        if let Err(e) = self.try_index_document(document_id).await {
+            log::error!("Failed to index knowledge document {}: {}", document_id, e);
+            let _ = sqlx::query(
+                "UPDATE knowledge_documents SET status = $2, error = $3, updated_at = NOW() WHERE id = $1",
+            )
+            .bind(document_id)
+            .bind(DocumentStatus::Failed.as_str())
+            .bind(e.to_string())
+            .execute(&self.pool)
+            .await;
+        }
+    }
+
+    async fn try_index_document(&self, document_id: Uuid) -> Result<()> {
+        let (title, source_type, content, blocks, course_id): (
+            String,
+            String,
+            String,
+            Option<serde_json::Value>,
+            Option<String>,
+        ) = sqlx::query_as(
+            r#"
+            UPDATE knowledge_documents SET status = $2, error = NULL, updated_at = NOW()
+            WHERE id = $1
+            RETURNING title, source_type, content, blocks, course_id
+            "#,
+        )
+        .bind(document_id)
+        .bind(DocumentStatus::Processing.as_str())
+        .fetch_one(&self.pool)
+        .await?;
+
+        self.remove_chunks(document_id).await?;
+
+        let mut seen = HashSet::new();
+        // Extracted blocks keep page numbers; older rows only have plain text
+        let blocks: Vec<TextBlock> = match blocks {
+            Some(blocks) => serde_json::from_value(blocks)?,
+            None => blocks_from_text(&content, None),
+        };
+        let chunks: Vec<Chunk> = chunk_blocks(&blocks, &self.config)
+            .into_iter()
+            .filter(|chunk| seen.insert(chunk.content_hash.clone()))
+            .collect();
+
+        let mut tx = self.pool.begin().await?;
+        let mut vectors = Vec::with_capacity(chunks.len());
+        for chunk in &chunks {
+            let chunk_id = Uuid::new_v4();
+            let metadata = serde_json::json!({
+                "document_id": document_id,
+                "title": title,
+                "source_type": source_type,
+                "chunk_index": chunk.index,
+                "heading": chunk.heading,
+                "page": chunk.page,
+                "token_count": chunk.token_count,
+                "course_id": course_id,
+            });
+            sqlx::query(
+                r#"
+                INSERT INTO knowledge_sources
+                (id, title, content, source_type, metadata, document_id, chunk_index, content_hash)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+                "#,
+            )
+            .bind(chunk_id)
+            .bind(&title)
+            .bind(&chunk.text)
+            .bind(&source_type)
+            .bind(&metadata)
+            .bind(document_id)
+            .bind(chunk.index as i32)
+            .bind(&chunk.content_hash)
+            .execute(&mut *tx)
+            .await?;
+
+            vectors.push(Document {
+                id: chunk_id.to_string(),
+                text: chunk.text.clone(),
+                metadata: metadata.to_string(),
+            });
+        }
+        tx.commit().await?;
+
+        if let Some(store) = &self.store {
+            store.add_documents(vectors).await?;
+        }
+
+        sqlx::query(
+            "UPDATE knowledge_documents SET status = $2, chunk_count = $3, updated_at = NOW() WHERE id = $1",
+        )
+        .bind(document_id)
+        .bind(DocumentStatus::Indexed.as_str())
+        .bind(chunks.len() as i32)
+        .execute(&self.pool)
+        .await?;
+
+        log::info!(
+            "Indexed knowledge document {} into {} chunks",
+            document_id,
+            chunks.len()
+        );
+        Ok(())
+    }
+
+    async fn remove_chunks(&self, document_id: Uuid) -> Result<()> {
+        let chunk_ids: Vec<Uuid> =
+            sqlx::query_scalar("DELETE FROM knowledge_sources WHERE document_id = $1 RETURNING id")
+                .bind(document_id)
+                .fetch_all(&self.pool)
+                .await?;
+        if let Some(store) = &self.store {
+            for id in chunk_ids {
+                store.delete_document(&id.to_string()).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Delete a document with its chunks and vectors. Returns false if unknown.
+    pub async fn delete_document(&self, document_id: Uuid) -> Result<bool> {
+        self.remove_chunks(document_id).await?;
+        let result = sqlx::query("DELETE FROM knowledge_documents WHERE id = $1")
+            .bind(document_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn get_document(&self, document_id: Uuid) -> Result<Option<KnowledgeDocument>> {
+        Ok(sqlx::query_as::<_, KnowledgeDocument>(&format!(
+            "SELECT {} FROM knowledge_documents WHERE id = $1",
+            DOCUMENT_COLUMNS
+        ))
+        .bind(document_id)
+        .fetch_optional(&self.pool)
+        .await?)
+    }
+
+    pub async fn list_documents(&self) -> Result<Vec<KnowledgeDocument>> {
+        Ok(sqlx::query_as::<_, KnowledgeDocument>(&format!(
+            "SELECT {} FROM knowledge_documents ORDER BY created_at DESC",
+            DOCUMENT_COLUMNS
+        ))
+        .fetch_all(&self.pool)
+        .await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_joins_wrapped_lines() {
+        let raw =
+            "Photo-\nsynthesis turns light\r\ninto sugar.\n\n\n\n# Cells\nThe   cell is small.";
+        assert_eq!(
+            normalize_text(raw),
+            "Photosynthesis turns light into sugar.\n\n# Cells\n\nThe cell is small."
+        );
+    }
+
+    #[test]
+    fn test_split_sentences_keeps_abbreviations_and_decimals() {
+        let sentences =
+            split_sentences("Dr. Smith measured 9.8 m/s. That is gravity! Is it constant? Yes.");
+        assert_eq!(
+            sentences,
+            vec![
+                "Dr. Smith measured 9.8 m/s.",
+                "That is gravity!",
+                "Is it constant?",
+                "Yes."
+            ]
+        );
+    }
+
+    #[test]
+    fn test_chunks_respect_headings_and_budget() {
+        let text = normalize_text(
+            "# Momentum\nMomentum is mass times velocity. It is conserved in collisions.\n\n# Friction\nFriction opposes motion.",
+        );
+        let chunks = chunk_document(&text, &ChunkingConfig::default());
+
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[0].heading.as_deref(), Some("Momentum"));
+        assert!(chunks[0].text.contains("conserved"));
+        assert!(!chunks[0].text.contains("Friction"));
+        assert_eq!(chunks[1].heading.as_deref(), Some("Friction"));
+    }
+
+    #[test]
+    fn test_chunks_overlap_by_whole_sentences() {
+        let text = (1..=12)
+            .map(|i| format!("Sentence number {} has exactly six words.", i))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let config = ChunkingConfig {
+            max_tokens: 40,
+            overlap_tokens: 12,
+        };
+        let chunks = chunk_document(&text, &config);
+
+        assert!(chunks.len() > 2);
+        for pair in chunks.windows(2) {
+            assert!(pair[0].token_count <= config.max_tokens);
+            let last_sentence = split_sentences(&pair[0].text).pop().unwrap();
+            assert!(pair[1].text.starts_with(&last_sentence));
+        }
+        assert!(chunks
+            .last()
+            .unwrap()
+            .text
+            .ends_with("Sentence number 12 has exactly six words."));
+    }
+
+    #[test]
+    fn test_chunks_keep_page_of_first_sentence() {
+        let blocks = vec![
+            TextBlock::heading(1, "Newton's Laws", Some(1)),
+            TextBlock::paragraph("Force equals mass times acceleration.", Some(1)),
+            TextBlock::paragraph("Every action has an equal and opposite reaction.", Some(2)),
+        ];
+        let config = ChunkingConfig {
+            max_tokens: 12,
+            overlap_tokens: 0,
+        };
+        let chunks = chunk_blocks(&blocks, &config);
+
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[0].page, Some(1));
+        assert_eq!(chunks[1].page, Some(2));
+        assert!(chunks
+            .iter()
+            .all(|c| c.heading.as_deref() == Some("Newton's Laws")));
+    }
+
+    #[test]
+    fn test_long_sentence_is_split() {
+        let text = vec!["word"; 500].join(" ");
+        let chunks = chunk_document(&text, &ChunkingConfig::default());
+        assert!(chunks.len() > 1);
+        assert!(chunks.iter().all(|c| c.token_count <= 400));
+    }
+
+    #[test]
+    fn test_content_hash_is_stable() {
+        assert_eq!(content_hash("abc"), content_hash("abc"));
+        assert_ne!(content_hash("abc"), content_hash("abd"));
+        assert_eq!(content_hash("abc").len(), 64);
+    }
+}
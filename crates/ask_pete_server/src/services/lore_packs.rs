@@ -0,0 +1,84 @@
+//! Lore pack loading and per-campaign / per-graph selection.
+
+use anyhow::Result;
+use infra_ai::lore::LoreRegistry;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::path::Path;
+use uuid::Uuid;
+
+/// What a lore pack assignment applies to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LoreScope {
+    Campaign,
+    Graph,
+}
+
+impl LoreScope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Campaign => "campaign",
+            Self::Graph => "graph",
+        }
+    }
+}
+
+/// The Iron Network plus the packs in `dir`; falls back to the Iron Network
+/// alone if a file there fails to load.
+pub fn load_registry(dir: impl AsRef<Path>) -> LoreRegistry {
+    let dir = dir.as_ref();
+    match LoreRegistry::load_dir(dir) {
+        Ok(registry) => registry,
+        Err(e) => {
+            eprintln!(
+                "⚠️ [Lore] Failed to load packs from {:?}: {:#}. Using the Iron Network.",
+                dir, e
+            );
+            LoreRegistry::builtin()
+        }
+    }
+}
+
+pub async fn assign_pack(
+    pool: &PgPool,
+    scope: LoreScope,
+    scope_id: &str,
+    pack_id: &str,
+) -> Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO lore_pack_assignments (scope, scope_id, pack_id)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (scope, scope_id) DO UPDATE SET pack_id = $3, updated_at = NOW()
+        "#,
+    )
+    .bind(scope.as_str())
+    .bind(scope_id)
+    .bind(pack_id)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// The pack assigned to the graph, else to the campaign. `None` means the
+/// Iron Network.
+pub async fn resolve_pack_id(
+    pool: &PgPool,
+    graph_id: Option<i32>,
+    campaign_id: Option<Uuid>,
+) -> Result<Option<String>> {
+    let pack_id: Option<String> = sqlx::query_scalar(
+        r#"
+        SELECT pack_id FROM lore_pack_assignments
+        WHERE (scope = 'graph' AND scope_id = $1) OR (scope = 'campaign' AND scope_id = $2)
+        ORDER BY (scope = 'graph') DESC
+        LIMIT 1
+        "#,
+    )
+    .bind(graph_id.map(|id| id.to_string()))
+    .bind(campaign_id.map(|id| id.to_string()))
+    .fetch_optional(pool)
+    .await?;
+    Ok(pack_id)
+}
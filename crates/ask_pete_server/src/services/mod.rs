@@ -23,12 +23,24 @@
 //! Contains standalone services that aren't tied to specific features:
 //! - Model Manager: Downloads and caches AI models from HuggingFace
 //! - Pete: AI teacher assistant using RAG (Retrieval-Augmented Generation)
+//! - Pedagogy Lint: rule-based review of story graphs for Pete's suggestions
+//! - Vocabulary Packs: seeding domain word lists and per-course/graph selection
+//! - Lore Packs: story worlds and Pete personas, selected per campaign/graph
+//! - Response Cache: reuses model replies for deterministic prompts
+//! - Model Recommender: sizes catalog models and quantizations to this machine
 
 pub mod chat_queue;
 pub mod downloader;
+pub mod extractors;
+pub mod knowledge_ingest;
+pub mod lore_packs;
 pub mod model_manager;
+pub mod model_recommender;
 pub mod model_registry; // [NEW]
 pub mod notebook_lm;
+pub mod pedagogy_lint;
 pub mod pete; // [NEW]
 pub mod recharge_center;
+pub mod response_cache;
+pub mod vocabulary_packs;
 pub mod weigh_station; // [NEW]
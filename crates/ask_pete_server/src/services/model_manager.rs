@@ -1,9 +1,11 @@
 use anyhow::{Context, Result};
 use hf_hub::api::sync::ApiBuilder;
+use infra_services::downloader::{DownloadRequest, Downloader, ModelStore};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
 use std::path::PathBuf;
+use std::sync::atomic::AtomicBool;
 
 /// Manages AI model downloads from HuggingFace and local caching
 ///
@@ -14,6 +16,8 @@ use std::path::PathBuf;
 /// - Recommending models based on scenario complexity
 pub struct ModelManager {
     cache_dir: PathBuf,
+    /// Where GGUF models are downloaded, within the configured disk quota
+    store: ModelStore,
     downloaded_models: HashMap<String, ModelInfo>,
 }
 
@@ -27,7 +31,9 @@ pub struct ModelInfo {
     pub recommended_for: Vec<String>,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+/// Ordered from lightest to heaviest, so roles can accept "this or simpler"
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum PluginComplexity {
     Simple,  // VaaM only, basic scenarios
     Medium,  // VaaM + Hero's Journey, standard narratives
@@ -42,10 +48,18 @@ impl ModelManager {
 
         Ok(Self {
             cache_dir,
+            store: ModelStore::from_env("models"),
             downloaded_models: HashMap::new(),
         })
     }
 
+    /// Download into `store` (share the download queue's store so both count
+    /// against the same quota and reservations)
+    pub fn with_store(mut self, store: ModelStore) -> Self {
+        self.store = store;
+        self
+    }
+
     /// Get the default cache directory for models
     /// Uses ~/.cache/askpeet/models on Unix-like systems
     /// Uses %LOCALAPPDATA%\askpeet\models on Windows
@@ -71,6 +85,7 @@ impl ModelManager {
             ModelDefinition {
                 id: "bartowski/gemma-2-2b-it-GGUF".to_string(),
                 filename: "gemma-2-2b-it-Q4_K_M.gguf".to_string(),
+                sha256: None,
                 alias: "pete".to_string(),
                 size_mb: 1500,
                 description: "Lightweight model for Pete teacher assistant (Gemma 2 2B)"
@@ -81,6 +96,7 @@ impl ModelManager {
             ModelDefinition {
                 id: "bartowski/gemma-2-9b-it-GGUF".to_string(),
                 filename: "gemma-2-9b-it-Q4_K_M.gguf".to_string(),
+                sha256: None,
                 alias: "narrator".to_string(),
                 size_mb: 6000,
                 description: "Balanced model for student AI narrator (Gemma 2 9B)".to_string(),
@@ -90,6 +106,7 @@ impl ModelManager {
             ModelDefinition {
                 id: "bartowski/gemma-2-27b-it-GGUF".to_string(),
                 filename: "gemma-2-27b-it-Q4_K_M.gguf".to_string(),
+                sha256: None,
                 alias: "advanced".to_string(),
                 size_mb: 18000,
                 description: "Powerful model for complex simulations (Gemma 2 27B)".to_string(),
@@ -99,27 +116,35 @@ impl ModelManager {
         ]
     }
 
-    /// Download a model from HuggingFace
+    /// Download a model from HuggingFace into the model store. The download
+    /// resumes after failures, reserves its size against the store's quota
+    /// up front and is checked against `model_def.sha256` (or, without one,
+    /// the SHA-256 the Hub publishes). A model already in the store was
+    /// verified when it was downloaded and is reused.
     pub async fn download_model(&mut self, model_def: &ModelDefinition) -> Result<PathBuf> {
-        log::info!(
-            "Downloading model: {} (alias: {})",
-            model_def.id,
-            model_def.alias
-        );
-
-        // Build HuggingFace API client
-        let api = ApiBuilder::new()
-            .with_cache_dir(self.cache_dir.clone())
-            .build()?;
-
-        // Get the model from HuggingFace
-        let repo = api.model(model_def.id.clone());
-        let filename = model_def.filename.clone();
-
-        // Download the GGUF file (quantized model format)
-        let model_path = tokio::task::spawn_blocking(move || repo.get(&filename)).await??;
-
-        log::info!("Model downloaded to: {:?}", model_path);
+        let model_path = self.store.path_for(&model_def.filename)?;
+        if !model_path.is_file() {
+            log::info!(
+                "Downloading model: {} (alias: {})",
+                model_def.id,
+                model_def.alias
+            );
+            let endpoint = std::env::var("HF_ENDPOINT")
+                .unwrap_or_else(|_| "https://huggingface.co".to_string());
+            let url = format!(
+                "{}/{}/resolve/main/{}",
+                endpoint.trim_end_matches('/'),
+                model_def.id,
+                model_def.filename
+            );
+            let request = DownloadRequest::new(url, &model_path)
+                .with_sha256(model_def.sha256.clone())
+                .with_size(model_def.size_mb as u64 * 1024 * 1024);
+            Downloader::new(self.store.clone())
+                .fetch(0, &request, &AtomicBool::new(false), |_| {})
+                .await?;
+            log::info!("Model downloaded to: {:?}", model_path);
+        }
 
         // Cache model info
         let model_info = ModelInfo {
@@ -137,6 +162,71 @@ impl ModelManager {
         Ok(model_path)
     }
 
+    /// Forget a downloaded model and remove its weights from the cache.
+    /// Returns false when the alias was never downloaded.
+    pub fn delete_model(&mut self, alias: &str) -> Result<bool> {
+        let Some(info) = self.downloaded_models.remove(alias) else {
+            return Ok(false);
+        };
+
+        let filename = info
+            .path
+            .as_ref()
+            .filter(|path| path.parent() == Some(self.store.dir()))
+            .and_then(|path| path.file_name())
+            .map(|name| name.to_string_lossy().into_owned());
+        if let Some(filename) = filename {
+            // Also drops a partial download and the checksum manifest entry
+            self.store.delete(&filename)?;
+        } else if let Some(path) = info.path {
+            // HF snapshots are symlinks into blobs/, so remove both
+            let blob = fs::canonicalize(&path).ok();
+            if path.exists() || path.is_symlink() {
+                fs::remove_file(&path)?;
+            }
+            if let Some(blob) = blob.filter(|blob| *blob != path && blob.exists()) {
+                fs::remove_file(blob)?;
+            }
+        }
+
+        log::info!("Deleted model: {}", alias);
+        Ok(true)
+    }
+
+    /// Free space left on the disk holding the cache, if it can be determined
+    pub fn free_space_bytes(&self) -> Option<u64> {
+        infra_services::downloader::free_space_bytes(&self.cache_dir)
+    }
+
+    /// Root of the HuggingFace cache used for all model downloads
+    pub fn cache_dir(&self) -> &std::path::Path {
+        &self.cache_dir
+    }
+
+    /// Download a BERT-family encoder (sentence embedder or cross-encoder
+    /// reranker: config, tokenizer and safetensors weights) and return the
+    /// snapshot directory that holds them.
+    pub async fn download_embedding_model(&self, model_id: &str) -> Result<PathBuf> {
+        log::info!("Downloading embedding model: {}", model_id);
+
+        let api = ApiBuilder::new()
+            .with_cache_dir(self.cache_dir.clone())
+            .build()?;
+        let repo = api.model(model_id.to_string());
+
+        let weights = tokio::task::spawn_blocking(move || -> Result<PathBuf> {
+            repo.get("config.json")?;
+            repo.get("tokenizer.json")?;
+            Ok(repo.get("model.safetensors")?)
+        })
+        .await??;
+
+        weights
+            .parent()
+            .map(|dir| dir.to_path_buf())
+            .context("Embedding model has no snapshot directory")
+    }
+
     /// Check if a model is already downloaded
     pub fn has_model(&self, alias: &str) -> bool {
         self.downloaded_models
@@ -192,6 +282,9 @@ impl ModelManager {
 pub struct ModelDefinition {
     pub id: String,
     pub filename: String,
+    /// Expected SHA-256 of `filename`, where the publisher lists one
+    #[serde(default)]
+    pub sha256: Option<String>,
     pub alias: String,
     pub size_mb: usize,
     pub description: String,
@@ -0,0 +1,676 @@
+use crate::services::model_manager::{ModelDefinition, ModelManager, PluginComplexity};
+use anyhow::{Context, Result};
+use candle_core::quantized::gguf_file::{Content, Value};
+use infra_services::hardware_scanner::{HardwareScanner, SystemSpecs};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+const GIB: f64 = 1024.0 * 1024.0 * 1024.0;
+
+/// Tokenizer, scratch buffers and the runtime itself, on top of weights and KV cache
+const RUNTIME_OVERHEAD_BYTES: u64 = 384 * 1024 * 1024;
+
+/// Memory left for the OS and the rest of the server before we call it swapping
+const SWAP_HEADROOM_BYTES: u64 = 512 * 1024 * 1024;
+
+/// KV cache per token when the GGUF header isn't on disk yet; typical of
+/// the 2B–30B grouped-query models in the catalog
+const FALLBACK_KV_BYTES_PER_TOKEN: u64 = 256 * 1024;
+
+/// CPU decoding is memory-bound: every token streams the whole weight file
+/// once, so throughput is roughly bandwidth / weight bytes.
+const BANDWIDTH_PER_CORE: f64 = 2.5e9;
+const MAX_CPU_BANDWIDTH: f64 = 40e9;
+
+/// GGUF quantizations published for the catalog models, lowest quality first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub enum Quantization {
+    #[serde(rename = "Q2_K")]
+    Q2K,
+    #[serde(rename = "Q3_K_M")]
+    Q3KM,
+    #[serde(rename = "Q4_K_M")]
+    Q4KM,
+    #[serde(rename = "Q5_K_M")]
+    Q5KM,
+    #[serde(rename = "Q6_K")]
+    Q6K,
+    #[serde(rename = "Q8_0")]
+    Q8,
+}
+
+impl Quantization {
+    pub const ALL: [Quantization; 6] = [
+        Quantization::Q2K,
+        Quantization::Q3KM,
+        Quantization::Q4KM,
+        Quantization::Q5KM,
+        Quantization::Q6K,
+        Quantization::Q8,
+    ];
+
+    /// The tag used in GGUF file names, e.g. "Q4_K_M"
+    pub fn label(self) -> &'static str {
+        match self {
+            Quantization::Q2K => "Q2_K",
+            Quantization::Q3KM => "Q3_K_M",
+            Quantization::Q4KM => "Q4_K_M",
+            Quantization::Q5KM => "Q5_K_M",
+            Quantization::Q6K => "Q6_K",
+            Quantization::Q8 => "Q8_0",
+        }
+    }
+
+    /// Average bits per weight including block scales (llama.cpp figures)
+    pub fn bits_per_weight(self) -> f64 {
+        match self {
+            Quantization::Q2K => 3.35,
+            Quantization::Q3KM => 3.91,
+            Quantization::Q4KM => 4.85,
+            Quantization::Q5KM => 5.69,
+            Quantization::Q6K => 6.59,
+            Quantization::Q8 => 8.5,
+        }
+    }
+
+    /// Reads the tag out of a file name like "gemma-2-2b-it-Q4_K_M.gguf"
+    pub fn from_filename(filename: &str) -> Option<Self> {
+        let upper = filename.to_uppercase();
+        Self::ALL
+            .into_iter()
+            .find(|quant| upper.contains(quant.label()))
+    }
+
+    /// Maps the `general.file_type` header (llama.cpp `llama_ftype`)
+    pub fn from_file_type(file_type: u64) -> Option<Self> {
+        match file_type {
+            10 => Some(Quantization::Q2K),
+            12 => Some(Quantization::Q3KM),
+            15 => Some(Quantization::Q4KM),
+            17 => Some(Quantization::Q5KM),
+            18 => Some(Quantization::Q6K),
+            7 => Some(Quantization::Q8),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProfileSource {
+    /// Read from the GGUF header of a downloaded file
+    Gguf,
+    /// Derived from the catalog's advertised download size
+    Catalog,
+}
+
+/// What the estimates need to know about a model, independent of quantization
+#[derive(Debug, Clone, Serialize)]
+pub struct ModelProfile {
+    pub parameter_count: u64,
+    /// Quantization of the file this profile was taken from
+    pub quantization: Option<Quantization>,
+    /// Exact weight size for that quantization
+    pub weight_bytes: u64,
+    pub kv_bytes_per_token: u64,
+    /// Longest context the model was trained for
+    pub context_length: Option<u64>,
+    pub source: ProfileSource,
+}
+
+impl ModelProfile {
+    /// Reads only the header and tensor table, not the weights
+    pub fn from_gguf(path: &Path) -> Result<Self> {
+        let mut file =
+            std::fs::File::open(path).with_context(|| format!("Cannot open {}", path.display()))?;
+        let weight_bytes = file.metadata()?.len();
+        let content = Content::read(&mut file)
+            .with_context(|| format!("Invalid GGUF header in {}", path.display()))?;
+
+        let parameter_count = content
+            .tensor_infos
+            .values()
+            .map(|info| info.shape.elem_count() as u64)
+            .sum();
+
+        let arch = match content.metadata.get("general.architecture") {
+            Some(Value::String(arch)) => arch.clone(),
+            _ => "llama".to_string(),
+        };
+        let meta = |key: &str| {
+            content
+                .metadata
+                .get(&format!("{}.{}", arch, key))
+                .and_then(value_u64)
+        };
+
+        // K and V, f16, for every layer: heads_kv * head_dim wide
+        let kv_bytes_per_token = match (meta("block_count"), meta("attention.head_count_kv")) {
+            (Some(layers), Some(kv_heads)) => {
+                let head_dim = meta("attention.key_length").or_else(|| {
+                    let heads = meta("attention.head_count")?;
+                    Some(meta("embedding_length")? / heads.max(1))
+                });
+                head_dim.map(|head_dim| 2 * layers * kv_heads * head_dim * 2)
+            }
+            _ => None,
+        };
+
+        let quantization = content
+            .metadata
+            .get("general.file_type")
+            .and_then(value_u64)
+            .and_then(Quantization::from_file_type)
+            .or_else(|| {
+                path.file_name()
+                    .and_then(|name| Quantization::from_filename(&name.to_string_lossy()))
+            });
+
+        Ok(Self {
+            parameter_count,
+            quantization,
+            weight_bytes,
+            kv_bytes_per_token: kv_bytes_per_token.unwrap_or(FALLBACK_KV_BYTES_PER_TOKEN),
+            context_length: meta("context_length"),
+            source: ProfileSource::Gguf,
+        })
+    }
+
+    /// Back-of-envelope profile for a model that hasn't been downloaded
+    pub fn from_catalog(definition: &ModelDefinition) -> Self {
+        let quantization =
+            Quantization::from_filename(&definition.filename).unwrap_or(Quantization::Q4KM);
+        let weight_bytes = definition.size_mb as u64 * 1024 * 1024;
+
+        Self {
+            parameter_count: (weight_bytes as f64 * 8.0 / quantization.bits_per_weight()) as u64,
+            quantization: Some(quantization),
+            weight_bytes,
+            kv_bytes_per_token: FALLBACK_KV_BYTES_PER_TOKEN,
+            context_length: None,
+            source: ProfileSource::Catalog,
+        }
+    }
+
+    fn weight_bytes_at(&self, quantization: Option<Quantization>) -> u64 {
+        match quantization {
+            Some(quant) if Some(quant) != self.quantization => {
+                (self.parameter_count as f64 * quant.bits_per_weight() / 8.0) as u64
+            }
+            _ => self.weight_bytes,
+        }
+    }
+}
+
+fn value_u64(value: &Value) -> Option<u64> {
+    match value {
+        Value::U8(v) => Some(*v as u64),
+        Value::U16(v) => Some(*v as u64),
+        Value::U32(v) => Some(*v as u64),
+        Value::U64(v) => Some(*v),
+        Value::I32(v) => u64::try_from(*v).ok(),
+        Value::I64(v) => u64::try_from(*v).ok(),
+        _ => None,
+    }
+}
+
+/// The jobs local models do for the server
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ModelRole {
+    /// Live chat with students; must answer quickly
+    Navigator,
+    /// Curriculum blueprints; long JSON output, runs in the background
+    Architect,
+    /// Vocabulary and node weighing; short structured replies
+    WeighStation,
+}
+
+impl ModelRole {
+    pub const ALL: [ModelRole; 3] = [
+        ModelRole::Navigator,
+        ModelRole::Architect,
+        ModelRole::WeighStation,
+    ];
+
+    /// Heaviest catalog model worth spending on the role
+    pub fn complexity(self) -> PluginComplexity {
+        match self {
+            ModelRole::Navigator => PluginComplexity::Simple,
+            ModelRole::WeighStation => PluginComplexity::Medium,
+            ModelRole::Architect => PluginComplexity::Complex,
+        }
+    }
+
+    /// Slowest generation that still feels usable for the role
+    pub fn min_tokens_per_sec(self) -> f64 {
+        match self {
+            ModelRole::Navigator => 8.0,
+            ModelRole::WeighStation => 4.0,
+            ModelRole::Architect => 2.0,
+        }
+    }
+
+    /// Below this the Architect's JSON falls apart; chat tolerates more loss
+    pub fn min_quantization(self) -> Quantization {
+        match self {
+            ModelRole::Architect => Quantization::Q4KM,
+            _ => Quantization::Q3KM,
+        }
+    }
+
+    /// Context the role is run with, which sizes the KV cache
+    pub fn context_tokens(self) -> u64 {
+        match self {
+            ModelRole::Navigator => 2048,
+            ModelRole::WeighStation => 4096,
+            ModelRole::Architect => infra_ai::iron_split::IRON_SPLIT_CONTEXT_WINDOW as u64,
+        }
+    }
+}
+
+/// A catalog entry plus where its weights live locally, if downloaded
+#[derive(Debug, Clone)]
+pub struct CatalogModel {
+    pub definition: ModelDefinition,
+    pub complexity: PluginComplexity,
+    pub path: Option<PathBuf>,
+}
+
+impl CatalogModel {
+    /// Pairs each catalog model with the complexity `recommend_model` assigns
+    /// it, looking for weights in the HF cache and then in `models_dir`.
+    pub fn from_manager(manager: &ModelManager, models_dir: &Path) -> Vec<Self> {
+        let complexities = [
+            PluginComplexity::Simple,
+            PluginComplexity::Medium,
+            PluginComplexity::Complex,
+        ];
+
+        ModelManager::list_available_models()
+            .into_iter()
+            .filter_map(|definition| {
+                let complexity = complexities
+                    .into_iter()
+                    .find(|c| manager.recommend_model(*c) == definition.alias)?;
+                let path = manager
+                    .get_model_path(&definition.alias)
+                    .cloned()
+                    .or_else(|| Some(models_dir.join(&definition.filename)))
+                    .filter(|path| path.is_file());
+                Some(Self {
+                    definition,
+                    complexity,
+                    path,
+                })
+            })
+            .collect()
+    }
+}
+
+/// One model at one quantization on this machine
+#[derive(Debug, Clone, Serialize)]
+pub struct ModelEstimate {
+    pub alias: String,
+    pub repo_id: String,
+    /// File to download for this quantization
+    pub filename: String,
+    pub quantization: Option<Quantization>,
+    pub complexity: PluginComplexity,
+    pub parameters_b: f64,
+    pub context_tokens: u64,
+    pub ram_bytes: u64,
+    pub tokens_per_sec: f64,
+    pub fits_in_memory: bool,
+    pub installed: bool,
+    pub source: ProfileSource,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RoleRecommendation {
+    pub role: ModelRole,
+    pub pick: Option<ModelEstimate>,
+    pub reason: String,
+    /// Set when the pick works but misses the role's speed target
+    pub warning: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RecommendationReport {
+    pub specs: SystemSpecs,
+    pub roles: Vec<RoleRecommendation>,
+    /// Every catalog model at every quantization, sized for the Weigh Station's context
+    pub candidates: Vec<ModelEstimate>,
+}
+
+struct Profiled {
+    model: CatalogModel,
+    profile: ModelProfile,
+}
+
+/// Matches catalog models and quantizations to the detected hardware
+pub struct ModelRecommender {
+    specs: SystemSpecs,
+    catalog: Vec<Profiled>,
+}
+
+impl ModelRecommender {
+    /// Profiles each model from its GGUF header when downloaded, from the
+    /// catalog size otherwise. Reads files, so call off the async runtime.
+    pub fn new(specs: SystemSpecs, catalog: Vec<CatalogModel>) -> Self {
+        let catalog = catalog
+            .into_iter()
+            .map(|model| {
+                let profile = model
+                    .path
+                    .as_deref()
+                    .and_then(|path| match ModelProfile::from_gguf(path) {
+                        Ok(profile) => Some(profile),
+                        Err(e) => {
+                            log::warn!("Falling back to catalog size: {:#}", e);
+                            None
+                        }
+                    })
+                    .unwrap_or_else(|| ModelProfile::from_catalog(&model.definition));
+                Profiled { model, profile }
+            })
+            .collect();
+
+        Self { specs, catalog }
+    }
+
+    pub fn specs(&self) -> &SystemSpecs {
+        &self.specs
+    }
+
+    fn estimate(
+        &self,
+        entry: &Profiled,
+        quantization: Option<Quantization>,
+        context_tokens: u64,
+    ) -> ModelEstimate {
+        let definition = &entry.model.definition;
+        let profile = &entry.profile;
+        let context_tokens = profile
+            .context_length
+            .map_or(context_tokens, |max| context_tokens.min(max));
+
+        let weight_bytes = profile.weight_bytes_at(quantization);
+        let ram_bytes =
+            weight_bytes + profile.kv_bytes_per_token * context_tokens + RUNTIME_OVERHEAD_BYTES;
+
+        let own_file = quantization == profile.quantization;
+        let filename = match (quantization, profile.quantization) {
+            (Some(quant), Some(own)) if !own_file => {
+                definition.filename.replace(own.label(), quant.label())
+            }
+            _ => definition.filename.clone(),
+        };
+
+        ModelEstimate {
+            alias: definition.alias.clone(),
+            repo_id: definition.id.clone(),
+            filename,
+            quantization,
+            complexity: entry.model.complexity,
+            parameters_b: profile.parameter_count as f64 / 1e9,
+            context_tokens,
+            ram_bytes,
+            tokens_per_sec: tokens_per_sec(&self.specs, weight_bytes),
+            fits_in_memory: swap_warning(&self.specs, ram_bytes).is_none(),
+            installed: own_file && entry.model.path.is_some(),
+            source: profile.source,
+        }
+    }
+
+    /// Quantizations we can offer: any tag can be swapped in the file name,
+    /// otherwise only the file as published.
+    fn variants(&self, entry: &Profiled) -> Vec<Option<Quantization>> {
+        match entry.profile.quantization {
+            Some(own) if entry.model.definition.filename.contains(own.label()) => {
+                Quantization::ALL.into_iter().map(Some).collect()
+            }
+            own => vec![own],
+        }
+    }
+
+    /// Estimates for every model and quantization at the given context
+    pub fn estimates(&self, context_tokens: u64) -> Vec<ModelEstimate> {
+        self.catalog
+            .iter()
+            .flat_map(|entry| {
+                self.variants(entry)
+                    .into_iter()
+                    .map(move |quant| self.estimate(entry, quant, context_tokens))
+            })
+            .collect()
+    }
+
+    /// Heaviest model the role allows, at the best quantization that fits
+    /// in memory and keeps up with the role's speed target. If nothing is
+    /// fast enough, the fastest variant that fits, with a warning.
+    pub fn recommend(&self, role: ModelRole) -> RoleRecommendation {
+        let mut models: Vec<&Profiled> = self
+            .catalog
+            .iter()
+            .filter(|entry| entry.model.complexity <= role.complexity())
+            .collect();
+        models.sort_by(|a, b| {
+            b.model
+                .complexity
+                .cmp(&a.model.complexity)
+                .then(b.profile.parameter_count.cmp(&a.profile.parameter_count))
+        });
+
+        let mut usable = Vec::new();
+        for entry in models {
+            let mut variants = self.variants(entry);
+            variants.reverse();
+            for quant in variants {
+                if quant.is_some_and(|q| q < role.min_quantization()) {
+                    continue;
+                }
+                let estimate = self.estimate(entry, quant, role.context_tokens());
+                if !estimate.fits_in_memory {
+                    continue;
+                }
+                if estimate.tokens_per_sec >= role.min_tokens_per_sec() {
+                    let reason = format!(
+                        "{} at {} needs ~{:.1} GB and runs ~{:.0} tokens/s",
+                        estimate.alias,
+                        quant.map_or("its published quantization", |q| q.label()),
+                        estimate.ram_bytes as f64 / GIB,
+                        estimate.tokens_per_sec
+                    );
+                    return RoleRecommendation {
+                        role,
+                        pick: Some(estimate),
+                        reason,
+                        warning: None,
+                    };
+                }
+                usable.push(estimate);
+            }
+        }
+
+        match usable
+            .into_iter()
+            .max_by(|a, b| a.tokens_per_sec.total_cmp(&b.tokens_per_sec))
+        {
+            Some(estimate) => RoleRecommendation {
+                role,
+                reason: format!(
+                    "Nothing reaches {:.0} tokens/s here; {} is the fastest model that fits",
+                    role.min_tokens_per_sec(),
+                    estimate.alias
+                ),
+                warning: Some(format!(
+                    "Expect ~{:.1} tokens/s from {}",
+                    estimate.tokens_per_sec, estimate.alias
+                )),
+                pick: Some(estimate),
+            },
+            None => RoleRecommendation {
+                role,
+                pick: None,
+                reason: format!(
+                    "No catalog model fits in {:.1} GB of free memory",
+                    self.specs.available_memory_bytes as f64 / GIB
+                ),
+                warning: None,
+            },
+        }
+    }
+
+    pub fn report(&self) -> RecommendationReport {
+        RecommendationReport {
+            specs: self.specs.clone(),
+            roles: ModelRole::ALL
+                .into_iter()
+                .map(|role| self.recommend(role))
+                .collect(),
+            candidates: self.estimates(ModelRole::WeighStation.context_tokens()),
+        }
+    }
+}
+
+fn tokens_per_sec(specs: &SystemSpecs, weight_bytes: u64) -> f64 {
+    let bandwidth = (specs.cpu_cores as f64 * BANDWIDTH_PER_CORE).min(MAX_CPU_BANDWIDTH);
+    bandwidth / weight_bytes.max(1) as f64
+}
+
+/// Some(message) when loading `ram_bytes` would push the machine into swap
+pub fn swap_warning(specs: &SystemSpecs, ram_bytes: u64) -> Option<String> {
+    let budget = specs
+        .available_memory_bytes
+        .saturating_sub(SWAP_HEADROOM_BYTES);
+    (ram_bytes > budget).then(|| {
+        format!(
+            "needs ~{:.1} GB but only {:.1} GB is free; the OS will swap and generation will crawl",
+            ram_bytes as f64 / GIB,
+            specs.available_memory_bytes as f64 / GIB
+        )
+    })
+}
+
+/// Checks a GGUF file against current free memory before it is loaded.
+/// Scans the hardware, so call it right before each load.
+pub fn load_warning(path: &Path, context_tokens: u64) -> Option<String> {
+    let profile = ModelProfile::from_gguf(path).ok()?;
+    let ram_bytes =
+        profile.weight_bytes + profile.kv_bytes_per_token * context_tokens + RUNTIME_OVERHEAD_BYTES;
+    swap_warning(&HardwareScanner::scan(), ram_bytes)
+        .map(|warning| format!("{}: {}", path.display(), warning))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GB: u64 = 1024 * 1024 * 1024;
+
+    fn specs(available_gb: u64, cpu_cores: usize) -> SystemSpecs {
+        SystemSpecs {
+            total_memory_gb: available_gb,
+            total_memory_bytes: available_gb * GB,
+            available_memory_bytes: available_gb * GB,
+            cpu_cores,
+        }
+    }
+
+    fn catalog() -> Vec<CatalogModel> {
+        let complexities = [
+            PluginComplexity::Simple,
+            PluginComplexity::Medium,
+            PluginComplexity::Complex,
+        ];
+        ModelManager::list_available_models()
+            .into_iter()
+            .zip(complexities)
+            .map(|(definition, complexity)| CatalogModel {
+                definition,
+                complexity,
+                path: None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_quantization_from_filename_and_file_type() {
+        assert_eq!(
+            Quantization::from_filename("gemma-2-9b-it-Q5_K_M.gguf"),
+            Some(Quantization::Q5KM)
+        );
+        assert_eq!(
+            Quantization::from_filename("mistral-7b-instruct-v0.1.q8_0.gguf"),
+            Some(Quantization::Q8)
+        );
+        assert_eq!(Quantization::from_filename("model.safetensors"), None);
+        assert_eq!(Quantization::from_file_type(15), Some(Quantization::Q4KM));
+        assert_eq!(Quantization::from_file_type(1), None);
+    }
+
+    #[test]
+    fn test_estimates_scale_with_quantization() {
+        let recommender = ModelRecommender::new(specs(32, 8), catalog());
+        let pete: Vec<ModelEstimate> = recommender
+            .estimates(2048)
+            .into_iter()
+            .filter(|e| e.alias == "pete")
+            .collect();
+        assert_eq!(pete.len(), Quantization::ALL.len());
+
+        let q4 = pete
+            .iter()
+            .find(|e| e.quantization == Some(Quantization::Q4KM))
+            .unwrap();
+        let q8 = pete
+            .iter()
+            .find(|e| e.quantization == Some(Quantization::Q8))
+            .unwrap();
+        assert_eq!(q4.filename, "gemma-2-2b-it-Q4_K_M.gguf");
+        assert_eq!(q8.filename, "gemma-2-2b-it-Q8_0.gguf");
+        assert!(q8.ram_bytes > q4.ram_bytes);
+        assert!(q8.tokens_per_sec < q4.tokens_per_sec);
+        assert!((2.0..3.0).contains(&q4.parameters_b));
+    }
+
+    #[test]
+    fn test_roles_get_the_heaviest_model_that_keeps_up() {
+        let recommender = ModelRecommender::new(specs(32, 16), catalog());
+
+        let navigator = recommender.recommend(ModelRole::Navigator).pick.unwrap();
+        assert_eq!(navigator.alias, "pete");
+        assert_eq!(navigator.quantization, Some(Quantization::Q8));
+
+        let architect = recommender.recommend(ModelRole::Architect);
+        let pick = architect.pick.unwrap();
+        assert_eq!(pick.alias, "advanced");
+        assert_eq!(pick.quantization, Some(Quantization::Q4KM));
+        assert!(pick.tokens_per_sec >= ModelRole::Architect.min_tokens_per_sec());
+        assert!(architect.warning.is_none());
+    }
+
+    #[test]
+    fn test_small_machine_gets_slow_pick_or_none() {
+        let recommender = ModelRecommender::new(specs(4, 4), catalog());
+
+        let navigator = recommender.recommend(ModelRole::Navigator);
+        let pick = navigator.pick.unwrap();
+        assert_eq!(pick.alias, "pete");
+        assert!(pick.quantization >= Some(ModelRole::Navigator.min_quantization()));
+        assert!(navigator.warning.is_some());
+
+        let architect = recommender.recommend(ModelRole::Architect);
+        assert!(architect.pick.is_none());
+        assert!(architect.reason.contains("No catalog model fits"));
+    }
+
+    #[test]
+    fn test_swap_warning_only_past_free_memory() {
+        let machine = specs(8, 4);
+        assert!(swap_warning(&machine, 4 * GB).is_none());
+        let warning = swap_warning(&machine, 8 * GB).unwrap();
+        assert!(warning.contains("swap"));
+    }
+}
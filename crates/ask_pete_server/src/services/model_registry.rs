@@ -8,6 +8,7 @@ pub fn get_pete_brains() -> Vec<ModelConfig> {
             hf_repo: "bartowski/Llama-3.2-1B-Instruct-GGUF".to_string(),
             filename: "Llama-3.2-1B-Instruct-Q4_K_M.gguf".to_string(),
             size_mb: 800.0,
+            sha256: None,
         },
         ModelConfig {
             name: "Deep Thinker Pete".to_string(),
@@ -16,6 +17,7 @@ pub fn get_pete_brains() -> Vec<ModelConfig> {
             hf_repo: "bartowski/Llama-3.2-3B-Instruct-GGUF".to_string(),
             filename: "Llama-3.2-3B-Instruct-Q4_K_M.gguf".to_string(),
             size_mb: 2200.0,
+            sha256: None,
         },
     ]
 }
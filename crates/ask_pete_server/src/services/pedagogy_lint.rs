@@ -0,0 +1,882 @@
+//! # Pedagogical Linting
+//!
+//! Rule-based checks over a whole story graph, in the order a learner meets
+//! the stations (breadth-first from the entry stations):
+//!
+//! - **Walls of text**: stations (or single paragraphs) too long to read in one go.
+//! - **Vocabulary load**: too many target words introduced at one station.
+//! - **Missing checkpoints**: endings not preceded by a reflection or assessment.
+//! - **Flat branches**: choices whose options lead to near-identical stations or
+//!   rejoin straight away.
+//! - **Garden balance**: Knowledge / Skills / Community activities badly skewed.
+//! - **Accessibility**: images without alt text, vague link text, colour-only
+//!   instructions, centred long text and shouting in capitals.
+//!
+//! Every finding names a station and a concrete fix. `PeteAssistant` may add
+//! LLM-written suggestions on top (see `services::pete`).
+
+use crate::services::pete::{PeteSuggestion, Severity, SuggestionCategory};
+use pete_core::expert::{StoryGraph, StoryNode};
+use regex::Regex;
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+use std::sync::OnceLock;
+
+/// The three activity gardens a station can take part in
+pub const GARDENS: [&str; 3] = ["Knowledge", "Skills", "Community"];
+
+#[derive(Debug, Clone)]
+pub struct LintConfig {
+    /// Words per station before it is a wall of text
+    pub max_words_per_node: usize,
+    /// Words per station before the finding becomes critical
+    pub critical_words_per_node: usize,
+    /// Words in one paragraph without a break
+    pub max_paragraph_words: usize,
+    /// New target vocabulary words per station
+    pub max_new_terms_per_node: usize,
+    /// How many stations before an ending may hold its reflection/assessment
+    pub checkpoint_lookback: usize,
+    /// Word-set similarity above which two branch targets count as the same
+    pub branch_similarity: f32,
+    /// Smallest acceptable share of garden activities for any one garden
+    pub min_garden_share: f32,
+}
+
+impl Default for LintConfig {
+    fn default() -> Self {
+        Self {
+            max_words_per_node: 150,
+            critical_words_per_node: 300,
+            max_paragraph_words: 100,
+            max_new_terms_per_node: 3,
+            checkpoint_lookback: 2,
+            branch_similarity: 0.8,
+            min_garden_share: 0.15,
+        }
+    }
+}
+
+/// Run every rule over `graph`. `vocabulary` is the scenario's target word list.
+/// Findings are ordered by severity, then by where the learner meets them.
+pub fn lint_graph(
+    graph: &StoryGraph,
+    vocabulary: &[String],
+    config: &LintConfig,
+) -> Vec<PeteSuggestion> {
+    let index = GraphIndex::new(graph);
+    let mut findings = Vec::new();
+    findings.extend(walls_of_text(&index, config));
+    findings.extend(vocabulary_load(&index, vocabulary, config));
+    findings.extend(missing_checkpoints(&index, config));
+    findings.extend(flat_branches(&index, config));
+    findings.extend(garden_balance(&index, config));
+    findings.extend(accessibility(&index));
+
+    let position: HashMap<&str, usize> = index
+        .order
+        .iter()
+        .enumerate()
+        .map(|(pos, node)| (node.id.as_str(), pos))
+        .collect();
+    findings.sort_by_key(|s| {
+        (
+            severity_rank(s.severity),
+            s.node_id
+                .as_deref()
+                .and_then(|id| position.get(id).copied())
+                .unwrap_or(usize::MAX),
+        )
+    });
+    findings
+}
+
+fn severity_rank(severity: Severity) -> u8 {
+    match severity {
+        Severity::Critical => 0,
+        Severity::Warning => 1,
+        Severity::Info => 2,
+    }
+}
+
+fn suggestion(
+    node: &StoryNode,
+    rule: &str,
+    category: SuggestionCategory,
+    severity: Severity,
+    message: String,
+    fix: String,
+    source: &str,
+) -> PeteSuggestion {
+    PeteSuggestion {
+        category,
+        severity,
+        message,
+        source: source.to_string(),
+        node_id: Some(node.id.clone()),
+        rule: rule.to_string(),
+        fix,
+    }
+}
+
+// ============================================================================
+// Graph helpers
+// ============================================================================
+
+struct GraphIndex<'a> {
+    /// Stations in learner order; unreachable stations come last
+    order: Vec<&'a StoryNode>,
+    by_id: HashMap<&'a str, &'a StoryNode>,
+    outgoing: HashMap<&'a str, Vec<&'a str>>,
+    incoming: HashMap<&'a str, Vec<&'a str>>,
+}
+
+impl<'a> GraphIndex<'a> {
+    fn new(graph: &'a StoryGraph) -> Self {
+        let by_id: HashMap<&str, &StoryNode> =
+            graph.nodes.iter().map(|n| (n.id.as_str(), n)).collect();
+        let mut outgoing: HashMap<&str, Vec<&str>> = HashMap::new();
+        let mut incoming: HashMap<&str, Vec<&str>> = HashMap::new();
+        for conn in &graph.connections {
+            let (from, to) = (conn.from_node.as_str(), conn.to_node.as_str());
+            if !by_id.contains_key(from) || !by_id.contains_key(to) {
+                continue;
+            }
+            let targets = outgoing.entry(from).or_default();
+            if !targets.contains(&to) {
+                targets.push(to);
+                incoming.entry(to).or_default().push(from);
+            }
+        }
+
+        let mut order = Vec::with_capacity(graph.nodes.len());
+        let mut seen = HashSet::new();
+        let mut queue: VecDeque<&str> = graph
+            .nodes
+            .iter()
+            .map(|n| n.id.as_str())
+            .filter(|id| !incoming.contains_key(id))
+            .collect();
+        // Graphs that are one big cycle still need a start
+        if queue.is_empty() {
+            queue.extend(graph.nodes.first().map(|n| n.id.as_str()));
+        }
+        while let Some(id) = queue.pop_front() {
+            if !seen.insert(id) {
+                continue;
+            }
+            order.push(by_id[id]);
+            queue.extend(outgoing.get(id).into_iter().flatten().copied());
+        }
+        order.extend(graph.nodes.iter().filter(|n| !seen.contains(n.id.as_str())));
+
+        Self {
+            order,
+            by_id,
+            outgoing,
+            incoming,
+        }
+    }
+
+    fn targets(&self, id: &str) -> &[&'a str] {
+        self.outgoing.get(id).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    fn sources(&self, id: &str) -> &[&'a str] {
+        self.incoming.get(id).map(Vec::as_slice).unwrap_or_default()
+    }
+}
+
+fn word_count(text: &str) -> usize {
+    text.split_whitespace().count()
+}
+
+fn word_set(text: &str) -> BTreeSet<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| w.len() > 2)
+        .map(str::to_lowercase)
+        .collect()
+}
+
+fn jaccard(a: &BTreeSet<String>, b: &BTreeSet<String>) -> f32 {
+    if a.is_empty() && b.is_empty() {
+        return 1.0;
+    }
+    a.intersection(b).count() as f32 / a.union(b).count() as f32
+}
+
+/// Case-insensitive whole-word (or whole-phrase) match
+fn mentions(text_lower: &str, term: &str) -> bool {
+    let term = term.trim().to_lowercase();
+    if term.is_empty() {
+        return false;
+    }
+    text_lower.match_indices(&term).any(|(start, _)| {
+        let end = start + term.len();
+        let before = text_lower[..start].chars().next_back();
+        let after = text_lower[end..].chars().next();
+        !before.is_some_and(char::is_alphanumeric) && !after.is_some_and(char::is_alphanumeric)
+    })
+}
+
+// ============================================================================
+// Rules
+// ============================================================================
+
+fn walls_of_text(index: &GraphIndex, config: &LintConfig) -> Vec<PeteSuggestion> {
+    let mut findings = Vec::new();
+    for node in &index.order {
+        let words = word_count(&node.content);
+        if words > config.max_words_per_node {
+            let severity = if words > config.critical_words_per_node {
+                Severity::Critical
+            } else {
+                Severity::Warning
+            };
+            let parts = words.div_ceil(config.max_words_per_node.max(1));
+            findings.push(suggestion(
+                node,
+                "wall_of_text",
+                SuggestionCategory::CognitiveLoad,
+                severity,
+                format!(
+                    "'{}' has {} words (limit {}).",
+                    node.title, words, config.max_words_per_node
+                ),
+                format!(
+                    "Split '{}' into {} stations of about {} words, each ending with a question or action.",
+                    node.title,
+                    parts,
+                    words / parts
+                ),
+                "Mayer: Segmenting Principle",
+            ));
+            continue;
+        }
+
+        let longest = node
+            .content
+            .split("\n\n")
+            .map(word_count)
+            .max()
+            .unwrap_or(0);
+        if longest > config.max_paragraph_words {
+            findings.push(suggestion(
+                node,
+                "wall_of_text",
+                SuggestionCategory::CognitiveLoad,
+                Severity::Info,
+                format!(
+                    "'{}' has a {}-word paragraph with no break.",
+                    node.title, longest
+                ),
+                "Break the paragraph after each idea and turn a list of steps into bullets."
+                    .to_string(),
+                "Mayer: Segmenting Principle",
+            ));
+        }
+    }
+    findings
+}
+
+fn vocabulary_load(
+    index: &GraphIndex,
+    vocabulary: &[String],
+    config: &LintConfig,
+) -> Vec<PeteSuggestion> {
+    let mut introduced: HashSet<String> = HashSet::new();
+    let mut findings = Vec::new();
+
+    for (pos, node) in index.order.iter().enumerate() {
+        let text = format!("{} {}", node.title, node.content).to_lowercase();
+        let new_terms: Vec<&String> = vocabulary
+            .iter()
+            .filter(|term| !introduced.contains(&term.to_lowercase()) && mentions(&text, term))
+            .collect();
+        introduced.extend(new_terms.iter().map(|t| t.to_lowercase()));
+
+        let load = new_terms.len().max(node.passenger_count as usize);
+        if load <= config.max_new_terms_per_node {
+            continue;
+        }
+
+        let (message, fix) = if new_terms.len() > config.max_new_terms_per_node {
+            let (keep, defer) = new_terms.split_at(config.max_new_terms_per_node);
+            let later = index
+                .order
+                .get(pos + 1)
+                .map(|n| format!("'{}'", n.title))
+                .unwrap_or_else(|| "a follow-up station".to_string());
+            (
+                format!(
+                    "'{}' introduces {} new vocabulary words ({}).",
+                    node.title,
+                    new_terms.len(),
+                    join_quoted(&new_terms)
+                ),
+                format!(
+                    "Keep {} here and introduce {} in {} or pre-teach them in a warm-up.",
+                    join_quoted(keep),
+                    join_quoted(defer),
+                    later
+                ),
+            )
+        } else {
+            (
+                format!(
+                    "'{}' carries {} new concepts (passenger count).",
+                    node.title, node.passenger_count
+                ),
+                format!(
+                    "Drop '{}' to {} concepts or split it into two stations.",
+                    node.title, config.max_new_terms_per_node
+                ),
+            )
+        };
+        findings.push(suggestion(
+            node,
+            "vocabulary_load",
+            SuggestionCategory::VocabularyOptimization,
+            Severity::Warning,
+            message,
+            fix,
+            "Sweller: Cognitive Load Theory",
+        ));
+    }
+    findings
+}
+
+fn join_quoted(terms: &[&String]) -> String {
+    terms
+        .iter()
+        .map(|t| format!("'{}'", t))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+const CHECKPOINT_CUES: &[&str] = &[
+    "reflect",
+    "quiz",
+    "check your",
+    "self-check",
+    "assessment",
+    "what do you think",
+    "why do you think",
+    "explain",
+    "in your own words",
+    "summarize",
+    "summarise",
+    "what would you",
+];
+
+/// A station that asks the learner to reflect or shows what they learned
+fn is_checkpoint(node: &StoryNode) -> bool {
+    if node.quest.is_some() {
+        return true;
+    }
+    let text = format!("{} {}", node.title, node.content).to_lowercase();
+    CHECKPOINT_CUES.iter().any(|cue| text.contains(cue))
+}
+
+fn missing_checkpoints(index: &GraphIndex, config: &LintConfig) -> Vec<PeteSuggestion> {
+    if index.order.len() < 2 {
+        return Vec::new();
+    }
+    let mut findings = Vec::new();
+    for node in index
+        .order
+        .iter()
+        .filter(|n| index.targets(&n.id).is_empty())
+    {
+        // Walk back from the ending up to `checkpoint_lookback` stations
+        let mut frontier = vec![node.id.as_str()];
+        let mut visited: HashSet<&str> = frontier.iter().copied().collect();
+        let mut found = false;
+        for depth in 0..=config.checkpoint_lookback {
+            if frontier.iter().any(|id| is_checkpoint(index.by_id[id])) {
+                found = true;
+                break;
+            }
+            if depth == config.checkpoint_lookback {
+                break;
+            }
+            frontier = frontier
+                .iter()
+                .flat_map(|id| index.sources(id).iter().copied())
+                .filter(|id| visited.insert(id))
+                .collect();
+        }
+        if found {
+            continue;
+        }
+
+        let before = index
+            .sources(&node.id)
+            .first()
+            .map(|id| {
+                format!(
+                    " or a quick quiz station between '{}' and it",
+                    index.by_id[id].title
+                )
+            })
+            .unwrap_or_default();
+        findings.push(suggestion(
+            node,
+            "missing_checkpoint",
+            SuggestionCategory::BestPractice,
+            Severity::Warning,
+            format!(
+                "Ending '{}' is reached without any reflection or assessment.",
+                node.title
+            ),
+            format!(
+                "Add a reflection prompt to '{}' (e.g. \"What would you do differently next time, and why?\"){}.",
+                node.title, before
+            ),
+            "Kolb: Reflective Observation",
+        ));
+    }
+    findings
+}
+
+fn flat_branches(index: &GraphIndex, config: &LintConfig) -> Vec<PeteSuggestion> {
+    let mut findings = Vec::new();
+    for node in &index.order {
+        let targets = index.targets(&node.id);
+        if targets.len() < 2 {
+            continue;
+        }
+        let nodes: Vec<&StoryNode> = targets.iter().map(|id| index.by_id[id]).collect();
+        let words: Vec<BTreeSet<String>> = nodes.iter().map(|n| word_set(&n.content)).collect();
+
+        let similar = (0..nodes.len())
+            .flat_map(|i| (i + 1..nodes.len()).map(move |j| (i, j)))
+            .find(|&(i, j)| jaccard(&words[i], &words[j]) >= config.branch_similarity);
+        if let Some((i, j)) = similar {
+            findings.push(suggestion(
+                node,
+                "flat_branch",
+                SuggestionCategory::BestPractice,
+                Severity::Warning,
+                format!(
+                    "The choice at '{}' leads to near-identical stations '{}' and '{}'.",
+                    node.title, nodes[i].title, nodes[j].title
+                ),
+                format!(
+                    "Give '{}' a different consequence (a new clue, setback or perspective), or merge the two options.",
+                    nodes[j].title
+                ),
+                "Branching scenario design: meaningful choice",
+            ));
+            continue;
+        }
+
+        // Every option is one short station that rejoins at the same place
+        let rejoin: HashSet<&[&str]> = nodes.iter().map(|n| index.targets(&n.id)).collect();
+        let short = nodes.iter().all(|n| word_count(&n.content) < 25);
+        if let (true, Some(&[merge])) = (short, rejoin.iter().next().copied()) {
+            if rejoin.len() == 1 {
+                findings.push(suggestion(
+                    node,
+                    "flat_branch",
+                    SuggestionCategory::BestPractice,
+                    Severity::Info,
+                    format!(
+                        "Every option at '{}' rejoins at '{}' after one short station.",
+                        node.title, index.by_id[merge].title
+                    ),
+                    format!(
+                        "Let each option from '{}' play out over its own stations with a distinct outcome before rejoining, or remove the choice.",
+                        node.title
+                    ),
+                    "Branching scenario design: meaningful choice",
+                ));
+            }
+        }
+    }
+    findings
+}
+
+fn canonical_garden(name: &str) -> Option<&'static str> {
+    GARDENS
+        .iter()
+        .copied()
+        .find(|g| g.eq_ignore_ascii_case(name.trim()))
+}
+
+fn garden_example(garden: &str) -> &'static str {
+    match garden {
+        "Knowledge" => "a short reading or worked example",
+        "Skills" => "a hands-on practice task",
+        _ => "a peer discussion or share-out",
+    }
+}
+
+fn garden_balance(index: &GraphIndex, config: &LintConfig) -> Vec<PeteSuggestion> {
+    let declared: Vec<(&StoryNode, Vec<&'static str>)> = index
+        .order
+        .iter()
+        .map(|n| {
+            let gardens: Vec<&'static str> = n
+                .gardens_active
+                .iter()
+                .filter_map(|g| canonical_garden(g))
+                .collect();
+            (*n, gardens)
+        })
+        .filter(|(_, gardens)| !gardens.is_empty())
+        .collect();
+    // Too few stations use gardens to judge balance
+    if declared.len() < 3 {
+        return Vec::new();
+    }
+
+    let mut counts: HashMap<&str, usize> = GARDENS.iter().map(|g| (*g, 0)).collect();
+    for (_, gardens) in &declared {
+        for garden in gardens {
+            *counts.entry(garden).or_default() += 1;
+        }
+    }
+    let total: usize = counts.values().sum();
+    let dominant = GARDENS
+        .iter()
+        .copied()
+        .max_by_key(|g| counts[g])
+        .unwrap_or("Knowledge");
+
+    let mut findings = Vec::new();
+    for garden in GARDENS {
+        let share = counts[garden] as f32 / total as f32;
+        if share >= config.min_garden_share {
+            continue;
+        }
+        // Suggest the last station that is only in the dominant garden
+        let candidate = declared
+            .iter()
+            .rev()
+            .find(|(_, gardens)| gardens.as_slice() == [dominant])
+            .or_else(|| declared.iter().rev().find(|(_, g)| !g.contains(&garden)));
+        let Some((node, _)) = candidate else {
+            continue;
+        };
+        findings.push(suggestion(
+            node,
+            "garden_balance",
+            SuggestionCategory::BestPractice,
+            Severity::Warning,
+            format!(
+                "{} activities are {:.0}% of the scenario's garden activities ({} dominates with {}).",
+                garden,
+                share * 100.0,
+                dominant,
+                counts[dominant]
+            ),
+            format!(
+                "Add a {} activity to '{}' ({}) and list '{}' in its gardens.",
+                garden,
+                node.title,
+                garden_example(garden),
+                garden
+            ),
+            "Ask Pete gardens: Knowledge, Skills, Community",
+        ));
+    }
+    findings
+}
+
+struct AccessibilityPatterns {
+    image_no_alt: Regex,
+    html_img: Regex,
+    vague_link: Regex,
+    colour_only: Regex,
+    shouting: Regex,
+}
+
+fn patterns() -> &'static AccessibilityPatterns {
+    static PATTERNS: OnceLock<AccessibilityPatterns> = OnceLock::new();
+    PATTERNS.get_or_init(|| AccessibilityPatterns {
+        image_no_alt: Regex::new(r"!\[\s*\]\(").expect("valid regex"),
+        html_img: Regex::new(r"(?i)<img\b[^>]*>").expect("valid regex"),
+        vague_link: Regex::new(
+            r"(?i)\[\s*(click here|here|link|this|read more|more)\s*\]\(|>\s*(click here|here|read more)\s*</a>",
+        )
+        .expect("valid regex"),
+        colour_only: Regex::new(
+            r"(?i)\b(red|green|blue|yellow|orange|purple)\s+(button|link|text|box|word|words|option|icon)s?\b",
+        )
+        .expect("valid regex"),
+        shouting: Regex::new(r"\b[A-Z]{2,}(?:[\s,!.]+[A-Z]{2,}){3,}\b").expect("valid regex"),
+    })
+}
+
+fn accessibility(index: &GraphIndex) -> Vec<PeteSuggestion> {
+    let patterns = patterns();
+    let mut findings = Vec::new();
+    for node in &index.order {
+        let content = &node.content;
+        let mut flag = |severity: Severity, message: String, fix: String| {
+            findings.push(suggestion(
+                node,
+                "accessibility",
+                SuggestionCategory::Accessibility,
+                severity,
+                message,
+                fix,
+                "WCAG 2.1",
+            ));
+        };
+
+        let missing_alt = patterns.image_no_alt.find_iter(content).count()
+            + patterns
+                .html_img
+                .find_iter(content)
+                .filter(|img| !img.as_str().to_lowercase().contains("alt="))
+                .count();
+        if missing_alt > 0 {
+            flag(
+                Severity::Critical,
+                format!(
+                    "'{}' has {} image(s) without alt text.",
+                    node.title, missing_alt
+                ),
+                "Describe what each image shows in its alt text (e.g. ![Train braking on a wet rail](...)).".to_string(),
+            );
+        }
+        if let Some(link) = patterns.vague_link.find(content) {
+            flag(
+                Severity::Warning,
+                format!("'{}' uses vague link text: {}", node.title, link.as_str()),
+                "Make the link text say where it goes (e.g. \"Newton's laws summary\" instead of \"click here\").".to_string(),
+            );
+        }
+        if let Some(colour) = patterns.colour_only.find(content) {
+            flag(
+                Severity::Warning,
+                format!(
+                    "'{}' identifies something by colour alone: \"{}\".",
+                    node.title,
+                    colour.as_str()
+                ),
+                "Also name the item by its label, icon or position so colour-blind learners can follow.".to_string(),
+            );
+        }
+        let alignment = node.style.alignment.to_lowercase();
+        if matches!(alignment.as_str(), "center" | "right" | "justify") && word_count(content) > 40
+        {
+            flag(
+                Severity::Info,
+                format!(
+                    "'{}' sets {} words of {}-aligned text.",
+                    node.title,
+                    word_count(content),
+                    alignment
+                ),
+                format!(
+                    "Left-align the body text of '{}'; keep centring for headings.",
+                    node.title
+                ),
+            );
+        }
+        if patterns.shouting.is_match(content) {
+            flag(
+                Severity::Info,
+                format!("'{}' has a run of text in all capitals.", node.title),
+                "Use bold for emphasis; screen readers may spell out capitals letter by letter."
+                    .to_string(),
+            );
+        }
+    }
+    findings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pete_core::expert::Connection;
+
+    fn node(id: &str, title: &str, content: &str) -> StoryNode {
+        StoryNode {
+            id: id.to_string(),
+            title: title.to_string(),
+            content: content.to_string(),
+            x: 0.0,
+            y: 0.0,
+            passenger_count: 0,
+            complexity_level: 1,
+            learner_profiles: vec![],
+            gardens_active: vec![],
+            required_stats: HashMap::new(),
+            logic: Default::default(),
+            style: Default::default(),
+            quest: None,
+            mass: None,
+            analysis_hash: None,
+        }
+    }
+
+    fn graph(nodes: Vec<StoryNode>, edges: &[(&str, &str)]) -> StoryGraph {
+        StoryGraph {
+            id: "g".to_string(),
+            title: "Test".to_string(),
+            nodes,
+            connections: edges
+                .iter()
+                .enumerate()
+                .map(|(i, (from, to))| Connection {
+                    id: format!("c{}", i),
+                    from_node: from.to_string(),
+                    to_node: to.to_string(),
+                })
+                .collect(),
+        }
+    }
+
+    fn rules<'a>(findings: &'a [PeteSuggestion], rule: &str) -> Vec<&'a PeteSuggestion> {
+        findings.iter().filter(|s| s.rule == rule).collect()
+    }
+
+    #[test]
+    fn test_wall_of_text() {
+        let long = "word ".repeat(320);
+        let g = graph(
+            vec![node("a", "Intro", &long), node("b", "End", "Reflect: why?")],
+            &[("a", "b")],
+        );
+        let findings = lint_graph(&g, &[], &LintConfig::default());
+        let walls = rules(&findings, "wall_of_text");
+        assert_eq!(walls.len(), 1);
+        assert_eq!(walls[0].node_id.as_deref(), Some("a"));
+        assert_eq!(walls[0].severity, Severity::Critical);
+        assert!(walls[0].fix.contains("3 stations"));
+        // Critical findings sort first
+        assert_eq!(findings[0].rule, "wall_of_text");
+    }
+
+    #[test]
+    fn test_vocabulary_counts_only_new_terms() {
+        let vocab: Vec<String> = ["inertia", "momentum", "friction", "impulse", "net force"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let g = graph(
+            vec![
+                node("a", "Start", "Inertia and momentum matter."),
+                node(
+                    "b",
+                    "Dense",
+                    "Momentum, friction, impulse and the net force all act. Inertia too.",
+                ),
+                node("c", "End", "Explain in your own words."),
+            ],
+            &[("a", "b"), ("b", "c")],
+        );
+        let findings = lint_graph(&g, &vocab, &LintConfig::default());
+        let vocab_findings = rules(&findings, "vocabulary_load");
+        // b introduces friction, impulse, net force: exactly the limit
+        assert!(vocab_findings.is_empty());
+
+        let strict = LintConfig {
+            max_new_terms_per_node: 2,
+            ..Default::default()
+        };
+        let findings = lint_graph(&g, &vocab, &strict);
+        let vocab_findings = rules(&findings, "vocabulary_load");
+        assert_eq!(vocab_findings.len(), 1);
+        assert_eq!(vocab_findings[0].node_id.as_deref(), Some("b"));
+        assert!(vocab_findings[0].fix.contains("'net force'"));
+        assert!(vocab_findings[0].fix.contains("'End'"));
+    }
+
+    #[test]
+    fn test_missing_checkpoint_before_ending() {
+        let g = graph(
+            vec![
+                node("a", "Start", "The train leaves."),
+                node("b", "Middle", "It speeds up."),
+                node("c", "Good End", "You arrive."),
+                node("d", "Quiz", "Quiz: what slowed the train?"),
+                node("e", "Other End", "You arrive late."),
+            ],
+            &[("a", "b"), ("b", "c"), ("a", "d"), ("d", "e")],
+        );
+        let findings = lint_graph(&g, &[], &LintConfig::default());
+        let missing = rules(&findings, "missing_checkpoint");
+        assert_eq!(missing.len(), 1);
+        assert_eq!(missing[0].node_id.as_deref(), Some("c"));
+        assert!(missing[0].fix.contains("between 'Middle'"));
+    }
+
+    #[test]
+    fn test_flat_branches() {
+        let g = graph(
+            vec![
+                node("a", "Fork", "Which track?"),
+                node("b", "Left", "You take the track and arrive at the station."),
+                node(
+                    "c",
+                    "Right",
+                    "You take the track and arrive at the station!",
+                ),
+                node("d", "End", "Reflect on the trip."),
+            ],
+            &[("a", "b"), ("a", "c"), ("b", "d"), ("c", "d")],
+        );
+        let findings = lint_graph(&g, &[], &LintConfig::default());
+        let flat = rules(&findings, "flat_branch");
+        assert_eq!(flat.len(), 1);
+        assert_eq!(flat[0].node_id.as_deref(), Some("a"));
+        assert_eq!(flat[0].severity, Severity::Warning);
+
+        let g = graph(
+            vec![
+                node("a", "Fork", "Which track?"),
+                node("b", "Left", "Trees pass by."),
+                node("c", "Right", "A river glints below."),
+                node("d", "End", "Reflect on the trip."),
+            ],
+            &[("a", "b"), ("a", "c"), ("b", "d"), ("c", "d")],
+        );
+        let findings = lint_graph(&g, &[], &LintConfig::default());
+        let flat = rules(&findings, "flat_branch");
+        assert_eq!(flat.len(), 1);
+        assert_eq!(flat[0].severity, Severity::Info);
+        assert!(flat[0].message.contains("rejoins at 'End'"));
+    }
+
+    #[test]
+    fn test_garden_balance() {
+        let mut nodes: Vec<StoryNode> = (0..4)
+            .map(|i| {
+                let mut n = node(&format!("n{}", i), &format!("Station {}", i), "Read on.");
+                n.gardens_active = vec!["knowledge".to_string()];
+                n
+            })
+            .collect();
+        nodes[1].gardens_active.push("Skills".to_string());
+        nodes[3].content = "Reflect on what you read.".to_string();
+        let g = graph(nodes, &[("n0", "n1"), ("n1", "n2"), ("n2", "n3")]);
+        let findings = lint_graph(&g, &[], &LintConfig::default());
+        let gardens = rules(&findings, "garden_balance");
+        assert_eq!(gardens.len(), 1);
+        assert!(gardens[0].message.starts_with("Community"));
+        assert_eq!(gardens[0].node_id.as_deref(), Some("n3"));
+        assert!(gardens[0].fix.contains("peer discussion"));
+    }
+
+    #[test]
+    fn test_accessibility_findings() {
+        let mut centred = node(
+            "a",
+            "Signals",
+            "Press the RED BUTTON NOW OR ELSE. ![](signal.png) See [here](http://x). Click the green button.",
+        );
+        centred.content.push_str(&" more".repeat(40));
+        centred.style.alignment = "center".to_string();
+        let g = graph(vec![centred], &[]);
+        let findings = lint_graph(&g, &[], &LintConfig::default());
+        let a11y = rules(&findings, "accessibility");
+        assert_eq!(a11y.len(), 5);
+        assert_eq!(a11y[0].severity, Severity::Critical);
+        assert!(a11y.iter().all(|s| s.node_id.as_deref() == Some("a")));
+        assert!(a11y.iter().any(|s| s.message.contains("by colour alone")));
+    }
+}
@@ -1,5 +1,20 @@
+use crate::services::pedagogy_lint::{self, LintConfig};
+use crate::services::response_cache::{
+    CacheKey, CachePolicy, PromptTemplate, ResponseCache, SamplingParams,
+};
 use anyhow::Result;
+use infra_ai::socratic_engine::SocraticEngine;
+use infra_ai::worker_pool::{InferencePool, JobSpec, PoolError, Priority};
+use infra_db::conversation_memory::Citation;
+use pete_core::expert::StoryGraph;
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+/// Bump when `review_prompt` changes so cached reviews are not reused
+const REVIEW_TEMPLATE: PromptTemplate = PromptTemplate::new("scenario_review", 1);
+const REVIEW_MAX_TOKENS: usize = 512;
 
 /// Pete - AI Teacher Assistant for ASK PETE
 ///
@@ -12,7 +27,13 @@ use serde::{Deserialize, Serialize};
 /// Architecture inspired by Open Notebook's multi-model orchestration,
 /// but implemented in pure Rust with local vector DB RAG.
 pub struct PeteAssistant {
-    // TODO: Add AI orchestrator
+    lint_config: LintConfig,
+    /// Optional model that reviews the graph after the rule-based linter
+    engine: Option<Arc<RwLock<SocraticEngine>>>,
+    /// Reviews of an unchanged graph are reused instead of re-running the model
+    cache: Option<Arc<ResponseCache>>,
+    /// Reviews queue as batch work behind chat on the shared pool
+    workers: Option<InferencePool>,
     // TODO: Add vector DB client
     // TODO: Add knowledge base
 }
@@ -20,7 +41,7 @@ pub struct PeteAssistant {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PeteResponse {
     pub answer: String,
-    pub citations: Vec<String>,
+    pub citations: Vec<Citation>,
     pub confidence: f32,
     pub suggestions: Vec<PeteSuggestion>,
 }
@@ -31,6 +52,15 @@ pub struct PeteSuggestion {
     pub severity: Severity,
     pub message: String,
     pub source: String,
+    /// Station the suggestion applies to (`None` for the whole scenario)
+    #[serde(default)]
+    pub node_id: Option<String>,
+    /// Lint rule that produced it, or "llm_review"
+    #[serde(default)]
+    pub rule: String,
+    /// Concrete change the designer can make
+    #[serde(default)]
+    pub fix: String,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
@@ -55,7 +85,34 @@ impl PeteAssistant {
         // TODO: Initialize vector DB connection
         // TODO: Load knowledge base
 
-        Ok(Self {})
+        Ok(Self {
+            lint_config: LintConfig::default(),
+            engine: None,
+            cache: None,
+            workers: None,
+        })
+    }
+
+    /// Let the AI model add suggestions the rules cannot see
+    pub fn with_engine(mut self, engine: Arc<RwLock<SocraticEngine>>) -> Self {
+        self.engine = Some(engine);
+        self
+    }
+
+    pub fn with_response_cache(mut self, cache: Arc<ResponseCache>) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Queue model reviews behind chat and Mirror traffic on the shared pool
+    pub fn with_worker_pool(mut self, pool: InferencePool) -> Self {
+        self.workers = Some(pool);
+        self
+    }
+
+    pub fn with_lint_config(mut self, config: LintConfig) -> Self {
+        self.lint_config = config;
+        self
     }
 
     /// Answer a teacher's question using RAG
@@ -75,26 +132,183 @@ impl PeteAssistant {
     }
 
     /// Analyze a scenario and provide suggestions
-    pub async fn analyze_scenario(&self, _scenario: &ScenarioData) -> Vec<PeteSuggestion> {
-        // TODO: Implement scenario analysis
-        // - Check vocab/node ratio
-        // - Identify missing reflection checkpoints
-        // - Analyze cognitive load distribution
-        // - Verify framework appropriateness
-
-        vec![]
+    ///
+    /// Runs the pedagogical linter over the whole graph, then (when a model
+    /// is connected) asks it for up to three further suggestions. Model output
+    /// that does not name a station in the graph is dropped. The model's reply
+    /// is cached per prompt unless `policy` is `CachePolicy::Bypass`.
+    ///
+    /// A generation waits for a batch slot on the worker pool; a saturated
+    /// pool is returned as an error so the caller can answer 503.
+    pub async fn analyze_scenario(
+        &self,
+        scenario: &ScenarioData,
+        policy: CachePolicy,
+    ) -> std::result::Result<Vec<PeteSuggestion>, PoolError> {
+        let mut suggestions =
+            pedagogy_lint::lint_graph(&scenario.graph, &scenario.vocabulary, &self.lint_config);
+
+        if let Some(engine) = &self.engine {
+            let prompt = review_prompt(scenario, &suggestions);
+            let model_id = engine.read().await.model_id();
+            let complete = || async {
+                // Only real generation takes a worker slot; cache hits skip the queue
+                let _slot = match &self.workers {
+                    Some(pool) => Some(
+                        pool.acquire(JobSpec::new(Priority::Batch, model_id.clone()))
+                            .await?,
+                    ),
+                    None => None,
+                };
+                engine
+                    .write()
+                    .await
+                    .complete(&prompt, REVIEW_MAX_TOKENS)
+                    .await
+                    .ok_or_else(|| anyhow::anyhow!("no review from the model"))
+            };
+            let reply = match &self.cache {
+                Some(cache) => {
+                    let key = CacheKey::new(
+                        REVIEW_TEMPLATE,
+                        &model_id,
+                        &SamplingParams::max_tokens(REVIEW_MAX_TOKENS),
+                        &prompt,
+                    );
+                    cache.get_or_generate(&key, policy, complete).await
+                }
+                None => complete().await,
+            };
+            match reply {
+                Ok(reply) => {
+                    suggestions.extend(parse_review(&reply, &scenario.graph, &suggestions))
+                }
+                Err(e) => {
+                    if let Some(busy) = e.downcast_ref::<PoolError>() {
+                        return Err(busy.clone());
+                    }
+                }
+            }
+        }
+
+        Ok(suggestions)
     }
 }
 
-/// Temporary scenario data structure
-/// TODO: Replace with actual domain model when implemented
+/// A scenario as Pete sees it: the story graph plus its target vocabulary
 #[derive(Debug, Clone)]
 pub struct ScenarioData {
-    pub nodes: Vec<String>,
+    pub graph: StoryGraph,
     pub vocabulary: Vec<String>,
     pub framework: Option<String>,
 }
 
+#[derive(Debug, Deserialize)]
+struct ReviewReply {
+    #[serde(default)]
+    suggestions: Vec<ReviewSuggestion>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ReviewSuggestion {
+    node_id: String,
+    category: SuggestionCategory,
+    #[serde(default = "default_review_severity")]
+    severity: Severity,
+    message: String,
+    fix: String,
+}
+
+fn default_review_severity() -> Severity {
+    Severity::Info
+}
+
+fn review_prompt(scenario: &ScenarioData, findings: &[PeteSuggestion]) -> String {
+    let mut prompt = String::from(
+        "You are Pete, an instructional design coach. Review this branching scenario.\n\n",
+    );
+    if let Some(framework) = &scenario.framework {
+        prompt.push_str(&format!("Framework: {}\n", framework));
+    }
+    prompt.push_str(&format!(
+        "Target vocabulary: {}\n\nStations:\n",
+        scenario.vocabulary.join(", ")
+    ));
+    for node in &scenario.graph.nodes {
+        let next: Vec<&str> = scenario
+            .graph
+            .connections
+            .iter()
+            .filter(|c| c.from_node == node.id)
+            .map(|c| c.to_node.as_str())
+            .collect();
+        let preview: String = node.content.chars().take(300).collect();
+        prompt.push_str(&format!(
+            "- [{}] {} -> [{}]: {}\n",
+            node.id,
+            node.title,
+            next.join(", "),
+            preview
+        ));
+    }
+    prompt.push_str("\nAlready reported:\n");
+    for finding in findings {
+        prompt.push_str(&format!(
+            "- {} ({}): {}\n",
+            finding.rule,
+            finding.node_id.as_deref().unwrap_or("scenario"),
+            finding.message
+        ));
+    }
+    prompt.push_str(
+        "\nAdd at most 3 NEW suggestions. Reply with JSON only:\n\
+         {\"suggestions\": [{\"node_id\": \"<station id>\", \"category\": \"CognitiveLoad|VocabularyOptimization|Accessibility|BestPractice\", \
+         \"severity\": \"Info|Warning\", \"message\": \"<problem>\", \"fix\": \"<concrete change>\"}]}\n",
+    );
+    prompt
+}
+
+fn parse_review(
+    reply: &str,
+    graph: &StoryGraph,
+    findings: &[PeteSuggestion],
+) -> Vec<PeteSuggestion> {
+    let review = match infra_ai::json_utils::extract_and_parse_json::<ReviewReply>(reply) {
+        Ok(review) => review,
+        Err(e) => {
+            log::warn!("Pete review reply was not valid JSON: {}", e);
+            return Vec::new();
+        }
+    };
+    let node_ids: HashSet<&str> = graph.nodes.iter().map(|n| n.id.as_str()).collect();
+    let known: HashSet<(&str, &str)> = findings
+        .iter()
+        .filter_map(|f| f.node_id.as_deref().map(|id| (id, f.message.as_str())))
+        .collect();
+
+    review
+        .suggestions
+        .into_iter()
+        .filter(|s| node_ids.contains(s.node_id.as_str()))
+        .filter(|s| !s.fix.trim().is_empty())
+        .filter(|s| !known.contains(&(s.node_id.as_str(), s.message.as_str())))
+        .take(3)
+        .map(|s| PeteSuggestion {
+            category: s.category,
+            // The model may advise, but only rules raise critical findings
+            severity: match s.severity {
+                Severity::Critical => Severity::Warning,
+                other => other,
+            },
+            message: s.message,
+            source: "Pete (AI review)".to_string(),
+            node_id: Some(s.node_id),
+            rule: "llm_review".to_string(),
+            fix: s.fix,
+        })
+        .collect()
+}
+
 impl Default for PeteAssistant {
     fn default() -> Self {
         Self::new().expect("Failed to create PeteAssistant")
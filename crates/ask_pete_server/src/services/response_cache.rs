@@ -0,0 +1,597 @@
+//! Content-addressed cache for deterministic AI calls.
+//!
+//! Weighing a word or a node and reviewing a scenario give the same answer for
+//! the same input, yet they used to re-run the model every time a graph was
+//! saved or reloaded. Responses are keyed by a SHA-256 of the prompt template
+//! (name and version), model id, sampling parameters and input, and looked up
+//! in memory (LRU) first, then Postgres. Entries expire after a TTL; bumping a
+//! template's version retires its old entries without a purge.
+//!
+//! Chat is not deterministic and should pass `CachePolicy::Bypass`.
+
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use infra_ai::local_inference::GenerationConfig;
+use lru::LruCache;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::num::NonZeroUsize;
+use std::sync::Mutex;
+use tokio::sync::RwLock;
+
+/// A prompt template; bump `version` whenever its wording changes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PromptTemplate {
+    pub name: &'static str,
+    pub version: u32,
+}
+
+impl PromptTemplate {
+    pub const fn new(name: &'static str, version: u32) -> Self {
+        Self { name, version }
+    }
+}
+
+/// Sampling parameters that change the output. `None` means the model's default.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct SamplingParams {
+    pub temperature: Option<f32>,
+    pub top_p: Option<f32>,
+    pub max_tokens: usize,
+}
+
+impl SamplingParams {
+    /// Only a token limit; the model picks the rest
+    pub fn max_tokens(max_tokens: usize) -> Self {
+        Self {
+            max_tokens,
+            ..Default::default()
+        }
+    }
+
+    fn fingerprint(&self) -> String {
+        let value = |v: Option<f32>| v.map(|v| format!("{:.3}", v)).unwrap_or_default();
+        format!(
+            "t={};p={};n={}",
+            value(self.temperature),
+            value(self.top_p),
+            self.max_tokens
+        )
+    }
+}
+
+impl From<&GenerationConfig> for SamplingParams {
+    fn from(config: &GenerationConfig) -> Self {
+        Self {
+            temperature: Some(config.temperature),
+            top_p: Some(config.top_p),
+            max_tokens: config.max_tokens,
+        }
+    }
+}
+
+/// Whether a call may be answered from (and stored in) the cache
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CachePolicy {
+    Use,
+    /// Call the model and replace the cached reply (e.g. "review again")
+    Refresh,
+    /// Always call the model and leave the cache untouched
+    Bypass,
+}
+
+impl CachePolicy {
+    /// Sampling hotter than this is treated as non-deterministic
+    pub const MAX_CACHEABLE_TEMPERATURE: f32 = 0.3;
+
+    pub fn for_sampling(sampling: &SamplingParams) -> Self {
+        match sampling.temperature {
+            Some(t) if t > Self::MAX_CACHEABLE_TEMPERATURE => Self::Bypass,
+            _ => Self::Use,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheKey {
+    /// Hex-encoded SHA-256
+    pub hash: String,
+    pub template: &'static str,
+    pub model_id: String,
+}
+
+impl CacheKey {
+    pub fn new(
+        template: PromptTemplate,
+        model_id: &str,
+        sampling: &SamplingParams,
+        input: &str,
+    ) -> Self {
+        let mut hasher = Sha256::new();
+        // NUL-separated so ("ab", "c") and ("a", "bc") differ
+        for part in [
+            template.name,
+            &template.version.to_string(),
+            model_id,
+            &sampling.fingerprint(),
+            input,
+        ] {
+            hasher.update(part.as_bytes());
+            hasher.update([0u8]);
+        }
+        Self {
+            hash: hasher
+                .finalize()
+                .iter()
+                .map(|b| format!("{:02x}", b))
+                .collect(),
+            template: template.name,
+            model_id: model_id.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct CacheCounts {
+    pub memory_hits: u64,
+    pub db_hits: u64,
+    pub misses: u64,
+    pub bypassed: u64,
+}
+
+impl CacheCounts {
+    /// Share of cacheable lookups answered without the model
+    pub fn hit_rate(&self) -> f64 {
+        let hits = self.memory_hits + self.db_hits;
+        let lookups = hits + self.misses;
+        if lookups == 0 {
+            0.0
+        } else {
+            hits as f64 / lookups as f64
+        }
+    }
+
+    fn add(&mut self, other: &CacheCounts) {
+        self.memory_hits += other.memory_hits;
+        self.db_hits += other.db_hits;
+        self.misses += other.misses;
+        self.bypassed += other.bypassed;
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TemplateStats {
+    #[serde(flatten)]
+    pub counts: CacheCounts,
+    pub hit_rate: f64,
+}
+
+impl From<CacheCounts> for TemplateStats {
+    fn from(counts: CacheCounts) -> Self {
+        Self {
+            hit_rate: counts.hit_rate(),
+            counts,
+        }
+    }
+}
+
+/// Hit-rate metrics since startup, overall and per template
+#[derive(Debug, Clone, Serialize)]
+pub struct CacheStats {
+    #[serde(flatten)]
+    pub total: TemplateStats,
+    pub memory_entries: usize,
+    pub memory_capacity: usize,
+    pub ttl_secs: i64,
+    pub templates: BTreeMap<String, TemplateStats>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Lookup {
+    MemoryHit,
+    DbHit,
+    Miss,
+    Bypassed,
+}
+
+struct MemoryEntry {
+    template: &'static str,
+    model_id: String,
+    response: String,
+    expires_at: DateTime<Utc>,
+}
+
+pub struct ResponseCache {
+    memory: RwLock<LruCache<String, MemoryEntry>>,
+    pool: Option<PgPool>,
+    ttl: Duration,
+    counts: Mutex<BTreeMap<&'static str, CacheCounts>>,
+}
+
+impl ResponseCache {
+    pub const DEFAULT_CAPACITY: usize = 1000;
+    pub const DEFAULT_TTL_SECS: i64 = 7 * 24 * 60 * 60;
+
+    pub fn new(pool: Option<PgPool>, capacity: usize, ttl: Duration) -> Self {
+        let capacity = NonZeroUsize::new(capacity)
+            .unwrap_or(NonZeroUsize::new(Self::DEFAULT_CAPACITY).unwrap());
+        Self {
+            memory: RwLock::new(LruCache::new(capacity)),
+            pool,
+            ttl,
+            counts: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Sized from `AI_CACHE_CAPACITY` and `AI_CACHE_TTL_SECS`
+    pub fn from_env(pool: Option<PgPool>) -> Self {
+        let capacity = std::env::var("AI_CACHE_CAPACITY")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(Self::DEFAULT_CAPACITY);
+        let ttl_secs = std::env::var("AI_CACHE_TTL_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(Self::DEFAULT_TTL_SECS);
+        Self::new(pool, capacity, Duration::seconds(ttl_secs))
+    }
+
+    /// Cached response for `key`, or the result of `generate` (stored on success)
+    pub async fn get_or_generate<F, Fut>(
+        &self,
+        key: &CacheKey,
+        policy: CachePolicy,
+        generate: F,
+    ) -> Result<String>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<String>>,
+    {
+        match policy {
+            CachePolicy::Bypass => {
+                self.record(key.template, Lookup::Bypassed);
+                return generate().await;
+            }
+            CachePolicy::Refresh => self.record(key.template, Lookup::Miss),
+            CachePolicy::Use => {
+                if let Some(response) = self.get(key).await {
+                    return Ok(response);
+                }
+            }
+        }
+        let response = generate().await?;
+        self.put(key, &response).await;
+        Ok(response)
+    }
+
+    /// Look `key` up in memory, then Postgres; counts a hit or a miss
+    pub async fn get(&self, key: &CacheKey) -> Option<String> {
+        let now = Utc::now();
+        {
+            let mut memory = self.memory.write().await;
+            match memory.get(&key.hash) {
+                Some(entry) if entry.expires_at > now => {
+                    let response = entry.response.clone();
+                    drop(memory);
+                    self.record(key.template, Lookup::MemoryHit);
+                    return Some(response);
+                }
+                Some(_) => {
+                    memory.pop(&key.hash);
+                }
+                None => {}
+            }
+        }
+
+        if let Some(pool) = &self.pool {
+            match fetch_row(pool, &key.hash).await {
+                Ok(Some((response, expires_at))) => {
+                    self.memory.write().await.put(
+                        key.hash.clone(),
+                        MemoryEntry {
+                            template: key.template,
+                            model_id: key.model_id.clone(),
+                            response: response.clone(),
+                            expires_at,
+                        },
+                    );
+                    self.record(key.template, Lookup::DbHit);
+                    return Some(response);
+                }
+                Ok(None) => {}
+                Err(e) => log::warn!("AI cache lookup failed: {}", e),
+            }
+        }
+
+        self.record(key.template, Lookup::Miss);
+        None
+    }
+
+    /// Store a response in both tiers. Postgres failures are logged, not returned.
+    pub async fn put(&self, key: &CacheKey, response: &str) {
+        let expires_at = Utc::now() + self.ttl;
+        self.memory.write().await.put(
+            key.hash.clone(),
+            MemoryEntry {
+                template: key.template,
+                model_id: key.model_id.clone(),
+                response: response.to_string(),
+                expires_at,
+            },
+        );
+
+        if let Some(pool) = &self.pool {
+            let result = sqlx::query(
+                r#"
+                INSERT INTO ai_response_cache (cache_key, template, model_id, response, expires_at)
+                VALUES ($1, $2, $3, $4, $5)
+                ON CONFLICT (cache_key) DO UPDATE
+                SET response = $4, expires_at = $5, created_at = NOW(), hit_count = 0
+                "#,
+            )
+            .bind(&key.hash)
+            .bind(key.template)
+            .bind(&key.model_id)
+            .bind(response)
+            .bind(expires_at)
+            .execute(pool)
+            .await;
+            if let Err(e) = result {
+                log::warn!("AI cache write failed: {}", e);
+            }
+        }
+    }
+
+    /// Drop one entry from both tiers; returns how many were removed
+    pub async fn invalidate(&self, hash: &str) -> Result<u64> {
+        let mut removed = self.memory.write().await.pop(hash).is_some() as u64;
+        if let Some(pool) = &self.pool {
+            let result = sqlx::query("DELETE FROM ai_response_cache WHERE cache_key = $1")
+                .bind(hash)
+                .execute(pool)
+                .await?;
+            removed = removed.max(result.rows_affected());
+        }
+        Ok(removed)
+    }
+
+    /// Drop every entry matching the template and/or model (all entries when
+    /// both are `None`); returns how many were removed
+    pub async fn invalidate_where(
+        &self,
+        template: Option<&str>,
+        model_id: Option<&str>,
+    ) -> Result<u64> {
+        let matches = |entry: &MemoryEntry| {
+            template.is_none_or(|t| entry.template == t)
+                && model_id.is_none_or(|m| entry.model_id == m)
+        };
+        let mut memory = self.memory.write().await;
+        let stale: Vec<String> = memory
+            .iter()
+            .filter(|(_, entry)| matches(entry))
+            .map(|(hash, _)| hash.clone())
+            .collect();
+        for hash in &stale {
+            memory.pop(hash);
+        }
+        drop(memory);
+
+        let mut removed = stale.len() as u64;
+        if let Some(pool) = &self.pool {
+            let result = sqlx::query(
+                r#"
+                DELETE FROM ai_response_cache
+                WHERE ($1::TEXT IS NULL OR template = $1)
+                  AND ($2::TEXT IS NULL OR model_id = $2)
+                "#,
+            )
+            .bind(template)
+            .bind(model_id)
+            .execute(pool)
+            .await?;
+            removed = removed.max(result.rows_affected());
+        }
+        Ok(removed)
+    }
+
+    /// Delete expired Postgres rows (memory entries expire on lookup)
+    pub async fn purge_expired(&self) -> Result<u64> {
+        let Some(pool) = &self.pool else {
+            return Ok(0);
+        };
+        let result = sqlx::query("DELETE FROM ai_response_cache WHERE expires_at <= NOW()")
+            .execute(pool)
+            .await?;
+        Ok(result.rows_affected())
+    }
+
+    pub async fn stats(&self) -> CacheStats {
+        let memory = self.memory.read().await;
+        let counts = self.counts.lock().unwrap();
+        let mut total = CacheCounts::default();
+        for template_counts in counts.values() {
+            total.add(template_counts);
+        }
+        CacheStats {
+            total: total.into(),
+            memory_entries: memory.len(),
+            memory_capacity: memory.cap().get(),
+            ttl_secs: self.ttl.num_seconds(),
+            templates: counts
+                .iter()
+                .map(|(name, counts)| (name.to_string(), (*counts).into()))
+                .collect(),
+        }
+    }
+
+    fn record(&self, template: &'static str, lookup: Lookup) {
+        let mut counts = self.counts.lock().unwrap();
+        let entry = counts.entry(template).or_default();
+        match lookup {
+            Lookup::MemoryHit => entry.memory_hits += 1,
+            Lookup::DbHit => entry.db_hits += 1,
+            Lookup::Miss => entry.misses += 1,
+            Lookup::Bypassed => entry.bypassed += 1,
+        }
+        log::debug!("AI cache {:?} for template '{}'", lookup, template);
+    }
+}
+
+async fn fetch_row(pool: &PgPool, hash: &str) -> Result<Option<(String, DateTime<Utc>)>> {
+    let row = sqlx::query_as::<_, (String, DateTime<Utc>)>(
+        r#"
+        UPDATE ai_response_cache SET hit_count = hit_count + 1
+        WHERE cache_key = $1 AND expires_at > NOW()
+        RETURNING response, expires_at
+        "#,
+    )
+    .bind(hash)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    const TEMPLATE: PromptTemplate = PromptTemplate::new("weigh_node", 1);
+
+    fn key(input: &str) -> CacheKey {
+        CacheKey::new(TEMPLATE, "mock", &SamplingParams::max_tokens(300), input)
+    }
+
+    #[test]
+    fn test_key_covers_every_component() {
+        let sampling = SamplingParams::max_tokens(300);
+        let base = CacheKey::new(TEMPLATE, "mock", &sampling, "text");
+        assert_eq!(base, CacheKey::new(TEMPLATE, "mock", &sampling, "text"));
+        assert_eq!(base.hash.len(), 64);
+
+        let variants = [
+            CacheKey::new(
+                PromptTemplate::new("weigh_node", 2),
+                "mock",
+                &sampling,
+                "text",
+            ),
+            CacheKey::new(TEMPLATE, "gemini:flash", &sampling, "text"),
+            CacheKey::new(TEMPLATE, "mock", &SamplingParams::max_tokens(512), "text"),
+            CacheKey::new(TEMPLATE, "mock", &sampling, "text!"),
+        ];
+        for variant in variants {
+            assert_ne!(base.hash, variant.hash);
+        }
+    }
+
+    #[test]
+    fn test_hot_sampling_bypasses_cache() {
+        let hot = SamplingParams {
+            temperature: Some(0.7),
+            ..SamplingParams::max_tokens(200)
+        };
+        let cold = SamplingParams {
+            temperature: Some(0.1),
+            ..SamplingParams::max_tokens(200)
+        };
+        assert_eq!(CachePolicy::for_sampling(&hot), CachePolicy::Bypass);
+        assert_eq!(CachePolicy::for_sampling(&cold), CachePolicy::Use);
+        assert_eq!(
+            CachePolicy::for_sampling(&SamplingParams::max_tokens(512)),
+            CachePolicy::Use
+        );
+    }
+
+    #[tokio::test]
+    async fn test_memory_tier_hits_and_bypass() {
+        let cache = ResponseCache::new(None, 10, Duration::minutes(5));
+        let calls = AtomicUsize::new(0);
+        let generate = || async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            Ok("{\"complexity_score\": 4}".to_string())
+        };
+
+        for _ in 0..3 {
+            let reply = cache
+                .get_or_generate(&key("a long node"), CachePolicy::Use, generate)
+                .await
+                .unwrap();
+            assert_eq!(reply, "{\"complexity_score\": 4}");
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        cache
+            .get_or_generate(&key("a long node"), CachePolicy::Bypass, generate)
+            .await
+            .unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        let refreshed = cache
+            .get_or_generate(&key("a long node"), CachePolicy::Refresh, || async {
+                Ok("{\"complexity_score\": 6}".to_string())
+            })
+            .await
+            .unwrap();
+        assert_eq!(refreshed, "{\"complexity_score\": 6}");
+        assert_eq!(
+            cache.get(&key("a long node")).await.as_deref(),
+            Some(refreshed.as_str())
+        );
+
+        let stats = cache.stats().await;
+        assert_eq!(stats.total.counts.memory_hits, 3);
+        assert_eq!(stats.total.counts.misses, 2);
+        assert_eq!(stats.total.counts.bypassed, 1);
+        assert!((stats.total.hit_rate - 3.0 / 5.0).abs() < 1e-9);
+        assert_eq!(stats.templates["weigh_node"].counts.memory_hits, 3);
+    }
+
+    #[tokio::test]
+    async fn test_failed_generation_is_not_cached() {
+        let cache = ResponseCache::new(None, 10, Duration::minutes(5));
+        let failed = cache
+            .get_or_generate(&key("x"), CachePolicy::Use, || async {
+                Err(anyhow::anyhow!("model offline"))
+            })
+            .await;
+        assert!(failed.is_err());
+        assert_eq!(cache.get(&key("x")).await, None);
+    }
+
+    #[tokio::test]
+    async fn test_ttl_and_invalidation() {
+        let expired = ResponseCache::new(None, 10, Duration::seconds(-1));
+        expired.put(&key("a"), "stale").await;
+        assert_eq!(expired.get(&key("a")).await, None);
+        assert_eq!(expired.stats().await.memory_entries, 0);
+
+        let cache = ResponseCache::new(None, 10, Duration::minutes(5));
+        let other = CacheKey::new(
+            PromptTemplate::new("weigh_word", 1),
+            "mock",
+            &SamplingParams::max_tokens(300),
+            "a",
+        );
+        cache.put(&key("a"), "one").await;
+        cache.put(&key("b"), "two").await;
+        cache.put(&other, "three").await;
+
+        assert_eq!(cache.invalidate(&key("a").hash).await.unwrap(), 1);
+        assert_eq!(cache.get(&key("a")).await, None);
+
+        assert_eq!(
+            cache
+                .invalidate_where(Some("weigh_node"), None)
+                .await
+                .unwrap(),
+            1
+        );
+        assert_eq!(cache.get(&key("b")).await, None);
+        assert_eq!(cache.get(&other).await.as_deref(), Some("three"));
+    }
+}
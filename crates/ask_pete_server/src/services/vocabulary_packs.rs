@@ -0,0 +1,130 @@
+//! Vocabulary pack persistence: seeding pack words into `vocabulary_words`
+//! and remembering which pack each course or story graph uses.
+
+use anyhow::Result;
+use infra_ai::vocabulary::{VocabularyPack, VocabularyRegistry};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::path::Path;
+
+/// What a pack assignment applies to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PackScope {
+    Course,
+    Graph,
+}
+
+impl PackScope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Course => "course",
+            Self::Graph => "graph",
+        }
+    }
+}
+
+/// Built-in packs plus those in `dir`; falls back to the built-in packs if
+/// a file there fails to parse.
+pub fn load_registry(dir: impl AsRef<Path>) -> VocabularyRegistry {
+    let dir = dir.as_ref();
+    match VocabularyRegistry::load_dir(dir) {
+        Ok(registry) => registry,
+        Err(e) => {
+            eprintln!(
+                "⚠️ [Vocabulary] Failed to load packs from {:?}: {:#}. Using built-in packs.",
+                dir, e
+            );
+            VocabularyRegistry::builtin()
+        }
+    }
+}
+
+/// Seeds the words of `pack` into `vocabulary_words` and returns how many
+/// rows were written. Curated pack data replaces whatever the weigh station
+/// inferred earlier, but a word that already belongs to a pack is left alone:
+/// instructor edits survive a restart and packs sharing a word don't clobber
+/// each other (the first pack seeded keeps it).
+pub async fn seed_pack(pool: &PgPool, pack: &VocabularyPack) -> Result<usize> {
+    let mut tx = pool.begin().await?;
+    let mut seeded = 0;
+    for term in &pack.terms {
+        seeded += sqlx::query(
+            r#"
+            INSERT INTO vocabulary_words
+                (word, definition, grade_level, tier, weight, tags, pack_id, related_terms, misconceptions)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            ON CONFLICT (word) DO UPDATE
+            SET definition = $2, grade_level = $3, tier = $4, weight = $5, tags = $6,
+                pack_id = $7, related_terms = $8, misconceptions = $9
+            WHERE vocabulary_words.pack_id IS NULL
+            "#,
+        )
+        .bind(&term.word)
+        .bind(&term.definition)
+        .bind(term.grade_level as i32)
+        .bind(term.tier as i32)
+        .bind(term.cognitive_weight() as i32)
+        .bind(vec![pack.id.clone()])
+        .bind(&pack.id)
+        .bind(&term.related_concepts)
+        .bind(&term.misconceptions)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected() as usize;
+    }
+    tx.commit().await?;
+    Ok(seeded)
+}
+
+pub async fn seed_registry(pool: &PgPool, registry: &VocabularyRegistry) -> Result<usize> {
+    let mut seeded = 0;
+    for pack in registry.packs() {
+        seeded += seed_pack(pool, pack).await?;
+    }
+    Ok(seeded)
+}
+
+pub async fn assign_pack(
+    pool: &PgPool,
+    scope: PackScope,
+    scope_id: &str,
+    pack_id: &str,
+) -> Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO vocabulary_pack_assignments (scope, scope_id, pack_id)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (scope, scope_id) DO UPDATE SET pack_id = $3, updated_at = NOW()
+        "#,
+    )
+    .bind(scope.as_str())
+    .bind(scope_id)
+    .bind(pack_id)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// The pack assigned to the graph, else to the course. `None` means the
+/// engine's default pack.
+pub async fn resolve_pack_id(
+    pool: &PgPool,
+    graph_id: Option<i32>,
+    course_id: Option<&str>,
+) -> Result<Option<String>> {
+    let graph_id = graph_id.map(|id| id.to_string());
+    let pack_id: Option<String> = sqlx::query_scalar(
+        r#"
+        SELECT pack_id FROM vocabulary_pack_assignments
+        WHERE (scope = 'graph' AND scope_id = $1) OR (scope = 'course' AND scope_id = $2)
+        ORDER BY (scope = 'graph') DESC
+        LIMIT 1
+        "#,
+    )
+    .bind(graph_id)
+    .bind(course_id)
+    .fetch_optional(pool)
+    .await?;
+    Ok(pack_id)
+}
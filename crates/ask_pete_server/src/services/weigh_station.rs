@@ -1,8 +1,21 @@
-use anyhow::{Context, Result};
+use crate::services::response_cache::{
+    CacheKey, CachePolicy, PromptTemplate, ResponseCache, SamplingParams,
+};
+use anyhow::{anyhow, Result};
 use infra_ai::local_inference::GenerationConfig;
+use infra_ai::prompt_assembler::{priority, PromptAssembler, PromptSection};
+use infra_ai::vocabulary::{VocabularyPack, VocabularyRegistry, VocabularyTerm};
+use infra_ai::worker_pool::{InferencePool, JobSpec, Priority};
 use infra_ai::LocalModel;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use sqlx::PgPool;
+use std::sync::Arc;
+
+/// Bump when the word prompt changes so cached replies are not reused
+const WEIGH_WORD_TEMPLATE: PromptTemplate = PromptTemplate::new("weigh_word", 1);
+/// Bump when the node prompt (or its system prompt) changes
+const WEIGH_NODE_TEMPLATE: PromptTemplate = PromptTemplate::new("weigh_node", 1);
 
 /// Standardized output for the Weigh Station
 #[derive(Debug, Serialize, Deserialize, Clone, sqlx::FromRow)]
@@ -27,16 +40,55 @@ impl WordPhysics {
             tags: vec!["basic".to_string()],
         }
     }
+
+    /// A curated pack entry; no inference needed
+    pub fn from_pack(pack: &VocabularyPack, term: &VocabularyTerm) -> Self {
+        Self {
+            word: term.word.clone(),
+            definition: term.definition.clone(),
+            grade_level: term.grade_level as i32,
+            tier: term.tier as i32,
+            weight: term.cognitive_weight() as i32,
+            tags: vec![pack.id.clone()],
+        }
+    }
 }
 
 pub struct WeighStationService {
     db: Option<PgPool>,
     llm: Option<LocalModel>, // Optional to handle missing AI
+    vocabulary: Option<Arc<VocabularyRegistry>>,
+    cache: Option<Arc<ResponseCache>>,
+    workers: Option<InferencePool>,
 }
 
 impl WeighStationService {
     pub fn new(db: Option<PgPool>, llm: Option<LocalModel>) -> Self {
-        Self { db, llm }
+        Self {
+            db,
+            llm,
+            vocabulary: None,
+            cache: None,
+            workers: None,
+        }
+    }
+
+    /// Weigh pack words from the pack's tier and grade level instead of the LLM
+    pub fn with_vocabulary_packs(mut self, registry: Arc<VocabularyRegistry>) -> Self {
+        self.vocabulary = Some(registry);
+        self
+    }
+
+    /// Reuse model replies for text that has been weighed before
+    pub fn with_response_cache(mut self, cache: Arc<ResponseCache>) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Queue model calls behind chat and Mirror traffic on the shared pool
+    pub fn with_worker_pool(mut self, pool: InferencePool) -> Self {
+        self.workers = Some(pool);
+        self
     }
 
     pub fn calculate_intrinsic_load(text: &str) -> f64 {
@@ -64,7 +116,14 @@ impl WeighStationService {
             return Ok(cached);
         }
 
-        // 2. FAST PATH (Heuristics)
+        // 2. PACK PATH (Curated vocabulary)
+        if let Some((pack, term)) = self.vocabulary.as_ref().and_then(|v| v.find_term(word)) {
+            let physics = WordPhysics::from_pack(pack, term);
+            self.store_in_depot(&physics).await?;
+            return Ok(physics);
+        }
+
+        // 3. FAST PATH (Heuristics)
         if word.len() <= 5 {
             let physics = WordPhysics::simple(word);
             // Save to DB so we have it for metrics later
@@ -72,7 +131,7 @@ impl WeighStationService {
             return Ok(physics);
         }
 
-        // 3. SLOW PATH (AI Inference)
+        // 4. SLOW PATH (AI Inference)
         if let Some(llm) = &self.llm {
             self.ask_pete_to_weigh(llm, word).await
         } else {
@@ -120,20 +179,67 @@ impl WeighStationService {
             temperature: 0.2,
             ..Default::default()
         };
+        // Fails early (rather than truncating) if the model's window is too small
+        let prompt = PromptAssembler::for_local_model(llm, config.max_tokens)
+            .assemble(vec![PromptSection::fixed("word", prompt)])?
+            .text;
 
-        let json_response = llm.generate(prompt, config).await?;
-
-        // Clean and parse
-        let clean_json = infra_ai::json_utils::extract_json_from_text(&json_response)
-            .unwrap_or_else(|| json_response.to_string());
-
-        let physics: WordPhysics =
-            serde_json::from_str(&clean_json).context("Failed to parse Pete's weighing ticket")?;
+        let physics: WordPhysics = self
+            .generate_json(llm, WEIGH_WORD_TEMPLATE, prompt, config)
+            .await?
+            .ok_or_else(|| anyhow!("Failed to parse Pete's weighing ticket"))?;
 
         self.store_in_depot(&physics).await?;
         Ok(physics)
     }
 
+    /// Run the model (through the response cache, if any) and parse its JSON.
+    /// `None` if the reply does not parse; such replies are not kept in the cache.
+    async fn generate_json<T: DeserializeOwned>(
+        &self,
+        llm: &LocalModel,
+        template: PromptTemplate,
+        prompt: String,
+        config: GenerationConfig,
+    ) -> Result<Option<T>> {
+        let sampling = SamplingParams::from(&config);
+        let model_id = llm.model_id();
+        let key = CacheKey::new(template, &model_id, &sampling, &prompt);
+        let workers = self.workers.clone();
+        let generate = || async move {
+            // Only real generation takes a worker slot; cache hits skip the queue
+            let _slot = match &workers {
+                Some(pool) => Some(
+                    pool.acquire(JobSpec::new(Priority::WeighStation, model_id))
+                        .await?,
+                ),
+                None => None,
+            };
+            Ok(llm.generate(prompt, config).await?)
+        };
+        let reply = match &self.cache {
+            Some(cache) => {
+                cache
+                    .get_or_generate(&key, CachePolicy::for_sampling(&sampling), generate)
+                    .await?
+            }
+            None => generate().await?,
+        };
+
+        let clean_json = infra_ai::json_utils::extract_json_from_text(&reply)
+            .unwrap_or_else(|| reply.to_string());
+        match serde_json::from_str(&clean_json) {
+            Ok(parsed) => Ok(Some(parsed)),
+            Err(e) => {
+                log::warn!("Unreadable {} reply: {}", template.name, e);
+                if let Some(cache) = &self.cache {
+                    cache.invalidate(&key.hash).await.ok();
+                }
+                Ok(None)
+            }
+        }
+    }
+
     async fn store_in_depot(&self, p: &WordPhysics) -> Result<()> {
         let pool = match &self.db {
             Some(p) => p,
@@ -172,32 +278,36 @@ impl WeighStationService {
 
         // 2. AI Inference
         if let Some(llm) = &self.llm {
-            let prompt = pete_core::prompts::weigh_station::generate_weigh_prompt(content);
-            let system_prompt = pete_core::prompts::weigh_station::WEIGH_STATION_SYSTEM_PROMPT;
-
             let config = GenerationConfig {
                 max_tokens: 300,
                 temperature: 0.1, // Low temp for consistent scoring
                 ..Default::default()
             };
 
-            // We need a way to pass system prompt.
-            // Assuming LocalModel supports it or we prepend it.
-            // For now, prepending to user prompt if API doesn't support separate system prompt.
-            // But LocalModel::generate takes (prompt, config).
-            // Let's prepend.
-            let full_prompt = format!("{}\n\n{}", system_prompt, prompt);
+            // System prompt is prepended (LocalModel has no separate system role);
+            // long node text is cut to fit the context window
+            let full_prompt = PromptAssembler::for_local_model(llm, config.max_tokens)
+                .assemble(vec![
+                    PromptSection::fixed(
+                        "system",
+                        pete_core::prompts::weigh_station::WEIGH_STATION_SYSTEM_PROMPT,
+                    ),
+                    PromptSection::text(
+                        "content",
+                        priority::KNOWLEDGE,
+                        pete_core::prompts::weigh_station::generate_weigh_prompt(content),
+                    ),
+                ])?
+                .text;
 
-            let json_response = llm.generate(full_prompt, config).await?;
-
-            let clean_json = infra_ai::json_utils::extract_json_from_text(&json_response)
-                .unwrap_or_else(|| json_response.to_string());
-
-            let physics: NodePhysics = serde_json::from_str(&clean_json).unwrap_or(NodePhysics {
-                complexity_score: 5,
-                concept_count: 3,
-                reasoning: "Failed to parse AI response, defaulting to medium.".to_string(),
-            });
+            let physics: NodePhysics = self
+                .generate_json(llm, WEIGH_NODE_TEMPLATE, full_prompt, config)
+                .await?
+                .unwrap_or(NodePhysics {
+                    complexity_score: 5,
+                    concept_count: 3,
+                    reasoning: "Failed to parse AI response, defaulting to medium.".to_string(),
+                });
 
             Ok(physics)
         } else {
@@ -31,7 +31,15 @@ pub struct AppState {
     pub quest_command_inbox: QuestCommandInbox,                    // [NEW]
     pub shared_graph_manager: Arc<RwLock<pete_core::graph_manager::GraphManager>>, // [NEW]
     pub quest_repo: Arc<dyn crate::repositories::quest_repo::QuestRepository>, // [NEW] Repository Pattern
-                                                                               // pub memory_store: Option<Arc<crate::ai::memory::LanceDbConnection>>, // [NEW] - Local Vector DB
+    pub memory_store: Option<Arc<dyn infra_db::VectorStore>>, // Local Vector DB (hybrid BM25 + vectors)
+    pub vocabulary_packs: Arc<infra_ai::vocabulary::VocabularyRegistry>,
+    pub lore_packs: Arc<infra_ai::lore::LoreRegistry>,
+    pub plugins: Arc<crate::plugins::registry::PluginRegistry>,
+    pub response_cache: Arc<crate::services::response_cache::ResponseCache>,
+    pub download_queue: Arc<infra_services::downloader::DownloadQueue>,
+    pub inference_pool: infra_ai::worker_pool::InferencePool,
+    /// Read once at startup so scheduling never waits on the engine lock
+    pub socratic_model_id: String,
 }
 
 impl axum::extract::FromRef<AppState> for PgPool {
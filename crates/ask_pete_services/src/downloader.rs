@@ -1,55 +1,1245 @@
-use anyhow::Result;
+//! Model downloads that survive failures.
+//!
+//! Bytes are written to `<file>.part`. A retry sends `Range: bytes=<len>-` and
+//! appends when the server answers 206, so a dropped connection costs only the
+//! bytes in flight. A finished file is checked against its expected SHA-256
+//! (when known), recorded in the directory's `checksums.json` manifest and only
+//! then renamed into place: a model path holds a complete file or nothing.
+//! Without an expected hash, a Hugging Face download is checked against the
+//! SHA-256 the Hub publishes for the file.
+//!
+//! `ModelStore` owns the model directory and its disk quota (including space
+//! reserved by downloads in flight); `DownloadQueue`
+//! runs several downloads at once, reports all of them on one progress channel
+//! and can cancel any of them.
+
+use anyhow::{bail, Context, Result};
 use futures_util::StreamExt;
-use std::fs::File;
-use std::io::Write;
-use std::path::PathBuf;
-use tokio::sync::mpsc::Sender;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::mpsc::{self, Receiver, Sender};
+use tokio::sync::Semaphore;
+
+/// Checksum manifest kept next to the models
+pub const MANIFEST_FILE: &str = "checksums.json";
+const PART_EXTENSION: &str = "part";
+
+/// Serialises read-modify-write of manifests across concurrent downloads
+static MANIFEST_LOCK: Mutex<()> = Mutex::new(());
+
+pub type DownloadId = u64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DownloadState {
+    Queued,
+    Downloading,
+    Verifying,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+impl DownloadState {
+    pub fn is_finished(&self) -> bool {
+        matches!(self, Self::Completed | Self::Failed | Self::Cancelled)
+    }
+}
 
+#[derive(Debug, Clone, Serialize)]
 pub struct DownloadProgress {
+    pub id: DownloadId,
+    pub filename: String,
+    pub state: DownloadState,
     pub percent: f32,
     pub downloaded_bytes: u64,
     pub total_bytes: u64,
+    pub error: Option<String>,
 }
 
+impl DownloadProgress {
+    fn new(id: DownloadId, filename: &str) -> Self {
+        Self {
+            id,
+            filename: filename.to_string(),
+            state: DownloadState::Queued,
+            percent: 0.0,
+            downloaded_bytes: 0,
+            total_bytes: 0,
+            error: None,
+        }
+    }
+
+    fn at(&self, state: DownloadState, downloaded_bytes: u64, total_bytes: u64) -> Self {
+        let percent = if total_bytes > 0 {
+            (downloaded_bytes as f32 / total_bytes as f32) * 100.0
+        } else {
+            0.0
+        };
+        Self {
+            state,
+            percent,
+            downloaded_bytes,
+            total_bytes,
+            ..self.clone()
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DownloadRequest {
+    pub url: String,
+    pub path: PathBuf,
+    /// Expected hex SHA-256; a file that does not match is discarded
+    pub sha256: Option<String>,
+    /// Expected size, checked against the quota before any bytes are fetched
+    pub size_bytes: Option<u64>,
+}
+
+impl DownloadRequest {
+    pub fn new(url: impl Into<String>, path: impl Into<PathBuf>) -> Self {
+        Self {
+            url: url.into(),
+            path: path.into(),
+            sha256: None,
+            size_bytes: None,
+        }
+    }
+
+    pub fn with_sha256(mut self, sha256: Option<String>) -> Self {
+        self.sha256 = sha256;
+        self
+    }
+
+    pub fn with_size(mut self, size_bytes: u64) -> Self {
+        self.size_bytes = Some(size_bytes);
+        self
+    }
+
+    fn filename(&self) -> String {
+        self.path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    }
+}
+
+/// Returned (inside `anyhow::Error`) when a download is cancelled
+#[derive(Debug)]
+pub struct Cancelled;
+
+impl std::fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "download cancelled")
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+/// Download `url` to `path`, resuming a previous partial download if present
 pub async fn download_file(
     url: String,
     path: PathBuf,
     progress_sender: Sender<DownloadProgress>,
 ) -> Result<()> {
-    // Create parent directories if they don't exist
-    if let Some(parent) = path.parent() {
-        std::fs::create_dir_all(parent)?;
+    let dir = path.parent().unwrap_or(Path::new(".")).to_path_buf();
+    let downloader = Downloader::new(ModelStore::new(dir, None));
+    let request = DownloadRequest::new(url, path);
+    let cancel = AtomicBool::new(false);
+    downloader
+        .fetch(0, &request, &cancel, |progress| {
+            // Ignore send errors (receiver might have dropped or be behind)
+            let _ = progress_sender.try_send(progress);
+        })
+        .await?;
+    Ok(())
+}
+
+// ============================================================================
+// Checksums
+// ============================================================================
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    pub sha256: String,
+    pub size_bytes: u64,
+    #[serde(default)]
+    pub source_url: Option<String>,
+}
+
+/// SHA-256 of every completed download in a directory, keyed by file name
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ChecksumManifest {
+    pub files: BTreeMap<String, ManifestEntry>,
+}
+
+impl ChecksumManifest {
+    /// The manifest in `dir`; empty if there is none yet
+    pub fn load(dir: &Path) -> Result<Self> {
+        let path = dir.join(MANIFEST_FILE);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let text = fs::read_to_string(&path)?;
+        serde_json::from_str(&text).with_context(|| format!("Invalid manifest {:?}", path))
+    }
+
+    /// Written to a temporary file and renamed, so readers never see half a manifest
+    pub fn save(&self, dir: &Path) -> Result<()> {
+        let tmp = dir.join(format!("{}.tmp", MANIFEST_FILE));
+        fs::write(&tmp, serde_json::to_string_pretty(self)?)?;
+        fs::rename(&tmp, dir.join(MANIFEST_FILE))?;
+        Ok(())
+    }
+
+    fn update(dir: &Path, apply: impl FnOnce(&mut Self)) -> Result<()> {
+        let _guard = MANIFEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let mut manifest = Self::load(dir)?;
+        apply(&mut manifest);
+        manifest.save(dir)
+    }
+}
+
+/// Hex-encoded SHA-256 of a file's contents
+pub fn sha256_file(path: &Path) -> Result<String> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; 1 << 20];
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect())
+}
+
+// ============================================================================
+// Model store (directory, quota, delete, verify)
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StoredModel {
+    pub filename: String,
+    pub size_bytes: u64,
+    /// From the manifest; `None` for files that were not downloaded here
+    pub sha256: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct VerifyReport {
+    pub filename: String,
+    pub expected_sha256: Option<String>,
+    pub actual_sha256: String,
+    pub size_bytes: u64,
+    /// The file matches its manifest entry (false when it has none)
+    pub ok: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DiskReport {
+    pub dir: PathBuf,
+    pub used_bytes: u64,
+    pub quota_bytes: Option<u64>,
+    /// Free space on the volume holding the models, if it could be read
+    pub free_bytes: Option<u64>,
+}
+
+/// The directory models are downloaded into, with an optional size cap
+#[derive(Debug, Clone)]
+pub struct ModelStore {
+    dir: PathBuf,
+    quota_bytes: Option<u64>,
+    /// Bytes promised to downloads in flight, shared by clones of the store
+    reserved: Arc<Mutex<u64>>,
+}
+
+impl ModelStore {
+    pub fn new(dir: impl Into<PathBuf>, quota_bytes: Option<u64>) -> Self {
+        Self {
+            dir: dir.into(),
+            quota_bytes,
+            reserved: Arc::new(Mutex::new(0)),
+        }
+    }
+
+    /// `MODEL_DIR` (default `default_dir`) capped at `MODEL_DISK_QUOTA_MB`, if set
+    pub fn from_env(default_dir: &str) -> Self {
+        let dir = std::env::var("MODEL_DIR").unwrap_or_else(|_| default_dir.to_string());
+        let quota = std::env::var("MODEL_DISK_QUOTA_MB")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .map(|mb| mb * 1024 * 1024);
+        Self::new(dir, quota)
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn quota_bytes(&self) -> Option<u64> {
+        self.quota_bytes
+    }
+
+    /// Bytes used by the store, partial downloads included
+    pub fn used_bytes(&self) -> u64 {
+        fs::read_dir(&self.dir)
+            .map(|entries| {
+                entries
+                    .flatten()
+                    .filter_map(|e| e.metadata().ok())
+                    .filter(|m| m.is_file())
+                    .map(|m| m.len())
+                    .sum()
+            })
+            .unwrap_or(0)
+    }
+
+    pub fn disk_report(&self) -> DiskReport {
+        DiskReport {
+            dir: self.dir.clone(),
+            used_bytes: self.used_bytes(),
+            quota_bytes: self.quota_bytes,
+            free_bytes: free_space_bytes(&self.dir),
+        }
+    }
+
+    /// Bytes reserved by downloads in flight and not yet written
+    pub fn reserved_bytes(&self) -> u64 {
+        *self.reserved.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Fails if `needed` more bytes would exceed the quota or the free space,
+    /// counting the space reserved by downloads in flight
+    pub fn check_room(&self, needed: u64) -> Result<()> {
+        self.check_room_with(needed, self.reserved_bytes())
+    }
+
+    /// Check for room and hold `needed` bytes until the reservation is
+    /// dropped, so concurrent downloads can't overrun the quota together
+    pub fn reserve(&self, needed: u64) -> Result<Reservation> {
+        let mut reserved = self.reserved.lock().unwrap_or_else(|e| e.into_inner());
+        self.check_room_with(needed, *reserved)?;
+        *reserved += needed;
+        Ok(Reservation {
+            reserved: self.reserved.clone(),
+            bytes: needed,
+        })
+    }
+
+    fn check_room_with(&self, needed: u64, reserved: u64) -> Result<()> {
+        if let Some(quota) = self.quota_bytes {
+            let used = self.used_bytes() + reserved;
+            if used + needed > quota {
+                bail!(
+                    "Model disk quota exceeded: {} MB used or reserved + {} MB needed > {} MB allowed",
+                    used / MB,
+                    needed / MB,
+                    quota / MB
+                );
+            }
+        }
+        if let Some(free) = free_space_bytes(&self.dir) {
+            if needed + reserved > free {
+                bail!(
+                    "Not enough disk space: {} MB needed, {} MB free ({} MB reserved)",
+                    needed / MB,
+                    free / MB,
+                    reserved / MB
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Completed models, by file name
+    pub fn list(&self) -> Result<Vec<StoredModel>> {
+        let manifest = ChecksumManifest::load(&self.dir)?;
+        let mut models: Vec<StoredModel> = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries
+                .flatten()
+                .filter_map(|entry| {
+                    let filename = entry.file_name().to_string_lossy().into_owned();
+                    let metadata = entry.metadata().ok()?;
+                    let internal = filename.starts_with(MANIFEST_FILE)
+                        || Path::new(&filename).extension() == Some(PART_EXTENSION.as_ref());
+                    if !metadata.is_file() || internal {
+                        return None;
+                    }
+                    Some(StoredModel {
+                        sha256: manifest.files.get(&filename).map(|e| e.sha256.clone()),
+                        filename,
+                        size_bytes: metadata.len(),
+                    })
+                })
+                .collect(),
+            Err(_) => Vec::new(),
+        };
+        models.sort_by(|a, b| a.filename.cmp(&b.filename));
+        Ok(models)
+    }
+
+    /// Remove a model, any partial download of it and its manifest entry.
+    /// `false` if there was nothing to delete.
+    pub fn delete(&self, filename: &str) -> Result<bool> {
+        let path = self.path_for(filename)?;
+        let mut removed = false;
+        for candidate in [path.clone(), part_path(&path)] {
+            if candidate.exists() {
+                fs::remove_file(&candidate)?;
+                removed = true;
+            }
+        }
+        if self.dir.exists() {
+            ChecksumManifest::update(&self.dir, |manifest| {
+                removed |= manifest.files.remove(filename).is_some();
+            })?;
+        }
+        Ok(removed)
+    }
+
+    /// Re-hash a model and compare it with its manifest entry
+    pub async fn verify(&self, filename: &str) -> Result<VerifyReport> {
+        let path = self.path_for(filename)?;
+        if !path.is_file() {
+            bail!("Model {} is not installed", filename);
+        }
+        let expected = ChecksumManifest::load(&self.dir)?.files.remove(filename);
+        let hash_path = path.clone();
+        let actual = tokio::task::spawn_blocking(move || sha256_file(&hash_path)).await??;
+        let size_bytes = fs::metadata(&path)?.len();
+        Ok(VerifyReport {
+            filename: filename.to_string(),
+            ok: expected
+                .as_ref()
+                .is_some_and(|e| e.sha256 == actual && e.size_bytes == size_bytes),
+            expected_sha256: expected.map(|e| e.sha256),
+            actual_sha256: actual,
+            size_bytes,
+        })
+    }
+
+    /// Path of `filename` inside the store; rejects anything that could escape it
+    pub fn path_for(&self, filename: &str) -> Result<PathBuf> {
+        let valid = !filename.is_empty()
+            && !filename.starts_with('.')
+            && !filename.contains(['/', '\\'])
+            && !filename.starts_with(MANIFEST_FILE);
+        if !valid {
+            bail!("Invalid model file name: {:?}", filename);
+        }
+        Ok(self.dir.join(filename))
+    }
+}
+
+/// Space held in a `ModelStore` for one download. Bytes are released as they
+/// are written (`used_bytes` counts them from then on) and the rest when the
+/// reservation is dropped.
+#[derive(Debug)]
+pub struct Reservation {
+    reserved: Arc<Mutex<u64>>,
+    bytes: u64,
+}
+
+impl Reservation {
+    pub fn bytes(&self) -> u64 {
+        self.bytes
+    }
+
+    /// `written` reserved bytes are now on disk
+    pub fn consume(&mut self, written: u64) {
+        let written = written.min(self.bytes);
+        self.bytes -= written;
+        *self.reserved.lock().unwrap_or_else(|e| e.into_inner()) -= written;
+    }
+}
+
+impl Drop for Reservation {
+    fn drop(&mut self) {
+        let bytes = self.bytes;
+        self.consume(bytes);
+    }
+}
+
+const MB: u64 = 1024 * 1024;
+
+/// Free space on the volume holding `path` (the mount point with the longest
+/// matching prefix), if it can be determined
+pub fn free_space_bytes(path: &Path) -> Option<u64> {
+    let mut probe = path.to_path_buf();
+    while !probe.exists() {
+        probe = probe.parent()?.to_path_buf();
+    }
+    let path = probe.canonicalize().ok()?;
+    let disks = sysinfo::Disks::new_with_refreshed_list();
+    disks
+        .list()
+        .iter()
+        .filter(|disk| path.starts_with(disk.mount_point()))
+        .max_by_key(|disk| disk.mount_point().as_os_str().len())
+        .map(|disk| disk.available_space())
+}
+
+fn part_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".");
+    name.push(PART_EXTENSION);
+    path.with_file_name(name)
+}
+
+// ============================================================================
+// Downloader
+// ============================================================================
+
+pub struct Downloader {
+    client: reqwest::Client,
+    store: ModelStore,
+    max_attempts: u32,
+    retry_delay: Duration,
+}
+
+impl Downloader {
+    pub fn new(store: ModelStore) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            store,
+            max_attempts: 5,
+            retry_delay: Duration::from_secs(2),
+        }
+    }
+
+    /// Attempts per download (each resumes where the last stopped) and the
+    /// pause between them
+    pub fn with_retries(mut self, max_attempts: u32, retry_delay: Duration) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self.retry_delay = retry_delay;
+        self
     }
 
-    let client = reqwest::Client::new();
-    let response = client.get(&url).send().await?;
-    let total_size = response.content_length().unwrap_or(0);
+    pub fn store(&self) -> &ModelStore {
+        &self.store
+    }
 
-    let mut stream = response.bytes_stream();
-    let mut file = File::create(&path)?;
-    let mut downloaded: u64 = 0;
+    /// Download, verify and move into place. Progress (including the final
+    /// state) goes to `report`; setting `cancel` stops the download and
+    /// discards the partial file.
+    pub async fn fetch(
+        &self,
+        id: DownloadId,
+        request: &DownloadRequest,
+        cancel: &AtomicBool,
+        report: impl Fn(DownloadProgress),
+    ) -> Result<PathBuf> {
+        let base = DownloadProgress::new(id, &request.filename());
+        let result = self.fetch_inner(&base, request, cancel, &report).await;
+        let done = match &result {
+            Ok(path) => {
+                let size = fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+                base.at(DownloadState::Completed, size, size)
+            }
+            Err(e) => {
+                let part = part_path(&request.path);
+                let state = if e.is::<Cancelled>() {
+                    let _ = fs::remove_file(&part);
+                    DownloadState::Cancelled
+                } else {
+                    DownloadState::Failed
+                };
+                let downloaded = fs::metadata(&part).map(|m| m.len()).unwrap_or(0);
+                DownloadProgress {
+                    error: Some(format!("{:#}", e)),
+                    ..base.at(state, downloaded, request.size_bytes.unwrap_or(0))
+                }
+            }
+        };
+        report(done);
+        result
+    }
 
-    while let Some(item) = stream.next().await {
-        let chunk = item?;
-        file.write_all(&chunk)?;
+    async fn fetch_inner(
+        &self,
+        base: &DownloadProgress,
+        request: &DownloadRequest,
+        cancel: &AtomicBool,
+        report: &impl Fn(DownloadProgress),
+    ) -> Result<PathBuf> {
+        if let Some(parent) = request.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let part = part_path(&request.path);
+        let mut reservation = None;
+        if let Some(size) = request.size_bytes {
+            let have = fs::metadata(&part).map(|m| m.len()).unwrap_or(0);
+            reservation = Some(self.store.reserve(size.saturating_sub(have))?);
+        }
+        let expected_sha256 = match &request.sha256 {
+            Some(sha256) => Some(sha256.clone()),
+            None => published_sha256(&request.url).await,
+        };
 
-        downloaded += chunk.len() as u64;
+        let mut attempt = 1;
+        loop {
+            match self
+                .attempt(base, request, &part, cancel, &mut reservation, report)
+                .await
+            {
+                Ok(()) => break,
+                Err(e) if e.is::<Cancelled>() || attempt >= self.max_attempts => return Err(e),
+                Err(e) if is_retryable(&e) => {
+                    log::warn!(
+                        "Download of {} failed (attempt {}/{}), resuming: {:#}",
+                        request.url,
+                        attempt,
+                        self.max_attempts,
+                        e
+                    );
+                    attempt += 1;
+                    tokio::time::sleep(self.retry_delay).await;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        let size = fs::metadata(&part)?.len();
+        report(base.at(DownloadState::Verifying, size, size));
+        let hash_path = part.clone();
+        let actual = tokio::task::spawn_blocking(move || sha256_file(&hash_path)).await??;
+        if let Some(expected) = &expected_sha256 {
+            if !expected.eq_ignore_ascii_case(&actual) {
+                fs::remove_file(&part)?;
+                bail!(
+                    "Checksum mismatch for {}: expected {}, got {}",
+                    base.filename,
+                    expected,
+                    actual
+                );
+            }
+        }
+
+        let dir = request.path.parent().unwrap_or(Path::new("."));
+        ChecksumManifest::update(dir, |manifest| {
+            manifest.files.insert(
+                base.filename.clone(),
+                ManifestEntry {
+                    sha256: actual,
+                    size_bytes: size,
+                    source_url: Some(request.url.clone()),
+                },
+            );
+        })?;
+        fs::rename(&part, &request.path)?;
+        log::info!("Downloaded {} to {:?}", request.url, request.path);
+        Ok(request.path.clone())
+    }
 
-        let percent = if total_size > 0 {
-            (downloaded as f32 / total_size as f32) * 100.0
+    /// One HTTP request, appending to the partial file
+    async fn attempt(
+        &self,
+        base: &DownloadProgress,
+        request: &DownloadRequest,
+        part: &Path,
+        cancel: &AtomicBool,
+        reservation: &mut Option<Reservation>,
+        report: &impl Fn(DownloadProgress),
+    ) -> Result<()> {
+        let mut offset = fs::metadata(part).map(|m| m.len()).unwrap_or(0);
+        let mut http = self.client.get(&request.url);
+        if offset > 0 {
+            http = http.header(reqwest::header::RANGE, format!("bytes={}-", offset));
+        }
+        let response = http.send().await.map_err(Retryable::from)?;
+        let status = response.status();
+
+        let total = match status.as_u16() {
+            206 => offset + response.content_length().unwrap_or(0),
+            // Nothing past what we have: the partial file is already complete
+            416 if offset > 0 => return Ok(()),
+            _ if status.is_success() => {
+                // Server ignored the range; start over
+                offset = 0;
+                response.content_length().unwrap_or(0)
+            }
+            _ if status.is_server_error() || status.as_u16() == 429 => {
+                return Err(Retryable(format!("HTTP {}", status)).into())
+            }
+            _ => bail!("HTTP {} fetching {}", status, request.url),
+        };
+        // The server's size wins over the expected one
+        let remaining = total.saturating_sub(offset);
+        let held = reservation.as_ref().map(Reservation::bytes).unwrap_or(0);
+        if reservation.is_none() || held < remaining {
+            *reservation = None;
+            *reservation = Some(self.store.reserve(remaining)?);
+        }
+
+        let mut file = if offset > 0 {
+            OpenOptions::new().append(true).open(part)?
         } else {
-            0.0
+            File::create(part)?
         };
+        let mut downloaded = offset;
+        report(base.at(DownloadState::Downloading, downloaded, total));
+
+        let mut stream = response.bytes_stream();
+        while let Some(item) = stream.next().await {
+            if cancel.load(Ordering::SeqCst) {
+                return Err(Cancelled.into());
+            }
+            let chunk = item.map_err(Retryable::from)?;
+            file.write_all(&chunk)?;
+            downloaded += chunk.len() as u64;
+            if let Some(reservation) = reservation.as_mut() {
+                reservation.consume(chunk.len() as u64);
+            }
+            report(base.at(DownloadState::Downloading, downloaded, total));
+        }
+        file.flush()?;
+
+        if total > 0 && downloaded < total {
+            return Err(Retryable(format!(
+                "connection closed at {} of {} bytes",
+                downloaded, total
+            ))
+            .into());
+        }
+        Ok(())
+    }
+}
+
+/// The SHA-256 Hugging Face publishes for a file behind a `/resolve/` URL:
+/// the `X-Linked-Etag` of its redirect (LFS files, i.e. all model weights).
+/// `None` for other URLs or when the Hub can't be asked.
+async fn published_sha256(url: &str) -> Option<String> {
+    if !url.contains("/resolve/") {
+        return None;
+    }
+    let client = reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .timeout(Duration::from_secs(30))
+        .build()
+        .ok()?;
+    let response = match client.head(url).send().await {
+        Ok(response) => response,
+        Err(e) => {
+            log::warn!("Could not look up the published checksum of {}: {}", url, e);
+            return None;
+        }
+    };
+    let etag = response.headers().get("x-linked-etag")?.to_str().ok()?;
+    let sha256 = etag.trim_start_matches("W/").trim_matches('"');
+    let valid = sha256.len() == 64 && sha256.chars().all(|c| c.is_ascii_hexdigit());
+    valid.then(|| sha256.to_ascii_lowercase())
+}
+
+/// Network failures worth resuming after
+#[derive(Debug)]
+struct Retryable(String);
+
+impl From<reqwest::Error> for Retryable {
+    fn from(e: reqwest::Error) -> Self {
+        Self(e.to_string())
+    }
+}
+
+impl std::fmt::Display for Retryable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for Retryable {}
+
+fn is_retryable(e: &anyhow::Error) -> bool {
+    e.is::<Retryable>()
+}
+
+// ============================================================================
+// Queue
+// ============================================================================
+
+struct Job {
+    progress: DownloadProgress,
+    path: PathBuf,
+    cancel: Arc<AtomicBool>,
+}
+
+/// Runs up to `max_concurrent` downloads at once. Progress for every job is
+/// sent on the channel returned by `new` and kept for `jobs()`.
+pub struct DownloadQueue {
+    downloader: Arc<Downloader>,
+    permits: Arc<Semaphore>,
+    jobs: Arc<Mutex<BTreeMap<DownloadId, Job>>>,
+    next_id: AtomicU64,
+    progress: Sender<DownloadProgress>,
+    runtime: tokio::runtime::Handle,
+}
+
+impl DownloadQueue {
+    /// Must be called inside a Tokio runtime; jobs are spawned on it
+    pub fn new(
+        downloader: Downloader,
+        max_concurrent: usize,
+    ) -> (Arc<Self>, Receiver<DownloadProgress>) {
+        let (tx, rx) = mpsc::channel(256);
+        let queue = Arc::new(Self {
+            downloader: Arc::new(downloader),
+            permits: Arc::new(Semaphore::new(max_concurrent.max(1))),
+            jobs: Arc::new(Mutex::new(BTreeMap::new())),
+            next_id: AtomicU64::new(1),
+            progress: tx,
+            runtime: tokio::runtime::Handle::current(),
+        });
+        (queue, rx)
+    }
+
+    pub fn store(&self) -> &ModelStore {
+        self.downloader.store()
+    }
+
+    /// Queue a download. A request for a file that is already queued or
+    /// downloading returns the existing job's id.
+    pub fn enqueue(&self, request: DownloadRequest) -> DownloadId {
+        let mut jobs = self.jobs.lock().unwrap();
+        if let Some((id, _)) = jobs
+            .iter()
+            .find(|(_, job)| job.path == request.path && !job.progress.state.is_finished())
+        {
+            return *id;
+        }
+
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        let cancel = Arc::new(AtomicBool::new(false));
+        jobs.insert(
+            id,
+            Job {
+                progress: DownloadProgress::new(id, &request.filename()),
+                path: request.path.clone(),
+                cancel: cancel.clone(),
+            },
+        );
+        drop(jobs);
+
+        let downloader = self.downloader.clone();
+        let permits = self.permits.clone();
+        let job_map = self.jobs.clone();
+        let sender = self.progress.clone();
+        self.runtime.spawn(async move {
+            let Ok(_permit) = permits.acquire_owned().await else {
+                return;
+            };
+            let report = |progress: DownloadProgress| {
+                if let Some(job) = job_map.lock().unwrap().get_mut(&progress.id) {
+                    job.progress = progress.clone();
+                }
+                // The channel is only a feed; `jobs()` always has the latest state
+                let _ = sender.try_send(progress);
+            };
+            if cancel.load(Ordering::SeqCst) {
+                report(DownloadProgress {
+                    state: DownloadState::Cancelled,
+                    ..DownloadProgress::new(id, &request.filename())
+                });
+                return;
+            }
+            if let Err(e) = downloader.fetch(id, &request, &cancel, report).await {
+                log::error!("Download {} failed: {:#}", request.url, e);
+            }
+        });
+        id
+    }
+
+    /// Ask a queued or running download to stop. `false` if it is unknown or
+    /// already finished.
+    pub fn cancel(&self, id: DownloadId) -> bool {
+        let jobs = self.jobs.lock().unwrap();
+        match jobs.get(&id) {
+            Some(job) if !job.progress.state.is_finished() => {
+                job.cancel.store(true, Ordering::SeqCst);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Latest progress of every job since startup, oldest first
+    pub fn jobs(&self) -> Vec<DownloadProgress> {
+        self.jobs
+            .lock()
+            .unwrap()
+            .values()
+            .map(|job| job.progress.clone())
+            .collect()
+    }
 
-        // Ignore send errors (receiver might have dropped)
-        let _ = progress_sender
-            .send(DownloadProgress {
-                percent,
-                downloaded_bytes: downloaded,
-                total_bytes: total_size,
+    pub fn job(&self, id: DownloadId) -> Option<DownloadProgress> {
+        self.jobs
+            .lock()
+            .unwrap()
+            .get(&id)
+            .map(|job| job.progress.clone())
+    }
+
+    /// Whether `filename` is queued or downloading
+    pub fn is_active(&self, filename: &str) -> bool {
+        self.jobs
+            .lock()
+            .unwrap()
+            .values()
+            .any(|job| job.progress.filename == filename && !job.progress.state.is_finished())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::TcpListener;
+
+    /// Serves one file with Range support. The first `drop_first` responses are
+    /// cut off halfway; `chunk_delay` slows the body down. HEAD requests get
+    /// `linked_etag` as `X-Linked-Etag`, like the Hugging Face Hub.
+    struct FileServer {
+        url: String,
+        requests: Arc<Mutex<Vec<Option<String>>>>,
+        handle: tokio::task::JoinHandle<()>,
+    }
+
+    impl FileServer {
+        async fn start(body: Vec<u8>, drop_first: usize, chunk_delay: Duration) -> Self {
+            Self::serve(body, drop_first, chunk_delay, None).await
+        }
+
+        /// Behind a Hub-style `/resolve/` URL, publishing `sha256`
+        async fn hub(body: Vec<u8>, sha256: String) -> Self {
+            Self::serve(body, 0, Duration::ZERO, Some(sha256)).await
+        }
+
+        async fn serve(
+            body: Vec<u8>,
+            drop_first: usize,
+            chunk_delay: Duration,
+            linked_etag: Option<String>,
+        ) -> Self {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let path = if linked_etag.is_some() {
+                "resolve/main/model.gguf"
+            } else {
+                "model.gguf"
+            };
+            let url = format!("http://{}/{}", listener.local_addr().unwrap(), path);
+            let requests = Arc::new(Mutex::new(Vec::new()));
+            let served = Arc::new(AtomicUsize::new(0));
+            let body = Arc::new(body);
+            let seen = requests.clone();
+            let handle = tokio::spawn(async move {
+                while let Ok((stream, _)) = listener.accept().await {
+                    let (body, seen, served) = (body.clone(), seen.clone(), served.clone());
+                    let linked_etag = linked_etag.clone();
+                    tokio::spawn(async move {
+                        let mut reader = BufReader::new(stream);
+                        let mut request_line = String::new();
+                        let _ = reader.read_line(&mut request_line).await;
+                        let mut range = None;
+                        loop {
+                            let mut line = String::new();
+                            if reader.read_line(&mut line).await.unwrap_or(0) == 0
+                                || line.trim().is_empty()
+                            {
+                                break;
+                            }
+                            if let Some((name, value)) = line.split_once(':') {
+                                if name.eq_ignore_ascii_case("range") {
+                                    range = Some(value.trim().to_string());
+                                }
+                            }
+                        }
+                        if request_line.starts_with("HEAD") {
+                            let head = format!(
+                                "HTTP/1.1 302 Found\r\nX-Linked-Etag: \"{}\"\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                                linked_etag.unwrap_or_default()
+                            );
+                            let _ = reader.get_mut().write_all(head.as_bytes()).await;
+                            return;
+                        }
+                        seen.lock().unwrap().push(range.clone());
+
+                        let start = range
+                            .as_deref()
+                            .and_then(|r| r.strip_prefix("bytes="))
+                            .and_then(|r| r.trim_end_matches('-').parse::<usize>().ok())
+                            .unwrap_or(0);
+                        let stream = reader.get_mut();
+                        if start >= body.len() && start > 0 {
+                            let _ = stream
+                                .write_all(b"HTTP/1.1 416 Range Not Satisfiable\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")
+                                .await;
+                            return;
+                        }
+                        let slice = &body[start..];
+                        let status = if start > 0 {
+                            "206 Partial Content"
+                        } else {
+                            "200 OK"
+                        };
+                        let head = format!(
+                            "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                            status,
+                            slice.len()
+                        );
+                        let _ = stream.write_all(head.as_bytes()).await;
+
+                        let cut = served.fetch_add(1, Ordering::SeqCst) < drop_first;
+                        let end = if cut { slice.len() / 2 } else { slice.len() };
+                        for chunk in slice[..end].chunks(1024) {
+                            if stream.write_all(chunk).await.is_err() {
+                                return;
+                            }
+                            if !chunk_delay.is_zero() {
+                                tokio::time::sleep(chunk_delay).await;
+                            }
+                        }
+                        let _ = stream.shutdown().await;
+                    });
+                }
+            });
+            Self {
+                url,
+                requests,
+                handle,
+            }
+        }
+
+        fn ranges(&self) -> Vec<Option<String>> {
+            self.requests.lock().unwrap().clone()
+        }
+    }
+
+    impl Drop for FileServer {
+        fn drop(&mut self) {
+            self.handle.abort();
+        }
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "ask_pete_downloads_{}_{}",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn body() -> Vec<u8> {
+        (0..50_000u32).map(|i| (i % 251) as u8).collect()
+    }
+
+    fn sha256(bytes: &[u8]) -> String {
+        Sha256::digest(bytes)
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+
+    fn downloader(dir: &Path, quota: Option<u64>) -> Downloader {
+        Downloader::new(ModelStore::new(dir, quota)).with_retries(3, Duration::from_millis(10))
+    }
+
+    #[tokio::test]
+    async fn test_resumes_with_range_and_records_checksum() {
+        let body = body();
+        let server = FileServer::start(body.clone(), 1, Duration::ZERO).await;
+        let dir = temp_dir("resume");
+        let path = dir.join("model.gguf");
+
+        let request = DownloadRequest::new(&server.url, &path).with_sha256(Some(sha256(&body)));
+        let updates = Mutex::new(Vec::new());
+        downloader(&dir, None)
+            .fetch(7, &request, &AtomicBool::new(false), |p| {
+                updates.lock().unwrap().push(p.state)
             })
-            .await;
+            .await
+            .unwrap();
+
+        assert_eq!(fs::read(&path).unwrap(), body);
+        assert!(!part_path(&path).exists());
+        let ranges = server.ranges();
+        assert_eq!(ranges.len(), 2);
+        assert_eq!(ranges[0], None);
+        assert_eq!(ranges[1], Some(format!("bytes={}-", body.len() / 2)));
+
+        let manifest = ChecksumManifest::load(&dir).unwrap();
+        assert_eq!(manifest.files["model.gguf"].sha256, sha256(&body));
+        let updates = updates.into_inner().unwrap();
+        assert!(updates.contains(&DownloadState::Verifying));
+        assert_eq!(updates.last(), Some(&DownloadState::Completed));
+
+        let store = ModelStore::new(&dir, None);
+        assert!(store.verify("model.gguf").await.unwrap().ok);
+        fs::write(&path, b"tampered").unwrap();
+        assert!(!store.verify("model.gguf").await.unwrap().ok);
     }
 
-    Ok(())
+    #[tokio::test]
+    async fn test_checksum_mismatch_discards_the_file() {
+        let server = FileServer::start(body(), 0, Duration::ZERO).await;
+        let dir = temp_dir("mismatch");
+        let path = dir.join("model.gguf");
+
+        let request = DownloadRequest::new(&server.url, &path).with_sha256(Some("00".repeat(32)));
+        let err = downloader(&dir, None)
+            .fetch(1, &request, &AtomicBool::new(false), |_| {})
+            .await
+            .unwrap_err();
+
+        assert!(err.to_string().contains("Checksum mismatch"));
+        assert!(!path.exists());
+        assert!(!part_path(&path).exists());
+        assert!(ChecksumManifest::load(&dir).unwrap().files.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_hub_downloads_are_checked_against_the_published_checksum() {
+        let body = body();
+        let dir = temp_dir("published");
+        let path = dir.join("model.gguf");
+
+        let tampered = FileServer::hub(body.clone(), "ab".repeat(32)).await;
+        let err = downloader(&dir, None)
+            .fetch(
+                1,
+                &DownloadRequest::new(&tampered.url, &path),
+                &AtomicBool::new(false),
+                |_| {},
+            )
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("Checksum mismatch"));
+        assert!(!path.exists());
+
+        let genuine = FileServer::hub(body.clone(), sha256(&body).to_uppercase()).await;
+        downloader(&dir, None)
+            .fetch(
+                2,
+                &DownloadRequest::new(&genuine.url, &path),
+                &AtomicBool::new(false),
+                |_| {},
+            )
+            .await
+            .unwrap();
+        assert_eq!(fs::read(&path).unwrap(), body);
+    }
+
+    #[test]
+    fn test_reservations_share_the_quota() {
+        let dir = temp_dir("reserve");
+        let store = ModelStore::new(&dir, Some(100_000));
+        let other = store.clone();
+
+        let mut first = store.reserve(60_000).unwrap();
+        assert!(other.reserve(60_000).is_err());
+        assert!(other.check_room(60_000).is_err());
+        let second = other.reserve(40_000).unwrap();
+        assert_eq!(store.reserved_bytes(), 100_000);
+
+        // Written bytes move from the reservation to `used_bytes`
+        fs::write(dir.join("first.gguf.part"), vec![0u8; 20_000]).unwrap();
+        first.consume(20_000);
+        assert_eq!(first.bytes(), 40_000);
+        assert!(store.reserve(1).is_err());
+
+        drop(second);
+        drop(first);
+        assert_eq!(store.reserved_bytes(), 0);
+        assert!(store.reserve(80_000).is_ok());
+        assert!(store.reserve(80_001).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_quota_is_enforced_before_downloading() {
+        let server = FileServer::start(body(), 0, Duration::ZERO).await;
+        let dir = temp_dir("quota");
+        let path = dir.join("model.gguf");
+
+        // Unknown size: caught once the response headers arrive
+        let err = downloader(&dir, Some(10_000))
+            .fetch(
+                1,
+                &DownloadRequest::new(&server.url, &path),
+                &AtomicBool::new(false),
+                |_| {},
+            )
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("quota"));
+        assert!(!path.exists());
+
+        // Known size: caught before any request is made
+        let request = DownloadRequest::new(&server.url, &path).with_size(50_000);
+        assert!(downloader(&dir, Some(10_000))
+            .fetch(2, &request, &AtomicBool::new(false), |_| {})
+            .await
+            .is_err());
+        assert_eq!(server.ranges().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_queue_runs_jobs_concurrently_and_cancels() {
+        let body = body();
+        let fast = FileServer::start(body.clone(), 0, Duration::ZERO).await;
+        let slow = FileServer::start(body.clone(), 0, Duration::from_millis(20)).await;
+        let dir = temp_dir("queue");
+
+        let (queue, mut rx) = DownloadQueue::new(downloader(&dir, None), 2);
+        let slow_id = queue.enqueue(DownloadRequest::new(&slow.url, dir.join("slow.gguf")));
+        let fast_id = queue.enqueue(DownloadRequest::new(&fast.url, dir.join("fast.gguf")));
+        assert_eq!(
+            queue.enqueue(DownloadRequest::new(&slow.url, dir.join("slow.gguf"))),
+            slow_id
+        );
+
+        let mut cancelled = false;
+        while let Some(progress) = rx.recv().await {
+            if progress.id == fast_id && progress.state == DownloadState::Completed && !cancelled {
+                assert!(queue.cancel(slow_id));
+                cancelled = true;
+            }
+            if progress.id == slow_id && progress.state.is_finished() {
+                break;
+            }
+        }
+
+        assert_eq!(queue.job(fast_id).unwrap().state, DownloadState::Completed);
+        assert_eq!(queue.job(slow_id).unwrap().state, DownloadState::Cancelled);
+        assert!(!queue.cancel(slow_id));
+        assert!(!dir.join("slow.gguf").exists());
+        assert!(!part_path(&dir.join("slow.gguf")).exists());
+
+        let store = queue.store();
+        let installed: Vec<String> = store
+            .list()
+            .unwrap()
+            .into_iter()
+            .map(|m| m.filename)
+            .collect();
+        assert_eq!(installed, vec!["fast.gguf"]);
+        assert!(store.delete("fast.gguf").unwrap());
+        assert!(!store.delete("fast.gguf").unwrap());
+        assert!(store.list().unwrap().is_empty());
+        assert!(store.delete("../etc/passwd").is_err());
+    }
 }
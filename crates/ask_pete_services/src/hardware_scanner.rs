@@ -1,8 +1,13 @@
+use serde::Serialize;
 use sysinfo::System;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct SystemSpecs {
     pub total_memory_gb: u64,
+    /// Exact figures for sizing models; `total_memory_gb` rounds down
+    pub total_memory_bytes: u64,
+    /// Memory the OS can hand out without swapping (free plus reclaimable cache)
+    pub available_memory_bytes: u64,
     pub cpu_cores: usize,
 }
 
@@ -19,6 +24,8 @@ impl HardwareScanner {
 
         SystemSpecs {
             total_memory_gb,
+            total_memory_bytes: total_memory,
+            available_memory_bytes: sys.available_memory(),
             cpu_cores,
         }
     }
@@ -1,5 +1,6 @@
 pub mod downloader;
 pub mod error;
+pub mod hardware_scanner;
 pub mod model_manager;
 pub mod model_registry;
 pub mod notebook_lm;
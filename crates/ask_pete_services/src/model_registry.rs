@@ -7,6 +7,10 @@ pub struct ModelConfig {
     pub hf_repo: String,
     pub filename: String,
     pub size_mb: f32,
+    /// Expected SHA-256 of `filename`, where the publisher lists one. Without
+    /// it, downloads are checked against the hash the Hub publishes.
+    #[serde(default)]
+    pub sha256: Option<String>,
 }
 
 pub fn get_pete_brains() -> Vec<ModelConfig> {
@@ -17,6 +21,7 @@ pub fn get_pete_brains() -> Vec<ModelConfig> {
             hf_repo: "bartowski/Llama-3.2-1B-Instruct-GGUF".to_string(),
             filename: "Llama-3.2-1B-Instruct-Q4_K_M.gguf".to_string(),
             size_mb: 800.0,
+            sha256: None,
         },
         ModelConfig {
             name: "Deep Thinker Pete".to_string(),
@@ -25,6 +30,7 @@ pub fn get_pete_brains() -> Vec<ModelConfig> {
             hf_repo: "bartowski/Llama-3.2-3B-Instruct-GGUF".to_string(),
             filename: "Llama-3.2-3B-Instruct-Q4_K_M.gguf".to_string(),
             size_mb: 2200.0,
+            sha256: None,
         },
     ]
 }
@@ -6,6 +6,7 @@ use crate::authoring::story_node::StoryNodeComponent;
 use crate::authoring::template_selector::TemplateSelector;
 use crate::authoring::word_smithy::WordSmithy;
 use leptos::prelude::*;
+use pete_core::layout::{compute_layout, LayoutConfig};
 use pete_core::trainyard::{Connection, StoryGraph, StoryNode};
 
 #[component]
@@ -103,6 +104,37 @@ pub fn NodeCanvas() -> impl IntoView {
         });
     };
 
+    // Auto-arrange handler: re-runs the layered layout over the current graph
+    let auto_arrange_handler = move |_| {
+        let current_nodes = nodes.get();
+        let current_connections = connections.get();
+        let ids: Vec<String> = current_nodes.iter().map(|n| n.get().id).collect();
+        let layout = compute_layout(
+            ids.iter().map(|id| id.as_str()),
+            current_connections
+                .iter()
+                .map(|c| (c.from_node.as_str(), c.to_node.as_str())),
+            &LayoutConfig::default(),
+        );
+        for node_signal in current_nodes.iter() {
+            node_signal.update(|n| {
+                if let Some(&(x, y)) = layout.positions.get(&n.id) {
+                    n.x = x;
+                    n.y = y;
+                }
+            });
+        }
+        set_view_transform.set((0.0, 0.0, 1.0));
+        set_toast_message.set(Some(format!(
+            "Auto-arranged into {} layers ({} crossings)",
+            layout.summary.layers, layout.summary.crossings
+        )));
+        leptos::task::spawn_local(async move {
+            gloo_timers::future::sleep(std::time::Duration::from_secs(3)).await;
+            set_toast_message.set(None);
+        });
+    };
+
     // Helper: Screen to World Coordinates
     let screen_to_world = move |sx: f64, sy: f64| -> (f64, f64) {
         let (vx, vy, scale) = view_transform.get();
@@ -249,6 +281,15 @@ pub fn NodeCanvas() -> impl IntoView {
                         <span>"💾 Save"</span>
                     </button>
 
+                    // Auto-arrange Button
+                    <button
+                        class="px-3 py-1 bg-slate-800 text-slate-300 font-bold rounded hover:bg-slate-700 transition-colors flex items-center gap-2 mr-4 border border-slate-600"
+                        on:click=auto_arrange_handler
+                        title="Auto-arrange stations into tidy lanes"
+                    >
+                        <span>"🧭 Auto-arrange"</span>
+                    </button>
+
                     // Reset View Button [NEW]
                     <button
                         class="px-3 py-1 bg-slate-800 text-slate-300 font-bold rounded hover:bg-slate-700 transition-colors flex items-center gap-2 mr-4 border border-slate-600"